@@ -9,6 +9,7 @@
 //! ```
 #![allow(unused_imports)]
 #![allow(unused_variables)]
+#![allow(clippy::result_large_err)]
 
 use axoasset::{AxoassetError, LocalAsset};
 use camino::Utf8PathBuf;
@@ -29,15 +30,15 @@ fn main() {
 }
 
 fn doit(args: Cli) -> Result<(), AxoassetError> {
-    #[cfg(feature = "compression-tar")]
+    #[cfg(feature = "compression-zstd")]
     if args.dest_path.as_str().ends_with("tar.zstd") {
         return LocalAsset::tar_zstd_dir(args.src_path, args.dest_path, args.with_root);
     }
-    #[cfg(feature = "compression-tar")]
+    #[cfg(feature = "compression-xz")]
     if args.dest_path.as_str().ends_with("tar.xz") {
         return LocalAsset::tar_xz_dir(args.src_path, args.dest_path, args.with_root);
     }
-    #[cfg(feature = "compression-tar")]
+    #[cfg(feature = "compression-gz")]
     if args.dest_path.as_str().ends_with("tar.gz") {
         return LocalAsset::tar_gz_dir(args.src_path, args.dest_path, args.with_root);
     }
@@ -47,7 +48,9 @@ fn doit(args: Cli) -> Result<(), AxoassetError> {
     }
 
     if !cfg!(any(
-        feature = "compression-tar",
+        feature = "compression-gz",
+        feature = "compression-xz",
+        feature = "compression-zstd",
         feature = "compression-zip"
     )) {
         panic!("this example must be built with --features=compression")
@@ -0,0 +1,149 @@
+//! Conventional names for release artifacts
+//!
+//! The axo toolchain names artifacts like `myapp-v1.2.3-x86_64-unknown-linux-gnu.tar.gz`:
+//! the app's name, its version (with a `v`), the target triple it's built for,
+//! and the format, all separated by `-` except for the format's `.`.
+
+use std::fmt;
+
+use crate::error::*;
+
+/// The formats [`ArtifactName::parse`][] recognizes, longest first so `.tar.gz`
+/// isn't mistaken for `.gz`
+const KNOWN_FORMATS: &[&str] = &[
+    "tar.zstd", "tar.bz2", "tar.zst", "tar.gz", "tar.xz", "tar.br", "tgz", "txz", "zip", "tar",
+    "exe", "msi", "pkg", "dmg", "deb", "rpm", "wasm", "gz", "xz", "zst", "br",
+];
+
+/// The starts of the architectures target triples begin with, which is how
+/// [`ArtifactName::parse`][] tells where a version ends and a triple begins
+const KNOWN_ARCHES: &[&str] = &[
+    "x86_64",
+    "i386",
+    "i586",
+    "i686",
+    "aarch64",
+    "arm",
+    "thumb",
+    "riscv",
+    "powerpc",
+    "mips",
+    "s390x",
+    "sparc",
+    "loongarch",
+    "wasm32",
+    "wasm64",
+    "universal",
+];
+
+/// The name of a release artifact, like `myapp-v1.2.3-x86_64-unknown-linux-gnu.tar.gz`
+///
+/// Use [`ToString`][] (or [`fmt::Display`][]) to get the name, and
+/// [`ArtifactName::parse`][] to go the other way.
+///
+/// ```
+/// # use axoasset::ArtifactName;
+/// let name = ArtifactName::new("myapp", "1.2.3", "x86_64-unknown-linux-gnu", "tar.gz");
+/// assert_eq!(name.to_string(), "myapp-v1.2.3-x86_64-unknown-linux-gnu.tar.gz");
+/// assert_eq!(ArtifactName::parse(&name.to_string()).unwrap(), name);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactName {
+    base: String,
+    version: String,
+    triple: String,
+    format: String,
+}
+
+impl ArtifactName {
+    /// Creates an ArtifactName
+    ///
+    /// A leading `v` on the version and a leading `.` on the format are optional.
+    pub fn new(
+        base: impl Into<String>,
+        version: impl AsRef<str>,
+        triple: impl Into<String>,
+        format: impl AsRef<str>,
+    ) -> Self {
+        let version = version.as_ref();
+        let version = match version.strip_prefix('v') {
+            Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+            _ => version,
+        };
+        Self {
+            base: base.into(),
+            version: version.to_owned(),
+            triple: triple.into(),
+            format: format.as_ref().trim_start_matches('.').to_owned(),
+        }
+    }
+
+    /// Parses a conventional artifact name back into its parts
+    ///
+    /// The format has to be a well-known one (like `tar.gz`, `zip`, or `exe`), the
+    /// version has to start with `v` and a digit, and the triple has to start with a
+    /// well-known architecture, since otherwise names with `-` in them are ambiguous.
+    pub fn parse(name: &str) -> Result<Self> {
+        let invalid = |reason: &str| AxoassetError::ArtifactNameInvalid {
+            name: name.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let (stem, format) = KNOWN_FORMATS
+            .iter()
+            .find_map(|format| {
+                let stem = name.strip_suffix(format)?.strip_suffix('.')?;
+                Some((stem, *format))
+            })
+            .ok_or_else(|| invalid("it doesn't end with a known format"))?;
+
+        let parts = stem.split('-').collect::<Vec<_>>();
+        let version_idx = parts
+            .iter()
+            .position(|part| {
+                part.strip_prefix('v')
+                    .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .filter(|&idx| idx > 0)
+            .ok_or_else(|| invalid("it doesn't have a version like `-v1.2.3`"))?;
+        let triple_idx = (version_idx + 1..parts.len())
+            .find(|&idx| KNOWN_ARCHES.iter().any(|arch| parts[idx].starts_with(arch)))
+            .ok_or_else(|| invalid("it doesn't have a target triple after the version"))?;
+
+        Ok(Self {
+            base: parts[..version_idx].join("-"),
+            version: parts[version_idx..triple_idx].join("-")[1..].to_owned(),
+            triple: parts[triple_idx..].join("-"),
+            format: format.to_owned(),
+        })
+    }
+
+    /// Gets the name of the app (like `myapp`)
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Gets the version, without the `v` (like `1.2.3`)
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Gets the target triple (like `x86_64-unknown-linux-gnu`)
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// Gets the format, without the leading `.` (like `tar.gz`)
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+}
+
+impl fmt::Display for ArtifactName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-v{}-{}.{}",
+            self.base, self.version, self.triple, self.format
+        )
+    }
+}
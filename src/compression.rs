@@ -1,23 +1,313 @@
 //! Compression-related methods, all used in `axoasset::Local`
 
 use camino::Utf8Path;
-#[cfg(feature = "compression-zip")]
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
 use camino::Utf8PathBuf;
 
 use crate::AxoassetError;
 
 /// Internal tar-file compression algorithms
-#[cfg(feature = "compression-tar")]
+///
+/// Each has its own feature, and the tar code shared between them is enabled by the
+/// internal `__tar` feature that all of those features turn on.
+#[cfg(feature = "__tar")]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum CompressionImpl {
     /// .gz
+    #[cfg(feature = "compression-gz")]
     Gzip,
     /// .xz
+    #[cfg(feature = "compression-xz")]
     Xzip,
     /// .zstd
+    #[cfg(feature = "compression-zstd")]
     Zstd,
 }
 
+/// The kinds of archive we know how to unpack
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ArchiveKind {
+    /// A tarball with the given compression
+    #[cfg(feature = "__tar")]
+    Tar(CompressionImpl),
+    /// A zip
+    #[cfg(feature = "compression-zip")]
+    Zip,
+}
+
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+impl ArchiveKind {
+    /// Guess the kind of archive from its filename
+    pub(crate) fn from_filename(filename: &str) -> Option<Self> {
+        let filename = filename.to_ascii_lowercase();
+        let has_extension = |extensions: &[&str]| {
+            extensions
+                .iter()
+                .any(|extension| filename.ends_with(extension))
+        };
+        #[cfg(feature = "compression-gz")]
+        if has_extension(&[".tar.gz", ".tgz"]) {
+            return Some(ArchiveKind::Tar(CompressionImpl::Gzip));
+        }
+        #[cfg(feature = "compression-xz")]
+        if has_extension(&[".tar.xz", ".txz"]) {
+            return Some(ArchiveKind::Tar(CompressionImpl::Xzip));
+        }
+        #[cfg(feature = "compression-zstd")]
+        if has_extension(&[".tar.zst", ".tar.zstd", ".tzst"]) {
+            return Some(ArchiveKind::Tar(CompressionImpl::Zstd));
+        }
+        #[cfg(feature = "compression-zip")]
+        if has_extension(&[".zip"]) {
+            return Some(ArchiveKind::Zip);
+        }
+        None
+    }
+}
+
+/// List what unpacking an archive of the given kind would do
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+pub(crate) fn plan_unpack(
+    archive: &Utf8Path,
+    kind: ArchiveKind,
+) -> crate::error::Result<Vec<crate::local::PlannedEntry>> {
+    match kind {
+        #[cfg(feature = "__tar")]
+        ArchiveKind::Tar(compression) => {
+            let tarball_bytes = open_tarball(archive, &compression)?;
+            plan_untar(&tarball_bytes).map_err(wrap_decompression_err(archive.as_str()))
+        }
+        #[cfg(feature = "compression-zip")]
+        ArchiveKind::Zip => {
+            let source = crate::LocalAsset::load_bytes(archive)?;
+            plan_unzip(&source).map_err(|details| AxoassetError::Decompression {
+                origin_path: archive.to_string(),
+                details: details.into(),
+            })
+        }
+    }
+}
+
+/// Checks if extracting a tarball entry at `path` would be refused
+///
+/// tar only refuses entries that use `..`, absolute paths are extracted inside the
+/// destination (see [`extracted_path`][]). Zips have
+/// [`zip::read::ZipFile::enclosed_name`][] for this.
+#[cfg(feature = "__tar")]
+fn extract_rejection(path: &std::path::Path) -> Option<crate::local::ExtractRejection> {
+    use std::path::Component;
+
+    let escapes = path
+        .components()
+        .any(|component| component == Component::ParentDir);
+    escapes.then_some(crate::local::ExtractRejection::OutsideDest)
+}
+
+#[cfg(feature = "__tar")]
+fn plan_untar(tarball_bytes: &[u8]) -> std::io::Result<Vec<crate::local::PlannedEntry>> {
+    use crate::local::{PlannedEntry, PlannedEntryKind};
+
+    let lossy = |path: &std::path::Path| Utf8PathBuf::from(path.to_string_lossy().into_owned());
+    let mut planned = vec![];
+    let mut archive = tar::Archive::new(tarball_bytes);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?;
+        let entry_type = entry.header().entry_type();
+        let target = entry.link_name()?.map(|target| lossy(&target));
+        let kind = match (entry_type, target) {
+            (tar::EntryType::Symlink, Some(target)) => PlannedEntryKind::Symlink { target },
+            (tar::EntryType::Link, Some(target)) => PlannedEntryKind::Hardlink { target },
+            _ if entry_type.is_dir() => PlannedEntryKind::Dir,
+            _ if entry_type.is_file() || entry_type.is_contiguous() => PlannedEntryKind::File,
+            _ => PlannedEntryKind::Other,
+        };
+        let size = match kind {
+            PlannedEntryKind::File => entry.size(),
+            _ => 0,
+        };
+        // Entries that are refused keep the path they were written with
+        let written_path = lossy(&path);
+        planned.push(PlannedEntry {
+            path: extracted_path(&written_path).unwrap_or(written_path),
+            size,
+            rejected: extract_rejection(&path),
+            kind,
+        });
+    }
+    Ok(planned)
+}
+
+#[cfg(feature = "compression-zip")]
+fn plan_unzip(source: &[u8]) -> zip::result::ZipResult<Vec<crate::local::PlannedEntry>> {
+    use crate::local::{ExtractRejection, PlannedEntry, PlannedEntryKind};
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(source))?;
+    let mut planned = vec![];
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        let is_dir = file.is_dir();
+        planned.push(PlannedEntry {
+            path: Utf8PathBuf::from(file.name().to_owned()),
+            size: if is_dir { 0 } else { file.size() },
+            kind: if is_dir {
+                PlannedEntryKind::Dir
+            } else {
+                PlannedEntryKind::File
+            },
+            rejected: match file.enclosed_name() {
+                Some(_) => None,
+                None if Utf8Path::new(file.name()).has_root() => {
+                    Some(ExtractRejection::AbsolutePath)
+                }
+                None => Some(ExtractRejection::OutsideDest),
+            },
+        });
+    }
+    Ok(planned)
+}
+
+/// Pack a directory into an archive of the given kind
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+pub(crate) fn archive_dir(
+    src_path: &Utf8Path,
+    dest_path: &Utf8Path,
+    layout: &crate::local::ArchiveLayout,
+    kind: ArchiveKind,
+    options: &crate::local::TarOptions,
+) -> crate::error::Result<()> {
+    match kind {
+        #[cfg(feature = "__tar")]
+        ArchiveKind::Tar(compression) => {
+            tar_dir(src_path, dest_path, layout, &compression, options)
+        }
+        #[cfg(feature = "compression-zip")]
+        ArchiveKind::Zip => {
+            // Only the options that apply to zips carry over
+            let zip_options = crate::local::ZipOptions {
+                provenance: options.provenance.clone(),
+                walk_order: options.walk_order,
+                empty_dirs: options.empty_dirs.clone(),
+                ..Default::default()
+            };
+            zip_dir(src_path, dest_path, layout, &zip_options)
+        }
+    }
+}
+
+/// Unpack an archive, and then any archives it contained, up to `max_depth` levels of nesting
+///
+/// Only archives that came out of the extraction are unpacked in turn, into the
+/// directory they were written to, and then removed. Anything that was already in
+/// `dest_path` is left alone. The paths of the nested archives that were unpacked
+/// are returned.
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+pub(crate) fn unpack_nested(
+    archive: &Utf8Path,
+    dest_path: &Utf8Path,
+    max_depth: usize,
+) -> crate::error::Result<Vec<camino::Utf8PathBuf>> {
+    use crate::LocalAsset;
+
+    let filename = crate::local::filename(archive)?;
+    let kind = ArchiveKind::from_filename(&filename).ok_or_else(|| {
+        AxoassetError::UnknownArchiveFormat {
+            origin_path: archive.to_string(),
+        }
+    })?;
+    let mut written = unpack_all_files(archive, dest_path, kind)?;
+
+    let mut unpacked = vec![];
+    for _ in 0..max_depth {
+        let nested = std::mem::take(&mut written)
+            .into_iter()
+            .filter_map(|path| {
+                let kind = ArchiveKind::from_filename(path.file_name()?)?;
+                Some((path, kind))
+            })
+            .collect::<Vec<_>>();
+        if nested.is_empty() {
+            break;
+        }
+        for (nested_archive, kind) in nested {
+            let parent = nested_archive.parent().unwrap_or(dest_path);
+            written.extend(unpack_all_files(&nested_archive, parent, kind)?);
+            LocalAsset::remove_file(&nested_archive)?;
+            unpacked.push(nested_archive);
+        }
+    }
+
+    Ok(unpacked)
+}
+
+/// Unpack an entire archive of the given kind into a directory, returning the paths
+/// of the regular files that were written
+///
+/// (symlinks aren't included, so nothing outside of what was extracted can be reached)
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+fn unpack_all_files(
+    archive: &Utf8Path,
+    dest_path: &Utf8Path,
+    kind: ArchiveKind,
+) -> crate::error::Result<Vec<Utf8PathBuf>> {
+    use crate::local::{ExtractHook, ExtractOptions};
+    use std::sync::{Arc, Mutex};
+
+    let entries = Arc::new(Mutex::new(vec![]));
+    let options = ExtractOptions {
+        on_entry: Some(ExtractHook::new({
+            let entries = entries.clone();
+            move |entry| {
+                if entry.is_dir {
+                    return;
+                }
+                if let Some(path) = extracted_path(entry.path) {
+                    entries.lock().unwrap().push(path);
+                }
+            }
+        })),
+    };
+    match kind {
+        #[cfg(feature = "__tar")]
+        ArchiveKind::Tar(compression) => untar_all(archive, dest_path, &compression, &options),
+        #[cfg(feature = "compression-zip")]
+        ArchiveKind::Zip => unzip_all(archive, dest_path, &options),
+    }?;
+
+    let mut written = vec![];
+    for path in std::mem::take(&mut *entries.lock().unwrap()) {
+        let path = dest_path.join(path);
+        let is_file = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_file());
+        if is_file && !written.contains(&path) {
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+/// Where an archive entry at `path` is extracted to, relative to the destination
+///
+/// This matches tar's `unpack_in`: leading `/`s and prefixes (like `C:`) are
+/// stripped, and entries that use `..` aren't extracted at all. (zips refuse
+/// absolute paths outright, so this also holds for the entries they do extract.)
+#[cfg(any(feature = "compression-zip", feature = "__tar"))]
+fn extracted_path(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    use camino::Utf8Component;
+
+    let mut extracted = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::Prefix(_) | Utf8Component::RootDir | Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => return None,
+            Utf8Component::Normal(part) => extracted.push(part),
+        }
+    }
+    (!extracted.as_str().is_empty()).then_some(extracted)
+}
+
 lazy_static::lazy_static! {
     static ref DEFAULT_GZ_LEVEL: u32 = {
         std::env::var("AXOASSET_GZ_LEVEL")
@@ -37,40 +327,124 @@ lazy_static::lazy_static! {
             .and_then(|val| val.parse().ok())
             .unwrap_or(3)
     };
+    static ref DEFAULT_BROTLI_LEVEL: i32 = {
+        std::env::var("AXOASSET_BROTLI_LEVEL")
+            .ok()
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(11)
+    };
+}
+
+#[cfg(feature = "compression-brotli")]
+pub(crate) fn brotli_file(src_path: &Utf8Path, dest_path: &Utf8Path) -> crate::error::Result<()> {
+    use crate::LocalAsset;
+
+    let source = LocalAsset::load_bytes(src_path)?;
+    let mut compressed = vec![];
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: *DEFAULT_BROTLI_LEVEL,
+        ..Default::default()
+    };
+    brotli::BrotliCompress(&mut source.as_slice(), &mut compressed, &params).map_err(
+        |details| AxoassetError::Compression {
+            reason: format!("failed to compress {src_path} with brotli"),
+            details,
+        },
+    )?;
+    std::fs::write(dest_path, compressed).map_err(|details| {
+        AxoassetError::LocalAssetWriteNewFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        }
+    })
 }
 
-#[cfg(feature = "compression-tar")]
+#[cfg(feature = "compression-brotli")]
+pub(crate) fn unbrotli_file(src_path: &Utf8Path, dest_path: &Utf8Path) -> crate::error::Result<()> {
+    use crate::LocalAsset;
+
+    let source = LocalAsset::load_bytes(src_path)?;
+    let mut decompressed = vec![];
+    brotli::BrotliDecompress(&mut source.as_slice(), &mut decompressed)
+        .map_err(wrap_decompression_err(src_path.as_str()))?;
+    std::fs::write(dest_path, decompressed).map_err(|details| {
+        AxoassetError::LocalAssetWriteNewFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        }
+    })
+}
+
+#[cfg(feature = "__tar")]
 pub(crate) fn tar_dir(
     src_path: &Utf8Path,
     dest_path: &Utf8Path,
-    with_root: Option<&Utf8Path>,
+    layout: &crate::local::ArchiveLayout,
     compression: &CompressionImpl,
+    options: &crate::local::TarOptions,
+) -> crate::error::Result<()> {
+    let final_zip_file = match std::fs::File::create(dest_path) {
+        Ok(file) => file,
+        Err(details) => {
+            return Err(AxoassetError::LocalAssetWriteNewFailed {
+                dest_path: dest_path.to_string(),
+                details,
+            })
+        }
+    };
+    write_tar(
+        src_path,
+        dest_path,
+        final_zip_file,
+        layout,
+        compression,
+        options,
+    )
+}
+
+/// Writes a tarball of `src_path` to `output`, which is called `dest_path` in errors
+/// (and in the gzip header)
+#[cfg(feature = "__tar")]
+pub(crate) fn write_tar<W: std::io::Write>(
+    src_path: &Utf8Path,
+    dest_path: &Utf8Path,
+    output: W,
+    layout: &crate::local::ArchiveLayout,
+    compression: &CompressionImpl,
+    options: &crate::local::TarOptions,
 ) -> crate::error::Result<()> {
     use crate::error::*;
+    #[cfg(feature = "compression-gz")]
     use flate2::{Compression, GzBuilder};
-    use std::fs;
+    #[cfg(feature = "compression-xz")]
     use xz2::write::XzEncoder;
+    #[cfg(feature = "compression-zstd")]
     use zstd::stream::Encoder as ZstdEncoder;
 
     // Set up the archive/compression
     // dir_name here is a prefix directory/path that the src dir's contents will be stored
     // under when being tarred. Having it be empty means the contents
     // will be placed in the root of the tarball.
-    let dir_name = with_root.unwrap_or_else(|| Utf8Path::new(""));
-    let zip_contents_name = format!("{}.tar", dest_path.file_name().unwrap());
-    let final_zip_file = match fs::File::create(dest_path) {
-        Ok(file) => file,
-        Err(details) => {
-            return Err(AxoassetError::LocalAssetWriteNewFailed {
-                dest_path: dest_path.to_string(),
-                details,
-            })
+    let prefix_dirs = layout.prefix_dirs()?;
+    let dir_name = Utf8Path::new(prefix_dirs.last().map(|dir| dir.as_str()).unwrap_or(""));
+    let provenance = match &options.provenance {
+        Some(provenance) => {
+            let created = provenance.created();
+            Some((created, provenance.render(src_path, created)?))
         }
+        None => None,
     };
+    let provenance = provenance
+        .as_ref()
+        .map(|(created, contents)| (*created, contents.as_bytes()));
+    let empty_dirs = empty_dirs(src_path, &options.empty_dirs)?;
+    let final_zip_file = output;
 
     match compression {
+        #[cfg(feature = "compression-gz")]
         CompressionImpl::Gzip => {
             // Wrap our file in compression
+            let zip_contents_name = format!("{}.tar", dest_path.file_name().unwrap_or_default());
             let zip_output = GzBuilder::new()
                 .filename(zip_contents_name)
                 .write(final_zip_file, Compression::new(*DEFAULT_GZ_LEVEL));
@@ -79,7 +453,14 @@ pub(crate) fn tar_dir(
             let mut tar = tar::Builder::new(zip_output);
 
             // Add the whole dir to the tar
-            if let Err(details) = tar.append_dir_all(dir_name, src_path) {
+            if let Err(details) = append_dir_all(
+                &mut tar,
+                dir_name,
+                src_path,
+                options,
+                &empty_dirs,
+                provenance,
+            ) {
                 return Err(AxoassetError::Compression {
                     reason: format!("failed to copy directory into tar: {src_path} => {dir_name}",),
                     details,
@@ -107,13 +488,21 @@ pub(crate) fn tar_dir(
             };
             // Drop the file to close it
         }
+        #[cfg(feature = "compression-xz")]
         CompressionImpl::Xzip => {
             let zip_output = XzEncoder::new(final_zip_file, *DEFAULT_XZ_LEVEL);
             // Write the tar to the compression stream
             let mut tar = tar::Builder::new(zip_output);
 
             // Add the whole dir to the tar
-            if let Err(details) = tar.append_dir_all(dir_name, src_path) {
+            if let Err(details) = append_dir_all(
+                &mut tar,
+                dir_name,
+                src_path,
+                options,
+                &empty_dirs,
+                provenance,
+            ) {
                 return Err(AxoassetError::Compression {
                     reason: format!("failed to copy directory into tar: {src_path} => {dir_name}",),
                     details,
@@ -141,6 +530,7 @@ pub(crate) fn tar_dir(
             };
             // Drop the file to close it
         }
+        #[cfg(feature = "compression-zstd")]
         CompressionImpl::Zstd => {
             // Wrap our file in compression
             let zip_output =
@@ -155,7 +545,14 @@ pub(crate) fn tar_dir(
             let mut tar = tar::Builder::new(zip_output);
 
             // Add the whole dir to the tar
-            if let Err(details) = tar.append_dir_all(dir_name, src_path) {
+            if let Err(details) = append_dir_all(
+                &mut tar,
+                dir_name,
+                src_path,
+                options,
+                &empty_dirs,
+                provenance,
+            ) {
                 return Err(AxoassetError::Compression {
                     reason: format!("failed to copy directory into tar: {src_path} => {dir_name}",),
                     details,
@@ -188,7 +585,380 @@ pub(crate) fn tar_dir(
     Ok(())
 }
 
-#[cfg(feature = "compression-tar")]
+/// Gets the dirs from [`crate::local::TarOptions::empty_dirs`][] (or the zip equivalent)
+/// that `src_path` doesn't already contain, along with their parents, parents first
+///
+/// Anything already at one of those paths (even a file) wins, so an archive never
+/// has two entries for the same path.
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+fn empty_dirs(
+    src_path: &Utf8Path,
+    empty_dirs: &[camino::Utf8PathBuf],
+) -> crate::error::Result<Vec<String>> {
+    let mut dirs = std::collections::BTreeSet::new();
+    for dir in empty_dirs {
+        // These have the same rules as prefixes, and the same parents-first expansion
+        let expanded = crate::local::ArchiveLayout::Prefix(dir.clone())
+            .prefix_dirs()
+            .map_err(|_| AxoassetError::ArchiveEmptyDirInvalid { path: dir.clone() })?;
+        dirs.extend(
+            expanded
+                .into_iter()
+                .filter(|dir| !src_path.join(dir).exists()),
+        );
+    }
+    // Sorting puts every dir after its parents
+    Ok(dirs.into_iter().collect())
+}
+
+/// Adds the contents of `src_path` to the tarball under `dir_name`, respecting [`crate::local::TarOptions`][],
+/// followed by the extra empty dirs and the rendered provenance file (and its mtime) if there is one
+#[cfg(feature = "__tar")]
+fn append_dir_all<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    dir_name: &Utf8Path,
+    src_path: &Utf8Path,
+    options: &crate::local::TarOptions,
+    empty_dirs: &[String],
+    provenance: Option<(u64, &[u8])>,
+) -> std::io::Result<()> {
+    // The outer dirs of the prefix don't exist in src_path, so give them its metadata
+    for ancestor in dir_name
+        .ancestors()
+        .skip(1)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+    {
+        if !ancestor.as_str().is_empty() {
+            tar.append_dir(ancestor, src_path)?;
+        }
+    }
+    if options.include_xattrs || options.walk_order == crate::local::WalkOrder::Sorted {
+        append_dir_all_sorted(tar, dir_name, src_path, options)?;
+    } else {
+        tar.append_dir_all(dir_name, src_path)?;
+    }
+    // These don't exist in src_path either, so they get its metadata too
+    for dir in empty_dirs {
+        tar.append_dir(dir_name.join(dir), src_path)?;
+    }
+
+    if let Some((created, contents)) = provenance {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(created);
+        tar.append_data(
+            &mut header,
+            dir_name.join(crate::local::Provenance::FILE_NAME),
+            contents,
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds a dir to a tar like [`tar::Builder::append_dir_all`][], but in the
+/// [`crate::local::TarOptions::walk_order`][] and with each entry's extended
+/// attributes if they're wanted
+#[cfg(feature = "__tar")]
+fn append_dir_all_sorted<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    dir_name: &Utf8Path,
+    src_path: &Utf8Path,
+    options: &crate::local::TarOptions,
+) -> std::io::Result<()> {
+    // This is the same walk as tar::Builder::append_dir_all, but with a chance to
+    // sort each dir and emit a pax header with the xattrs before each entry
+    let mut stack = vec![src_path.as_std_path().to_owned()];
+    while let Some(src) = stack.pop() {
+        let dest = dir_name
+            .as_std_path()
+            .join(src.strip_prefix(src_path).unwrap());
+        let xattrs = if options.include_xattrs {
+            read_xattrs(&src)?
+        } else {
+            vec![]
+        };
+        if !xattrs.is_empty() && dest != std::path::Path::new("") {
+            tar.append_pax_extensions(
+                xattrs
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_slice())),
+            )?;
+        }
+        if src.is_dir() {
+            let mut children = std::fs::read_dir(&src)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            if options.walk_order == crate::local::WalkOrder::Sorted {
+                // The stack pops from the back, so reverse to visit them in order
+                children.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+            }
+            stack.extend(children);
+            if dest != std::path::Path::new("") {
+                tar.append_dir(&dest, &src)?;
+            }
+        } else {
+            tar.append_path_with_name(&src, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Gets the extended attributes of a file as pax header key/value pairs
+#[cfg(feature = "__tar")]
+fn read_xattrs(path: &std::path::Path) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    if !xattr::SUPPORTED_PLATFORM {
+        return Ok(vec![]);
+    }
+    let mut xattrs = vec![];
+    for name in xattr::list(path)? {
+        // pax headers have to be utf8
+        let Some(name_str) = name.to_str() else {
+            continue;
+        };
+        if let Some(value) = xattr::get(path, &name)? {
+            xattrs.push((format!("SCHILY.xattr.{name_str}"), value));
+        }
+    }
+    xattrs.sort();
+    Ok(xattrs)
+}
+
+/// Incrementally builds a compressed tarball from in-memory or streamed contents
+///
+/// Unlike [`crate::LocalAsset::tar_gz_dir`][] and friends, the contents of the tarball
+/// don't need to exist on disk: entries can be appended from any [`std::io::Read`][]
+/// of known length. Async sources can be adapted with something like
+/// `tokio_util::io::SyncIoBridge`.
+///
+/// The tarball isn't complete until [`TarBuilder::finish`][] is called.
+#[cfg(feature = "__tar")]
+pub struct TarBuilder {
+    dest_path: camino::Utf8PathBuf,
+    tar: tar::Builder<TarEncoder>,
+}
+
+#[cfg(feature = "__tar")]
+enum TarEncoder {
+    #[cfg(feature = "compression-gz")]
+    Gzip(flate2::write::GzEncoder<std::fs::File>),
+    #[cfg(feature = "compression-xz")]
+    Xzip(xz2::write::XzEncoder<std::fs::File>),
+    #[cfg(feature = "compression-zstd")]
+    Zstd(zstd::stream::Encoder<'static, std::fs::File>),
+}
+
+#[cfg(feature = "__tar")]
+impl std::io::Write for TarEncoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(feature = "compression-gz")]
+            TarEncoder::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression-xz")]
+            TarEncoder::Xzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression-zstd")]
+            TarEncoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(feature = "compression-gz")]
+            TarEncoder::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "compression-xz")]
+            TarEncoder::Xzip(encoder) => encoder.flush(),
+            #[cfg(feature = "compression-zstd")]
+            TarEncoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(feature = "__tar")]
+impl TarBuilder {
+    /// Starts writing a new .tar.gz file at `dest_path`
+    #[cfg(feature = "compression-gz")]
+    pub fn tar_gz(dest_path: impl AsRef<Utf8Path>) -> crate::error::Result<Self> {
+        Self::new(dest_path.as_ref(), CompressionImpl::Gzip)
+    }
+
+    /// Starts writing a new .tar.xz file at `dest_path`
+    #[cfg(feature = "compression-xz")]
+    pub fn tar_xz(dest_path: impl AsRef<Utf8Path>) -> crate::error::Result<Self> {
+        Self::new(dest_path.as_ref(), CompressionImpl::Xzip)
+    }
+
+    /// Starts writing a new .tar.zstd file at `dest_path`
+    #[cfg(feature = "compression-zstd")]
+    pub fn tar_zstd(dest_path: impl AsRef<Utf8Path>) -> crate::error::Result<Self> {
+        Self::new(dest_path.as_ref(), CompressionImpl::Zstd)
+    }
+
+    fn new(dest_path: &Utf8Path, compression: CompressionImpl) -> crate::error::Result<Self> {
+        #[cfg(feature = "compression-gz")]
+        use flate2::{Compression, GzBuilder};
+
+        let file = std::fs::File::create(dest_path).map_err(|details| {
+            AxoassetError::LocalAssetWriteNewFailed {
+                dest_path: dest_path.to_string(),
+                details,
+            }
+        })?;
+        let encoder = match compression {
+            #[cfg(feature = "compression-gz")]
+            CompressionImpl::Gzip => TarEncoder::Gzip(
+                GzBuilder::new()
+                    .filename(format!("{}.tar", dest_path.file_name().unwrap_or_default()))
+                    .write(file, Compression::new(*DEFAULT_GZ_LEVEL)),
+            ),
+            #[cfg(feature = "compression-xz")]
+            CompressionImpl::Xzip => {
+                TarEncoder::Xzip(xz2::write::XzEncoder::new(file, *DEFAULT_XZ_LEVEL))
+            }
+            #[cfg(feature = "compression-zstd")]
+            CompressionImpl::Zstd => TarEncoder::Zstd(
+                zstd::stream::Encoder::new(file, *DEFAULT_ZSTD_LEVEL).map_err(|details| {
+                    AxoassetError::Compression {
+                        reason: "failed to create zstd encoder".to_string(),
+                        details,
+                    }
+                })?,
+            ),
+        };
+        Ok(Self {
+            dest_path: dest_path.to_owned(),
+            tar: tar::Builder::new(encoder),
+        })
+    }
+
+    /// Appends a file at `path` in the tarball, with exactly `len` bytes read from `contents`
+    ///
+    /// The file gets mode 0644 and the current time as its mtime. It's an error for
+    /// `contents` to produce fewer than `len` bytes, and any bytes after the first
+    /// `len` are ignored.
+    pub fn append_reader(
+        &mut self,
+        path: impl AsRef<Utf8Path>,
+        len: u64,
+        contents: impl std::io::Read,
+    ) -> crate::error::Result<()> {
+        let path = path.as_ref();
+        let mtime = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(len);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        let contents = ExactReader {
+            inner: contents,
+            remaining: len,
+        };
+        self.tar
+            .append_data(&mut header, path, contents)
+            .map_err(|details| AxoassetError::Compression {
+                reason: format!("failed to add {path} to tar: {}", self.dest_path),
+                details,
+            })
+    }
+
+    /// Appends a file at `path` in the tarball with the given contents
+    pub fn append_bytes(
+        &mut self,
+        path: impl AsRef<Utf8Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> crate::error::Result<()> {
+        let contents = contents.as_ref();
+        self.append_reader(path, contents.len() as u64, contents)
+    }
+
+    /// Appends an empty dir at `path` in the tarball
+    ///
+    /// The dir gets mode 0755 and an mtime of 0 (the Unix epoch), so adding it doesn't
+    /// make the tarball depend on when it was built. Its parents aren't added
+    /// automatically, so add them first if nothing else in the tarball implies them
+    /// (some unpacking tools won't create dirs that aren't listed).
+    pub fn add_empty_dir(&mut self, path: impl AsRef<Utf8Path>) -> crate::error::Result<()> {
+        let path = path.as_ref();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        header.set_mtime(0);
+        self.tar
+            .append_data(&mut header, path, std::io::empty())
+            .map_err(|details| AxoassetError::Compression {
+                reason: format!("failed to add {path} to tar: {}", self.dest_path),
+                details,
+            })
+    }
+
+    /// Finishes writing the tarball
+    pub fn finish(self) -> crate::error::Result<()> {
+        let dest_path = self.dest_path;
+        let encoder = self
+            .tar
+            .into_inner()
+            .map_err(|details| AxoassetError::Compression {
+                reason: format!("failed to write tar: {dest_path}"),
+                details,
+            })?;
+        let finished = match encoder {
+            #[cfg(feature = "compression-gz")]
+            TarEncoder::Gzip(encoder) => encoder.finish().map(drop),
+            #[cfg(feature = "compression-xz")]
+            TarEncoder::Xzip(encoder) => encoder.finish().map(drop),
+            #[cfg(feature = "compression-zstd")]
+            TarEncoder::Zstd(encoder) => encoder.finish().map(drop),
+        };
+        finished.map_err(|details| AxoassetError::Compression {
+            reason: format!("failed to write archive: {dest_path}"),
+            details,
+        })
+    }
+}
+
+#[cfg(feature = "__tar")]
+impl std::fmt::Debug for TarBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TarBuilder")
+            .field("dest_path", &self.dest_path)
+            .finish()
+    }
+}
+
+/// A reader that yields exactly `remaining` bytes, erroring if `inner` runs out early
+#[cfg(feature = "__tar")]
+struct ExactReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+#[cfg(feature = "__tar")]
+impl<R: std::io::Read> std::io::Read for ExactReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("contents ended {} bytes early", self.remaining),
+            ));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "__tar")]
 fn open_tarball(
     tarball: &Utf8Path,
     compression: &CompressionImpl,
@@ -203,7 +973,7 @@ fn open_tarball(
     Ok(tarball_bytes)
 }
 
-#[cfg(feature = "compression-tar")]
+#[cfg(feature = "__tar")]
 fn decompress_tarball_bytes(
     source: &[u8],
     tarball_bytes: &mut Vec<u8>,
@@ -211,19 +981,25 @@ fn decompress_tarball_bytes(
 ) -> std::io::Result<()> {
     use std::io::Read;
 
+    #[cfg(feature = "compression-gz")]
     use flate2::read::GzDecoder;
+    #[cfg(feature = "compression-xz")]
     use xz2::read::XzDecoder;
+    #[cfg(feature = "compression-zstd")]
     use zstd::stream::Decoder as ZstdDecoder;
 
     match compression {
+        #[cfg(feature = "compression-gz")]
         CompressionImpl::Gzip => {
             let mut decoder = GzDecoder::new(source);
             decoder.read_to_end(tarball_bytes)?;
         }
+        #[cfg(feature = "compression-xz")]
         CompressionImpl::Xzip => {
             let mut decoder = XzDecoder::new(source);
             decoder.read_to_end(tarball_bytes)?;
         }
+        #[cfg(feature = "compression-zstd")]
         CompressionImpl::Zstd => {
             let mut decoder = ZstdDecoder::new(source)?;
             decoder.read_to_end(tarball_bytes)?;
@@ -232,22 +1008,57 @@ fn decompress_tarball_bytes(
     Ok(())
 }
 
-#[cfg(feature = "compression-tar")]
+#[cfg(feature = "__tar")]
 pub(crate) fn untar_all(
     tarball: &Utf8Path,
     dest_path: &Utf8Path,
     compression: &CompressionImpl,
+    options: &crate::local::ExtractOptions,
 ) -> crate::error::Result<()> {
     let tarball_bytes = open_tarball(tarball, compression)?;
     let mut archive = tar::Archive::new(tarball_bytes.as_slice());
-    archive
-        .unpack(dest_path)
-        .map_err(wrap_decompression_err(tarball.as_str()))?;
+    match &options.on_entry {
+        None => archive.unpack(dest_path),
+        Some(hook) => unpack_tar_with_hook(&tarball_bytes, dest_path, hook),
+    }
+    .map_err(wrap_decompression_err(tarball.as_str()))?;
 
     Ok(())
 }
 
-#[cfg(feature = "compression-tar")]
+/// Unpacks a tarball entry by entry, telling the hook about each one first
+#[cfg(feature = "__tar")]
+fn unpack_tar_with_hook(
+    tarball_bytes: &[u8],
+    dest_path: &Utf8Path,
+    hook: &crate::local::ExtractHook,
+) -> std::io::Result<()> {
+    use crate::local::{ExtractEntry, ExtractHook};
+
+    // The tarball is already in memory, so counting the entries up front is cheap
+    let total = tar::Archive::new(tarball_bytes).entries()?.count();
+
+    std::fs::create_dir_all(dest_path)?;
+    let mut archive = tar::Archive::new(tarball_bytes);
+    for (index, entry) in archive.entries()?.enumerate() {
+        let mut entry = entry?;
+        let path = Utf8PathBuf::from(entry.path()?.to_string_lossy().into_owned());
+        ExtractHook::call(
+            Some(hook),
+            &ExtractEntry {
+                path: &path,
+                size: entry.size(),
+                is_dir: entry.header().entry_type().is_dir(),
+                index,
+                total,
+            },
+        );
+        entry.unpack_in(dest_path)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "__tar")]
 pub(crate) fn untar_file(
     tarball: &Utf8Path,
     filename: &str,
@@ -265,7 +1076,7 @@ pub(crate) fn untar_file(
     }
 }
 
-#[cfg(feature = "compression-tar")]
+#[cfg(feature = "__tar")]
 fn find_tarball_file_bytes(
     mut tarball: tar::Archive<&[u8]>,
     filename: &str,
@@ -289,9 +1100,24 @@ fn find_tarball_file_bytes(
 pub(crate) fn zip_dir(
     src_path: &Utf8Path,
     dest_path: &Utf8Path,
-    with_root: Option<&Utf8Path>,
+    layout: &crate::local::ArchiveLayout,
+    options: &crate::local::ZipOptions,
 ) -> crate::error::Result<()> {
-    zip_dir_impl(src_path, dest_path, with_root).map_err(|details| AxoassetError::Compression {
+    let prefix_dirs = layout.prefix_dirs()?;
+    let provenance = match &options.provenance {
+        Some(provenance) => Some(provenance.render(src_path, provenance.created())?),
+        None => None,
+    };
+    let empty_dirs = empty_dirs(src_path, &options.empty_dirs)?;
+    zip_dir_impl(
+        src_path,
+        dest_path,
+        &prefix_dirs,
+        options,
+        &empty_dirs,
+        provenance.as_deref(),
+    )
+    .map_err(|details| AxoassetError::Compression {
         reason: format!("failed to write zip: {}", dest_path),
         details: details.into(),
     })
@@ -301,8 +1127,12 @@ pub(crate) fn zip_dir(
 pub(crate) fn zip_dir_impl(
     src_path: &Utf8Path,
     dest_path: &Utf8Path,
-    with_root: Option<&Utf8Path>,
+    prefix_dirs: &[String],
+    zip_options: &crate::local::ZipOptions,
+    empty_dirs: &[String],
+    provenance: Option<&str>,
 ) -> zip::result::ZipResult<()> {
+    use crate::local::{ZipCompression, ZipCompressionHook};
     use std::{
         fs::File,
         io::{Read, Write},
@@ -314,18 +1144,18 @@ pub(crate) fn zip_dir_impl(
     // The `zip` crate lacks the conveniences of the `tar` crate so we need to manually
     // walk through all the subdirs of `src_path` and copy each entry. walkdir streamlines
     // that process for us.
-    let walkdir = crate::dirs::walk_dir(src_path);
+    let walkdir = crate::dirs::walk_dir(src_path).order(zip_options.walk_order);
     let it = walkdir.into_iter();
 
     let mut zip = zip::ZipWriter::new(file);
     let options = FileOptions::default().compression_method(CompressionMethod::STORE);
 
-    // If there's a root prefix, add entries for all of its components
-    if let Some(root) = with_root {
-        for path in root.ancestors() {
-            if !path.as_str().is_empty() {
-                zip.add_directory(path.as_str(), options)?;
-            }
+    // If there's a root prefix, add entries for all of its outer components
+    // (the innermost one is the root of the walk)
+    let with_root = prefix_dirs.last().map(Utf8Path::new);
+    if let Some((_, outer_dirs)) = prefix_dirs.split_last() {
+        for dir in outer_dirs {
+            zip.add_directory(dir.as_str(), options)?;
         }
     }
 
@@ -352,7 +1182,14 @@ pub(crate) fn zip_dir_impl(
         // Write file or directory explicitly
         // Some unzip tools unzip files with directory paths correctly, some do not!
         if path.is_file() {
-            zip.start_file(&unix_name, options)?;
+            let method = match ZipCompressionHook::pick(
+                zip_options.compression.as_ref(),
+                Utf8Path::new(&unix_name),
+            ) {
+                ZipCompression::Stored => CompressionMethod::STORE,
+                ZipCompression::Deflated => CompressionMethod::Deflated,
+            };
+            zip.start_file(&unix_name, options.compression_method(method))?;
             let mut f = File::open(path)?;
 
             f.read_to_end(&mut buffer)?;
@@ -364,28 +1201,102 @@ pub(crate) fn zip_dir_impl(
             zip.add_directory(&unix_name, options)?;
         }
     }
+    for dir in empty_dirs {
+        let name = match with_root {
+            Some(root) => format!("{root}/{dir}"),
+            None => dir.clone(),
+        };
+        zip.add_directory(name, options)?;
+    }
+    if let Some(contents) = provenance {
+        let name = match with_root {
+            Some(root) => format!("{root}/{}", crate::local::Provenance::FILE_NAME),
+            None => crate::local::Provenance::FILE_NAME.to_owned(),
+        };
+        zip.start_file(name, options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
     zip.finish()?;
     Ok(())
 }
 
 #[cfg(feature = "compression-zip")]
-pub(crate) fn unzip_all(zipfile: &Utf8Path, dest_path: &Utf8Path) -> crate::error::Result<()> {
+pub(crate) fn unzip_all(
+    zipfile: &Utf8Path,
+    dest_path: &Utf8Path,
+    options: &crate::local::ExtractOptions,
+) -> crate::error::Result<()> {
     use crate::LocalAsset;
 
     let source = LocalAsset::load_bytes(zipfile)?;
-    unzip_all_impl(&source, dest_path).map_err(|details| AxoassetError::Decompression {
+    unzip_all_impl(&source, dest_path, options).map_err(|details| AxoassetError::Decompression {
         origin_path: zipfile.to_string(),
         details: details.into(),
     })
 }
 
 #[cfg(feature = "compression-zip")]
-fn unzip_all_impl(source: &[u8], dest_path: &Utf8Path) -> zip::result::ZipResult<()> {
+fn unzip_all_impl(
+    source: &[u8],
+    dest_path: &Utf8Path,
+    options: &crate::local::ExtractOptions,
+) -> zip::result::ZipResult<()> {
     use std::io::Cursor;
 
     let seekable = Cursor::new(source);
     let mut archive = zip::ZipArchive::new(seekable)?;
-    archive.extract(dest_path)?;
+    match &options.on_entry {
+        None => archive.extract(dest_path)?,
+        Some(hook) => unzip_with_hook(&mut archive, dest_path, hook)?,
+    }
+    Ok(())
+}
+
+/// Extracts a zip entry by entry (the same way [`zip::ZipArchive::extract`][] does),
+/// telling the hook about each one first
+#[cfg(feature = "compression-zip")]
+fn unzip_with_hook<R: std::io::Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    dest_path: &Utf8Path,
+    hook: &crate::local::ExtractHook,
+) -> zip::result::ZipResult<()> {
+    use crate::local::{ExtractEntry, ExtractHook};
+    use std::fs;
+
+    let total = archive.len();
+    for index in 0..total {
+        let mut file = archive.by_index(index)?;
+        let Some(rel_path) = file.enclosed_name().map(|path| path.to_owned()) else {
+            return Err(zip::result::ZipError::InvalidArchive("Invalid file path"));
+        };
+        let path = Utf8PathBuf::from(file.name().to_owned());
+        ExtractHook::call(
+            Some(hook),
+            &ExtractEntry {
+                path: &path,
+                size: file.size(),
+                is_dir: file.is_dir(),
+                index,
+                total,
+            },
+        );
+
+        let out_path = dest_path.as_std_path().join(rel_path);
+        if file.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out_file = fs::File::create(&out_path)?;
+        std::io::copy(&mut file, &mut out_file)?;
+        #[cfg(unix)]
+        if let Some(mode) = file.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&out_path, fs::Permissions::from_mode(mode))?;
+        }
+    }
     Ok(())
 }
 
@@ -0,0 +1,365 @@
+//! Content digests (hashes) and the various ways they get written down
+//!
+//! [`Digest`][] is the one type used by every hashing and verification API in axoasset.
+//! It parses and formats all of the common notations:
+//!
+//! * `sha256:<hex>` (the notation [`Digest`][]'s Display impl uses)
+//! * `<hex>` (algorithm inferred from the length)
+//! * `sha256-<base64>` ([Subresource Integrity](https://www.w3.org/TR/SRI/))
+//!
+//! It can also pick the digest of a file out of a checksum file like `SHA256SUMS`,
+//! see [`Digest::from_checksum_file`][].
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use base64::Engine;
+use sha2::Digest as _;
+
+use crate::error::*;
+use crate::SourceFile;
+
+/// A hashing algorithm a [`Digest`][] can be computed with
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// sha256
+    Sha256,
+    /// sha384
+    Sha384,
+    /// sha512
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// All of the supported algorithms
+    pub const ALL: &'static [DigestAlgorithm] = &[
+        DigestAlgorithm::Sha256,
+        DigestAlgorithm::Sha384,
+        DigestAlgorithm::Sha512,
+    ];
+
+    /// Gets the conventional name of the algorithm (e.g. "sha256")
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha384 => "sha384",
+            DigestAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    /// Gets the algorithm with the given name, ignoring case and dashes
+    /// (so "sha256", "SHA256", and "sha-256" all work)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace('-', "");
+        Self::ALL.iter().copied().find(|algo| algo.name() == name)
+    }
+
+    /// Gets the number of bytes in a digest produced by this algorithm
+    pub fn output_len(self) -> usize {
+        match self {
+            DigestAlgorithm::Sha256 => 32,
+            DigestAlgorithm::Sha384 => 48,
+            DigestAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Gets the algorithm that produces digests of the given number of bytes
+    pub fn from_output_len(len: usize) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algo| algo.output_len() == len)
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The output of a hashing algorithm
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Digest {
+    /// The algorithm that produced the digest
+    algorithm: DigestAlgorithm,
+    /// The raw digest bytes
+    bytes: Vec<u8>,
+}
+
+impl Digest {
+    /// Creates a Digest from the raw output of the given algorithm
+    ///
+    /// Errors if there are the wrong number of bytes for the algorithm.
+    pub fn new(algorithm: DigestAlgorithm, bytes: Vec<u8>) -> Result<Self> {
+        if bytes.len() != algorithm.output_len() {
+            return Err(AxoassetError::DigestParse {
+                digest: hex_encode(&bytes),
+                reason: format!(
+                    "{algorithm} digests are {} bytes, but this one is {}",
+                    algorithm.output_len(),
+                    bytes.len()
+                ),
+            });
+        }
+        Ok(Self { algorithm, bytes })
+    }
+
+    /// Computes the Digest of some bytes
+    pub fn compute(algorithm: DigestAlgorithm, contents: &[u8]) -> Self {
+        let mut hasher = Self::hasher(algorithm);
+        hasher.update(contents);
+        hasher.finish()
+    }
+
+    /// Creates a [`DigestHasher`][] for incrementally computing a Digest
+    pub fn hasher(algorithm: DigestAlgorithm) -> DigestHasher {
+        let inner = match algorithm {
+            DigestAlgorithm::Sha256 => HasherImpl::Sha256(sha2::Sha256::new()),
+            DigestAlgorithm::Sha384 => HasherImpl::Sha384(sha2::Sha384::new()),
+            DigestAlgorithm::Sha512 => HasherImpl::Sha512(sha2::Sha512::new()),
+        };
+        DigestHasher { algorithm, inner }
+    }
+
+    /// Checks whether some bytes hash to this Digest
+    pub fn matches(&self, contents: &[u8]) -> bool {
+        Self::compute(self.algorithm, contents) == *self
+    }
+
+    /// Gets the algorithm that produced the Digest
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Gets the raw bytes of the Digest
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Formats the Digest as bare lowercase hex (e.g. `e3b0c442...`)
+    pub fn to_hex(&self) -> String {
+        hex_encode(&self.bytes)
+    }
+
+    /// Formats the Digest as a Subresource Integrity string (e.g. `sha256-47DEQpj8...`)
+    pub fn to_sri(&self) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&self.bytes);
+        format!("{}-{encoded}", self.algorithm)
+    }
+
+    /// Gets the digest of `filename` from a checksum file, like a `SHA256SUMS` or a
+    /// `.sha256` sidecar published next to an artifact
+    ///
+    /// Both the output of `sha256sum` and friends (`<hex>  name`, or `<hex> *name` in
+    /// binary mode) and of BSD's `shasum --tag` (`SHA256 (name) = <hex>`) are
+    /// understood, and names are compared without any leading directories. A file
+    /// containing nothing but a single digest applies to whatever file it's for.
+    /// Blank lines, `#` comments, and lines for other files are ignored, so the
+    /// digest can be pulled out of a signed (clearsigned) checksum file too.
+    pub fn from_checksum_file(source: &SourceFile, filename: &str) -> Result<Self> {
+        let invalid = |substr: &str, reason: String| AxoassetError::ChecksumFileParse {
+            source_file: source.clone(),
+            span: source.span_for_substr(substr),
+            filename: filename.to_owned(),
+            reason,
+        };
+        let lines = source
+            .contents()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+
+        if let [line] = lines[..] {
+            if !line.contains(char::is_whitespace) {
+                return line
+                    .parse()
+                    .map_err(|_| invalid(line, "invalid digest".to_owned()));
+            }
+        }
+        for line in lines {
+            let bsd = line.split_once(" (").and_then(|(algo, rest)| {
+                let (name, hex) = rest.rsplit_once(") = ")?;
+                Some((name, Some(algo), hex.trim()))
+            });
+            let Some((name, algo, hex)) = bsd.or_else(|| {
+                let (hex, name) = line.split_once(char::is_whitespace)?;
+                let name = name.trim_start();
+                Some((name.strip_prefix('*').unwrap_or(name), None, hex))
+            }) else {
+                continue;
+            };
+            let name = name.trim_start_matches("./");
+            let basename = name.rsplit(['/', '\\']).next().unwrap_or(name);
+            if name != filename && basename != filename {
+                continue;
+            }
+            let digest = match algo {
+                Some(algo) => format!("{algo}:{hex}").parse::<Digest>(),
+                None => hex.parse(),
+            };
+            return digest.map_err(|err| {
+                let reason = match err {
+                    AxoassetError::DigestParse { reason, .. } => reason,
+                    err => err.to_string(),
+                };
+                invalid(hex, format!("invalid digest: {reason}"))
+            });
+        }
+        Err(AxoassetError::ChecksumFileParse {
+            source_file: source.clone(),
+            span: None,
+            filename: filename.to_owned(),
+            reason: "there's no checksum for it in the file".to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.to_hex())
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = AxoassetError;
+
+    fn from_str(digest: &str) -> Result<Self> {
+        let digest = digest.trim();
+        let parse_err = |reason: String| AxoassetError::DigestParse {
+            digest: digest.to_owned(),
+            reason,
+        };
+
+        // algo:hex
+        if let Some((algo, hex)) = digest.split_once(':') {
+            let algorithm = DigestAlgorithm::from_name(algo)
+                .ok_or_else(|| parse_err(format!("unknown algorithm {algo}")))?;
+            let bytes = hex_decode(hex).ok_or_else(|| parse_err("invalid hex".to_owned()))?;
+            return Self::new(algorithm, bytes).map_err(|_| {
+                parse_err(format!(
+                    "expected {} hex digits for {algorithm}",
+                    algorithm.output_len() * 2
+                ))
+            });
+        }
+
+        // SRI: algo-base64
+        if let Some((algo, encoded)) = digest.split_once('-') {
+            if let Some(algorithm) = DigestAlgorithm::from_name(algo) {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .map_err(|e| parse_err(format!("invalid base64: {e}")))?;
+                return Self::new(algorithm, bytes).map_err(|_| {
+                    parse_err(format!(
+                        "expected {} bytes of base64 for {algorithm}",
+                        algorithm.output_len()
+                    ))
+                });
+            }
+        }
+
+        // bare hex
+        let bytes = hex_decode(digest).ok_or_else(|| {
+            parse_err("expected sha256:<hex>, sha256-<base64>, or bare hex".to_owned())
+        })?;
+        let algorithm = DigestAlgorithm::from_output_len(bytes.len()).ok_or_else(|| {
+            parse_err(format!(
+                "{} hex digits doesn't match the length of any known algorithm",
+                digest.len()
+            ))
+        })?;
+        Self::new(algorithm, bytes)
+    }
+}
+
+/// Incrementally computes a [`Digest`][], see [`Digest::hasher`][]
+///
+/// This also implements [`std::io::Write`][] so it can be used with [`std::io::copy`][].
+#[derive(Clone)]
+pub struct DigestHasher {
+    algorithm: DigestAlgorithm,
+    inner: HasherImpl,
+}
+
+#[derive(Clone)]
+enum HasherImpl {
+    Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
+    Sha512(sha2::Sha512),
+}
+
+impl DigestHasher {
+    /// Feeds more bytes into the hasher
+    pub fn update(&mut self, contents: &[u8]) {
+        match &mut self.inner {
+            HasherImpl::Sha256(hasher) => hasher.update(contents),
+            HasherImpl::Sha384(hasher) => hasher.update(contents),
+            HasherImpl::Sha512(hasher) => hasher.update(contents),
+        }
+    }
+
+    /// Gets the Digest of all the bytes fed into the hasher
+    pub fn finish(self) -> Digest {
+        let bytes = match self.inner {
+            HasherImpl::Sha256(hasher) => hasher.finalize().to_vec(),
+            HasherImpl::Sha384(hasher) => hasher.finalize().to_vec(),
+            HasherImpl::Sha512(hasher) => hasher.finalize().to_vec(),
+        };
+        Digest {
+            algorithm: self.algorithm,
+            bytes,
+        }
+    }
+}
+
+impl Write for DigestHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DigestHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestHasher")
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => Some(hex_digit(*hi)? << 4 | hex_digit(*lo)?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|val| val as u8)
+}
@@ -3,15 +3,19 @@
 //! Right now just a wrapper around WalkDirs that does some utf8 conversions and strip_prefixing,
 //! since we always end up doing that.
 
-use crate::error::*;
+use crate::{error::*, local::WalkOrder};
 use camino::{Utf8Path, Utf8PathBuf};
 
 /// Walk through this dir's descendants with `walkdirs`
+///
+/// Each dir's entries are visited sorted by file name, unless
+/// [`AxoassetWalkDir::order`][] says otherwise.
 pub fn walk_dir(dir: impl AsRef<Utf8Path>) -> AxoassetWalkDir {
     let dir = dir.as_ref();
     AxoassetWalkDir {
         root_dir: dir.to_owned(),
         inner: walkdir::WalkDir::new(dir),
+        order: WalkOrder::Sorted,
     }
 }
 
@@ -19,6 +23,7 @@ pub fn walk_dir(dir: impl AsRef<Utf8Path>) -> AxoassetWalkDir {
 pub struct AxoassetWalkDir {
     root_dir: Utf8PathBuf,
     inner: walkdir::WalkDir,
+    order: WalkOrder,
 }
 
 /// Wrapper around [`walkdir::IntoIter`][].
@@ -37,13 +42,24 @@ pub struct AxoassetDirEntry {
     pub entry: walkdir::DirEntry,
 }
 
+impl AxoassetWalkDir {
+    /// Set the order each dir's entries are visited in
+    pub fn order(self, order: WalkOrder) -> Self {
+        Self { order, ..self }
+    }
+}
+
 impl IntoIterator for AxoassetWalkDir {
     type IntoIter = AxoassetIntoIter;
     type Item = Result<AxoassetDirEntry>;
     fn into_iter(self) -> Self::IntoIter {
+        let inner = match self.order {
+            WalkOrder::Sorted => self.inner.sort_by_file_name(),
+            WalkOrder::Filesystem => self.inner,
+        };
         AxoassetIntoIter {
             root_dir: self.root_dir,
-            inner: self.inner.into_iter(),
+            inner: inner.into_iter(),
         }
     }
 }
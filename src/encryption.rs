@@ -0,0 +1,83 @@
+//! Encrypting and decrypting local files with [age](https://age-encryption.org)
+
+use std::io::{Read, Write};
+
+use camino::Utf8Path;
+
+use crate::{error::*, LocalAsset};
+
+pub(crate) fn encrypt_file(
+    src_path: &Utf8Path,
+    dest_path: &Utf8Path,
+    recipients: &[&dyn age::Recipient],
+) -> Result<()> {
+    let encrypt_err = |details: Box<dyn std::error::Error + Send + Sync>| {
+        AxoassetError::LocalAssetEncryptFailed {
+            origin_path: src_path.to_string(),
+            details,
+        }
+    };
+
+    let source = LocalAsset::load_bytes(src_path)?;
+    let encryptor = age::Encryptor::with_recipients(recipients.iter().copied())
+        .map_err(|details| encrypt_err(details.into()))?;
+    let mut encrypted = vec![];
+    let mut writer = encryptor
+        .wrap_output(&mut encrypted)
+        .map_err(|details| encrypt_err(details.into()))?;
+    writer
+        .write_all(&source)
+        .and_then(|_| writer.finish())
+        .map_err(|details| encrypt_err(details.into()))?;
+    std::fs::write(dest_path, encrypted).map_err(|details| {
+        AxoassetError::LocalAssetWriteNewFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        }
+    })
+}
+
+pub(crate) fn decrypt_file(
+    src_path: &Utf8Path,
+    dest_path: &Utf8Path,
+    identities: &[&dyn age::Identity],
+) -> Result<()> {
+    let decrypt_err = |details: Box<dyn std::error::Error + Send + Sync>| {
+        AxoassetError::LocalAssetDecryptFailed {
+            origin_path: src_path.to_string(),
+            details,
+        }
+    };
+
+    let source = LocalAsset::load_bytes(src_path)?;
+    let decryptor =
+        age::Decryptor::new(source.as_slice()).map_err(|details| decrypt_err(details.into()))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().copied())
+        .map_err(|details| decrypt_err(details.into()))?;
+    // Reading fails if the file was truncated or tampered with
+    let mut decrypted = vec![];
+    reader
+        .read_to_end(&mut decrypted)
+        .map_err(|details| decrypt_err(details.into()))?;
+    write_private(dest_path, &decrypted).map_err(|details| {
+        AxoassetError::LocalAssetWriteNewFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        }
+    })
+}
+
+/// Writes a file that only its owner can read, if it's new (on unix)
+fn write_private(dest_path: &Utf8Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(dest_path)?.write_all(contents)
+}
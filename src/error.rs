@@ -6,6 +6,16 @@ use thiserror::Error;
 /// Axoasset Result
 pub type Result<T> = std::result::Result<T, AxoassetError>;
 
+std::thread_local! {
+    /// The display root set by [`AxoassetError::with_display_root`][] on this thread
+    static DISPLAY_ROOT: std::cell::RefCell<Option<camino::Utf8PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// The display root set by [`AxoassetError::set_display_root`][]
+static GLOBAL_DISPLAY_ROOT: std::sync::RwLock<Option<camino::Utf8PathBuf>> =
+    std::sync::RwLock::new(None);
+
 /// The set of errors that can occur when axoasset is used
 #[derive(Debug, Error, Diagnostic)]
 #[non_exhaustive]
@@ -22,6 +32,381 @@ pub enum AxoassetError {
         details: reqwest::Error,
     },
 
+    /// This error indicates that a `file://` url didn't point at a local file.
+    #[error("failed to fetch asset at {origin_path}: it isn't a valid path on this machine")]
+    #[diagnostic(help(
+        "file:// urls need an absolute path and no host, like file:///path/to/file"
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteFileUrlInvalid {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+    },
+
+    /// This error indicates that a `data:` url couldn't be decoded.
+    #[error("failed to decode {origin_path}: {reason}")]
+    #[diagnostic(help("data: urls look like data:image/png;base64,iVBORw0KGgo..."))]
+    #[cfg(feature = "remote")]
+    RemoteDataUrlInvalid {
+        /// The start of the url, used as an identifier
+        origin_path: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    /// This error indicates that a remote asset redirected more times than allowed.
+    #[error("failed to fetch asset at {origin_path}: too many redirects")]
+    #[diagnostic(help("The limit can be changed with NetworkOptions::max_redirects."))]
+    #[cfg(feature = "remote")]
+    RemoteTooManyRedirects {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a request for a remote asset ran out of time.
+    #[error("failed to fetch asset at {origin_path}: the request timed out")]
+    #[diagnostic(help(
+        "The server may be down or unreachable. The limits can be changed with AxoClient::with_timeout and NetworkOptions."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteRequestTimedOut {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a server's TLS certificate has expired (or isn't valid yet).
+    #[error("failed to fetch asset at {origin_path}: the server's certificate has expired or isn't valid yet")]
+    #[diagnostic(help(
+        "If the certificate looks fine in a browser, check that this machine's clock is set correctly."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteTlsCertificateExpired {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a server's TLS certificate is for a different hostname.
+    #[error("failed to fetch asset at {origin_path}: the server's certificate isn't valid for that hostname")]
+    #[diagnostic(help(
+        "Make sure the url uses the server's canonical hostname (and not an IP address or alias)."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteTlsHostnameMismatch {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a server's TLS certificate was issued by an unknown
+    /// certificate authority.
+    #[error("failed to fetch asset at {origin_path}: the server's certificate was issued by an unknown certificate authority")]
+    #[diagnostic(help(
+        "If you're behind a proxy that intercepts https traffic, its certificate authority needs to be trusted (the tls-native-roots feature uses the system's trusted certificates, or add it with TlsOptions::root_certificates)."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteTlsUnknownIssuer {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a certificate authority given in
+    /// [`crate::remote::TlsOptions::root_certificates`][] couldn't be loaded.
+    #[error("failed to load certificate authority {}: {reason}", display_path(.origin_path))]
+    #[diagnostic(help(
+        "Certificates should be PEM files (-----BEGIN CERTIFICATE-----) or DER files."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteTlsCertificateInvalid {
+        /// The path of the certificate file
+        origin_path: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    /// This error indicates that GitHub's API rate limit was exhausted, and we weren't
+    /// willing to wait for it to reset.
+    #[error("GitHub's API rate limit was exceeded fetching {origin_path}, it resets in {wait_secs} seconds (at {reset_at})")]
+    #[diagnostic(help("Authenticated requests get a much higher rate limit."))]
+    #[cfg(feature = "remote")]
+    RemoteGithubRateLimited {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// How many seconds until the limit resets
+        wait_secs: u64,
+        /// When the limit resets, formatted for humans
+        reset_at: String,
+    },
+
+    /// This error indicates that a GitHub API request failed.
+    #[error("GitHub's API refused {url}{}: {message}", .status.map_or(String::new(), |status| format!(" ({status})")))]
+    #[diagnostic(help("Make sure the release exists and the token can write to the repo."))]
+    #[cfg(feature = "github")]
+    RemoteGithubApiFailed {
+        /// The url of the request
+        url: String,
+        /// The HTTP status GitHub answered with, if there was a response
+        status: Option<u16>,
+        /// What GitHub said was wrong
+        message: String,
+    },
+
+    /// This error indicates that a GitHub Release already has a different asset
+    /// with the name of one we were asked to upload.
+    #[error("the release {tag} already has an asset named {name}")]
+    #[diagnostic(help("Set GithubReleaseOptions::overwrite to replace it."))]
+    #[cfg(feature = "github")]
+    RemoteGithubAssetExists {
+        /// The name of the asset
+        name: String,
+        /// The tag of the release
+        tag: String,
+    },
+
+    /// This error indicates that a remote asset was bigger than we were willing to load.
+    #[error("refused to load {origin_path}: it's at least {received} bytes, over the limit of {max_bytes}")]
+    #[diagnostic(help("The limit is set with SourceLoadOptions::max_bytes."))]
+    #[cfg(feature = "remote")]
+    RemoteAssetTooLarge {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The most bytes we were willing to load
+        max_bytes: u64,
+        /// How big we know the asset is so far
+        received: u64,
+    },
+
+    /// This error indicates that the body of a remote asset was cut off partway through.
+    #[error("failed to load {origin_path}: the connection failed after {received} of {} bytes", .total.map_or("?".to_owned(), |total| total.to_string()))]
+    #[diagnostic(help("Check your network connection, or try again later."))]
+    #[cfg(feature = "remote")]
+    RemoteAssetIncomplete {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// How many bytes of the body arrived
+        received: u64,
+        /// How big the body was supposed to be, if the server said
+        total: Option<u64>,
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that a gzipped remote asset couldn't be decompressed.
+    #[error("failed to decompress {origin_path}{}", io_context(.details))]
+    #[diagnostic(help("The file may be corrupt or truncated, or not actually gzipped."))]
+    #[cfg(feature = "remote-gzip")]
+    RemoteAssetDecompressFailed {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: std::io::Error,
+    },
+
+    /// This error indicates that a range request was answered with something other
+    /// than the requested range.
+    #[error(
+        "failed to fetch bytes {range} of {origin_path}, the server responded with status {status}"
+    )]
+    #[diagnostic(help(
+        "The server may not support range requests, or the range may be past the end of the file."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteRangeNotSatisfied {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The range that was requested, like `0-99`
+        range: String,
+        /// The status of the response
+        status: u16,
+    },
+
+    /// This error indicates that a range request was made against a different version
+    /// of a remote asset than the one previously seen.
+    #[error("{origin_path} changed since it was last fetched (expected ETag {expected_etag}, found {found_etag:?})")]
+    #[diagnostic(help("Restart the download from the beginning."))]
+    #[cfg(feature = "remote")]
+    RemoteRangeResourceChanged {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The ETag we expected the asset to have
+        expected_etag: String,
+        /// The ETag the asset has now, if any
+        found_etag: Option<String>,
+    },
+
+    /// This error indicates that a remote asset's contents didn't have the digest
+    /// they were expected to.
+    #[error("{origin_path} has digest {actual}, but it was expected to have digest {expected}")]
+    #[diagnostic(help(
+        "The asset may have been corrupted in transit, or replaced since the digest was recorded."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteAssetChecksumMismatch {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The digest we expected the asset to have
+        expected: crate::Digest,
+        /// The digest the asset actually has
+        actual: crate::Digest,
+    },
+
+    /// This error indicates that a remote asset wasn't the size its entry in a
+    /// [`crate::remote::LockedAssets`][] says it should be.
+    #[error("{origin_path} is {actual} bytes, but it was locked at {expected} bytes")]
+    #[diagnostic(help(
+        "The asset may have been truncated in transit, or replaced since it was locked."
+    ))]
+    #[cfg(feature = "remote")]
+    RemoteAssetSizeMismatch {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The size in bytes we expected the asset to have
+        expected: u64,
+        /// The size in bytes the asset actually has
+        actual: u64,
+    },
+
+    /// This error indicates [`crate::AxoClient::load_locked`][] was asked for an asset
+    /// that isn't in the lockfile, with [`crate::remote::LockMode::Frozen`][].
+    #[error("{origin_path} isn't in the lockfile")]
+    #[diagnostic(help("Load it without LockMode::Frozen to add it to the lockfile."))]
+    #[cfg(feature = "remote")]
+    RemoteAssetNotLocked {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+    },
+
+    /// This error indicates a [`crate::remote::LockedAssets`][] file couldn't be parsed.
+    #[error("couldn't parse lockfile {}: {reason}", display_path(.source_file.origin_path()))]
+    #[cfg(feature = "remote")]
+    LockedAssetsParse {
+        /// The lockfile
+        #[source_code]
+        source_file: crate::SourceFile,
+        /// The part of the lockfile that's wrong
+        #[label]
+        span: Option<miette::SourceSpan>,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    /// This error wraps another remote error with the details of the HTTP exchange
+    /// that caused it, see [`crate::AxoClient::with_debug_capture`][].
+    #[error("{} {} failed", .capture.method, .capture.url)]
+    #[diagnostic(help("{capture}"))]
+    #[cfg(feature = "remote")]
+    RemoteRequestCaptured {
+        /// The captured details of the exchange
+        capture: Box<crate::remote::HttpCapture>,
+        /// The error the exchange failed with
+        #[source]
+        details: Box<AxoassetError>,
+    },
+
+    /// This error indicates that a remote zip's central directory couldn't be read.
+    #[error("failed to list the contents of {origin_path}: {reason}")]
+    #[cfg(feature = "remote")]
+    RemoteZipInvalid {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    /// This error indicates that a limit in [`crate::remote::RateLimitOptions`][]
+    /// isn't a usable rate
+    #[error("invalid rate limit for {limit}: {rate} requests per second")]
+    #[diagnostic(help(
+        "Rate limits should be positive numbers of requests per second, of at least one request a year."
+    ))]
+    #[cfg(feature = "remote")]
+    RateLimitInvalid {
+        /// Which limit it is (`requests_per_second`, or the host it's for)
+        limit: String,
+        /// The rate it was given
+        rate: f64,
+    },
+
+    /// This error indicates that a rule of an [`crate::remote::OriginMap`][] couldn't be parsed.
+    #[error("origin map rule on line {line} is invalid: {rule}")]
+    #[diagnostic(help("rules should look like `https://example.com/* -> file:///mirror/*`"))]
+    #[cfg(feature = "remote")]
+    OriginMapInvalid {
+        /// The line the rule is on (starting at 1)
+        line: usize,
+        /// The rule
+        rule: String,
+    },
+
+    /// This error indicates that a reqwest-middleware middleware rejected a request.
+    #[error("failed to fetch asset at {origin_path}: a middleware rejected the request")]
+    #[cfg(feature = "remote-middleware")]
+    RemoteMiddlewareFailed {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    /// This error indicates that a custom [`crate::remote::RemoteBackend`][] failed
+    /// to answer a request.
+    #[error("failed to fetch asset at {origin_path}")]
+    #[cfg(feature = "remote")]
+    RemoteBackendFailed {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    /// This error indicates that axoasset failed to construct an http client.
+    #[error("failed to initialize the http client")]
+    #[cfg(feature = "remote")]
+    RemoteClientBuildFailed {
+        /// Details of the error
+        #[source]
+        details: reqwest::Error,
+    },
+
+    /// This error indicates that binding to a specific network interface isn't
+    /// supported on this platform.
+    #[error("can't send traffic from network interface {interface} on this platform")]
+    #[diagnostic(help("Binding to an interface is only supported on Linux, Android, and Fuchsia, try binding to the interface's address instead."))]
+    #[cfg(feature = "remote")]
+    RemoteInterfaceUnsupported {
+        /// The interface that was requested
+        interface: String,
+    },
+
+    /// This error indicates a proxy given in [`crate::remote::ProxyOptions`][] (or
+    /// one of the proxy environment variables) wasn't a usable proxy url
+    #[error("invalid proxy {proxy}: {reason}")]
+    #[diagnostic(help("Proxies should be urls like http://proxy.corp:3128."))]
+    #[cfg(feature = "remote")]
+    RemoteProxyInvalid {
+        /// The proxy url (with any password hidden)
+        proxy: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
     /// error indicates that the provided URL did not properly parse and may
     /// either be invalid or an unsupported format.
     #[cfg(feature = "remote")]
@@ -62,6 +447,8 @@ pub enum AxoassetError {
 
     /// This error indicates that the mime type of the requested remote asset
     /// was not an image.
+    ///
+    /// This is no longer returned, since assets can have any mime type.
     #[error("when fetching asset at {origin_path}, the server's response mime type did not indicate an image.")]
     #[diagnostic(help(
         "Please make sure the asset url is correct and that the server is properly configured."
@@ -114,7 +501,7 @@ pub enum AxoassetError {
 
     /// This error indicates that axoasset failed to write a remote asset to the
     /// local filesystem.
-    #[error("failed to write asset at {origin_url} to {dest_path}: Could not find asset at provided path.")]
+    #[error("failed to write asset at {origin_url} to {}{}: Could not find asset at provided path.", display_path(.dest_path), io_context(.details))]
     #[diagnostic(help("Make sure your path is correct and your server is configured correctly."))]
     #[cfg(feature = "remote")]
     RemoteAssetWriteFailed {
@@ -129,7 +516,7 @@ pub enum AxoassetError {
 
     /// This error indicates that axoasset failed to fetch a local asset at the
     /// provided path.
-    #[error("failed to fetch asset at {origin_path}: Could not find asset at provided path.")]
+    #[error("failed to fetch asset at {origin_path}{}: Could not find asset at provided path.", io_context(.details))]
     LocalAssetNotFound {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
@@ -139,7 +526,7 @@ pub enum AxoassetError {
     },
 
     /// This error inidcates that axoasset failed to copy a local asset.
-    #[error("failed to copy asset from {origin_path} to {dest_path}")]
+    #[error("failed to copy asset from {} to {}{}", display_path(.origin_path), display_path(.dest_path), io_context(.details))]
     LocalAssetCopyFailed {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
@@ -150,9 +537,34 @@ pub enum AxoassetError {
         details: std::io::Error,
     },
 
+    /// This error indicates that some of the contents of a directory couldn't be
+    /// copied, see [`crate::local::CopyOptions::skip_errors`][].
+    #[error("failed to copy {} of the contents of {} to {}", .failures.len(), display_path(.origin_path), display_path(.dest_path))]
+    LocalAssetCopyDirIncomplete {
+        /// The directory being copied
+        origin_path: String,
+        /// The directory it was being copied to
+        dest_path: String,
+        /// Every error that was skipped, in the order they happened
+        #[related]
+        failures: Vec<AxoassetError>,
+    },
+
+    /// This error indicates that axoasset failed to rename (move) a local asset.
+    #[error("failed to move asset from {} to {}{}", display_path(.origin_path), display_path(.dest_path), io_context(.details))]
+    LocalAssetRenameFailed {
+        /// The origin path of the asset, used as an identifier
+        origin_path: String,
+        /// The path the asset was being moved to
+        dest_path: String,
+        /// Details of the error
+        #[source]
+        details: std::io::Error,
+    },
+
     /// This error indicates that axoasset failed to read a local asset at the
     /// provided path.
-    #[error("failed to read asset from {origin_path}")]
+    #[error("failed to read asset from {}{}", display_path(.origin_path), io_context(.details))]
     LocalAssetReadFailed {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
@@ -162,7 +574,7 @@ pub enum AxoassetError {
     },
 
     /// This error indicates that axoasset failed to write a local asset.
-    #[error("failed to write asset from {origin_path} to {dest_path}.")]
+    #[error("failed to write asset from {} to {}{}.", display_path(.origin_path), display_path(.dest_path), io_context(.details))]
     LocalAssetWriteFailed {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
@@ -174,7 +586,7 @@ pub enum AxoassetError {
     },
 
     /// This error indicates that axoasset failed to write a new asset
-    #[error("failed to write a new asset to {dest_path}.")]
+    #[error("failed to write a new asset to {}{}.", display_path(.dest_path), io_context(.details))]
     #[diagnostic(help("Make sure you have the correct permissions to create a new file."))]
     LocalAssetWriteNewFailed {
         /// The path where the asset was being written to
@@ -184,8 +596,33 @@ pub enum AxoassetError {
         details: std::io::Error,
     },
 
+    /// This error indicates that a file couldn't be encrypted
+    #[error("failed to encrypt {}", display_path(.origin_path))]
+    #[cfg(feature = "encryption")]
+    LocalAssetEncryptFailed {
+        /// The path of the file being encrypted
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    /// This error indicates that a file couldn't be decrypted
+    #[error("failed to decrypt {}", display_path(.origin_path))]
+    #[diagnostic(help(
+        "Make sure the file was encrypted for one of the given identities, and hasn't been modified since."
+    ))]
+    #[cfg(feature = "encryption")]
+    LocalAssetDecryptFailed {
+        /// The path of the file being decrypted
+        origin_path: String,
+        /// Details of the error
+        #[source]
+        details: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
     /// This error indicates that axoasset failed to create a new directory
-    #[error("failed to write a new directory to {dest_path}.")]
+    #[error("failed to write a new directory to {}{}.", display_path(.dest_path), io_context(.details))]
     #[diagnostic(help("Make sure you have the correct permissions to create a new directory."))]
     LocalAssetDirCreationFailed {
         /// The path where the directory was meant to be created
@@ -196,7 +633,7 @@ pub enum AxoassetError {
     },
 
     /// This error indicates that axoasset failed to delete an asset
-    #[error("failed to delete asset at {dest_path}.")]
+    #[error("failed to delete asset at {}{}.", display_path(.dest_path), io_context(.details))]
     LocalAssetRemoveFailed {
         /// The path that was going to be deleted
         dest_path: String,
@@ -205,16 +642,59 @@ pub enum AxoassetError {
         details: std::io::Error,
     },
 
+    /// This error indicates that a path wasn't removed because it wasn't inside the
+    /// directory it was supposed to be in
+    #[error("refused to delete {} because it isn't inside {}", display_path(.dest_path), display_path(.root))]
+    #[diagnostic(help("This usually means a path was joined incorrectly."))]
+    LocalAssetRemoveOutsideRoot {
+        /// The dir the path was supposed to be in
+        root: camino::Utf8PathBuf,
+        /// The path that was going to be deleted
+        dest_path: camino::Utf8PathBuf,
+    },
+
+    /// This error indicates that writing a file was aborted because it already existed
+    #[error("refused to overwrite existing file {}", display_path(.dest_path))]
+    LocalAssetOverwriteRefused {
+        /// The path that would have been overwritten
+        dest_path: camino::Utf8PathBuf,
+    },
+
+    /// This error indicates that axoasset failed to create a symlink
+    #[error("failed to create a symlink at {} pointing to {}{}", display_path(.link), display_path(.target), io_context(.details))]
+    #[diagnostic(help(
+        "On Windows, creating symlinks to files requires Developer Mode or admin rights."
+    ))]
+    LocalAssetSymlinkFailed {
+        /// The path the link was supposed to point to
+        target: String,
+        /// The path where the link was being created
+        link: String,
+        /// Details of the error
+        #[source]
+        details: std::io::Error,
+    },
+
+    /// This error indicates that axoasset failed to change a file's permissions
+    #[error("failed to change the permissions of {}{}", display_path(.dest_path), io_context(.details))]
+    LocalAssetPermissionsFailed {
+        /// The path whose permissions were being changed
+        dest_path: String,
+        /// Details of the error
+        #[source]
+        details: std::io::Error,
+    },
+
     /// This error indicates that axoasset could not determine the filename for
     /// a local asset.
-    #[error("could not determine file name for asset at {origin_path}")]
+    #[error("could not determine file name for asset at {}", display_path(.origin_path))]
     LocalAssetMissingFilename {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
     },
 
     /// This error indicates we ran into an issue when creating an archive.
-    #[error("failed to create archive: {reason}")]
+    #[error("failed to create archive: {reason}{}", io_context(.details))]
     Compression {
         /// A specific step that failed
         reason: String,
@@ -223,9 +703,13 @@ pub enum AxoassetError {
         details: std::io::Error,
     },
 
-    /// Some error decompressing a tarball/zip
-    #[cfg(any(feature = "compression-zip", feature = "compression-tar"))]
-    #[error("Failed to extract archive {origin_path}")]
+    /// Some error decompressing a tarball/zip/brotli file
+    #[cfg(any(
+        feature = "compression-zip",
+        feature = "__tar",
+        feature = "compression-brotli"
+    ))]
+    #[error("Failed to extract archive {}{}", display_path(.origin_path), io_context(.details))]
     Decompression {
         /// The origin path of the asset, used as an identifier
         origin_path: String,
@@ -234,8 +718,20 @@ pub enum AxoassetError {
         details: std::io::Error,
     },
 
+    /// This error indicates we were asked to unpack an archive but couldn't tell
+    /// what format it was in.
+    #[cfg(any(feature = "compression-zip", feature = "__tar"))]
+    #[error("couldn't determine the archive format of {}", display_path(.origin_path))]
+    #[diagnostic(help(
+        "The archive formats that are enabled are recognized by their file extension."
+    ))]
+    UnknownArchiveFormat {
+        /// The origin path of the archive
+        origin_path: String,
+    },
+
     /// This error indicates we ran `std::env::current_dir` and somehow got an error.
-    #[error("Failed to get the current working directory")]
+    #[error("Failed to get the current working directory{}", io_context(.details))]
     CurrentDir {
         /// Details of the error
         #[source]
@@ -247,9 +743,44 @@ pub enum AxoassetError {
         /// The problematic path
         path: std::path::PathBuf,
     },
+    /// This error indicates that [`crate::ArtifactName::parse`][] was given a name that
+    /// doesn't follow the convention.
+    #[error("{name} isn't a conventional artifact name: {reason}")]
+    #[diagnostic(help("Artifact names look like myapp-v1.2.3-x86_64-unknown-linux-gnu.tar.gz"))]
+    ArtifactNameInvalid {
+        /// The name
+        name: String,
+        /// What was wrong with it
+        reason: String,
+    },
+    /// This error indicates an [`crate::local::ArchiveLayout`][] had a prefix that
+    /// would put files outside of the archive's root.
+    #[error("invalid prefix for archive contents: {prefix}")]
+    #[diagnostic(help(
+        "Prefixes need to be relative paths without any `..` (and root dirs can't contain `/`)."
+    ))]
+    ArchivePrefixInvalid {
+        /// The prefix
+        prefix: camino::Utf8PathBuf,
+    },
+    /// This error indicates that an empty dir to add to an archive wasn't a valid path.
+    #[error("invalid empty dir for archive: {path}")]
+    #[diagnostic(help("Empty dirs need to be relative paths without any `..`."))]
+    ArchiveEmptyDirInvalid {
+        /// The path of the dir
+        path: camino::Utf8PathBuf,
+    },
+    /// This error indicates a directory being archived with a
+    /// [`crate::local::Provenance`][] already had a file where the provenance file goes.
+    #[error("can't add provenance to archive, there's already a file at {}", display_path(.origin_path))]
+    #[diagnostic(help("Rename or remove the file, or don't embed provenance in this archive."))]
+    ArchiveProvenanceConflict {
+        /// The existing file
+        origin_path: String,
+    },
     /// This error indicates we tried to strip_prefix a path that should have been
     /// a descendant of another, but it didn't work.
-    #[error("Child wasn't nested under its parent: {root_dir} => {child_dir}")]
+    #[error("Child wasn't nested under its parent: {} => {}", display_path(.root_dir), display_path(.child_dir))]
     #[diagnostic(help("Are symlinks involved?"))]
     PathNesting {
         /// The root/ancestor dir
@@ -258,7 +789,34 @@ pub enum AxoassetError {
         child_dir: camino::Utf8PathBuf,
     },
 
-    #[error("Failed to find {desired_filename} in an ancestor of {start_dir}")]
+    /// This error indicates a directory contains paths that differ only by case,
+    /// which would collide on case-insensitive filesystems.
+    #[error(
+        "{} contains paths that differ only by case:{}",
+        display_path(.root_dir),
+        format_case_collisions(.collisions)
+    )]
+    #[diagnostic(help(
+        "These will overwrite each other when extracted on Windows or macOS, rename them so they're unique."
+    ))]
+    PathCaseCollisions {
+        /// The dir that was checked
+        root_dir: camino::Utf8PathBuf,
+        /// Each group of paths (relative to root_dir) that collide with each other
+        collisions: Vec<Vec<camino::Utf8PathBuf>>,
+    },
+
+    /// This error indicates a glob pattern couldn't be parsed
+    #[error("invalid glob pattern {pattern}")]
+    GlobPattern {
+        /// The pattern
+        pattern: String,
+        /// Details of the error
+        #[source]
+        details: glob::PatternError,
+    },
+
+    #[error("Failed to find {desired_filename} in an ancestor of {}", display_path(.start_dir))]
     /// This error indicates we failed to find the desired file in an ancestor of the search dir.
     SearchFailed {
         /// The dir we started the search in
@@ -274,7 +832,35 @@ pub enum AxoassetError {
         desired_filename: String,
     },
 
-    #[error("Failed to walk to ancestor of {origin_path}")]
+    /// This error indicates we tried to parse a digest (hash) but it wasn't in a format we
+    /// understand.
+    #[error("failed to parse digest {digest}: {reason}")]
+    #[diagnostic(help("Digests should look like sha256:<hex>, sha256-<base64>, or bare hex."))]
+    DigestParse {
+        /// The digest we were trying to parse
+        digest: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    /// This error indicates a checksum file (like `SHA256SUMS`) couldn't be parsed, or
+    /// didn't have a checksum for the file we wanted, see
+    /// [`crate::Digest::from_checksum_file`][].
+    #[error("couldn't get the checksum for {filename} from {}: {reason}", display_path(.source_file.origin_path()))]
+    ChecksumFileParse {
+        /// The checksum file
+        #[source_code]
+        source_file: crate::SourceFile,
+        /// The part of the checksum file that's wrong
+        #[label]
+        span: Option<miette::SourceSpan>,
+        /// The file we wanted the checksum of
+        filename: String,
+        /// What was wrong with it
+        reason: String,
+    },
+
+    #[error("Failed to walk to ancestor of {}", display_path(.origin_path))]
     /// Walkdir failed to yield an entry
     WalkDirFailed {
         /// The root path we were trying to walkdirs
@@ -284,6 +870,92 @@ pub enum AxoassetError {
         details: walkdir::Error,
     },
 
+    /// This error indicates a [`crate::source::Suggestion`][] couldn't be applied
+    #[error("couldn't apply suggested fix to {}: {reason}", display_path(.source_file.origin_path()))]
+    InvalidSuggestion {
+        /// The SourceFile the suggestion was for
+        #[source_code]
+        source_file: crate::SourceFile,
+        /// Where the suggestion starts
+        #[label]
+        span: miette::SourceSpan,
+        /// Why it couldn't be applied
+        reason: String,
+    },
+
+    /// This error indicates a config file's schema version field wasn't a
+    /// non-negative integer, so we couldn't tell which migrations it needs.
+    #[error("the {key} field of {} must be a non-negative integer", display_path(.source_file.origin_path()))]
+    #[cfg(any(feature = "toml-edit", feature = "json-serde"))]
+    MigrationVersionInvalid {
+        /// The SourceFile we were migrating
+        #[source_code]
+        source_file: crate::SourceFile,
+        /// Roughly where the version field is
+        #[label]
+        span: Option<miette::SourceSpan>,
+        /// The name of the version field
+        key: String,
+    },
+
+    /// This error indicates a config file claims a newer schema version than we
+    /// know how to migrate to.
+    #[error("{} is version {version}, but the newest version this tool understands is {latest_version}", display_path(.origin_path))]
+    #[diagnostic(help("It was probably written by a newer version of this tool, try upgrading."))]
+    #[cfg(any(feature = "toml-edit", feature = "json-serde"))]
+    MigrationVersionTooNew {
+        /// The origin path of the SourceFile
+        origin_path: String,
+        /// The version the file claims to be
+        version: u64,
+        /// The newest version we have a migration for
+        latest_version: u64,
+    },
+
+    /// This error indicates a migration of a config file failed.
+    #[error("failed to migrate {} to version {version}", display_path(.origin_path))]
+    #[cfg(any(feature = "toml-edit", feature = "json-serde"))]
+    MigrationFailed {
+        /// The origin path of the SourceFile
+        origin_path: String,
+        /// The version the failed migration was upgrading to
+        version: u64,
+        /// Details of the error
+        #[source]
+        details: crate::migrate::MigrationError,
+    },
+
+    /// This error indicates we tried to automatically pick a format to deserialize
+    /// a SourceFile with, but couldn't tell what it was.
+    #[error("couldn't determine whether {} is JSON, TOML, or YAML", display_path(.origin_path))]
+    #[diagnostic(help("Try giving the file a .json, .toml, or .yaml extension."))]
+    SourceFormatUnknown {
+        /// The origin path of the SourceFile
+        origin_path: String,
+    },
+
+    /// This error indicates we automatically picked a format to deserialize a
+    /// SourceFile with, but support for that format wasn't enabled.
+    #[error("{} appears to be {format}, but {format} support isn't enabled", display_path(.origin_path))]
+    SourceFormatDisabled {
+        /// The origin path of the SourceFile
+        origin_path: String,
+        /// The format we detected
+        format: crate::source::SourceFormat,
+    },
+
+    /// This error indicates we failed to serialize a value to write it to a config file.
+    #[error("failed to serialize {format} for {}", display_path(.dest_path))]
+    ConfigSerializeFailed {
+        /// The config file we were writing
+        dest_path: String,
+        /// The format we were serializing to
+        format: crate::source::SourceFormat,
+        /// Details of the error
+        #[source]
+        details: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
     /// This error indicates we tried to deserialize some JSON with serde_json
     /// but failed.
     #[cfg(feature = "json-serde")]
@@ -300,6 +972,23 @@ pub enum AxoassetError {
         details: serde_json::Error,
     },
 
+    /// This error indicates we tried to deserialize a CSV (or TSV) record with the
+    /// csv crate but failed.
+    #[cfg(feature = "csv-serde")]
+    #[error("failed to parse CSV")]
+    Csv {
+        /// The SourceFile we were try to parse
+        #[source_code]
+        source: crate::SourceFile,
+        /// The range the error was found on (the field if we know which one, and
+        /// otherwise the record)
+        #[label]
+        span: Option<miette::SourceSpan>,
+        /// Details of the error
+        #[source]
+        details: csv::Error,
+    },
+
     /// This error indicates we tried to deserialize some TOML with toml-rs (serde)
     /// but failed.
     #[cfg(feature = "toml-serde")]
@@ -348,3 +1037,96 @@ pub enum AxoassetError {
         details: serde_yml::Error,
     },
 }
+
+impl AxoassetError {
+    /// Display local paths in error messages relative to `root`, for the whole process
+    ///
+    /// Paths that aren't under the root are displayed as-is. This only affects how
+    /// errors are displayed, the paths stored in them are unchanged. A typical choice
+    /// is the root of the project a tool is operating on, so messages say
+    /// `assets/logo.png` instead of a deep absolute path.
+    ///
+    /// Since this affects every thread, it's meant to be set once at startup. Use
+    /// [`AxoassetError::with_display_root`][] to change it for just a bit of code.
+    pub fn set_display_root(root: impl AsRef<camino::Utf8Path>) {
+        *GLOBAL_DISPLAY_ROOT
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(root.as_ref().to_owned());
+    }
+
+    /// Go back to displaying full paths in error messages, undoing
+    /// [`AxoassetError::set_display_root`][]
+    pub fn clear_display_root() {
+        *GLOBAL_DISPLAY_ROOT
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// Runs `f` with local paths in error messages displayed relative to `root`
+    /// on this thread
+    ///
+    /// This takes precedence over [`AxoassetError::set_display_root`][], and the
+    /// previous root is restored when `f` returns, even if it panics.
+    pub fn with_display_root<T>(root: impl AsRef<camino::Utf8Path>, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<camino::Utf8PathBuf>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                DISPLAY_ROOT.with(|root| *root.borrow_mut() = prev);
+            }
+        }
+
+        let root = root.as_ref().to_owned();
+        let prev = DISPLAY_ROOT.with(|cur| cur.borrow_mut().replace(root));
+        let _restore = Restore(prev);
+        f()
+    }
+}
+
+/// Formats a path for an error message, relative to the display root if it's under it
+fn display_path<P: AsRef<str> + ?Sized>(path: &P) -> String {
+    let path = camino::Utf8Path::new(path.as_ref());
+    let root = DISPLAY_ROOT.with(|root| root.borrow().clone()).or_else(|| {
+        GLOBAL_DISPLAY_ROOT
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    });
+    match root
+        .as_deref()
+        .and_then(|root| path.strip_prefix(root).ok())
+    {
+        Some(rel_path) if rel_path.as_str().is_empty() => ".".to_owned(),
+        Some(rel_path) => rel_path.to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Summarizes an io::Error to tack onto the end of an error message, like
+/// ` (permission denied, os error 13)`
+///
+/// The full error is still available as the source, but plenty of things only print
+/// the top-level message, and "failed to write foo" alone doesn't say why.
+fn io_context(details: &std::io::Error) -> String {
+    match details.raw_os_error() {
+        Some(code) => format!(" ({}, os error {code})", details.kind()),
+        // Errors made up by libraries (rather than the OS) are usually only
+        // described by their message
+        None if details.kind() == std::io::ErrorKind::Other => String::new(),
+        None => format!(" ({})", details.kind()),
+    }
+}
+
+fn format_case_collisions(collisions: &[Vec<camino::Utf8PathBuf>]) -> String {
+    let mut output = String::new();
+    for group in collisions {
+        let group = group
+            .iter()
+            .map(|path| path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        output.push_str("\n  ");
+        output.push_str(&group);
+    }
+    output
+}
@@ -9,19 +9,46 @@
 //! interesting or uniquely engineered; the purpose this library is primarily
 //! to unify and co-locate the logic to make debugging simpler and error handling
 //! more consistent and comprehensive.
+//!
+//! with `default-features = false` only the core is built ([`LocalAsset`][],
+//! [`SourceFile`][] and its span diagnostics, and [`Digest`][]), which needs just a
+//! few small dependencies. everything else is behind features, and `full` turns on
+//! all of them (see Cargo.toml for the few exceptions).
 
-#[cfg(any(feature = "compression-zip", feature = "compression-tar"))]
+pub mod artifact;
+#[cfg(any(
+    feature = "compression-zip",
+    feature = "__tar",
+    feature = "compression-brotli"
+))]
 pub(crate) mod compression;
+pub mod digest;
 pub(crate) mod dirs;
+#[cfg(feature = "encryption")]
+pub(crate) mod encryption;
 pub mod error;
 pub mod local;
+#[cfg(any(feature = "toml-edit", feature = "json-serde"))]
+pub mod migrate;
 #[cfg(feature = "remote")]
 pub mod remote;
 pub mod source;
 pub mod spanned;
+#[cfg(any(feature = "remote", feature = "__tar", feature = "compression-zip"))]
+pub(crate) mod time;
 
+#[cfg(feature = "encryption")]
+pub use age;
+pub use artifact::ArtifactName;
+#[cfg(feature = "__tar")]
+pub use compression::TarBuilder;
+#[cfg(feature = "csv-serde")]
+pub use csv;
+pub use digest::{Digest, DigestAlgorithm};
 pub use error::AxoassetError;
 pub use local::LocalAsset;
+#[cfg(any(feature = "toml-edit", feature = "json-serde"))]
+pub use migrate::Migrator;
 #[cfg(feature = "remote")]
 pub use remote::AxoClient;
 // Simplifies raw access to reqwest without depending on a separate copy
@@ -4,7 +4,834 @@ use std::fs;
 
 use camino::{Utf8Path, Utf8PathBuf};
 
-use crate::{dirs, error::*};
+use crate::{dirs, error::*, Digest, DigestAlgorithm};
+
+/// What to do about a write or copy that would replace an existing file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverwriteDecision {
+    /// Replace the existing file
+    Overwrite,
+    /// Leave the existing file alone, and carry on as if it had been written
+    Skip,
+    /// Leave the existing file alone, and fail with
+    /// [`AxoassetError::LocalAssetOverwriteRefused`][]
+    Abort,
+}
+
+/// A callback deciding whether an existing file should be overwritten, see
+/// [`CopyOptions::on_overwrite`][] and [`WriteOptions::on_overwrite`][]
+///
+/// This lets interactive CLIs ask the user before clobbering anything.
+#[derive(Clone)]
+pub struct OverwriteHook(std::sync::Arc<dyn Fn(&Utf8Path) -> OverwriteDecision + Send + Sync>);
+
+impl OverwriteHook {
+    /// Create an OverwriteHook from a function that's given the path that would be overwritten
+    pub fn new(hook: impl Fn(&Utf8Path) -> OverwriteDecision + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(hook))
+    }
+
+    /// Asks the hook what to do about `dest_path`, if it already exists
+    fn check(hook: Option<&Self>, dest_path: &Utf8Path) -> Result<OverwriteDecision> {
+        match hook {
+            Some(hook) if dest_path.symlink_metadata().is_ok() => match (hook.0)(dest_path) {
+                OverwriteDecision::Abort => Err(AxoassetError::LocalAssetOverwriteRefused {
+                    dest_path: dest_path.to_owned(),
+                }),
+                decision => Ok(decision),
+            },
+            _ => Ok(OverwriteDecision::Overwrite),
+        }
+    }
+}
+
+impl std::fmt::Debug for OverwriteHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OverwriteHook")
+    }
+}
+
+/// Options for copying local assets
+///
+/// The defaults match the behaviour of the plain copy functions like
+/// [`LocalAsset::copy_file_to_file`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CopyOptions {
+    /// If a destination file can't be overwritten because it's marked read-only,
+    /// clear the read-only flag and try again.
+    ///
+    /// This mostly matters on Windows, where re-running a build over the outputs of
+    /// a previous one otherwise fails with "Access is denied".
+    pub clear_readonly: bool,
+    /// If a destination file can't be overwritten because we lack permission to
+    /// write to it, remove it and try again (after trying `clear_readonly`, if enabled).
+    ///
+    /// Removing a file only requires write access to its directory, so this gets past
+    /// stale outputs owned by another user (like root-owned files left behind by a
+    /// container build). The replacement file gets fresh ownership and permissions.
+    pub remove_on_permission_denied: bool,
+    /// When copying a directory, give the copied directories (including empty ones)
+    /// the same modification times as the originals.
+    pub preserve_dir_mtimes: bool,
+    /// When copying a directory, give the copied directories (including empty ones)
+    /// the same permissions as the originals.
+    pub preserve_dir_permissions: bool,
+    /// Consulted before replacing any file that already exists (by default files
+    /// are always overwritten)
+    pub on_overwrite: Option<OverwriteHook>,
+    /// When copying a directory, keep going when something in it fails to copy,
+    /// and report every failure at the end with
+    /// [`AxoassetError::LocalAssetCopyDirIncomplete`][].
+    pub skip_errors: bool,
+    /// The order to copy a directory's contents in
+    pub walk_order: WalkOrder,
+}
+
+/// The order a directory's contents are visited in when copying or archiving it
+///
+/// This decides the order of entries in archives, so it affects their bytes.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Sorted by file name, so the result is the same on every machine and every run
+    #[default]
+    Sorted,
+    /// Whatever order the filesystem lists entries in, which skips sorting each
+    /// directory but can differ between machines (or even between runs)
+    Filesystem,
+}
+
+/// What to do when a file copied into a directory has the same name as one that's
+/// already there, see [`LocalAsset::copy_files_to_dir`][]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum CollisionStrategy {
+    /// Fail with [`AxoassetError::LocalAssetOverwriteRefused`][]
+    #[default]
+    Error,
+    /// Replace the existing file
+    Overwrite,
+    /// Copy the file under a new name with a numeric suffix, like `app-1.tar.gz`
+    Rename,
+    /// Keep whichever of the two files is larger
+    KeepLargest,
+}
+
+/// What happened to one of the files copied by [`LocalAsset::copy_files_to_dir`][]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyOutcome {
+    /// The file was copied to this path, which didn't exist yet
+    Copied(Utf8PathBuf),
+    /// The file was copied to this path, replacing a file with the same name
+    Replaced(Utf8PathBuf),
+    /// The file's name was taken, so it was copied to this path instead
+    Renamed(Utf8PathBuf),
+    /// The file's name was taken by a larger file, so it wasn't copied
+    Skipped,
+}
+
+/// What a [`CopyPlan`][] would do to a path, see [`PlannedChange::action`][]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeAction {
+    /// Create the file or directory, which doesn't exist yet
+    Create,
+    /// Replace what's already there
+    Overwrite,
+    /// Delete what's there (and everything in it, for a directory)
+    Delete,
+    /// Leave it alone
+    Skip,
+}
+
+/// Why a [`CopyPlan`][] would do what it does to a path, see [`PlannedChange::reason`][]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeReason {
+    /// There's nothing at the destination yet
+    Missing,
+    /// The destination file has different contents
+    Changed,
+    /// The destination is a different kind of thing (like a file where the origin has
+    /// a directory, or a symlink)
+    KindChanged,
+    /// The destination already has the same contents
+    Unchanged,
+    /// [`CopyOptions::on_overwrite`][] said to leave the destination alone (or one of
+    /// its parents)
+    OverwriteSkipped,
+    /// The destination has it, but the origin doesn't
+    NotInOrigin,
+}
+
+/// One path that a [`CopyPlan`][] covers, see [`CopyPlan::changes`][]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PlannedChange {
+    /// The path, relative to the origin and destination dirs
+    pub path: Utf8PathBuf,
+    /// Whether it's a directory (in the origin, or in the destination for deletes)
+    pub is_dir: bool,
+    /// What would be done to it
+    pub action: ChangeAction,
+    /// Why
+    pub reason: ChangeReason,
+}
+
+/// Everything copying or syncing a directory would do, see [`LocalAsset::plan_copy_dir`][]
+/// and [`LocalAsset::plan_sync_dir`][]
+///
+/// The plan can be shown to a user (or logged) before it's carried out with
+/// [`CopyPlan::execute`][]. It's a snapshot, so anything that changes on disk in
+/// between isn't accounted for.
+#[derive(Debug, Clone)]
+pub struct CopyPlan {
+    origin_path: Utf8PathBuf,
+    dest_path: Utf8PathBuf,
+    options: CopyOptions,
+    changes: Vec<PlannedChange>,
+}
+
+/// How hard a write should try to make sure its data survives a crash or power loss
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Leave it up to the OS to flush the data to disk whenever it likes
+    #[default]
+    None,
+    /// fsync the written file before returning
+    SyncFile,
+    /// fsync the written file, and then the directory containing it so that the
+    /// file's directory entry is also persisted
+    ///
+    /// Syncing directories isn't possible on Windows, so there this is the same
+    /// as [`Durability::SyncFile`][].
+    SyncFileAndDir,
+}
+
+/// Options for writing local assets
+///
+/// The defaults match the behaviour of the plain write functions like
+/// [`LocalAsset::write_new`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct WriteOptions {
+    /// How hard to try to make sure the write is persisted
+    pub durability: Durability,
+    /// The unix permissions to give the file (like `0o755` for an executable script)
+    ///
+    /// The mode is set on the open file before anything is written to it, so the file is
+    /// never visible with the wrong permissions, and the umask doesn't apply. Files that
+    /// already exist get their permissions changed too. This is ignored on platforms
+    /// without unix permissions.
+    pub mode: Option<u32>,
+    /// Consulted before replacing a file that already exists (by default files
+    /// are always overwritten)
+    pub on_overwrite: Option<OverwriteHook>,
+}
+
+/// How many files [`WriteBatch::commit`][] keeps open waiting to be fsynced
+const MAX_UNSYNCED_FILES: usize = 64;
+
+/// A set of writes that are buffered in memory and all done at once by
+/// [`WriteBatch::commit`][]
+///
+/// This is for writing lots of small files (like a generated site): parent
+/// directories are only created once, and with [`Durability::SyncFile`][] or
+/// [`Durability::SyncFileAndDir`][], every file is written before any of them are
+/// fsynced (and each directory is only fsynced once), so the OS can flush them to
+/// disk together instead of one at a time. Nothing is written until `commit`, and a
+/// batch that's dropped without committing is discarded.
+///
+/// ```no_run
+/// # use axoasset::local::{Durability, WriteBatch, WriteOptions};
+/// let mut options = WriteOptions::default();
+/// options.durability = Durability::SyncFileAndDir;
+/// let mut batch = WriteBatch::new(options);
+/// for page in ["index", "about", "blog"] {
+///     batch.write(format!("public/{page}.html"), format!("<h1>{page}</h1>"));
+/// }
+/// batch.commit()?;
+/// # Ok::<(), axoasset::AxoassetError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    options: WriteOptions,
+    writes: Vec<(Utf8PathBuf, Vec<u8>)>,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch, whose files will be written with the given
+    /// [`WriteOptions`][]
+    pub fn new(options: WriteOptions) -> Self {
+        Self {
+            options,
+            writes: vec![],
+        }
+    }
+
+    /// Queues writing `contents` to `dest_path`, creating any missing parent
+    /// directories
+    ///
+    /// If the same path is written more than once, the last write wins.
+    pub fn write(&mut self, dest_path: impl AsRef<Utf8Path>, contents: impl Into<Vec<u8>>) {
+        self.writes
+            .push((dest_path.as_ref().to_owned(), contents.into()));
+    }
+
+    /// Gets how many writes are queued
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Checks if no writes are queued
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Does every queued write, returning the paths that were written
+    ///
+    /// Writes happen in the order they were queued, and stop at the first one that
+    /// fails (leaving the earlier ones in place). Paths whose
+    /// [`WriteOptions::on_overwrite`][] hook said to skip them aren't returned.
+    ///
+    /// With [`Durability`][] other than `None`, written files are kept open and
+    /// fsynced a few dozen at a time (so a big batch doesn't run out of file
+    /// descriptors), and once every file is written each of their directories is
+    /// fsynced once.
+    pub fn commit(self) -> Result<Vec<Utf8PathBuf>> {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        let WriteBatch { options, writes } = self;
+        // Only the last write to each path matters
+        let mut last_write = BTreeMap::new();
+        for (idx, (dest_path, _)) in writes.iter().enumerate() {
+            last_write.insert(dest_path.clone(), idx);
+        }
+        let sync_all = |unsynced: &mut Vec<(usize, fs::File)>, written: &[Utf8PathBuf]| {
+            for (idx, file) in unsynced.drain(..) {
+                file.sync_all()
+                    .map_err(|details| AxoassetError::LocalAssetWriteNewFailed {
+                        dest_path: written[idx].to_string(),
+                        details,
+                    })?;
+            }
+            Ok::<_, AxoassetError>(())
+        };
+
+        let mut created_dirs = BTreeSet::new();
+        let mut written = vec![];
+        let mut unsynced = Vec::with_capacity(MAX_UNSYNCED_FILES);
+        for (idx, (dest_path, contents)) in writes.into_iter().enumerate() {
+            if last_write[&dest_path] != idx {
+                continue;
+            }
+            let write_err = |details| AxoassetError::LocalAssetWriteNewFailed {
+                dest_path: dest_path.to_string(),
+                details,
+            };
+            if dest_path.file_name().is_none() {
+                return Err(AxoassetError::LocalAssetMissingFilename {
+                    origin_path: dest_path.to_string(),
+                });
+            }
+            let dest_dir = dest_path.parent().unwrap();
+            if created_dirs.insert(dest_dir.to_owned()) {
+                fs::create_dir_all(dest_dir).map_err(write_err)?;
+            }
+            if OverwriteHook::check(options.on_overwrite.as_ref(), &dest_path)?
+                == OverwriteDecision::Overwrite
+            {
+                let file = open_and_write(&dest_path, &contents, &options).map_err(write_err)?;
+                if options.durability != Durability::None {
+                    unsynced.push((written.len(), file));
+                }
+                written.push(dest_path);
+                if unsynced.len() == MAX_UNSYNCED_FILES {
+                    sync_all(&mut unsynced, &written)?;
+                }
+            }
+        }
+        sync_all(&mut unsynced, &written)?;
+
+        if options.durability == Durability::SyncFileAndDir {
+            let mut synced_dirs = BTreeSet::new();
+            for dest_path in &written {
+                let dest_dir = dest_path.parent().unwrap();
+                if synced_dirs.insert(dest_dir) {
+                    sync_dir(dest_dir).map_err(|details| {
+                        AxoassetError::LocalAssetWriteNewFailed {
+                            dest_path: dest_path.to_string(),
+                            details,
+                        }
+                    })?;
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// What [`LocalAsset::symlink_with_options`][] should do on Windows when a file
+/// symlink can't be created (doing so requires Developer Mode or admin rights there)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SymlinkFileFallback {
+    /// Return an error
+    #[default]
+    Error,
+    /// Copy the target to where the link would have been
+    Copy,
+}
+
+/// Options for creating symlinks
+///
+/// The defaults match the behaviour of [`LocalAsset::symlink`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SymlinkOptions {
+    /// What to do on Windows when a symlink to a file can't be created
+    ///
+    /// (Symlinks to directories that can't be created always fall back to junctions.)
+    pub file_fallback: SymlinkFileFallback,
+}
+
+/// Options for removing local assets by glob
+///
+/// The defaults match the behaviour of [`LocalAsset::remove_glob`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RemoveGlobOptions {
+    /// Don't actually remove anything, just report what would have been removed
+    pub dry_run: bool,
+}
+
+/// Where the contents of a directory go inside an archive created from it
+///
+/// This behaves the same for every archive format: entries always use `/` separators,
+/// and every directory in the prefix gets its own entry (parents first), since
+/// some unpacking tools won't create directories that aren't listed.
+///
+/// `Option<impl AsRef<Utf8Path>>` converts into this, with `None` meaning
+/// [`ArchiveLayout::Flat`][] and `Some(path)` meaning [`ArchiveLayout::Prefix`][].
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ArchiveLayout {
+    /// Put the contents directly at the root of the archive
+    #[default]
+    Flat,
+    /// Put the contents in a single top-level directory (like `my-app-1.0.0/`)
+    RootDir(String),
+    /// Put the contents under an arbitrary relative path (like `share/doc/my-app/`)
+    ///
+    /// Leading `./` and trailing `/` are ignored, and `..` isn't allowed.
+    Prefix(Utf8PathBuf),
+}
+
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+impl ArchiveLayout {
+    /// Gets the directories that contents get nested under, outermost first
+    pub(crate) fn prefix_dirs(&self) -> Result<Vec<String>> {
+        let prefix = match self {
+            ArchiveLayout::Flat => return Ok(vec![]),
+            ArchiveLayout::RootDir(dir) => Utf8Path::new(dir),
+            ArchiveLayout::Prefix(prefix) => prefix,
+        };
+        let invalid = || AxoassetError::ArchivePrefixInvalid {
+            prefix: prefix.to_owned(),
+        };
+        let mut components = vec![];
+        for component in prefix.components() {
+            match component {
+                camino::Utf8Component::Normal(name) => components.push(name),
+                camino::Utf8Component::CurDir => {}
+                _ => return Err(invalid()),
+            }
+        }
+        if matches!(self, ArchiveLayout::RootDir(_)) && components.len() > 1 {
+            return Err(invalid());
+        }
+        Ok((1..=components.len())
+            .map(|len| components[..len].join("/"))
+            .collect())
+    }
+}
+
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+impl<P: AsRef<Utf8Path>> From<Option<P>> for ArchiveLayout {
+    fn from(with_root: Option<P>) -> Self {
+        match with_root {
+            Some(root) => ArchiveLayout::Prefix(root.as_ref().to_owned()),
+            None => ArchiveLayout::Flat,
+        }
+    }
+}
+
+/// A provenance file to embed in archives, see [`TarOptions::provenance`][] and
+/// [`ZipOptions::provenance`][]
+///
+/// The file is written to [`Provenance::FILE_NAME`][] next to the rest of the
+/// archive's contents (so under the root dir, if the archive has one), and is a
+/// JSON object like:
+///
+/// ```json
+/// {
+///   "tool": { "name": "my-app", "version": "1.0.0" },
+///   "created": "2024-01-01T00:00:00Z",
+///   "files": {
+///     "bin/my-app": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+///   }
+/// }
+/// ```
+///
+/// `files` lists the digest of every file in the archived directory, keyed by its
+/// `/`-separated path relative to the provenance file.
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Provenance {
+    /// The name of the tool that built the archive
+    pub tool_name: String,
+    /// The version of the tool that built the archive
+    pub tool_version: String,
+    /// When the archive was built
+    ///
+    /// By default this is `$SOURCE_DATE_EPOCH` if it's set (for reproducible
+    /// builds), and otherwise the current time.
+    pub timestamp: Option<std::time::SystemTime>,
+}
+
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+impl Provenance {
+    /// The name of the provenance file inside archives
+    pub const FILE_NAME: &'static str = "provenance.json";
+
+    /// Creates a Provenance for the given tool
+    pub fn new(tool_name: impl Into<String>, tool_version: impl Into<String>) -> Self {
+        Self {
+            tool_name: tool_name.into(),
+            tool_version: tool_version.into(),
+            timestamp: None,
+        }
+    }
+
+    /// Gets the creation time as a unix timestamp
+    pub(crate) fn created(&self) -> u64 {
+        let timestamp = self.timestamp.or_else(|| {
+            let secs = std::env::var("SOURCE_DATE_EPOCH").ok()?.parse().ok()?;
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+        });
+        timestamp
+            .unwrap_or_else(std::time::SystemTime::now)
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Renders the provenance file for archiving `src_dir`, created at `created`
+    pub(crate) fn render(&self, src_dir: &Utf8Path, created: u64) -> Result<String> {
+        let mut files = vec![];
+        for entry in dirs::walk_dir(src_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel_path = entry
+                .rel_path
+                .components()
+                .map(|component| component.as_str())
+                .collect::<Vec<_>>()
+                .join("/");
+            if rel_path == Self::FILE_NAME {
+                return Err(AxoassetError::ArchiveProvenanceConflict {
+                    origin_path: entry.full_path.to_string(),
+                });
+            }
+            let digest = LocalAsset::hash_file(&entry.full_path, DigestAlgorithm::Sha256)?;
+            files.push((rel_path, digest));
+        }
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut rendered = String::from("{\n");
+        rendered.push_str(&format!(
+            "  \"tool\": {{ \"name\": {}, \"version\": {} }},\n",
+            json_string(&self.tool_name),
+            json_string(&self.tool_version),
+        ));
+        rendered.push_str(&format!(
+            "  \"created\": \"{}\",\n",
+            crate::time::UtcTime::from_unix(created).to_rfc3339()
+        ));
+        rendered.push_str("  \"files\": {");
+        for (idx, (path, digest)) in files.iter().enumerate() {
+            let sep = if idx == 0 { "" } else { "," };
+            rendered.push_str(&format!("{sep}\n    {}: \"{digest}\"", json_string(path)));
+        }
+        if !files.is_empty() {
+            rendered.push_str("\n  ");
+        }
+        rendered.push_str("}\n}\n");
+        Ok(rendered)
+    }
+}
+
+/// Quotes a string for JSON
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+fn json_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Options for creating tarballs
+///
+/// The defaults match the behaviour of the plain tar functions like
+/// [`LocalAsset::tar_gz_dir`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct TarOptions {
+    /// Record each file's extended attributes in the tarball (as pax `SCHILY.xattr.*`
+    /// headers, the same way GNU and BSD tar do).
+    ///
+    /// This preserves things like macOS resource forks and Finder metadata, and on
+    /// Linux POSIX ACLs (which are stored as extended attributes). It's off by default
+    /// because extended attributes are rarely intended to be part of an archive and
+    /// make its contents depend on the machine that built it.
+    pub include_xattrs: bool,
+    /// Embed a [`Provenance`][] file in the tarball, recording what built it and the
+    /// digests of everything in it
+    #[cfg(any(feature = "__tar", feature = "compression-zip"))]
+    pub provenance: Option<Provenance>,
+    /// The order to add the directory's contents to the tarball in
+    pub walk_order: WalkOrder,
+    /// Dirs to include in the tarball even if they don't exist (or are empty), relative
+    /// to where the directory's contents go
+    ///
+    /// Their parents are included too. Some installers expect empty dirs (like
+    /// `config/` or `logs/`) to exist after unpacking, and git can't track them.
+    pub empty_dirs: Vec<Utf8PathBuf>,
+}
+
+std::thread_local! {
+    /// The current dir set by [`LocalAsset::with_current_dir`][] on this thread
+    static CURRENT_DIR_OVERRIDE: std::cell::RefCell<Option<Utf8PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// The current dir set by [`LocalAsset::set_current_dir_override`][]
+static GLOBAL_CURRENT_DIR_OVERRIDE: std::sync::RwLock<Option<Utf8PathBuf>> =
+    std::sync::RwLock::new(None);
+
+/// The temp dir set by [`LocalAsset::set_temp_dir`][]
+static TEMP_DIR_OVERRIDE: std::sync::RwLock<Option<Utf8PathBuf>> = std::sync::RwLock::new(None);
+
+/// An entry of an archive that's about to be extracted, see [`ExtractOptions::on_entry`][]
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ExtractEntry<'a> {
+    /// The path of the entry within the archive
+    pub path: &'a Utf8Path,
+    /// The uncompressed size of the entry in bytes
+    pub size: u64,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// The position of the entry in the archive (starting at 0)
+    pub index: usize,
+    /// How many entries the archive has in total
+    pub total: usize,
+}
+
+/// A callback that's told about each entry of an archive as it's extracted
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Clone)]
+pub struct ExtractHook(std::sync::Arc<dyn Fn(&ExtractEntry<'_>) + Send + Sync>);
+
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+impl ExtractHook {
+    /// Create an ExtractHook from a function
+    pub fn new(hook: impl Fn(&ExtractEntry<'_>) + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(hook))
+    }
+
+    /// Tells the hook about an entry
+    pub(crate) fn call(hook: Option<&Self>, entry: &ExtractEntry<'_>) {
+        if let Some(hook) = hook {
+            (hook.0)(entry)
+        }
+    }
+}
+
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+impl std::fmt::Debug for ExtractHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ExtractHook")
+    }
+}
+
+/// Options for extracting archives
+///
+/// The defaults match the behaviour of the plain extraction functions like
+/// [`LocalAsset::untar_gz_all`][].
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ExtractOptions {
+    /// Called with each entry of the archive just before it's extracted
+    ///
+    /// This is useful for showing progress, or keeping an audit trail of exactly
+    /// which paths an archive wrote to.
+    pub on_entry: Option<ExtractHook>,
+}
+
+/// An entry of an archive, as it would be extracted, see [`LocalAsset::plan_unpack_archive`][]
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PlannedEntry {
+    /// The path the entry would be extracted to, relative to the destination dir
+    ///
+    /// This is the path written in the archive, except that tarball entries have any
+    /// leading `/` (or Windows prefix) and `.` components removed, like tar does when
+    /// extracting them.
+    pub path: Utf8PathBuf,
+    /// The size of the entry's contents in bytes (0 for directories and links)
+    pub size: u64,
+    /// What kind of thing the entry would be extracted as
+    pub kind: PlannedEntryKind,
+    /// Why the entry would be refused, if it would be
+    pub rejected: Option<ExtractRejection>,
+}
+
+/// What kind of thing an archive entry is, see [`PlannedEntry::kind`][]
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PlannedEntryKind {
+    /// A regular file
+    File,
+    /// A directory
+    Dir,
+    /// A symlink pointing to `target`
+    Symlink {
+        /// The path the link points to
+        target: Utf8PathBuf,
+    },
+    /// A hard link to `target`, another entry of the archive
+    Hardlink {
+        /// The path of the linked entry
+        target: Utf8PathBuf,
+    },
+    /// Something else, like a device file or a fifo
+    Other,
+}
+
+/// Why an archive entry would be refused when extracting it, see [`PlannedEntry::rejected`][]
+///
+/// Tarballs skip entries like these, while zips fail to extract at all if they
+/// contain any.
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtractRejection {
+    /// The path uses `..` to climb out of the destination
+    OutsideDest,
+    /// The path is absolute
+    ///
+    /// Only zips refuse these. tar strips the leading `/` and extracts the entry
+    /// inside the destination, so tarball entries are planned at that path instead.
+    AbsolutePath,
+}
+
+/// How a file is compressed within a zip, see [`ZipOptions::compression`][]
+#[cfg(feature = "compression-zip")]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ZipCompression {
+    /// Store the file as-is
+    #[default]
+    Stored,
+    /// Compress the file with deflate
+    Deflated,
+}
+
+/// A callback picking the [`ZipCompression`][] for each file in a zip, given the
+/// file's path within the archive
+#[cfg(feature = "compression-zip")]
+#[derive(Clone)]
+pub struct ZipCompressionHook(std::sync::Arc<dyn Fn(&Utf8Path) -> ZipCompression + Send + Sync>);
+
+#[cfg(feature = "compression-zip")]
+impl ZipCompressionHook {
+    /// Create a ZipCompressionHook from a function
+    pub fn new(hook: impl Fn(&Utf8Path) -> ZipCompression + Send + Sync + 'static) -> Self {
+        Self(std::sync::Arc::new(hook))
+    }
+
+    /// A ZipCompressionHook that stores files whose extension says they're already
+    /// compressed (images, fonts, media, archives...) and deflates everything else
+    ///
+    /// Compressing those files again wastes time and usually makes them slightly bigger.
+    pub fn skip_compressed() -> Self {
+        const COMPRESSED_EXTENSIONS: &[&str] = &[
+            "7z", "avif", "br", "bz2", "gif", "gz", "heic", "jar", "jpeg", "jpg", "lz4", "lzma",
+            "m4a", "mkv", "mov", "mp3", "mp4", "ogg", "opus", "png", "rar", "tbz", "tgz", "txz",
+            "webm", "webp", "whl", "woff", "woff2", "xz", "zip", "zst", "zstd",
+        ];
+        Self::new(|path| {
+            let compressed = path.extension().is_some_and(|ext| {
+                COMPRESSED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+            });
+            if compressed {
+                ZipCompression::Stored
+            } else {
+                ZipCompression::Deflated
+            }
+        })
+    }
+
+    /// Asks the hook how to compress the file at `path` within the archive
+    pub(crate) fn pick(hook: Option<&Self>, path: &Utf8Path) -> ZipCompression {
+        hook.map(|hook| (hook.0)(path)).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "compression-zip")]
+impl std::fmt::Debug for ZipCompressionHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ZipCompressionHook")
+    }
+}
+
+/// Options for creating zips
+///
+/// The defaults match the behaviour of [`LocalAsset::zip_dir`][].
+#[cfg(feature = "compression-zip")]
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ZipOptions {
+    /// Pick how each file is compressed (by default every file is stored uncompressed)
+    ///
+    /// [`ZipCompressionHook::skip_compressed`][] is a good choice for zips with a
+    /// mix of text and already-compressed files.
+    pub compression: Option<ZipCompressionHook>,
+    /// Embed a [`Provenance`][] file in the zip, recording what built it and the
+    /// digests of everything in it
+    pub provenance: Option<Provenance>,
+    /// The order to add the directory's contents to the zip in
+    pub walk_order: WalkOrder,
+    /// Dirs to include in the zip even if they don't exist (or are empty), see
+    /// [`TarOptions::empty_dirs`][]
+    pub empty_dirs: Vec<Utf8PathBuf>,
+}
 
 /// A local asset contains a path on the local filesystem and its contents
 #[derive(Debug)]
@@ -133,13 +960,32 @@ impl LocalAsset {
 
     /// Writes an asset to a path on the local filesystem
     pub fn write_new(contents: &str, dest_path: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+        LocalAsset::write_new_with_options(contents, dest_path, &WriteOptions::default())
+    }
+
+    /// Writes an asset and all of its parent directories on the local filesystem.
+    pub fn write_new_all(contents: &str, dest_path: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+        LocalAsset::write_new_all_with_options(contents, dest_path, &WriteOptions::default())
+    }
+
+    /// Writes an asset to a path on the local filesystem, with the given [`WriteOptions`][]
+    pub fn write_new_with_options(
+        contents: impl AsRef<[u8]>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &WriteOptions,
+    ) -> Result<Utf8PathBuf> {
         let dest_path = dest_path.as_ref();
         if dest_path.file_name().is_none() {
             return Err(AxoassetError::LocalAssetMissingFilename {
                 origin_path: dest_path.to_string(),
             });
         }
-        match fs::write(dest_path, contents) {
+        if OverwriteHook::check(options.on_overwrite.as_ref(), dest_path)?
+            == OverwriteDecision::Skip
+        {
+            return Ok(dest_path.into());
+        }
+        match write_file(dest_path, contents.as_ref(), options) {
             Ok(_) => Ok(dest_path.into()),
             Err(details) => Err(AxoassetError::LocalAssetWriteNewFailed {
                 dest_path: dest_path.to_string(),
@@ -148,8 +994,13 @@ impl LocalAsset {
         }
     }
 
-    /// Writes an asset and all of its parent directories on the local filesystem.
-    pub fn write_new_all(contents: &str, dest_path: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+    /// Writes an asset and all of its parent directories on the local filesystem,
+    /// with the given [`WriteOptions`][]
+    pub fn write_new_all_with_options(
+        contents: impl AsRef<[u8]>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &WriteOptions,
+    ) -> Result<Utf8PathBuf> {
         let dest_path = dest_path.as_ref();
         if dest_path.file_name().is_none() {
             return Err(AxoassetError::LocalAssetMissingFilename {
@@ -157,16 +1008,81 @@ impl LocalAsset {
             });
         }
         let dest_dir = dest_path.parent().unwrap();
-        match fs::create_dir_all(dest_dir) {
-            Ok(_) => (),
-            Err(details) => {
-                return Err(AxoassetError::LocalAssetWriteNewFailed {
-                    dest_path: dest_path.to_string(),
-                    details,
+        if let Err(details) = fs::create_dir_all(dest_dir) {
+            return Err(AxoassetError::LocalAssetWriteNewFailed {
+                dest_path: dest_path.to_string(),
+                details,
+            });
+        }
+        LocalAsset::write_new_with_options(contents, dest_path, options)
+    }
+
+    /// Serializes `value` and writes it to a config file, as JSON, TOML, or YAML
+    /// depending on the extension of `dest_path`
+    ///
+    /// If `dest_path` is an existing TOML file (and the `toml-edit` feature is enabled),
+    /// the new value is merged into it as described in [`SourceFile::merge_toml`][crate::SourceFile::merge_toml],
+    /// so comments and formatting of the existing file are preserved. Note that this
+    /// means keys that are only in the existing file are kept. JSON and YAML files
+    /// are always rewritten from scratch.
+    #[cfg(any(feature = "json-serde", feature = "toml-serde", feature = "yaml-serde"))]
+    pub fn write_config<T: serde::Serialize>(
+        dest_path: impl AsRef<Utf8Path>,
+        value: &T,
+    ) -> Result<Utf8PathBuf> {
+        use crate::source::SourceFormat;
+
+        let dest_path = dest_path.as_ref();
+        let format = dest_path
+            .extension()
+            .and_then(SourceFormat::from_extension)
+            .ok_or_else(|| AxoassetError::SourceFormatUnknown {
+                origin_path: dest_path.to_string(),
+            })?;
+        let serialize_err = |details: Box<dyn std::error::Error + Send + Sync>| {
+            AxoassetError::ConfigSerializeFailed {
+                dest_path: dest_path.to_string(),
+                format,
+                details,
+            }
+        };
+        let contents = match format {
+            #[cfg(feature = "json-serde")]
+            SourceFormat::Json => {
+                let mut json = serde_json::to_string_pretty(value)
+                    .map_err(|details| serialize_err(details.into()))?;
+                json.push('\n');
+                json
+            }
+            #[cfg(feature = "toml-serde")]
+            SourceFormat::Toml => {
+                let toml =
+                    toml::to_string(value).map_err(|details| serialize_err(details.into()))?;
+                // Merge into an existing file so its comments survive
+                #[cfg(feature = "toml-edit")]
+                let toml = if dest_path.is_file() {
+                    let overlay = toml
+                        .parse::<toml_edit::DocumentMut>()
+                        .map_err(|details| serialize_err(details.into()))?;
+                    crate::SourceFile::load_local(dest_path)?.merge_toml(&overlay)?
+                } else {
+                    toml
+                };
+                toml
+            }
+            #[cfg(feature = "yaml-serde")]
+            SourceFormat::Yaml => {
+                serde_yml::to_string(value).map_err(|details| serialize_err(details.into()))?
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(AxoassetError::SourceFormatDisabled {
+                    origin_path: dest_path.to_string(),
+                    format,
                 })
             }
-        }
-        LocalAsset::write_new(contents, dest_path)
+        };
+        Self::write_new(&contents, dest_path)
     }
 
     /// Creates a new directory
@@ -181,6 +1097,35 @@ impl LocalAsset {
         }
     }
 
+    /// Creates a new directory, or does nothing if there's already one there
+    ///
+    /// Unlike [`LocalAsset::create_dir`][] this only fails if the directory really
+    /// can't be made: something other than a directory is in the way, the parent
+    /// doesn't exist, or permission is denied. A symlink to a directory counts as a
+    /// directory.
+    pub fn ensure_dir(dest: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+        let dest_path = dest.as_ref();
+        let details = match fs::create_dir(dest_path) {
+            Ok(_) => return Ok(dest_path.into()),
+            // Checking afterwards (rather than before) also covers someone else
+            // creating it at the same time
+            Err(details) if details.kind() == std::io::ErrorKind::AlreadyExists => {
+                if dest_path.is_dir() {
+                    return Ok(dest_path.into());
+                }
+                std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    "something other than a directory is already there",
+                )
+            }
+            Err(details) => details,
+        };
+        Err(AxoassetError::LocalAssetDirCreationFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        })
+    }
+
     /// Creates a new directory, including all parent directories
     pub fn create_dir_all(dest: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
         let dest_path = dest.as_ref();
@@ -236,6 +1181,115 @@ impl LocalAsset {
         Ok(())
     }
 
+    /// Removes a directory and all of its contents, but only if it's strictly inside
+    /// `root`
+    ///
+    /// Both paths are resolved (following symlinks and `..`) before they're compared,
+    /// so a bad path join (like an empty or `..`-laden dir name) can't escape `root`.
+    /// `root` itself is never removed. A symlink inside `root` is removed without
+    /// touching what it points to, and like [`LocalAsset::remove_dir_all`][] it's
+    /// fine for `target` not to exist.
+    ///
+    /// This is intended for "clean" commands, where a mistake deletes someone's home
+    /// directory.
+    pub fn remove_dir_all_within(
+        root: impl AsRef<Utf8Path>,
+        target: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        let root = root.as_ref();
+        let target = target.as_ref();
+        let remove_err = |details| AxoassetError::LocalAssetRemoveFailed {
+            dest_path: target.to_string(),
+            details,
+        };
+        let Ok(metadata) = fs::symlink_metadata(target) else {
+            return Ok(());
+        };
+        let canonical_root = root.canonicalize_utf8().map_err(remove_err)?;
+        // Resolve everything but the last component, so a symlink is checked (and
+        // removed) as itself rather than as what it points to
+        let resolved = match (target.parent(), target.file_name()) {
+            (Some(parent), Some(name)) => {
+                let parent = if parent.as_str().is_empty() {
+                    Utf8Path::new(".")
+                } else {
+                    parent
+                };
+                parent.canonicalize_utf8().map_err(remove_err)?.join(name)
+            }
+            _ => target.canonicalize_utf8().map_err(remove_err)?,
+        };
+        let inside = resolved
+            .strip_prefix(&canonical_root)
+            .is_ok_and(|rel| !rel.as_str().is_empty());
+        if !inside {
+            return Err(AxoassetError::LocalAssetRemoveOutsideRoot {
+                root: root.to_owned(),
+                dest_path: target.to_owned(),
+            });
+        }
+
+        let removed = if metadata.is_dir() {
+            fs::remove_dir_all(&resolved)
+        } else {
+            fs::remove_file(&resolved)
+        };
+        removed.map_err(remove_err)
+    }
+
+    /// Removes every file and directory matching a glob pattern (like `target/*.tar.gz`)
+    ///
+    /// Directories are removed along with all of their contents. The paths that were
+    /// removed are returned in sorted order (paths inside of a removed directory
+    /// aren't listed separately).
+    pub fn remove_glob(pattern: &str) -> Result<Vec<Utf8PathBuf>> {
+        Self::remove_glob_with_options(pattern, &RemoveGlobOptions::default())
+    }
+
+    /// Removes every file and directory matching a glob pattern, with the given
+    /// [`RemoveGlobOptions`][]
+    pub fn remove_glob_with_options(
+        pattern: &str,
+        options: &RemoveGlobOptions,
+    ) -> Result<Vec<Utf8PathBuf>> {
+        let paths = glob::glob(pattern).map_err(|details| AxoassetError::GlobPattern {
+            pattern: pattern.to_owned(),
+            details,
+        })?;
+        let mut matches = vec![];
+        for path in paths {
+            let path = path.map_err(|e| AxoassetError::LocalAssetReadFailed {
+                origin_path: e.path().display().to_string(),
+                details: std::io::Error::new(e.error().kind(), e.to_string()),
+            })?;
+            let path = Utf8PathBuf::from_path_buf(path)
+                .map_err(|path| AxoassetError::Utf8Path { path })?;
+            matches.push(path);
+        }
+        matches.sort();
+
+        let mut removed: Vec<Utf8PathBuf> = vec![];
+        for path in matches {
+            // Sorting puts dirs right before their contents
+            if removed.last().is_some_and(|dir| path.starts_with(dir)) {
+                continue;
+            }
+            if !options.dry_run {
+                let is_dir = path
+                    .symlink_metadata()
+                    .map(|meta| meta.is_dir())
+                    .unwrap_or(false);
+                if is_dir {
+                    Self::remove_dir_all(&path)?;
+                } else {
+                    Self::remove_file(&path)?;
+                }
+            }
+            removed.push(path);
+        }
+        Ok(removed)
+    }
+
     /// Copies an asset from one location on the local filesystem to the given directory
     ///
     /// The destination will use the same file name as the origin has.
@@ -256,6 +1310,78 @@ impl LocalAsset {
         Ok(dest_path)
     }
 
+    /// Copies many files into one directory, resolving files with the same name
+    /// with the given [`CollisionStrategy`][]
+    ///
+    /// Files collide with files already in `dest_dir` as well as with ones copied
+    /// earlier in the same call. Returns what happened to each file, in order, so
+    /// any renames can be reported.
+    pub fn copy_files_to_dir(
+        origin_paths: impl IntoIterator<Item = impl AsRef<Utf8Path>>,
+        dest_dir: impl AsRef<Utf8Path>,
+        strategy: CollisionStrategy,
+    ) -> Result<Vec<(Utf8PathBuf, CopyOutcome)>> {
+        let dest_dir = dest_dir.as_ref();
+        let file_len = |path: &Utf8Path| {
+            fs::metadata(path)
+                .map(|meta| meta.len())
+                .map_err(|details| AxoassetError::LocalAssetReadFailed {
+                    origin_path: path.to_string(),
+                    details,
+                })
+        };
+
+        let mut outcomes = vec![];
+        for origin_path in origin_paths {
+            let origin_path = origin_path.as_ref();
+            let filename = filename(origin_path)?;
+            let dest_path = dest_dir.join(&filename);
+            let outcome = if !dest_path.exists() {
+                CopyOutcome::Copied(dest_path)
+            } else {
+                match strategy {
+                    CollisionStrategy::Error => {
+                        return Err(AxoassetError::LocalAssetOverwriteRefused { dest_path })
+                    }
+                    CollisionStrategy::Overwrite => CopyOutcome::Replaced(dest_path),
+                    CollisionStrategy::Rename => {
+                        // Put the suffix before all the extensions, so app.tar.gz
+                        // becomes app-1.tar.gz
+                        let split = filename
+                            .char_indices()
+                            .skip(1)
+                            .find(|&(_, c)| c == '.')
+                            .map(|(idx, _)| idx)
+                            .unwrap_or(filename.len());
+                        let (stem, extensions) = filename.split_at(split);
+                        let renamed = (1..)
+                            .map(|n| dest_dir.join(format!("{stem}-{n}{extensions}")))
+                            .find(|path| !path.exists())
+                            .expect("ran out of numbers");
+                        CopyOutcome::Renamed(renamed)
+                    }
+                    CollisionStrategy::KeepLargest => {
+                        if file_len(origin_path)? > file_len(&dest_path)? {
+                            CopyOutcome::Replaced(dest_path)
+                        } else {
+                            CopyOutcome::Skipped
+                        }
+                    }
+                }
+            };
+            match &outcome {
+                CopyOutcome::Copied(dest_path)
+                | CopyOutcome::Replaced(dest_path)
+                | CopyOutcome::Renamed(dest_path) => {
+                    Self::copy_file_to_file(origin_path, dest_path)?;
+                }
+                CopyOutcome::Skipped => {}
+            }
+            outcomes.push((origin_path.to_owned(), outcome));
+        }
+        Ok(outcomes)
+    }
+
     /// Copies an asset from one location on the local filesystem to another
     ///
     /// Both paths are assumed to be file names.
@@ -263,11 +1389,46 @@ impl LocalAsset {
         origin_path: impl AsRef<Utf8Path>,
         dest_path: impl AsRef<Utf8Path>,
     ) -> Result<()> {
-        let origin_path = origin_path.as_ref();
-        let dest_path = dest_path.as_ref();
+        Self::copy_file_to_file_with_options(origin_path, dest_path, &CopyOptions::default())
+    }
 
-        fs::copy(origin_path, dest_path).map_err(|e| AxoassetError::LocalAssetCopyFailed {
-            origin_path: origin_path.to_string(),
+    /// Copies an asset from one location on the local filesystem to another,
+    /// with the given [`CopyOptions`][]
+    ///
+    /// Both paths are assumed to be file names.
+    pub fn copy_file_to_file_with_options(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &CopyOptions,
+    ) -> Result<()> {
+        let origin_path = origin_path.as_ref();
+        let dest_path = dest_path.as_ref();
+
+        if OverwriteHook::check(options.on_overwrite.as_ref(), dest_path)?
+            == OverwriteDecision::Skip
+        {
+            return Ok(());
+        }
+        let mut result = fs::copy(origin_path, dest_path);
+        if let Err(e) = &result {
+            if options.clear_readonly
+                && e.kind() == std::io::ErrorKind::PermissionDenied
+                && clear_readonly(dest_path)
+            {
+                result = fs::copy(origin_path, dest_path);
+            }
+        }
+        if let Err(e) = &result {
+            if options.remove_on_permission_denied
+                && e.kind() == std::io::ErrorKind::PermissionDenied
+                && dest_path.is_file()
+                && fs::remove_file(dest_path).is_ok()
+            {
+                result = fs::copy(origin_path, dest_path);
+            }
+        }
+        result.map_err(|e| AxoassetError::LocalAssetCopyFailed {
+            origin_path: origin_path.to_string(),
             dest_path: dest_path.to_string(),
             details: e,
         })?;
@@ -275,6 +1436,37 @@ impl LocalAsset {
         Ok(())
     }
 
+    /// Moves a file or directory from `origin_path` to `dest_path`
+    ///
+    /// Like [`std::fs::rename`][] this replaces an existing file at `dest_path`. Files
+    /// can't be renamed across filesystems, so in that case the file is copied and the
+    /// original removed instead. (Directories aren't copied, and fail to move across
+    /// filesystems.)
+    pub fn rename(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        let origin_path = origin_path.as_ref();
+        let dest_path = dest_path.as_ref();
+        let rename_err = |details| AxoassetError::LocalAssetRenameFailed {
+            origin_path: origin_path.to_string(),
+            dest_path: dest_path.to_string(),
+            details,
+        };
+
+        match fs::rename(origin_path, dest_path) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices && origin_path.is_file() => {
+                if let Err(e) = fs::copy(origin_path, dest_path) {
+                    // Don't leave a partial copy behind
+                    let _ = fs::remove_file(dest_path);
+                    return Err(rename_err(e));
+                }
+                fs::remove_file(origin_path).map_err(rename_err)
+            }
+            result => result.map_err(rename_err),
+        }
+    }
+
     /// Recursively copies a directory from one location to the given directory
     ///
     /// The destination will use the same dir name as the origin has, so
@@ -303,21 +1495,55 @@ impl LocalAsset {
     pub fn copy_dir_to_dir(
         origin_path: impl AsRef<Utf8Path>,
         dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        Self::copy_dir_to_dir_with_options(origin_path, dest_path, &CopyOptions::default())
+    }
+
+    /// Recursively copies a directory from one location to another, with the
+    /// given [`CopyOptions`][]
+    ///
+    /// Both paths are assumed to be the names of the directory being copied
+    /// (i.e. dest_path is not the parent dir).
+    pub fn copy_dir_to_dir_with_options(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &CopyOptions,
     ) -> Result<()> {
         let origin_path = origin_path.as_ref();
         let dest_path = dest_path.as_ref();
 
-        for entry in dirs::walk_dir(origin_path) {
-            let entry = entry?;
+        let mut failures = vec![];
+        let mut check = |result: Result<()>| match result {
+            Err(err) if options.skip_errors => {
+                failures.push(err);
+                Ok(())
+            }
+            result => result,
+        };
+
+        let mut copied_dirs = vec![];
+        for entry in dirs::walk_dir(origin_path).order(options.walk_order) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    check(Err(err))?;
+                    continue;
+                }
+            };
             let from = &entry.full_path;
             let to = dest_path.join(&entry.rel_path);
 
             if entry.file_type().is_dir() {
                 // create directories (even empty ones!)
-                LocalAsset::create_dir(to)?;
+                check(LocalAsset::create_dir(&to).map(|_| ()))?;
+                if options.preserve_dir_mtimes || options.preserve_dir_permissions {
+                    copied_dirs.push((from.clone(), to));
+                }
             } else if entry.file_type().is_file() {
                 // copy files
-                LocalAsset::copy_file_to_file(from, to)?;
+                check(LocalAsset::copy_file_to_file_with_options(
+                    from, to, options,
+                ))?;
             } else {
                 // other kinds of file presumed to be symlinks which we don't handle
                 debug_assert!(
@@ -326,11 +1552,375 @@ impl LocalAsset {
                 );
             }
         }
+
+        // Copying the contents of a dir changes its mtime (and restrictive permissions
+        // could stop us copying the contents), so only fix up the dirs at the end,
+        // children before parents.
+        for (from, to) in copied_dirs.iter().rev() {
+            check(copy_dir_metadata(from, to, options).map_err(|details| {
+                AxoassetError::LocalAssetCopyFailed {
+                    origin_path: from.to_string(),
+                    dest_path: to.to_string(),
+                    details,
+                }
+            }))?;
+        }
+
+        if !failures.is_empty() {
+            return Err(AxoassetError::LocalAssetCopyDirIncomplete {
+                origin_path: origin_path.to_string(),
+                dest_path: dest_path.to_string(),
+                failures,
+            });
+        }
+        Ok(())
+    }
+
+    /// Works out what [`LocalAsset::copy_dir_to_dir_with_options`][] would do, as a
+    /// [`CopyPlan`][] that can be reviewed and then executed
+    ///
+    /// Every file and directory in the origin gets a [`PlannedChange`][] (in the
+    /// order given by [`CopyOptions::walk_order`][]), except symlinks, which aren't
+    /// copied. Unlike a plain copy, files whose contents are already the same at the
+    /// destination are skipped, and [`CopyOptions::on_overwrite`][] is consulted now
+    /// rather than during execution. The destination dir itself isn't listed, and is
+    /// created if it's missing.
+    pub fn plan_copy_dir(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &CopyOptions,
+    ) -> Result<CopyPlan> {
+        CopyPlan::new(origin_path.as_ref(), dest_path.as_ref(), options, false)
+    }
+
+    /// Works out what making `dest_path` a copy of `origin_path` would do, as a
+    /// [`CopyPlan`][] that can be reviewed and then executed
+    ///
+    /// This is [`LocalAsset::plan_copy_dir`][] plus deleting everything in the
+    /// destination that isn't in the origin. Those deletes come after everything
+    /// else, and only the outermost path is listed when a whole directory would go.
+    pub fn plan_sync_dir(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &CopyOptions,
+    ) -> Result<CopyPlan> {
+        CopyPlan::new(origin_path.as_ref(), dest_path.as_ref(), options, true)
+    }
+
+    /// Creates a symlink at `link` pointing to `target`
+    ///
+    /// `target` is interpreted the same way as any other path (so relative to the
+    /// current working directory if it's relative), and the link will store the
+    /// relative path from `link`'s directory to it. This keeps the link valid if the
+    /// whole tree gets moved around.
+    ///
+    /// On Windows directory links fall back to junctions if symlinks can't be
+    /// created, while file links return an error (see [`LocalAsset::symlink_with_options`][]).
+    pub fn symlink(target: impl AsRef<Utf8Path>, link: impl AsRef<Utf8Path>) -> Result<()> {
+        Self::symlink_with_options(target, link, &SymlinkOptions::default())
+    }
+
+    /// Creates a symlink at `link` pointing to `target`, with the given [`SymlinkOptions`][]
+    ///
+    /// See [`LocalAsset::symlink`][] for details.
+    pub fn symlink_with_options(
+        target: impl AsRef<Utf8Path>,
+        link: impl AsRef<Utf8Path>,
+        options: &SymlinkOptions,
+    ) -> Result<()> {
+        let target = target.as_ref();
+        let link = link.as_ref();
+        let link_dir = link.parent().unwrap_or_else(|| Utf8Path::new(""));
+        let relative_target = relative_path(link_dir, target)?;
+
+        create_symlink(target, &relative_target, link, options).map_err(|details| {
+            AxoassetError::LocalAssetSymlinkFailed {
+                target: target.to_string(),
+                link: link.to_string(),
+                details,
+            }
+        })
+    }
+
+    /// Makes a file executable
+    ///
+    /// On unix this adds the execute bit for everyone who can read the file (so `0o644`
+    /// becomes `0o755` and `0o600` becomes `0o700`), following symlinks. Windows decides
+    /// what's executable by extension instead, so there this only checks that the file
+    /// exists. See [`LocalAsset::is_executable`][] for checking the result.
+    pub fn make_executable(dest: impl AsRef<Utf8Path>) -> Result<()> {
+        let dest_path = dest.as_ref();
+        let wrap_err = |details| AxoassetError::LocalAssetPermissionsFailed {
+            dest_path: dest_path.to_string(),
+            details,
+        };
+        let metadata = fs::metadata(dest_path).map_err(wrap_err)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mut permissions = metadata.permissions();
+            let mode = permissions.mode();
+            let executable = mode | ((mode & 0o444) >> 2);
+            if executable != mode {
+                permissions.set_mode(executable);
+                fs::set_permissions(dest_path, permissions).map_err(wrap_err)?;
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = metadata;
         Ok(())
     }
 
+    /// Checks whether a path is a file that can be run as a program
+    ///
+    /// On unix that means any of its execute bits are set. On Windows it means its
+    /// extension is one of those in the `PATHEXT` environment variable (compared
+    /// case-insensitively), or `.com`, `.exe`, `.bat` or `.cmd` if that isn't set,
+    /// the same way the shell decides. Symlinks are followed, and missing files and
+    /// directories aren't executable.
+    pub fn is_executable(path: impl AsRef<Utf8Path>) -> bool {
+        let path = path.as_ref();
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        if !metadata.is_file() {
+            return false;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode() & 0o111 != 0
+        }
+        #[cfg(not(unix))]
+        {
+            let pathext = std::env::var("PATHEXT").unwrap_or_default();
+            has_executable_extension(path, &pathext)
+        }
+    }
+
+    /// Finds the paths in a directory that differ only by case
+    ///
+    /// Each returned group contains the paths (relative to `dir`) that would collide
+    /// with each other on a case-insensitive filesystem like the defaults on Windows and
+    /// macOS. Groups and the paths in them are sorted.
+    pub fn find_case_collisions(dir: impl AsRef<Utf8Path>) -> Result<Vec<Vec<Utf8PathBuf>>> {
+        let dir = dir.as_ref();
+        let mut folded = std::collections::BTreeMap::<String, Vec<Utf8PathBuf>>::new();
+        for entry in dirs::walk_dir(dir) {
+            let entry = entry?;
+            if entry.rel_path.as_str().is_empty() {
+                continue;
+            }
+            folded
+                .entry(entry.rel_path.as_str().to_lowercase())
+                .or_default()
+                .push(entry.rel_path);
+        }
+        let collisions = folded
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort();
+                group
+            })
+            .collect();
+        Ok(collisions)
+    }
+
+    /// Checks that no paths in a directory differ only by case, which would break
+    /// extracting an archive of it on a case-insensitive filesystem
+    ///
+    /// All of the collisions are reported in a single error. This is intended to be
+    /// run on a staged directory before archiving it.
+    pub fn check_case_collisions(dir: impl AsRef<Utf8Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        let collisions = Self::find_case_collisions(dir)?;
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(AxoassetError::PathCaseCollisions {
+                root_dir: dir.to_owned(),
+                collisions,
+            })
+        }
+    }
+
+    /// Computes the [`Digest`][] of a file's contents, without loading it all into memory
+    pub fn hash_file(path: impl AsRef<Utf8Path>, algorithm: DigestAlgorithm) -> Result<Digest> {
+        let path = path.as_ref();
+        let read_err = |details| AxoassetError::LocalAssetReadFailed {
+            origin_path: path.to_string(),
+            details,
+        };
+        let mut file = fs::File::open(path).map_err(read_err)?;
+        let mut hasher = Digest::hasher(algorithm);
+        std::io::copy(&mut file, &mut hasher).map_err(read_err)?;
+        Ok(hasher.finish())
+    }
+
+    /// Checks whether two files have exactly the same contents
+    ///
+    /// This compares the sizes first, and then streams through both files comparing
+    /// them a chunk at a time, stopping at the first difference. That makes it much
+    /// cheaper than hashing both files when all you need to know is whether they match
+    /// (like when deciding whether a copy can be skipped).
+    pub fn files_identical(a: impl AsRef<Utf8Path>, b: impl AsRef<Utf8Path>) -> Result<bool> {
+        use std::io::Read;
+
+        let a = a.as_ref();
+        let b = b.as_ref();
+        let read_err = |path: &Utf8Path| {
+            let origin_path = path.to_string();
+            move |details| AxoassetError::LocalAssetReadFailed {
+                origin_path,
+                details,
+            }
+        };
+        let mut file_a = fs::File::open(a).map_err(read_err(a))?;
+        let mut file_b = fs::File::open(b).map_err(read_err(b))?;
+        let len_a = file_a.metadata().map_err(read_err(a))?.len();
+        let len_b = file_b.metadata().map_err(read_err(b))?.len();
+        if len_a != len_b {
+            return Ok(false);
+        }
+
+        let mut buf_a = vec![0; 64 * 1024];
+        let mut buf_b = vec![0; 64 * 1024];
+        loop {
+            let read = file_a.read(&mut buf_a).map_err(read_err(a))?;
+            if read == 0 {
+                // Make sure b didn't grow out from under us
+                return Ok(file_b.read(&mut buf_b[..1]).map_err(read_err(b))? == 0);
+            }
+            // b may return less than a at a time, so read exactly as much as a did
+            match file_b.read_exact(&mut buf_b[..read]) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+                Err(details) => return Err(read_err(b)(details)),
+            }
+            if buf_a[..read] != buf_b[..read] {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Computes a deterministic [`Digest`][] of an entire directory tree
+    ///
+    /// The digest covers the relative path of every file, directory (including empty
+    /// ones), and symlink in the tree, along with the contents of every file and the
+    /// target of every symlink. It doesn't depend on things like mtimes, permissions,
+    /// or the order the filesystem lists entries in, so two trees with the same
+    /// contents always have the same digest.
+    ///
+    /// Files are hashed in parallel using one thread per available CPU.
+    pub fn hash_dir(dir: impl AsRef<Utf8Path>, algorithm: DigestAlgorithm) -> Result<Digest> {
+        let dir = dir.as_ref();
+        let mut entries = vec![];
+        for entry in dirs::walk_dir(dir) {
+            let entry = entry?;
+            if entry.rel_path.as_str().is_empty() {
+                continue;
+            }
+            entries.push(entry);
+        }
+        // Use the same separators on every platform so the digest is portable
+        let rel_path = |entry: &dirs::AxoassetDirEntry| {
+            entry
+                .rel_path
+                .components()
+                .map(|component| component.as_str())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        entries.sort_by_cached_key(rel_path);
+
+        // Hash all the files on a pool of threads that take the next unhashed file
+        let files = entries
+            .iter()
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.full_path.as_path())
+            .collect::<Vec<_>>();
+        let next_file = std::sync::atomic::AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+            .min(files.len())
+            .max(1);
+        let mut file_digests = std::collections::HashMap::new();
+        std::thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut digests = vec![];
+                        loop {
+                            let idx = next_file.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            let Some(file) = files.get(idx) else {
+                                return Ok(digests);
+                            };
+                            digests.push((*file, Self::hash_file(file, algorithm)?));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for worker in workers {
+                let digests: Result<Vec<_>> = worker.join().expect("hashing thread panicked");
+                file_digests.extend(digests?);
+            }
+            Ok::<(), AxoassetError>(())
+        })?;
+
+        // Hash a manifest of the whole tree
+        let mut hasher = Digest::hasher(algorithm);
+        for entry in &entries {
+            let path = rel_path(entry);
+            let line = if entry.file_type().is_dir() {
+                format!("dir\0{path}\n")
+            } else if entry.file_type().is_symlink() {
+                let target = fs::read_link(&entry.full_path).map_err(|details| {
+                    AxoassetError::LocalAssetReadFailed {
+                        origin_path: entry.full_path.to_string(),
+                        details,
+                    }
+                })?;
+                format!("symlink\0{path}\0{}\n", target.display())
+            } else {
+                let digest = &file_digests[entry.full_path.as_path()];
+                format!("file\0{path}\0{}\n", digest.to_hex())
+            };
+            hasher.update(line.as_bytes());
+        }
+        Ok(hasher.finish())
+    }
+
     /// Get the current working directory
+    ///
+    /// This is what axoasset resolves relative paths against when it works out paths
+    /// itself, like in [`LocalAsset::search_ancestors`][], [`LocalAsset::set_temp_dir`][],
+    /// or when computing relative symlink targets. It can be overridden without touching
+    /// the process's real working directory with [`LocalAsset::with_current_dir`][]
+    /// (for the current thread) or [`LocalAsset::set_current_dir_override`][] (for the
+    /// whole process).
+    ///
+    /// Note that the paths given to functions that load, write, copy, or remove files
+    /// are handed to the OS as-is, so relative ones still refer to the process's real
+    /// working directory. Join them onto this first if they should follow an override.
     pub fn current_dir() -> Result<Utf8PathBuf> {
+        if let Some(dir) = CURRENT_DIR_OVERRIDE.with(|dir| dir.borrow().clone()) {
+            return Ok(dir);
+        }
+        let global = GLOBAL_CURRENT_DIR_OVERRIDE
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        if let Some(dir) = global {
+            return Ok(dir);
+        }
+        Self::process_current_dir()
+    }
+
+    /// Get the process's real working directory, ignoring any overrides
+    fn process_current_dir() -> Result<Utf8PathBuf> {
         let cur_dir =
             std::env::current_dir().map_err(|details| AxoassetError::CurrentDir { details })?;
         let cur_dir = Utf8PathBuf::from_path_buf(cur_dir)
@@ -338,6 +1928,89 @@ impl LocalAsset {
         Ok(cur_dir)
     }
 
+    /// Runs `f` with [`LocalAsset::current_dir`][] reporting `dir` on this thread
+    ///
+    /// Inside `f`, axoasset resolves the relative paths it works out itself against
+    /// `dir` (see [`LocalAsset::current_dir`][] for which ones), and other threads are
+    /// unaffected, which makes this suitable for tests that run in parallel. A
+    /// relative `dir` is itself resolved against the current dir
+    /// (including any override that was already in effect). The previous override
+    /// is restored when `f` returns, even if it panics.
+    pub fn with_current_dir<T>(dir: impl AsRef<Utf8Path>, f: impl FnOnce() -> T) -> Result<T> {
+        struct Restore(Option<Utf8PathBuf>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                CURRENT_DIR_OVERRIDE.with(|dir| *dir.borrow_mut() = prev);
+            }
+        }
+
+        let dir = normalize_path(dir.as_ref())?;
+        let prev = CURRENT_DIR_OVERRIDE.with(|cur| cur.borrow_mut().replace(dir));
+        let _restore = Restore(prev);
+        Ok(f())
+    }
+
+    /// Overrides [`LocalAsset::current_dir`][] for every thread in the process,
+    /// or clears the override if `dir` is `None`
+    ///
+    /// This is intended for long-running processes that want a stable base dir for
+    /// axoasset regardless of who calls `std::env::set_current_dir`. A relative `dir`
+    /// is resolved against the process's real working directory. Overrides set with
+    /// [`LocalAsset::with_current_dir`][] take precedence over this one.
+    pub fn set_current_dir_override(dir: Option<impl AsRef<Utf8Path>>) -> Result<()> {
+        let dir = match dir {
+            Some(dir) if dir.as_ref().is_relative() => Some(normalize_path(
+                &Self::process_current_dir()?.join(dir.as_ref()),
+            )?),
+            Some(dir) => Some(normalize_path(dir.as_ref())?),
+            None => None,
+        };
+        *GLOBAL_CURRENT_DIR_OVERRIDE
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = dir;
+        Ok(())
+    }
+
+    /// Get the directory temporary files are staged in
+    ///
+    /// This is where things like `AxoClient::download_temp` put their files, and defaults
+    /// to the system's temp dir (`std::env::temp_dir`). It can be changed with
+    /// [`LocalAsset::set_temp_dir`][].
+    pub fn temp_dir() -> Result<Utf8PathBuf> {
+        let dir = TEMP_DIR_OVERRIDE
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        if let Some(dir) = dir {
+            return Ok(dir);
+        }
+        Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .map_err(|path| AxoassetError::Utf8Path { path })
+    }
+
+    /// Stage temporary files in `dir` for the whole process, or go back to the
+    /// system's temp dir if `dir` is `None`
+    ///
+    /// This is useful when the system's temp dir is a small tmpfs, or is on a different
+    /// filesystem from where files end up (so they can't be moved into place without
+    /// copying them). A relative `dir` is resolved against [`LocalAsset::current_dir`][].
+    /// The directory is created if it doesn't exist yet.
+    pub fn set_temp_dir(dir: Option<impl AsRef<Utf8Path>>) -> Result<()> {
+        let dir = match dir {
+            Some(dir) => {
+                let dir = normalize_path(&Self::current_dir()?.join(dir.as_ref()))?;
+                Self::create_dir_all(&dir)?;
+                Some(dir)
+            }
+            None => None,
+        };
+        *TEMP_DIR_OVERRIDE
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = dir;
+        Ok(())
+    }
+
     /// Find a desired file in the provided dir or an ancestor of it.
     ///
     /// On success returns the path to the found file.
@@ -376,36 +2049,62 @@ impl LocalAsset {
 
     /// Creates a new .tar.gz file from a provided directory
     ///
-    /// The with_root argument specifies that all contents of dest_dir should be placed
-    /// under the given path within the archive. If None then the contents of the dir will
-    /// be placed directly in the root. root_dir can be a proper path with subdirs
-    /// (e.g. `root_dir = "some/dir/prefix"` is valid).
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    /// The with_root argument specifies where the contents of dest_dir should be placed
+    /// within the archive, see [`ArchiveLayout`][]. `None` places them directly in the
+    /// root, and `Some(path)` places them under that path, which can have subdirs
+    /// (e.g. `Some("some/dir/prefix")` is valid).
+    #[cfg(feature = "compression-gz")]
     pub fn tar_gz_dir(
         origin_dir: impl AsRef<Utf8Path>,
         dest_dir: impl AsRef<Utf8Path>,
-        with_root: Option<impl AsRef<Utf8Path>>,
+        with_root: impl Into<ArchiveLayout>,
+    ) -> Result<()> {
+        Self::tar_gz_dir_with_options(origin_dir, dest_dir, with_root, &TarOptions::default())
+    }
+
+    /// Creates a new .tar.gz file from a provided directory, with the given [`TarOptions`][]
+    ///
+    /// See [`LocalAsset::tar_gz_dir`][] for details.
+    #[cfg(feature = "compression-gz")]
+    pub fn tar_gz_dir_with_options(
+        origin_dir: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &TarOptions,
     ) -> Result<()> {
         crate::compression::tar_dir(
             Utf8Path::new(origin_dir.as_ref()),
             Utf8Path::new(dest_dir.as_ref()),
-            with_root.as_ref().map(|p| p.as_ref()),
+            &with_root.into(),
             &crate::compression::CompressionImpl::Gzip,
+            options,
         )
     }
 
     /// Extracts the entire tarball at `tarball` to a provided directory
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-gz")]
     pub fn untar_gz_all(tarball: &Utf8Path, dest_path: &Utf8Path) -> Result<()> {
+        Self::untar_gz_all_with_options(tarball, dest_path, &ExtractOptions::default())
+    }
+
+    /// Extracts the entire tarball at `tarball` to a provided directory, with the
+    /// given [`ExtractOptions`][]
+    #[cfg(feature = "compression-gz")]
+    pub fn untar_gz_all_with_options(
+        tarball: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &ExtractOptions,
+    ) -> Result<()> {
         crate::compression::untar_all(
-            tarball,
-            dest_path,
+            tarball.as_ref(),
+            dest_path.as_ref(),
             &crate::compression::CompressionImpl::Gzip,
+            options,
         )
     }
 
     /// Extracts the file named `filename` within the tarball at `tarball` and returns its contents as bytes
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-gz")]
     pub fn untar_gz_file(tarball: &Utf8Path, filename: &str) -> Result<Vec<u8>> {
         crate::compression::untar_file(
             tarball,
@@ -416,39 +2115,65 @@ impl LocalAsset {
 
     /// Creates a new .tar.xz file from a provided directory
     ///
-    /// The with_root argument specifies that all contents of dest_dir should be placed
-    /// under the given path within the archive. If None then the contents of the dir will
-    /// be placed directly in the root. root_dir can be a proper path with subdirs
-    /// (e.g. `root_dir = "some/dir/prefix"` is valid).
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    /// The with_root argument specifies where the contents of dest_dir should be placed
+    /// within the archive, see [`ArchiveLayout`][]. `None` places them directly in the
+    /// root, and `Some(path)` places them under that path, which can have subdirs
+    /// (e.g. `Some("some/dir/prefix")` is valid).
+    #[cfg(feature = "compression-xz")]
     pub fn tar_xz_dir(
         origin_dir: impl AsRef<Utf8Path>,
         dest_dir: impl AsRef<Utf8Path>,
-        with_root: Option<impl AsRef<Utf8Path>>,
+        with_root: impl Into<ArchiveLayout>,
+    ) -> Result<()> {
+        Self::tar_xz_dir_with_options(origin_dir, dest_dir, with_root, &TarOptions::default())
+    }
+
+    /// Creates a new .tar.xz file from a provided directory, with the given [`TarOptions`][]
+    ///
+    /// See [`LocalAsset::tar_xz_dir`][] for details.
+    #[cfg(feature = "compression-xz")]
+    pub fn tar_xz_dir_with_options(
+        origin_dir: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &TarOptions,
     ) -> Result<()> {
         crate::compression::tar_dir(
             Utf8Path::new(origin_dir.as_ref()),
             Utf8Path::new(dest_dir.as_ref()),
-            with_root.as_ref().map(|p| p.as_ref()),
+            &with_root.into(),
             &crate::compression::CompressionImpl::Xzip,
+            options,
         )
     }
 
     /// Extracts the entire tarball at `tarball` to a provided directory
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-xz")]
     pub fn untar_xz_all(
         tarball: impl AsRef<Utf8Path>,
         dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        Self::untar_xz_all_with_options(tarball, dest_path, &ExtractOptions::default())
+    }
+
+    /// Extracts the entire tarball at `tarball` to a provided directory, with the
+    /// given [`ExtractOptions`][]
+    #[cfg(feature = "compression-xz")]
+    pub fn untar_xz_all_with_options(
+        tarball: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &ExtractOptions,
     ) -> Result<()> {
         crate::compression::untar_all(
             Utf8Path::new(tarball.as_ref()),
             Utf8Path::new(dest_path.as_ref()),
             &crate::compression::CompressionImpl::Xzip,
+            options,
         )
     }
 
     /// Extracts the file named `filename` within the tarball at `tarball` and returns its contents as bytes
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-xz")]
     pub fn untar_xz_file(tarball: impl AsRef<Utf8Path>, filename: &str) -> Result<Vec<u8>> {
         crate::compression::untar_file(
             Utf8Path::new(tarball.as_ref()),
@@ -459,39 +2184,65 @@ impl LocalAsset {
 
     /// Creates a new .tar.zstd file from a provided directory
     ///
-    /// The with_root argument specifies that all contents of dest_dir should be placed
-    /// under the given path within the archive. If None then the contents of the dir will
-    /// be placed directly in the root. root_dir can be a proper path with subdirs
-    /// (e.g. `root_dir = "some/dir/prefix"` is valid).
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    /// The with_root argument specifies where the contents of dest_dir should be placed
+    /// within the archive, see [`ArchiveLayout`][]. `None` places them directly in the
+    /// root, and `Some(path)` places them under that path, which can have subdirs
+    /// (e.g. `Some("some/dir/prefix")` is valid).
+    #[cfg(feature = "compression-zstd")]
     pub fn tar_zstd_dir(
         origin_dir: impl AsRef<Utf8Path>,
         dest_dir: impl AsRef<Utf8Path>,
-        with_root: Option<impl AsRef<Utf8Path>>,
+        with_root: impl Into<ArchiveLayout>,
+    ) -> Result<()> {
+        Self::tar_zstd_dir_with_options(origin_dir, dest_dir, with_root, &TarOptions::default())
+    }
+
+    /// Creates a new .tar.zstd file from a provided directory, with the given [`TarOptions`][]
+    ///
+    /// See [`LocalAsset::tar_zstd_dir`][] for details.
+    #[cfg(feature = "compression-zstd")]
+    pub fn tar_zstd_dir_with_options(
+        origin_dir: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &TarOptions,
     ) -> Result<()> {
         crate::compression::tar_dir(
             Utf8Path::new(origin_dir.as_ref()),
             Utf8Path::new(dest_dir.as_ref()),
-            with_root.as_ref().map(|p| p.as_ref()),
+            &with_root.into(),
             &crate::compression::CompressionImpl::Zstd,
+            options,
         )
     }
 
     /// Extracts the entire tarball at `tarball` to a provided directory
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-zstd")]
     pub fn untar_zstd_all(
         tarball: impl AsRef<Utf8Path>,
         dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        Self::untar_zstd_all_with_options(tarball, dest_path, &ExtractOptions::default())
+    }
+
+    /// Extracts the entire tarball at `tarball` to a provided directory, with the
+    /// given [`ExtractOptions`][]
+    #[cfg(feature = "compression-zstd")]
+    pub fn untar_zstd_all_with_options(
+        tarball: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        options: &ExtractOptions,
     ) -> Result<()> {
         crate::compression::untar_all(
             Utf8Path::new(tarball.as_ref()),
             Utf8Path::new(dest_path.as_ref()),
             &crate::compression::CompressionImpl::Zstd,
+            options,
         )
     }
 
     /// Extracts the file named `filename` within the tarball at `tarball` and returns its contents as bytes
-    #[cfg(any(feature = "compression", feature = "compression-tar"))]
+    #[cfg(feature = "compression-zstd")]
     pub fn untar_zstd_file(tarball: impl AsRef<Utf8Path>, filename: &str) -> Result<Vec<u8>> {
         crate::compression::untar_file(
             Utf8Path::new(tarball.as_ref()),
@@ -502,29 +2253,54 @@ impl LocalAsset {
 
     /// Creates a new .zip file from a provided directory
     ///
-    /// The with_root argument specifies that all contents of dest_dir should be placed
-    /// under the given path within the archive. If None then the contents of the dir will
-    /// be placed directly in the root. root_dir can be a proper path with subdirs
-    /// (e.g. `root_dir = "some/dir/prefix"` is valid).
+    /// The with_root argument specifies where the contents of dest_dir should be placed
+    /// within the archive, see [`ArchiveLayout`][]. `None` places them directly in the
+    /// root, and `Some(path)` places them under that path, which can have subdirs
+    /// (e.g. `Some("some/dir/prefix")` is valid).
     #[cfg(any(feature = "compression", feature = "compression-zip"))]
     pub fn zip_dir(
         origin_dir: impl AsRef<Utf8Path>,
         dest_dir: impl AsRef<Utf8Path>,
-        with_root: Option<impl AsRef<Utf8Path>>,
+        with_root: impl Into<ArchiveLayout>,
+    ) -> Result<()> {
+        Self::zip_dir_with_options(origin_dir, dest_dir, with_root, &ZipOptions::default())
+    }
+
+    /// Creates a new .zip file from a provided directory, with the given [`ZipOptions`][]
+    ///
+    /// See [`LocalAsset::zip_dir`][] for details.
+    #[cfg(any(feature = "compression", feature = "compression-zip"))]
+    pub fn zip_dir_with_options(
+        origin_dir: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &ZipOptions,
     ) -> Result<()> {
         crate::compression::zip_dir(
             Utf8Path::new(origin_dir.as_ref()),
             Utf8Path::new(dest_dir.as_ref()),
-            with_root.as_ref().map(|p| p.as_ref()),
+            &with_root.into(),
+            options,
         )
     }
 
     /// Extracts a .zip file to the a provided directory
     #[cfg(any(feature = "compression", feature = "compression-zip"))]
     pub fn unzip_all(zipfile: impl AsRef<Utf8Path>, dest_dir: impl AsRef<Utf8Path>) -> Result<()> {
+        Self::unzip_all_with_options(zipfile, dest_dir, &ExtractOptions::default())
+    }
+
+    /// Extracts a .zip file to the a provided directory, with the given [`ExtractOptions`][]
+    #[cfg(any(feature = "compression", feature = "compression-zip"))]
+    pub fn unzip_all_with_options(
+        zipfile: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        options: &ExtractOptions,
+    ) -> Result<()> {
         crate::compression::unzip_all(
             Utf8Path::new(zipfile.as_ref()),
             Utf8Path::new(dest_dir.as_ref()),
+            options,
         )
     }
 
@@ -533,6 +2309,203 @@ impl LocalAsset {
     pub fn unzip_file(zipfile: impl AsRef<Utf8Path>, filename: &str) -> Result<Vec<u8>> {
         crate::compression::unzip_file(Utf8Path::new(zipfile.as_ref()), filename)
     }
+
+    /// Compresses the single file at `origin_path` with brotli, writing the result to `dest_path`
+    ///
+    /// This is intended for producing precompressed `.br` web assets (css, js, ...)
+    /// that a server can send as-is to clients that accept brotli encoding.
+    #[cfg(feature = "compression-brotli")]
+    pub fn brotli_file(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        crate::compression::brotli_file(origin_path.as_ref(), dest_path.as_ref())
+    }
+
+    /// Decompresses the brotli-compressed file at `origin_path`, writing the result to `dest_path`
+    #[cfg(feature = "compression-brotli")]
+    pub fn unbrotli_file(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+    ) -> Result<()> {
+        crate::compression::unbrotli_file(origin_path.as_ref(), dest_path.as_ref())
+    }
+
+    /// Encrypts the file at `origin_path` with [age](https://age-encryption.org), writing
+    /// the result to `dest_path`
+    ///
+    /// Any of the `recipients` will be able to decrypt the file, see the [`age`][]
+    /// crate for the kinds of recipients (public keys, passphrases, plugins) available.
+    /// This is intended for caching tokens and other secrets on disk.
+    #[cfg(feature = "encryption")]
+    pub fn encrypt_file(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        recipients: &[&dyn age::Recipient],
+    ) -> Result<()> {
+        crate::encryption::encrypt_file(origin_path.as_ref(), dest_path.as_ref(), recipients)
+    }
+
+    /// Decrypts the age-encrypted file at `origin_path` with any of the given
+    /// `identities`, writing the result to `dest_path`
+    ///
+    /// Nothing is written if the file can't be decrypted, or has been tampered with.
+    /// On unix a new `dest_path` is created readable by its owner only (mode `0o600`),
+    /// since it's probably a secret.
+    #[cfg(feature = "encryption")]
+    pub fn decrypt_file(
+        origin_path: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        identities: &[&dyn age::Identity],
+    ) -> Result<()> {
+        crate::encryption::decrypt_file(origin_path.as_ref(), dest_path.as_ref(), identities)
+    }
+
+    /// Extracts an entire archive to the provided directory, and then any archives that
+    /// were inside it, up to `max_depth` levels of nesting
+    ///
+    /// This is useful for artifacts that wrap a tarball inside of a zip. Archive formats
+    /// are determined by file extension, and only formats whose features are enabled
+    /// are supported. Nested archives are unpacked into the directory they were found
+    /// in and then removed. A `max_depth` of 0 only unpacks the outer archive.
+    ///
+    /// The paths of the nested archives that were unpacked are returned.
+    #[cfg(any(feature = "compression-zip", feature = "__tar"))]
+    pub fn unpack_archive_nested(
+        archive: impl AsRef<Utf8Path>,
+        dest_dir: impl AsRef<Utf8Path>,
+        max_depth: usize,
+    ) -> Result<Vec<Utf8PathBuf>> {
+        crate::compression::unpack_nested(archive.as_ref(), dest_dir.as_ref(), max_depth)
+    }
+
+    /// Lists what extracting an archive would do, without writing anything
+    ///
+    /// Every entry is returned in the order it would be extracted, with its size and
+    /// type, and whether it would be refused (see [`ExtractRejection`][]). This lets
+    /// tools show users what an untrusted archive contains before unpacking it. The
+    /// archive format is determined by file extension, as with
+    /// [`LocalAsset::unpack_archive_nested`][].
+    #[cfg(any(feature = "compression-zip", feature = "__tar"))]
+    pub fn plan_unpack_archive(archive: impl AsRef<Utf8Path>) -> Result<Vec<PlannedEntry>> {
+        let archive = archive.as_ref();
+        let kind = crate::compression::ArchiveKind::from_filename(&filename(archive)?).ok_or_else(
+            || AxoassetError::UnknownArchiveFormat {
+                origin_path: archive.to_string(),
+            },
+        )?;
+        crate::compression::plan_unpack(archive, kind)
+    }
+
+    /// Archives a directory like [`LocalAsset::tar_gz_dir_with_options`][] and friends,
+    /// but reuses the existing archive at `dest_path` untouched if nothing has changed
+    /// since it was built
+    ///
+    /// The archive format is determined by the extension of `dest_path` (`.tar.gz`,
+    /// `.tar.xz`, `.tar.zst`, or `.zip`). A fingerprint of each archive is kept in
+    /// `fingerprint_dir` (a cache dir of your choosing, like `target/axoasset`, which is
+    /// created if needed), recording a [`LocalAsset::hash_dir`][] digest of
+    /// `origin_dir`, the layout and options used, and a digest of the archive itself.
+    /// The archive is only rebuilt if any of those no longer match, so editing or
+    /// deleting the archive also forces a rebuild. Nothing is written next to the
+    /// archive, and one fingerprint_dir can be shared by any number of archives.
+    ///
+    /// Returns `true` if the archive was (re)built, and `false` if it was reused.
+    #[cfg(any(feature = "__tar", feature = "compression-zip"))]
+    pub fn archive_dir_if_changed(
+        origin_dir: impl AsRef<Utf8Path>,
+        dest_path: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &TarOptions,
+        fingerprint_dir: impl AsRef<Utf8Path>,
+    ) -> Result<bool> {
+        let origin_dir = origin_dir.as_ref();
+        let dest_path = dest_path.as_ref();
+        let layout = with_root.into();
+        let kind = crate::compression::ArchiveKind::from_filename(&filename(dest_path)?)
+            .ok_or_else(|| AxoassetError::UnknownArchiveFormat {
+                origin_path: dest_path.to_string(),
+            })?;
+        // Fingerprints are keyed by where the archive is, which also decides its format
+        let algorithm = DigestAlgorithm::Sha256;
+        let key = normalize_path(&Self::process_current_dir()?.join(dest_path))?;
+        let fingerprint_path = fingerprint_dir.as_ref().join(format!(
+            "{}.fingerprint",
+            Digest::compute(algorithm, key.as_str().as_bytes()).to_hex()
+        ));
+
+        let inputs = archive_fingerprint_inputs(origin_dir, &layout, options)?;
+        if dest_path.is_file() && fingerprint_path.is_file() {
+            let expected = format!(
+                "{inputs}archive {}\n",
+                Self::hash_file(dest_path, algorithm)?.to_hex()
+            );
+            if Self::load_string(&fingerprint_path)? == expected {
+                return Ok(false);
+            }
+        }
+
+        // Make sure a stale fingerprint can't outlive a failed rebuild
+        if fingerprint_path.is_file() {
+            Self::remove_file(&fingerprint_path)?;
+        }
+        crate::compression::archive_dir(origin_dir, dest_path, &layout, kind, options)?;
+        let fingerprint = format!(
+            "{inputs}archive {}\n",
+            Self::hash_file(dest_path, algorithm)?.to_hex()
+        );
+        Self::write_new_all(&fingerprint, &fingerprint_path)?;
+        Ok(true)
+    }
+}
+
+/// Describes everything that goes into building an archive of `origin_dir`, one
+/// field per line, for [`LocalAsset::archive_dir_if_changed`][]
+#[cfg(any(feature = "__tar", feature = "compression-zip"))]
+fn archive_fingerprint_inputs(
+    origin_dir: &Utf8Path,
+    layout: &ArchiveLayout,
+    options: &TarOptions,
+) -> Result<String> {
+    let TarOptions {
+        include_xattrs,
+        provenance,
+        walk_order,
+        empty_dirs,
+    } = options;
+    let mut inputs = format!(
+        "axoasset {}\nsource {}\n",
+        env!("CARGO_PKG_VERSION"),
+        LocalAsset::hash_dir(origin_dir, DigestAlgorithm::Sha256)?.to_hex()
+    );
+    inputs.push_str(&match layout {
+        ArchiveLayout::Flat => "layout flat\n".to_owned(),
+        ArchiveLayout::RootDir(dir) => format!("layout root {}\n", json_string(dir)),
+        ArchiveLayout::Prefix(prefix) => {
+            format!("layout prefix {}\n", json_string(prefix.as_str()))
+        }
+    });
+    inputs.push_str(&format!("xattrs {include_xattrs}\n"));
+    inputs.push_str(match walk_order {
+        WalkOrder::Sorted => "walk sorted\n",
+        WalkOrder::Filesystem => "walk filesystem\n",
+    });
+    for dir in empty_dirs {
+        inputs.push_str(&format!("empty-dir {}\n", json_string(dir.as_str())));
+    }
+    if let Some(provenance) = provenance {
+        let timestamp = provenance
+            .timestamp
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_secs().to_string())
+            .unwrap_or_else(|| "default".to_owned());
+        inputs.push_str(&format!(
+            "provenance {} {} {timestamp}\n",
+            json_string(&provenance.tool_name),
+            json_string(&provenance.tool_version),
+        ));
+    }
+    Ok(inputs)
 }
 
 /// Get the filename of a path, or a pretty error
@@ -545,3 +2518,456 @@ pub fn filename(origin_path: &Utf8Path) -> Result<String> {
         })
     }
 }
+
+/// Makes a path absolute (relative to the current dir) and lexically resolves any
+/// `.` and `..` components in it, without touching the filesystem
+pub(crate) fn normalize_path(path: &Utf8Path) -> Result<Utf8PathBuf> {
+    use camino::Utf8Component;
+
+    let path = if path.is_relative() {
+        LocalAsset::current_dir()?.join(path)
+    } else {
+        path.to_owned()
+    };
+    let mut normalized = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => {
+                if !matches!(
+                    normalized.components().next_back(),
+                    Some(Utf8Component::Normal(_))
+                ) {
+                    // Can't go above the root, so `/..` is just `/`
+                    continue;
+                }
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Computes the relative path that gets from `from_dir` to `to`
+///
+/// If the two paths share no common root (e.g. they're on different drives on
+/// Windows) then the normalized absolute `to` is returned.
+pub(crate) fn relative_path(from_dir: &Utf8Path, to: &Utf8Path) -> Result<Utf8PathBuf> {
+    let from_dir = normalize_path(from_dir)?;
+    let to = normalize_path(to)?;
+    let common = from_dir
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return Ok(to);
+    }
+
+    let mut relative = Utf8PathBuf::new();
+    for _ in from_dir.components().skip(common) {
+        relative.push("..");
+    }
+    for component in to.components().skip(common) {
+        relative.push(component);
+    }
+    if relative.as_str().is_empty() {
+        relative.push(".");
+    }
+    Ok(relative)
+}
+
+#[cfg(unix)]
+fn create_symlink(
+    _target: &Utf8Path,
+    relative_target: &Utf8Path,
+    link: &Utf8Path,
+    _options: &SymlinkOptions,
+) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(relative_target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(
+    target: &Utf8Path,
+    relative_target: &Utf8Path,
+    link: &Utf8Path,
+    options: &SymlinkOptions,
+) -> std::io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+
+    if target.is_dir() {
+        if symlink_dir(relative_target, link).is_ok() {
+            return Ok(());
+        }
+        // Junctions don't need any special privileges, but they do need absolute targets
+        let absolute_target = normalize_path(target)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        let output = std::process::Command::new("cmd")
+            .args(["/C", "mklink", "/J"])
+            .arg(link.as_std_path())
+            .arg(absolute_target.as_std_path())
+            .output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ))
+        }
+    } else {
+        match symlink_file(relative_target, link) {
+            Ok(()) => Ok(()),
+            Err(e) => match options.file_fallback {
+                SymlinkFileFallback::Error => Err(e),
+                SymlinkFileFallback::Copy => fs::copy(target, link).map(|_| ()),
+            },
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(
+    _target: &Utf8Path,
+    _relative_target: &Utf8Path,
+    _link: &Utf8Path,
+    _options: &SymlinkOptions,
+) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symlinks aren't supported on this platform",
+    ))
+}
+
+/// Writes a file, following the given [`WriteOptions`][]
+fn write_file(
+    dest_path: &Utf8Path,
+    contents: &[u8],
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    let file = open_and_write(dest_path, contents, options)?;
+    match options.durability {
+        Durability::None => {}
+        Durability::SyncFile => file.sync_all()?,
+        Durability::SyncFileAndDir => {
+            file.sync_all()?;
+            if let Some(dest_dir) = dest_path.parent() {
+                sync_dir(dest_dir)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a file like [`write_file`][] without syncing it, handing back the open file
+fn open_and_write(
+    dest_path: &Utf8Path,
+    contents: &[u8],
+    options: &WriteOptions,
+) -> std::io::Result<fs::File> {
+    use std::io::Write;
+
+    let mut open_options = fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(mode);
+    }
+    let mut file = open_options.open(dest_path)?;
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        // The mode given to open is subject to the umask and only applies to new files
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+    file.write_all(contents)?;
+    Ok(file)
+}
+
+/// fsyncs a directory, so that changes to its entries are persisted
+fn sync_dir(dir: &Utf8Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // An empty parent means the file was a bare relative path
+        let dir = if dir.as_str().is_empty() {
+            Utf8Path::new(".")
+        } else {
+            dir
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+impl CopyPlan {
+    fn new(
+        origin_path: &Utf8Path,
+        dest_path: &Utf8Path,
+        options: &CopyOptions,
+        sync: bool,
+    ) -> Result<Self> {
+        use std::collections::BTreeSet;
+
+        let mut changes = vec![];
+        // Paths whose contents can't already be at the destination, or are being skipped
+        let mut missing = BTreeSet::new();
+        let mut skipped = BTreeSet::new();
+        let under = |set: &BTreeSet<Utf8PathBuf>, path: &Utf8Path| {
+            path.ancestors().skip(1).any(|dir| set.contains(dir))
+        };
+        for entry in dirs::walk_dir(origin_path).order(options.walk_order) {
+            let entry = entry?;
+            let is_dir = entry.file_type().is_dir();
+            // The dest dir itself is always kept, and symlinks aren't copied
+            if entry.rel_path.as_str().is_empty() || !(is_dir || entry.file_type().is_file()) {
+                continue;
+            }
+            let path = entry.rel_path;
+            let to = dest_path.join(&path);
+            let (action, reason) = if under(&skipped, &path) {
+                (ChangeAction::Skip, ChangeReason::OverwriteSkipped)
+            } else if under(&missing, &path) {
+                (ChangeAction::Create, ChangeReason::Missing)
+            } else {
+                match to.symlink_metadata() {
+                    Err(_) => (ChangeAction::Create, ChangeReason::Missing),
+                    Ok(existing) if is_dir && existing.is_dir() => {
+                        (ChangeAction::Skip, ChangeReason::Unchanged)
+                    }
+                    Ok(existing)
+                        if !is_dir
+                            && existing.is_file()
+                            && LocalAsset::files_identical(&entry.full_path, &to)? =>
+                    {
+                        (ChangeAction::Skip, ChangeReason::Unchanged)
+                    }
+                    Ok(existing) => {
+                        let reason = if is_dir || !existing.is_file() {
+                            ChangeReason::KindChanged
+                        } else {
+                            ChangeReason::Changed
+                        };
+                        match OverwriteHook::check(options.on_overwrite.as_ref(), &to)? {
+                            OverwriteDecision::Skip => {
+                                (ChangeAction::Skip, ChangeReason::OverwriteSkipped)
+                            }
+                            _ => (ChangeAction::Overwrite, reason),
+                        }
+                    }
+                }
+            };
+            match (action, reason) {
+                (ChangeAction::Skip, ChangeReason::OverwriteSkipped) => {
+                    skipped.insert(path.clone());
+                }
+                (ChangeAction::Overwrite, ChangeReason::KindChanged) => {
+                    missing.insert(path.clone());
+                }
+                (ChangeAction::Create | ChangeAction::Overwrite, _) if is_dir => {
+                    missing.insert(path.clone());
+                }
+                _ => {}
+            }
+            changes.push(PlannedChange {
+                path,
+                is_dir,
+                action,
+                reason,
+            });
+        }
+
+        if sync && dest_path.is_dir() {
+            let mut deleted = BTreeSet::new();
+            for entry in dirs::walk_dir(dest_path).order(options.walk_order) {
+                let entry = entry?;
+                let is_dir = entry.file_type().is_dir();
+                let path = entry.rel_path;
+                if path.as_str().is_empty()
+                    || under(&deleted, &path)
+                    || under(&missing, &path)
+                    || under(&skipped, &path)
+                {
+                    continue;
+                }
+                let in_origin = origin_path
+                    .join(&path)
+                    .symlink_metadata()
+                    .is_ok_and(|origin| !origin.is_symlink());
+                if in_origin {
+                    continue;
+                }
+                if is_dir {
+                    deleted.insert(path.clone());
+                }
+                changes.push(PlannedChange {
+                    path,
+                    is_dir,
+                    action: ChangeAction::Delete,
+                    reason: ChangeReason::NotInOrigin,
+                });
+            }
+        }
+
+        Ok(Self {
+            origin_path: origin_path.to_owned(),
+            dest_path: dest_path.to_owned(),
+            options: options.clone(),
+            changes,
+        })
+    }
+
+    /// Gets the dir being copied from
+    pub fn origin_path(&self) -> &Utf8Path {
+        &self.origin_path
+    }
+
+    /// Gets the dir being copied to
+    pub fn dest_path(&self) -> &Utf8Path {
+        &self.dest_path
+    }
+
+    /// Gets what would happen to each path, in the order it would happen
+    pub fn changes(&self) -> &[PlannedChange] {
+        &self.changes
+    }
+
+    /// Checks whether executing the plan would leave every path alone
+    pub fn is_noop(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| change.action == ChangeAction::Skip)
+    }
+
+    /// Carries out the plan
+    ///
+    /// The [`CopyOptions`][] the plan was made with apply, except that
+    /// [`CopyOptions::on_overwrite`][] isn't asked again. With
+    /// [`CopyOptions::skip_errors`][] every change is attempted and the failures are
+    /// reported at the end with [`AxoassetError::LocalAssetCopyDirIncomplete`][].
+    pub fn execute(&self) -> Result<()> {
+        let mut options = self.options.clone();
+        options.on_overwrite = None;
+        let mut failures = vec![];
+        let mut check = |result: Result<()>| match result {
+            Err(err) if options.skip_errors => {
+                failures.push(err);
+                Ok(())
+            }
+            result => result,
+        };
+        let remove = |path: &Utf8Path| match path.symlink_metadata() {
+            Ok(existing) if existing.is_dir() => LocalAsset::remove_dir_all(path),
+            _ => LocalAsset::remove_file(path),
+        };
+
+        LocalAsset::create_dir_all(&self.dest_path)?;
+        let mut copied_dirs = vec![(self.origin_path.clone(), self.dest_path.clone())];
+        for change in &self.changes {
+            let from = self.origin_path.join(&change.path);
+            let to = self.dest_path.join(&change.path);
+            match change.action {
+                ChangeAction::Skip => {}
+                ChangeAction::Delete => check(remove(&to))?,
+                ChangeAction::Create | ChangeAction::Overwrite => {
+                    if change.reason == ChangeReason::KindChanged {
+                        check(remove(&to))?;
+                    }
+                    if change.is_dir {
+                        check(LocalAsset::create_dir(&to).map(|_| ()))?;
+                    } else {
+                        check(LocalAsset::copy_file_to_file_with_options(
+                            &from, &to, &options,
+                        ))?;
+                    }
+                }
+            }
+            if change.is_dir
+                && change.action != ChangeAction::Delete
+                && change.reason != ChangeReason::OverwriteSkipped
+            {
+                copied_dirs.push((from, to));
+            }
+        }
+
+        // Like copy_dir_to_dir, fix up the dirs at the end, children before parents
+        if options.preserve_dir_mtimes || options.preserve_dir_permissions {
+            for (from, to) in copied_dirs.iter().rev() {
+                check(copy_dir_metadata(from, to, &options).map_err(|details| {
+                    AxoassetError::LocalAssetCopyFailed {
+                        origin_path: from.to_string(),
+                        dest_path: to.to_string(),
+                        details,
+                    }
+                }))?;
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(AxoassetError::LocalAssetCopyDirIncomplete {
+                origin_path: self.origin_path.to_string(),
+                dest_path: self.dest_path.to_string(),
+                failures,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Copies the metadata [`CopyOptions`][] asks for from one directory to another
+fn copy_dir_metadata(from: &Utf8Path, to: &Utf8Path, options: &CopyOptions) -> std::io::Result<()> {
+    let metadata = fs::metadata(from)?;
+    if options.preserve_dir_mtimes {
+        let mtime = filetime::FileTime::from_last_modification_time(&metadata);
+        filetime::set_file_mtime(to, mtime)?;
+    }
+    if options.preserve_dir_permissions {
+        fs::set_permissions(to, metadata.permissions())?;
+    }
+    Ok(())
+}
+
+/// Checks whether a path has one of the extensions in a `PATHEXT`-style list
+/// (like `.COM;.EXE;.BAT`), using Windows' defaults if it's empty
+#[cfg(not(unix))]
+fn has_executable_extension(path: &Utf8Path, pathext: &str) -> bool {
+    let Some(extension) = path.extension() else {
+        return false;
+    };
+    let pathext = if pathext.trim().is_empty() {
+        ".COM;.EXE;.BAT;.CMD"
+    } else {
+        pathext
+    };
+    pathext
+        .split(';')
+        .filter_map(|allowed| allowed.trim().strip_prefix('.'))
+        .any(|allowed| allowed.eq_ignore_ascii_case(extension))
+}
+
+/// Makes an existing file writable again, returning whether anything changed
+fn clear_readonly(path: &Utf8Path) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    let mut permissions = metadata.permissions();
+    if !permissions.readonly() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Only give the owner write access back, rather than making it writable by everyone
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    {
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+    }
+    fs::set_permissions(path, permissions).is_ok()
+}
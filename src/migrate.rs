@@ -0,0 +1,229 @@
+//! Schema migrations for config files
+//!
+//! A [`Migrator`][] holds an ordered list of transforms, each keyed by the schema
+//! version it upgrades a document to. The document records its current version in
+//! a top-level integer field, and migrating it runs every transform newer than that
+//! version (in order), bumping the field as it goes.
+
+use std::collections::BTreeMap;
+
+use crate::{error::*, SourceFile};
+
+/// The error a migration can fail with
+pub type MigrationError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+type MigrationFn<Doc> =
+    Box<dyn Fn(&mut Doc) -> std::result::Result<(), MigrationError> + Send + Sync>;
+
+/// A document format that a [`Migrator`][] can operate on
+///
+/// This is implemented for [`toml_edit::DocumentMut`][] (which preserves comments
+/// and formatting) and [`serde_json::Value`][].
+pub trait MigrationDocument: private::Sealed {}
+
+mod private {
+    use crate::{error::Result, SourceFile};
+
+    /// The operations a [`super::Migrator`][] needs from a document
+    pub trait Sealed: Sized {
+        /// Parses the document out of a SourceFile
+        fn parse(source: &SourceFile) -> Result<Self>;
+        /// Gets a top-level field, `None` if it's missing, and `Some(None)` if it
+        /// isn't a non-negative integer
+        fn get_version(&self, key: &str) -> Option<Option<u64>>;
+        /// Sets a top-level integer field
+        fn set_version(&mut self, key: &str, version: u64);
+        /// Renders the document back to text
+        fn render(&self) -> String;
+    }
+}
+
+#[cfg(feature = "toml-edit")]
+impl MigrationDocument for toml_edit::DocumentMut {}
+
+#[cfg(feature = "toml-edit")]
+impl private::Sealed for toml_edit::DocumentMut {
+    fn parse(source: &SourceFile) -> Result<Self> {
+        source.deserialize_toml_edit()
+    }
+    fn get_version(&self, key: &str) -> Option<Option<u64>> {
+        let version = self.get(key)?;
+        Some(
+            version
+                .as_integer()
+                .and_then(|version| u64::try_from(version).ok()),
+        )
+    }
+    fn set_version(&mut self, key: &str, version: u64) {
+        let version = i64::try_from(version).unwrap_or(i64::MAX);
+        match self.get_mut(key).and_then(|item| item.as_value_mut()) {
+            // Keep any comments attached to the existing field
+            Some(value) => {
+                let decor = value.decor().clone();
+                *value = version.into();
+                *value.decor_mut() = decor;
+            }
+            None => self[key] = toml_edit::value(version),
+        }
+    }
+    fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "json-serde")]
+impl MigrationDocument for serde_json::Value {}
+
+#[cfg(feature = "json-serde")]
+impl private::Sealed for serde_json::Value {
+    fn parse(source: &SourceFile) -> Result<Self> {
+        source.deserialize_json()
+    }
+    fn get_version(&self, key: &str) -> Option<Option<u64>> {
+        Some(self.get(key)?.as_u64())
+    }
+    fn set_version(&mut self, key: &str, version: u64) {
+        if let Some(object) = self.as_object_mut() {
+            object.insert(key.to_owned(), version.into());
+        }
+    }
+    fn render(&self) -> String {
+        let mut rendered = serde_json::to_string_pretty(self).unwrap_or_default();
+        rendered.push('\n');
+        rendered
+    }
+}
+
+/// Upgrades config files to the latest version of their schema
+///
+/// `Doc` is the document type the migrations operate on (see [`MigrationDocument`][]).
+/// A document without the version field is considered to be version 0.
+pub struct Migrator<Doc> {
+    version_key: String,
+    migrations: BTreeMap<u64, MigrationFn<Doc>>,
+}
+
+impl<Doc> std::fmt::Debug for Migrator<Doc> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migrator")
+            .field("version_key", &self.version_key)
+            .field("migrations", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<Doc: MigrationDocument> Migrator<Doc> {
+    /// Creates a Migrator that tracks the schema version in the given top-level field
+    pub fn new(version_key: impl Into<String>) -> Self {
+        Self {
+            version_key: version_key.into(),
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a migration that upgrades a document to `version`
+    ///
+    /// Migrations always run in order of version, regardless of the order they're
+    /// added in. Adding a second migration for the same version replaces the first.
+    pub fn migration(
+        mut self,
+        version: u64,
+        migration: impl Fn(&mut Doc) -> std::result::Result<(), MigrationError> + Send + Sync + 'static,
+    ) -> Self {
+        self.migrations.insert(version, Box::new(migration));
+        self
+    }
+
+    /// Gets the version documents are migrated to (0 if there are no migrations)
+    pub fn latest_version(&self) -> u64 {
+        self.migrations.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Runs every migration newer than the document's version
+    ///
+    /// If nothing needed to run the original SourceFile is returned untouched.
+    /// Documents from a version newer than [`Migrator::latest_version`][] are
+    /// rejected, since they were presumably written by a newer tool.
+    pub fn migrate(&self, source: &SourceFile) -> Result<Migrated> {
+        let mut doc = Doc::parse(source)?;
+        let from_version = match doc.get_version(&self.version_key) {
+            None => 0,
+            Some(Some(version)) => version,
+            Some(None) => {
+                return Err(AxoassetError::MigrationVersionInvalid {
+                    source_file: source.clone(),
+                    span: source.span_for_substr(&self.version_key),
+                    key: self.version_key.clone(),
+                })
+            }
+        };
+        let latest_version = self.latest_version();
+        if from_version > latest_version {
+            return Err(AxoassetError::MigrationVersionTooNew {
+                origin_path: source.origin_path().to_owned(),
+                version: from_version,
+                latest_version,
+            });
+        }
+
+        let mut applied = vec![];
+        for (&version, migration) in self.migrations.range((
+            std::ops::Bound::Excluded(from_version),
+            std::ops::Bound::Unbounded,
+        )) {
+            migration(&mut doc).map_err(|details| AxoassetError::MigrationFailed {
+                origin_path: source.origin_path().to_owned(),
+                version,
+                details,
+            })?;
+            doc.set_version(&self.version_key, version);
+            applied.push(version);
+        }
+
+        let source_file = if applied.is_empty() {
+            source.clone()
+        } else {
+            SourceFile::new(source.origin_path(), doc.render())
+        };
+        Ok(Migrated {
+            source_file,
+            from_version,
+            applied,
+        })
+    }
+}
+
+/// The result of [`Migrator::migrate`][]
+#[derive(Debug, Clone)]
+pub struct Migrated {
+    source_file: SourceFile,
+    from_version: u64,
+    applied: Vec<u64>,
+}
+
+impl Migrated {
+    /// Gets the migrated document (with the same origin path as the original)
+    pub fn source_file(&self) -> &SourceFile {
+        &self.source_file
+    }
+
+    /// Gets the version the document had before migrating
+    pub fn from_version(&self) -> u64 {
+        self.from_version
+    }
+
+    /// Gets the version the document has now
+    pub fn to_version(&self) -> u64 {
+        self.applied.last().copied().unwrap_or(self.from_version)
+    }
+
+    /// Gets the versions of the migrations that ran, in the order they ran
+    pub fn applied(&self) -> &[u64] {
+        &self.applied
+    }
+
+    /// Whether any migrations ran (and so the document needs to be written back)
+    pub fn changed(&self) -> bool {
+        !self.applied.is_empty()
+    }
+}
@@ -2,8 +2,49 @@
 
 use camino::{Utf8Path, Utf8PathBuf};
 use std::fs;
+use std::sync::Arc;
+use std::time::Instant;
 
-use crate::{error::*, SourceFile};
+use crate::{error::*, Digest, SourceFile};
+
+mod auth;
+mod backend;
+#[cfg(feature = "remote-blocking")]
+mod blocking;
+mod cache;
+mod capture;
+mod coalesce;
+mod github;
+#[cfg(feature = "github")]
+mod github_release;
+mod lock;
+mod metrics;
+mod network;
+mod origin;
+mod rate_limit;
+mod request;
+mod tls;
+#[cfg(feature = "remote-upload")]
+mod upload;
+mod zip_index;
+
+pub use auth::{CredentialProvider, Credentials};
+pub use backend::{HttpBackend, RemoteBackend};
+pub use cache::RemoteCache;
+pub use capture::{DebugCaptureOptions, HttpCapture};
+pub use github::GithubRateLimitOptions;
+#[cfg(feature = "github")]
+pub use github_release::{GithubReleaseAsset, GithubReleaseOptions};
+pub use lock::{LockMode, LockedAsset, LockedAssets};
+pub use metrics::{RemoteMetrics, TransferStats};
+pub use network::{IpVersion, NetworkOptions, ProxyOptions};
+pub use origin::OriginMap;
+pub use rate_limit::RateLimitOptions;
+pub use request::RemoteRequest;
+pub use tls::{TlsBackend, TlsOptions};
+#[cfg(feature = "remote-upload")]
+pub use upload::TarUploadOptions;
+pub use zip_index::RemoteZipEntry;
 
 /// An unparsed Url (borrowed)
 pub type UrlStr = str;
@@ -12,32 +53,420 @@ pub type UrlString = String;
 
 /// A client for http file requests
 ///
+/// `file://` urls are also supported, and are read from the local filesystem like
+/// a [`crate::LocalAsset`][] would be. That way configs that naturally contain urls
+/// can point at local files too. So are `data:` urls (base64 or percent-encoded),
+/// which are handy for embedding small files like icons in a config.
+///
 /// Note that you can and should freely Clone this, as the Client (and its
 /// underlying request pool) will be shared between the Clones.
 #[derive(Debug, Clone)]
 pub struct AxoClient {
-    client: reqwest::Client,
+    http: HttpBackend,
+    backends: std::collections::BTreeMap<String, Arc<dyn RemoteBackend>>,
+    metrics: Option<Arc<dyn RemoteMetrics>>,
+    github_rate_limit: Option<GithubRateLimitOptions>,
+    rate_limiter: Option<Arc<rate_limit::RateLimiter>>,
+    #[cfg(feature = "remote-cookies")]
+    cookie_jar: Option<Arc<reqwest::cookie::Jar>>,
+    #[cfg(feature = "remote-gzip")]
+    decode_gzip: bool,
+    in_flight: Option<Arc<coalesce::InFlight>>,
+    debug_capture: Option<DebugCaptureOptions>,
+    credentials: Option<CredentialProvider>,
+    cache: Option<RemoteCache>,
+    origin_map: Option<OriginMap>,
+    timeout: Option<std::time::Duration>,
+    user_agent: Option<String>,
+    sniff_content: bool,
 }
 
 impl AxoClient {
     /// Create an AxoClient with the given reqwest::Client
     pub fn with_reqwest(client: reqwest::Client) -> Self {
-        Self { client }
+        Self {
+            http: HttpBackend::new(client),
+            backends: Default::default(),
+            metrics: None,
+            github_rate_limit: None,
+            rate_limiter: None,
+            #[cfg(feature = "remote-cookies")]
+            cookie_jar: None,
+            #[cfg(feature = "remote-gzip")]
+            decode_gzip: false,
+            in_flight: None,
+            debug_capture: None,
+            credentials: None,
+            cache: None,
+            origin_map: None,
+            timeout: None,
+            user_agent: None,
+            sniff_content: false,
+        }
+    }
+
+    /// Create an AxoClient from the given reqwest::ClientBuilder, persisting cookies
+    /// in the given jar
+    ///
+    /// Cookies set by any response (including the intermediate responses of a redirect
+    /// chain, which is where SSO logins usually set them) are stored in the jar and
+    /// sent back on later requests to matching urls by this AxoClient and its clones.
+    /// The jar can be pre-populated with [`reqwest::cookie::Jar::add_cookie_str`][]
+    /// to resume an existing session.
+    #[cfg(feature = "remote-cookies")]
+    pub fn with_cookie_jar(
+        builder: reqwest::ClientBuilder,
+        jar: Arc<reqwest::cookie::Jar>,
+    ) -> Result<Self> {
+        let client = builder
+            .cookie_provider(jar.clone())
+            .build()
+            .map_err(|details| AxoassetError::RemoteClientBuildFailed { details })?;
+        Ok(Self {
+            cookie_jar: Some(jar),
+            ..Self::with_reqwest(client)
+        })
+    }
+
+    /// Create an AxoClient from the given reqwest::ClientBuilder, connecting to the
+    /// network according to the given [`NetworkOptions`][]
+    pub fn with_network_options(
+        builder: reqwest::ClientBuilder,
+        options: &NetworkOptions,
+    ) -> Result<Self> {
+        let client = options
+            .configure(builder)?
+            .build()
+            .map_err(|details| AxoassetError::RemoteClientBuildFailed { details })?;
+        Ok(Self::with_reqwest(client))
+    }
+
+    /// Gets the cookie jar attached to this AxoClient, if any
+    #[cfg(feature = "remote-cookies")]
+    pub fn cookie_jar(&self) -> Option<&Arc<reqwest::cookie::Jar>> {
+        self.cookie_jar.as_ref()
+    }
+
+    /// Report metrics about every remote operation this AxoClient (and its clones)
+    /// performs to the given sink
+    pub fn with_metrics(mut self, metrics: Arc<dyn RemoteMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Handle GitHub's API rate limits by waiting for them to reset and retrying
+    ///
+    /// This is off by default, so rate limited responses are returned like any other.
+    /// With [`GithubRateLimitOptions::default`][] requests to api.github.com that hit
+    /// a rate limit wait for it to reset and retry, as long as that takes less than a
+    /// minute.
+    pub fn with_github_rate_limit(mut self, options: GithubRateLimitOptions) -> Self {
+        self.github_rate_limit = Some(options);
+        self
+    }
+
+    /// Limit how fast requests are sent, to avoid tripping servers' throttling
+    ///
+    /// Requests that would exceed a limit wait until they're allowed (see
+    /// [`RateLimitOptions`][]). The limits are shared by all clones of this AxoClient,
+    /// so they hold across concurrent loads. Retries count against them too. Limits
+    /// apply to the urls actually requested, after any [`OriginMap`][] rules.
+    ///
+    /// Limits that aren't usable rates (zero, negative, NaN, or absurdly small) are
+    /// an error.
+    pub fn with_rate_limit(mut self, options: RateLimitOptions) -> Result<Self> {
+        self.rate_limiter = Some(Arc::new(rate_limit::RateLimiter::new(&options)?));
+        Ok(self)
+    }
+
+    /// Decompress gzipped single-file assets (urls ending in `.gz`, but not `.tar.gz`)
+    /// when loading them with [`AxoClient::load_string`][], [`AxoClient::load_bytes`][],
+    /// or [`AxoClient::load_source`][]
+    ///
+    /// Assets loaded with [`AxoClient::load_asset`][] (and so written to disk) are
+    /// left compressed, as are tarballs, which are meant to be unpacked instead.
+    #[cfg(feature = "remote-gzip")]
+    pub fn with_gzip_decoding(mut self, decode_gzip: bool) -> Self {
+        self.decode_gzip = decode_gzip;
+        self
+    }
+
+    /// Name assets from misconfigured servers by what their contents look like
+    ///
+    /// When a response has no `Content-Type` (or just `application/octet-stream`), no
+    /// filename in its `Content-Disposition`, and a url without an extension, the asset's filename
+    /// normally gets no extension either. With this enabled, the start of the body is
+    /// checked for the signatures of common formats (see [`sniff_extension`][]) and
+    /// the extension is picked from that instead.
+    pub fn with_content_sniffing(mut self, sniff_content: bool) -> Self {
+        self.sniff_content = sniff_content;
+        self
+    }
+
+    /// Send every request through the given [reqwest-middleware][reqwest_middleware]
+    /// stack, so existing tracing, retry, or auth middleware can be reused
+    ///
+    /// Requests are still built with this AxoClient's reqwest::Client, but they're
+    /// sent with [`ClientWithMiddleware::execute`][reqwest_middleware::ClientWithMiddleware::execute],
+    /// so the middleware's own client is the one that makes the connection (usually
+    /// you'll want both to be clones of the same reqwest::Client). Request initialisers
+    /// registered with the middleware's builder don't run, since they only apply to
+    /// requests built by the middleware client.
+    #[cfg(feature = "remote-middleware")]
+    pub fn with_middleware(mut self, middleware: reqwest_middleware::ClientWithMiddleware) -> Self {
+        self.http = self.http.with_middleware(middleware);
+        self
+    }
+
+    /// Share one download between concurrent loads of the same url
+    ///
+    /// When this is enabled, calling [`AxoClient::load_asset`][], [`AxoClient::load_string`][],
+    /// [`AxoClient::load_bytes`][], or [`AxoClient::load_source`][] for a url that's
+    /// already being loaded the same way (by this AxoClient or one of its clones) waits
+    /// for that load and returns a copy of its result, instead of fetching it again.
+    /// This only applies to loads that overlap, nothing is cached once they finish.
+    /// If the shared load fails, the waiting loads retry it rather than all failing.
+    ///
+    /// Requests made with [`AxoClient::request`][] are never shared, since they can
+    /// have different headers.
+    pub fn with_request_coalescing(mut self, coalesce: bool) -> Self {
+        self.in_flight = coalesce.then(Default::default);
+        self
+    }
+
+    /// Attach sanitized details of the HTTP exchange to errors, so they can be
+    /// included in bug reports
+    ///
+    /// When this is enabled, requests that fail to send, hit a rate limit, or get an
+    /// unexpected response to a range request fail with
+    /// [`AxoassetError::RemoteRequestCaptured`][], which wraps the usual error with an
+    /// [`HttpCapture`][] of the request and response. The capture is shown as the
+    /// error's help text.
+    pub fn with_debug_capture(mut self, options: DebugCaptureOptions) -> Self {
+        self.debug_capture = Some(options);
+        self
+    }
+
+    /// Authenticate requests with the [`Credentials`][] the provider returns for
+    /// their url
+    ///
+    /// This is how to fetch assets from private endpoints, like private GitHub
+    /// releases or an internal artifact store. Use [`CredentialProvider::for_host`][]
+    /// to make sure a token is only ever sent to the host it's for. Requests made
+    /// with [`AxoClient::request`][] that set their own credentials (or their own
+    /// `Authorization` header) don't use the provider.
+    ///
+    /// Like browsers, reqwest drops the `Authorization` header when following a
+    /// redirect to a different host.
+    pub fn with_credentials(mut self, provider: CredentialProvider) -> Self {
+        self.credentials = Some(provider);
+        self
+    }
+
+    /// Cache assets on disk, so loading them again only costs a request to check
+    /// they haven't changed
+    ///
+    /// This applies to [`AxoClient::load_asset`][], [`AxoClient::load_string`][],
+    /// [`AxoClient::load_bytes`][], [`AxoClient::load_source`][],
+    /// [`AxoClient::load_and_write_to_file`][], and [`AxoClient::load_and_write_to_dir`][].
+    /// See [`RemoteCache`][] for how assets are cached. Cache hits are reported with
+    /// [`RemoteMetrics::record_cache_hit`][].
+    pub fn with_cache(mut self, cache: RemoteCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Load assets from the urls an [`OriginMap`][] rewrites their urls to
+    ///
+    /// This applies to every request the AxoClient makes, so a mirror (or a local
+    /// copy of one, with `file://` urls) can stand in for the real origins without
+    /// changing the urls anything asks for. Errors, metrics, and the cache still
+    /// refer to the original urls. Credentials are chosen for the rewritten url.
+    pub fn with_origin_map(mut self, origin_map: OriginMap) -> Self {
+        self.origin_map = Some(origin_map);
+        self
+    }
+
+    /// Give up on any request that takes longer than `timeout` in total
+    ///
+    /// This covers connecting, sending the request, and reading the whole response,
+    /// so a server that hangs (or a firewall that quietly drops packets) can't stall
+    /// a load forever. Requests that run out of time fail with
+    /// [`AxoassetError::RemoteRequestTimedOut`][]. Individual requests can set their
+    /// own limit with [`RemoteRequest::timeout`][], and limits on connecting and on
+    /// each read can be set with [`NetworkOptions`][].
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request with the given `User-Agent` header (like `"cargo-dist/0.28.0"`)
+    ///
+    /// Some CDNs and APIs (GitHub's included) refuse or throttle requests that don't
+    /// identify what's making them. This replaces any User-Agent set on the
+    /// reqwest::Client, and individual requests can set their own with
+    /// [`RemoteRequest::user_agent`][]. Without one, requests to the GitHub API are
+    /// sent as `axoasset/<version>`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Answer requests for urls with the given scheme (like `gs` or `s3`) with a
+    /// custom [`RemoteBackend`][]
+    ///
+    /// This is how downstream crates can load assets from cloud storage or internal
+    /// artifact stores that don't speak plain HTTP. Everything else the AxoClient
+    /// does (caching, checksums, ranges, metrics, writing to disk) works the same as
+    /// for any other url. Backends can also be registered for `http`, `https`,
+    /// `file`, or `data` to replace the built-in ones.
+    pub fn with_backend(
+        mut self,
+        scheme: impl Into<String>,
+        backend: Arc<dyn RemoteBackend>,
+    ) -> Self {
+        self.backends
+            .insert(scheme.into().to_ascii_lowercase(), backend);
+        self
+    }
+
+    /// Gets the metrics sink attached to this AxoClient, if any
+    pub fn metrics(&self) -> Option<&dyn RemoteMetrics> {
+        self.metrics.as_deref()
+    }
+
+    /// Starts building a GET request for the URL, for when it needs query parameters
+    /// or headers
+    pub fn request(&self, url: &UrlStr) -> RemoteRequest<'_> {
+        RemoteRequest::new(self, url)
     }
 
     /// Loads an asset from a URL and returns a [`RemoteAsset`][] containing its body
     pub async fn load_asset(&self, url: &UrlStr) -> Result<RemoteAsset> {
-        let response = self.get(url).await?;
-        let filename = filename(url, response.headers())?;
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(wrap_reqwest_err(url))?
-            .to_vec();
-        Ok(RemoteAsset {
-            url: url.to_string(),
-            contents: bytes,
-            filename,
+        self.coalesced("asset", url, || async {
+            if let Some(cache) = &self.cache {
+                return self.load_cached(url, cache).await;
+            }
+            self.load_asset_with(url, self.new_request(reqwest::Method::GET, url), None)
+                .await
+        })
+        .await
+    }
+
+    /// Loads an asset like [`AxoClient::load_asset`][], calling `progress` as its body
+    /// downloads
+    ///
+    /// `progress` is called with the number of bytes downloaded so far and the total
+    /// size of the asset (if the server said what it is), once before the download
+    /// starts and then after every chunk of the body arrives. This is intended for
+    /// drawing progress bars.
+    pub async fn load_asset_with_progress(
+        &self,
+        url: &UrlStr,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<RemoteAsset> {
+        self.load_asset_with(
+            url,
+            self.new_request(reqwest::Method::GET, url),
+            Some(&mut progress),
+        )
+        .await
+    }
+
+    /// Loads an asset like [`AxoClient::load_asset`][], checking that its body has
+    /// the expected [`Digest`][]
+    ///
+    /// The body is hashed with the expected digest's algorithm, and
+    /// [`AxoassetError::RemoteAssetChecksumMismatch`][] is returned if it doesn't match.
+    pub async fn load_verified(&self, url: &UrlStr, expected: &Digest) -> Result<RemoteAsset> {
+        let asset = self.load_asset(url).await?;
+        let actual = Digest::compute(expected.algorithm(), asset.as_bytes());
+        if &actual != expected {
+            return Err(AxoassetError::RemoteAssetChecksumMismatch {
+                origin_path: url.to_string(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        Ok(asset)
+    }
+
+    /// Loads an asset unless it's unchanged since the response `cache_info` was
+    /// taken from
+    ///
+    /// The request is sent with `If-None-Match` and `If-Modified-Since` headers built
+    /// from `cache_info`, so a server that supports them can answer with a cheap
+    /// `304 Not Modified` instead of the whole asset. Either way, the result includes
+    /// the validators to use next time.
+    pub async fn load_if_modified(
+        &self,
+        url: &UrlStr,
+        cache_info: &CacheInfo,
+    ) -> Result<ConditionalLoad> {
+        use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+
+        let mut request = self.new_request(reqwest::Method::GET, url);
+        if let Some(etag) = &cache_info.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cache_info.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let (response, transfer) = self.send_tracked(url, request).await?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            // A 304 only has to repeat the validators that changed
+            let cache_info = CacheInfo {
+                etag: header(ETAG).or_else(|| cache_info.etag.clone()),
+                last_modified: header(LAST_MODIFIED).or_else(|| cache_info.last_modified.clone()),
+            };
+            return Ok(ConditionalLoad::NotModified { cache_info });
+        }
+        let cache_info = CacheInfo {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        let asset = self.read_asset(url, response, transfer, None).await?;
+        Ok(ConditionalLoad::Modified { asset, cache_info })
+    }
+
+    /// HEADs the URL and returns what the server says about the asset, without
+    /// downloading it
+    ///
+    /// This is useful for deciding whether to download something at all (by comparing
+    /// [`RemoteMetadata::cache_info`][] with what you have), or for sizing a progress
+    /// bar up front. Unlike most loads, an error status (like a 404) is an error,
+    /// since its headers describe the error page rather than the asset.
+    pub async fn load_metadata(&self, url: &UrlStr) -> Result<RemoteMetadata> {
+        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG, LAST_MODIFIED};
+
+        let request = self.new_request(reqwest::Method::HEAD, url);
+        let (response, transfer) = self.send_tracked(url, request).await?;
+        let response = self
+            .error_for_status(url, response, transfer.capture)
+            .await?;
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        Ok(RemoteMetadata {
+            // reqwest's content_length is the length of the (empty) HEAD body
+            content_length: header(CONTENT_LENGTH).and_then(|len| len.trim().parse().ok()),
+            content_type: header(CONTENT_TYPE),
+            cache_info: CacheInfo {
+                etag: header(ETAG),
+                last_modified: header(LAST_MODIFIED),
+            },
         })
     }
 
@@ -47,75 +476,1034 @@ impl AxoClient {
         Ok(SourceFile::new(url, text))
     }
 
+    /// GETs the URL and returns a [`crate::SourceFile`][] containing its body,
+    /// reading it in chunks according to the given [`SourceLoadOptions`][]
+    ///
+    /// This is intended for documents that could be huge (or that come from somewhere
+    /// that might send something huge by mistake): the body is refused as soon as
+    /// it's known to be over [`SourceLoadOptions::max_bytes`][], rather than after
+    /// it's all been downloaded, and a body that's cut off says how much of it arrived.
+    /// These loads aren't cached or shared between concurrent callers.
+    pub async fn load_source_with_options(
+        &self,
+        url: &UrlStr,
+        options: &SourceLoadOptions,
+    ) -> Result<SourceFile> {
+        self.load_source_with_progress(url, options, |_, _| {})
+            .await
+    }
+
+    /// Loads a [`crate::SourceFile`][] like [`AxoClient::load_source_with_options`][],
+    /// calling `progress` as it downloads
+    ///
+    /// `progress` is called the same way as with [`AxoClient::load_asset_with_progress`][].
+    pub async fn load_source_with_progress(
+        &self,
+        url: &UrlStr,
+        options: &SourceLoadOptions,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<SourceFile> {
+        let too_large = |received| AxoassetError::RemoteAssetTooLarge {
+            origin_path: url.to_string(),
+            max_bytes: options.max_bytes.unwrap_or_default(),
+            received,
+        };
+        let over_max = |len: u64| options.max_bytes.is_some_and(|max| len > max);
+
+        let mut response = self
+            .send(url, self.new_request(reqwest::Method::GET, url))
+            .await?;
+        let total = response.content_length();
+        if let Some(total) = total.filter(|total| over_max(*total)) {
+            return Err(too_large(total));
+        }
+        // The server could be lying about the length, so only trust it so far
+        let capacity = total.unwrap_or(0).min(MAX_PREALLOCATION);
+        let mut bytes = Vec::with_capacity(capacity as usize);
+        progress(0, total);
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(details) => {
+                    self.record_bytes_downloaded(url, bytes.len());
+                    return Err(AxoassetError::RemoteAssetIncomplete {
+                        origin_path: url.to_string(),
+                        received: bytes.len() as u64,
+                        total,
+                        details,
+                    });
+                }
+            };
+            bytes.extend_from_slice(&chunk);
+            if over_max(bytes.len() as u64) {
+                self.record_bytes_downloaded(url, bytes.len());
+                return Err(too_large(bytes.len() as u64));
+            }
+            progress(bytes.len() as u64, total);
+        }
+        self.record_bytes_downloaded(url, bytes.len());
+
+        #[cfg(feature = "remote-gzip")]
+        if self.decodes_gzip(url) {
+            bytes = gunzip(url, &bytes, options.max_bytes)?;
+            if over_max(bytes.len() as u64) {
+                return Err(too_large(bytes.len() as u64));
+            }
+        }
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        };
+        Ok(SourceFile::new(url, text))
+    }
+
     /// GETs the URL and returns its body as a `String`
     pub async fn load_string(&self, url: &UrlStr) -> Result<String> {
-        let response = self.get(url).await?;
-        let text = response.text().await.map_err(wrap_reqwest_err(url))?;
-        Ok(text)
+        self.coalesced("string", url, || async {
+            if let Some(cache) = &self.cache {
+                let bytes = self.load_cached_bytes(url, cache).await?;
+                return Ok(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            self.load_string_with(url, self.new_request(reqwest::Method::GET, url))
+                .await
+        })
+        .await
     }
 
     /// GETs the URL and returns its body as a `Vec<u8>`
     pub async fn load_bytes(&self, url: &UrlStr) -> Result<Vec<u8>> {
-        let response = self.get(url).await?;
+        self.coalesced("bytes", url, || async {
+            if let Some(cache) = &self.cache {
+                return self.load_cached_bytes(url, cache).await;
+            }
+            self.load_bytes_with(url, self.new_request(reqwest::Method::GET, url))
+                .await
+        })
+        .await
+    }
+
+    /// Runs a load, sharing it with any identical loads in flight if
+    /// [`AxoClient::with_request_coalescing`][] is enabled
+    async fn coalesced<T, Fut>(
+        &self,
+        kind: &'static str,
+        url: &UrlStr,
+        load: impl FnOnce() -> Fut,
+    ) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        match &self.in_flight {
+            Some(in_flight) => in_flight.run(kind, url, load).await,
+            None => load().await,
+        }
+    }
+
+    /// Loads an asset through the cache, revalidating the cached copy if there is one
+    async fn load_cached(&self, url: &UrlStr, cache: &RemoteCache) -> Result<RemoteAsset> {
+        let start = Instant::now();
+        let cached = cache.get(url);
+        let cache_info = cached
+            .as_ref()
+            .map(|cached| cached.cache_info.clone())
+            .unwrap_or_default();
+        match self.load_if_modified(url, &cache_info).await? {
+            ConditionalLoad::Modified { asset, cache_info } => {
+                cache.put(
+                    url,
+                    &asset.filename,
+                    &asset.resolved_url,
+                    &cache_info,
+                    &asset.contents,
+                );
+                Ok(asset)
+            }
+            ConditionalLoad::NotModified { .. } => {
+                let Some(cached) = cached else {
+                    // We didn't ask for a 304, so this server is confused
+                    return self
+                        .load_asset_with(url, self.new_request(reqwest::Method::GET, url), None)
+                        .await;
+                };
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_cache_hit(url);
+                }
+                Ok(RemoteAsset {
+                    filename: cached.filename,
+                    url: url.to_owned(),
+                    resolved_url: cached.resolved_url.unwrap_or_else(|| url.to_owned()),
+                    contents: cached.contents,
+                    stats: TransferStats {
+                        duration: start.elapsed(),
+                        ..TransferStats::default()
+                    },
+                })
+            }
+        }
+    }
+
+    /// Loads an asset's body through the cache, decompressing it like
+    /// [`AxoClient::load_bytes`][] would
+    async fn load_cached_bytes(&self, url: &UrlStr, cache: &RemoteCache) -> Result<Vec<u8>> {
+        let bytes = self.load_cached(url, cache).await?.into_bytes();
+        #[cfg(feature = "remote-gzip")]
+        if self.decodes_gzip(url) {
+            return gunzip(url, &bytes, None);
+        }
+        Ok(bytes)
+    }
+
+    /// Sends the request and returns a [`RemoteAsset`][] containing the body
+    async fn load_asset_with(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+        progress: Option<&mut (dyn FnMut(u64, Option<u64>) + Send)>,
+    ) -> Result<RemoteAsset> {
+        let (response, transfer) = self.send_tracked(url, request).await?;
+        self.read_asset(url, response, transfer, progress).await
+    }
+
+    /// Reads a response into a [`RemoteAsset`][]
+    async fn read_asset(
+        &self,
+        url: &UrlStr,
+        mut response: reqwest::Response,
+        transfer: Transfer,
+        progress: Option<&mut (dyn FnMut(u64, Option<u64>) + Send)>,
+    ) -> Result<RemoteAsset> {
+        let mut filename = self.filename(url, &response)?;
+        let sniff = self.needs_sniffing(&filename, &response);
+        let resolved_url = response.url().to_string();
+        let bytes = match progress {
+            None => response
+                .bytes()
+                .await
+                .map_err(wrap_reqwest_err(url))?
+                .to_vec(),
+            Some(progress) => {
+                let total = response.content_length();
+                // The server could be lying about the length, so only trust it so far
+                let capacity = total.unwrap_or(0).min(MAX_PREALLOCATION);
+                let mut bytes = Vec::with_capacity(capacity as usize);
+                progress(0, total);
+                while let Some(chunk) = response.chunk().await.map_err(wrap_reqwest_err(url))? {
+                    bytes.extend_from_slice(&chunk);
+                    progress(bytes.len() as u64, total);
+                }
+                bytes
+            }
+        };
+        self.record_bytes_downloaded(url, bytes.len());
+        if sniff {
+            filename = sniffed_filename(filename, &bytes);
+        }
+        Ok(RemoteAsset {
+            url: url.to_string(),
+            resolved_url,
+            stats: transfer.finish(bytes.len()),
+            contents: bytes,
+            filename,
+        })
+    }
+
+    /// Sends the request and returns the body as a `String`
+    async fn load_string_with(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+    ) -> Result<String> {
+        let response = self.send(url, request).await?;
+        #[cfg(feature = "remote-gzip")]
+        if self.decodes_gzip(url) {
+            let bytes = self.read_gzip_body(url, response).await?;
+            return Ok(String::from_utf8_lossy(&bytes).into_owned());
+        }
+        let text = response.text().await.map_err(wrap_reqwest_err(url))?;
+        self.record_bytes_downloaded(url, text.len());
+        Ok(text)
+    }
+
+    /// Sends the request and returns the body as a `Vec<u8>`
+    async fn load_bytes_with(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+    ) -> Result<Vec<u8>> {
+        let response = self.send(url, request).await?;
+        #[cfg(feature = "remote-gzip")]
+        if self.decodes_gzip(url) {
+            return self.read_gzip_body(url, response).await;
+        }
         let bytes = response
             .bytes()
             .await
             .map_err(wrap_reqwest_err(url))?
             .to_vec();
+        self.record_bytes_downloaded(url, bytes.len());
         Ok(bytes)
     }
 
+    /// Whether the body of the url should be gunzipped when loading it
+    #[cfg(feature = "remote-gzip")]
+    fn decodes_gzip(&self, url: &UrlStr) -> bool {
+        if !self.decode_gzip {
+            return false;
+        }
+        let path = url::Url::parse(url)
+            .map(|url| url.path().to_ascii_lowercase())
+            .unwrap_or_default();
+        path.ends_with(".gz") && !path.ends_with(".tar.gz")
+    }
+
+    /// Reads a gzipped response body and decompresses it
+    #[cfg(feature = "remote-gzip")]
+    async fn read_gzip_body(&self, url: &UrlStr, response: reqwest::Response) -> Result<Vec<u8>> {
+        let compressed = response.bytes().await.map_err(wrap_reqwest_err(url))?;
+        self.record_bytes_downloaded(url, compressed.len());
+        gunzip(url, &compressed, None)
+    }
+
     /// GETs the URL and write its bytes to the given local file
+    ///
+    /// The body is streamed to the file as it arrives rather than being loaded into
+    /// memory first, so this is fine for huge assets. It goes to a temporary file in
+    /// the same directory that only replaces `dest_file` once it's complete, so if the
+    /// download fails partway through, any existing file is left as it was. (Unless
+    /// the AxoClient has a [`RemoteCache`][], in which case the asset is loaded into
+    /// memory so it can be cached.)
     pub async fn load_and_write_to_file(
         &self,
         url: &UrlStr,
         dest_file: impl AsRef<Utf8Path>,
     ) -> Result<()> {
-        let asset = self.load_asset(url).await?;
-        asset.write_to_file(dest_file).await
+        if self.cache.is_some() {
+            return self.load_asset(url).await?.write_to_file(dest_file).await;
+        }
+        self.load_and_write_to_file_with_progress(url, dest_file, |_, _| {})
+            .await
+    }
+
+    /// Writes an asset to a file like [`AxoClient::load_and_write_to_file`][], calling
+    /// `progress` as it downloads
+    ///
+    /// `progress` is called the same way as with [`AxoClient::load_asset_with_progress`][].
+    pub async fn load_and_write_to_file_with_progress(
+        &self,
+        url: &UrlStr,
+        dest_file: impl AsRef<Utf8Path>,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<()> {
+        let (response, _) = self
+            .send_tracked(url, self.new_request(reqwest::Method::GET, url))
+            .await?;
+        self.stream_to_file(url, response, dest_file.as_ref(), &mut progress)
+            .await?;
+        Ok(())
     }
 
     /// GETs the URL and write its bytes to the given local dir
     ///
     /// The filename used will be computed from the url/mime, and the resulting
-    /// filepath will be returned.
+    /// filepath will be returned. Like [`AxoClient::load_and_write_to_file`][] the
+    /// body is streamed to the file as it arrives.
     pub async fn load_and_write_to_dir(
         &self,
         url: &UrlStr,
         dest_dir: impl AsRef<Utf8Path>,
     ) -> Result<Utf8PathBuf> {
-        let asset = self.load_asset(url).await?;
-        asset.write_to_dir(dest_dir).await
+        if self.cache.is_some() {
+            return self.load_asset(url).await?.write_to_dir(dest_dir).await;
+        }
+        let (response, _) = self
+            .send_tracked(url, self.new_request(reqwest::Method::GET, url))
+            .await?;
+        let filename = self.filename(url, &response)?;
+        let dest_path = dest_dir.as_ref().join(&filename);
+        if !self.needs_sniffing(&filename, &response) {
+            self.stream_to_file(url, response, &dest_path, &mut |_, _| {})
+                .await?;
+            return Ok(dest_path);
+        }
+        // The name depends on the body, so it's only picked once the body is on disk
+        let (temp_path, _) = self
+            .stream_to_temp(url, response, &dest_path, &mut |_, _| {})
+            .await?;
+        let start = read_sniff_start(&temp_path)?;
+        let dest_path = dest_dir.as_ref().join(sniffed_filename(filename, &start));
+        persist_temp(url, temp_path, &dest_path)?;
+        Ok(dest_path)
+    }
+
+    /// GETs the URL and writes its bytes to the given local dir, after checking them
+    /// against the checksum file at `checksum_url`
+    ///
+    /// The checksum file can be a sidecar like `app.tar.gz.sha256` or a list like
+    /// `SHA256SUMS` (see [`Digest::from_checksum_file`][] for the formats), and the
+    /// asset's checksum is looked up in it by the asset's filename. The asset is
+    /// downloaded to a temporary file first, so nothing appears in `dest_dir` unless it
+    /// matches. A mismatch is reported as
+    /// [`AxoassetError::RemoteAssetChecksumMismatch`][] with both digests.
+    pub async fn copy_with_checksum_file(
+        &self,
+        url: &UrlStr,
+        checksum_url: &UrlStr,
+        dest_dir: impl AsRef<Utf8Path>,
+    ) -> Result<Utf8PathBuf> {
+        let checksums = self.load_source(checksum_url).await?;
+        let download = self.download_temp(url).await?;
+        let expected = Digest::from_checksum_file(&checksums, download.filename())?;
+        let actual = crate::LocalAsset::hash_file(download.path(), expected.algorithm())?;
+        if actual != expected {
+            return Err(AxoassetError::RemoteAssetChecksumMismatch {
+                origin_path: url.to_string(),
+                expected,
+                actual,
+            });
+        }
+        let dest_path = dest_dir.as_ref().join(download.filename());
+        download.persist(dest_path)
+    }
+
+    /// Streams a response body to `dest_path`
+    ///
+    /// The body is written to a temporary file next to `dest_path` which only replaces
+    /// it once the whole body has arrived, so a failed download leaves whatever was
+    /// there before untouched.
+    async fn stream_to_file(
+        &self,
+        url: &UrlStr,
+        response: reqwest::Response,
+        dest_path: &Utf8Path,
+        progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+    ) -> Result<usize> {
+        let (temp_path, written) = self
+            .stream_to_temp(url, response, dest_path, progress)
+            .await?;
+        persist_temp(url, temp_path, dest_path)?;
+        Ok(written)
+    }
+
+    /// Streams a response body to a temporary file in the same dir as `dest_path`
+    ///
+    /// The file is deleted when the returned path is dropped, unless it's persisted.
+    async fn stream_to_temp(
+        &self,
+        url: &UrlStr,
+        response: reqwest::Response,
+        dest_path: &Utf8Path,
+        progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+    ) -> Result<(tempfile::TempPath, usize)> {
+        let dest_dir = match dest_path.parent() {
+            Some(parent) if !parent.as_str().is_empty() => parent,
+            _ => Utf8Path::new("."),
+        };
+        let (file, temp_path) = tempfile::NamedTempFile::new_in(dest_dir)
+            .map_err(|details| AxoassetError::RemoteAssetWriteFailed {
+                origin_url: url.to_string(),
+                dest_path: dest_path.to_owned(),
+                details,
+            })?
+            .into_parts();
+        let mut file = tokio::fs::File::from_std(file);
+        let written = self
+            .write_body(url, response, &mut file, dest_path, progress)
+            .await?;
+        Ok((temp_path, written))
+    }
+
+    /// Writes a response body to a file a chunk at a time, returning its length
+    async fn write_body(
+        &self,
+        url: &UrlStr,
+        mut response: reqwest::Response,
+        file: &mut (impl tokio::io::AsyncWrite + Unpin),
+        dest_path: &Utf8Path,
+        progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+    ) -> Result<usize> {
+        use tokio::io::AsyncWriteExt;
+
+        let write_err = |details| AxoassetError::RemoteAssetWriteFailed {
+            origin_url: url.to_string(),
+            dest_path: dest_path.to_owned(),
+            details,
+        };
+        let total = response.content_length();
+        let mut downloaded = 0;
+        progress(0, total);
+        while let Some(chunk) = response.chunk().await.map_err(wrap_reqwest_err(url))? {
+            file.write_all(&chunk).await.map_err(write_err)?;
+            downloaded += chunk.len();
+            progress(downloaded as u64, total);
+        }
+        file.flush().await.map_err(write_err)?;
+        self.record_bytes_downloaded(url, downloaded);
+        Ok(downloaded)
+    }
+
+    /// GETs the URL and streams its body into a temporary file
+    ///
+    /// The file is deleted when the returned [`TempDownload`][] is dropped, unless it's
+    /// moved somewhere permanent with [`TempDownload::persist`][]. This is useful for
+    /// verifying a download before installing it, without partially-verified content
+    /// ever appearing at the final destination.
+    ///
+    /// The file is created in [`LocalAsset::temp_dir`][crate::LocalAsset::temp_dir].
+    pub async fn download_temp(&self, url: &UrlStr) -> Result<TempDownload> {
+        let (response, transfer) = self
+            .send_tracked(url, self.new_request(reqwest::Method::GET, url))
+            .await?;
+        let mut filename = self.filename(url, &response)?;
+        let sniff = self.needs_sniffing(&filename, &response);
+        let temp_dir = crate::LocalAsset::temp_dir()?;
+        let file = tempfile::NamedTempFile::new_in(&temp_dir).map_err(|details| {
+            AxoassetError::RemoteAssetWriteFailed {
+                origin_url: url.to_string(),
+                dest_path: temp_dir,
+                details,
+            }
+        })?;
+        let path = Utf8PathBuf::from_path_buf(file.path().to_owned())
+            .map_err(|path| AxoassetError::Utf8Path { path })?;
+
+        let mut writer = file
+            .as_file()
+            .try_clone()
+            .map(tokio::fs::File::from_std)
+            .map_err(|details| AxoassetError::RemoteAssetWriteFailed {
+                origin_url: url.to_string(),
+                dest_path: path.clone(),
+                details,
+            })?;
+        let downloaded = self
+            .write_body(url, response, &mut writer, &path, &mut |_, _| {})
+            .await?;
+        drop(writer);
+        if sniff {
+            let start = read_sniff_start(file.path())?;
+            filename = sniffed_filename(filename, &start);
+        }
+
+        Ok(TempDownload {
+            url: url.to_owned(),
+            filename,
+            path,
+            file,
+            stats: transfer.finish(downloaded),
+        })
+    }
+
+    /// GETs the bytes in `range` of the URL, for resuming or parallelizing a download
+    ///
+    /// If `etag` is given (usually from [`RemoteRange::etag`][] of an earlier range) the
+    /// request is made with `If-Range`, so the server only returns the range if the
+    /// asset still has that ETag. If the asset has changed since, this errors with
+    /// [`AxoassetError::RemoteRangeResourceChanged`][] instead of returning bytes from a
+    /// different version. Note that servers only honor strong ETags (ones that don't
+    /// start with `W/`) in `If-Range`, so a weak ETag always looks like a change.
+    ///
+    /// The returned range may be shorter than requested if it extends past the end
+    /// of the asset.
+    pub async fn load_range(
+        &self,
+        url: &UrlStr,
+        range: std::ops::Range<u64>,
+        etag: Option<&str>,
+    ) -> Result<RemoteRange> {
+        use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+
+        let range_str = format!("{}-{}", range.start, range.end.saturating_sub(1));
+        let not_satisfied = |status: reqwest::StatusCode| AxoassetError::RemoteRangeNotSatisfied {
+            origin_path: url.to_string(),
+            range: range_str.clone(),
+            status: status.as_u16(),
+        };
+        if range.is_empty() {
+            return Err(not_satisfied(reqwest::StatusCode::RANGE_NOT_SATISFIABLE));
+        }
+        let mut request = self
+            .new_request(reqwest::Method::GET, url)
+            .header(RANGE, format!("bytes={range_str}"));
+        if let Some(etag) = etag {
+            request = request.header(IF_RANGE, etag);
+        }
+        let (response, mut transfer) = self.send_tracked(url, request).await?;
+
+        let status = response.status();
+        let found_etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_owned());
+        let checked = (|| {
+            if status == reqwest::StatusCode::OK {
+                if let Some(expected_etag) = etag {
+                    return Err(AxoassetError::RemoteRangeResourceChanged {
+                        origin_path: url.to_string(),
+                        expected_etag: expected_etag.to_owned(),
+                        found_etag: found_etag.clone(),
+                    });
+                }
+            }
+            if status != reqwest::StatusCode::PARTIAL_CONTENT {
+                return Err(not_satisfied(status));
+            }
+            if let (Some(expected_etag), Some(found_etag)) = (etag, &found_etag) {
+                if expected_etag != found_etag {
+                    return Err(AxoassetError::RemoteRangeResourceChanged {
+                        origin_path: url.to_string(),
+                        expected_etag: expected_etag.to_owned(),
+                        found_etag: Some(found_etag.clone()),
+                    });
+                }
+            }
+
+            // Make sure we got the range we asked for (or a prefix of it)
+            response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|header| header.to_str().ok())
+                .and_then(parse_content_range)
+                .filter(|(found, _)| found.start == range.start && found.end <= range.end)
+                .ok_or_else(|| not_satisfied(status))
+        })();
+        let (found_range, total_len) = match checked {
+            Ok(found) => found,
+            Err(details) => {
+                return Err(self
+                    .captured(transfer.capture, Some(response), details)
+                    .await)
+            }
+        };
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(wrap_reqwest_err(url))?
+            .to_vec();
+        if bytes.len() as u64 != found_range.end - found_range.start {
+            let capture = transfer.capture.take();
+            return Err(self.captured(capture, None, not_satisfied(status)).await);
+        }
+        self.record_bytes_downloaded(url, bytes.len());
+
+        Ok(RemoteRange {
+            stats: transfer.finish(bytes.len()),
+            bytes,
+            range: found_range,
+            total_len,
+            etag: found_etag.or_else(|| etag.map(|etag| etag.to_owned())),
+        })
+    }
+
+    /// Lists the entries of a remote zip, downloading only its central directory
+    ///
+    /// This uses range requests (see [`AxoClient::load_range`][]) to fetch the end of
+    /// the zip and then the central directory it points to, so checking what's in a
+    /// huge zip only costs a few requests and kilobytes. All the ranges are checked
+    /// to come from the same version of the zip.
+    pub async fn list_zip(&self, url: &UrlStr) -> Result<Vec<RemoteZipEntry>> {
+        let invalid = |reason: String| AxoassetError::RemoteZipInvalid {
+            origin_path: url.to_string(),
+            reason,
+        };
+
+        let probe = self.load_range(url, 0..1, None).await?;
+        let total_len = probe
+            .total_len()
+            .ok_or_else(|| invalid("the server didn't say how big it is".to_owned()))?;
+        let etag = probe.etag();
+        let tail_start = total_len.saturating_sub(zip_index::MAX_TAIL_LEN);
+        let tail = self.load_range(url, tail_start..total_len, etag).await?;
+        let dir =
+            zip_index::find_central_directory(tail.as_bytes(), tail_start).map_err(invalid)?;
+
+        // The central directory usually fits in the tail we already have
+        let tail_range = tail.range();
+        let dir_bytes = if dir.range.start >= tail_range.start && dir.range.end <= tail_range.end {
+            let start = (dir.range.start - tail_range.start) as usize;
+            let end = (dir.range.end - tail_range.start) as usize;
+            tail.as_bytes()[start..end].to_vec()
+        } else {
+            self.load_range(url, dir.range.clone(), etag)
+                .await?
+                .into_bytes()
+        };
+        zip_index::parse_central_directory(&dir_bytes, dir.entries).map_err(invalid)
+    }
+
+    /// GETs a remote directory listing and returns the URLs of the entries it contains
+    ///
+    /// See [`parse_index`][] for the supported listing formats.
+    pub async fn load_index(&self, url: &UrlStr) -> Result<Vec<UrlString>> {
+        let text = self.load_string(url).await?;
+        parse_index(url, &text)
     }
 
     /// GETs the URL and returns the raw [`reqwest::Response`][]
     pub async fn get(&self, url: &UrlStr) -> Result<reqwest::Response> {
-        self.client
-            .get(url)
-            .send()
+        self.send(url, self.new_request(reqwest::Method::GET, url))
             .await
-            .map_err(wrap_reqwest_err(url))
     }
 
     /// HEADs the URL and returns the raw [`reqwest::Response`][]
     pub async fn head(&self, url: &UrlStr) -> Result<reqwest::Response> {
-        self.client
-            .head(url)
-            .send()
+        self.send(url, self.new_request(reqwest::Method::HEAD, url))
             .await
-            .map_err(wrap_reqwest_err(url))
     }
+
+    /// Starts building a request, with the credentials for the url (if any)
+    fn new_request(&self, method: reqwest::Method, url: &UrlStr) -> reqwest::RequestBuilder {
+        let url = self.resolve_origin(url);
+        let request = self.request_to(method, &url);
+        match self
+            .credentials
+            .as_ref()
+            .and_then(|provider| provider.credentials(&url))
+        {
+            Some(credentials) => credentials.apply(request),
+            None => request,
+        }
+    }
+
+    /// Gets the url to actually request for a url, after applying the
+    /// [`AxoClient::with_origin_map`][] rules
+    pub(crate) fn resolve_origin<'u>(&self, url: &'u UrlStr) -> std::borrow::Cow<'u, UrlStr> {
+        match self.origin_map.as_ref().and_then(|map| map.apply(url)) {
+            Some(rewritten) => std::borrow::Cow::Owned(rewritten),
+            None => std::borrow::Cow::Borrowed(url),
+        }
+    }
+
+    /// Starts building a request for a url, without any credentials
+    pub(crate) fn request_to(
+        &self,
+        method: reqwest::Method,
+        url: &UrlStr,
+    ) -> reqwest::RequestBuilder {
+        let request = match url::Url::parse(url) {
+            // reqwest refuses to build requests for some of these, but a backend
+            // can answer them
+            Ok(parsed) if !matches!(parsed.scheme(), "http" | "https") => {
+                reqwest::RequestBuilder::from_parts(
+                    self.http.client().clone(),
+                    reqwest::Request::new(method, parsed),
+                )
+            }
+            _ => self.http.client().request(method, url),
+        };
+        let request = match &self.user_agent {
+            Some(user_agent) => request.header(reqwest::header::USER_AGENT, user_agent),
+            None => request,
+        };
+        match self.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// Sends a request, recording metrics about it and waiting out rate limits
+    async fn send(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let (response, _) = self.send_tracked(url, request).await?;
+        Ok(response)
+    }
+
+    /// Like [`AxoClient::send`][] but also tracks the [`TransferStats`][] of the request
+    async fn send_tracked(
+        &self,
+        url: &UrlStr,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<(reqwest::Response, Transfer)> {
+        let start = Instant::now();
+        let mut retries = 0;
+        // Build the request to capture it, then pick it back up, so even requests
+        // with streaming bodies (which can't be cloned) are captured
+        let capture = match &self.debug_capture {
+            Some(options) => {
+                let (client, built) = request.build_split();
+                let built = built.map_err(wrap_reqwest_err(url))?;
+                let capture = HttpCapture::request(options, &built);
+                request = reqwest::RequestBuilder::from_parts(client, built);
+                Some(capture)
+            }
+            None => None,
+        };
+        loop {
+            let retry = request.try_clone();
+            let response = match self.send_once(url, request).await {
+                Ok(response) => response,
+                Err(details) => return Err(self.captured(capture, None, details).await),
+            };
+            let limited = self.github_rate_limit.as_ref().and_then(|options| {
+                Some((options, github::rate_limited(options, url, &response)?))
+            });
+            let Some((options, limit)) = limited else {
+                let transfer = Transfer {
+                    start,
+                    retries,
+                    capture,
+                };
+                return Ok((response, transfer));
+            };
+            match retry {
+                Some(retry) if retries < options.max_retries && limit.wait <= options.max_wait => {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_retry(url);
+                    }
+                    tokio::time::sleep(limit.wait).await;
+                    retries += 1;
+                    request = retry;
+                }
+                _ => {
+                    let details = AxoassetError::RemoteGithubRateLimited {
+                        origin_path: url.to_string(),
+                        wait_secs: limit.wait.as_secs(),
+                        reset_at: limit.reset_at(),
+                    };
+                    return Err(self.captured(capture, Some(response), details).await);
+                }
+            }
+        }
+    }
+
+    /// Sends a request once the rate limit allows it, recording metrics about it
+    async fn send_once(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.wait(&self.resolve_origin(url)).await;
+        }
+        let start = Instant::now();
+        let response = self.execute(url, request).await;
+        if let Some(metrics) = &self.metrics {
+            let status = response.as_ref().ok().map(|r| r.status().as_u16());
+            metrics.record_request(url, status, start.elapsed());
+        }
+        response
+    }
+
+    /// Actually sends a request, with the backend for its url's scheme
+    async fn execute(
+        &self,
+        url: &UrlStr,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let request = request.build().map_err(wrap_reqwest_err(url))?;
+        let backend = self.backend(request.url().scheme());
+        if request.method() == reqwest::Method::HEAD {
+            backend.head(url, request).await
+        } else {
+            backend.fetch(url, request).await
+        }
+    }
+
+    /// Gets the backend that answers requests for urls with the given scheme
+    fn backend(&self, scheme: &str) -> &dyn RemoteBackend {
+        match self.backends.get(scheme) {
+            Some(backend) => backend.as_ref(),
+            None if scheme == "file" => &backend::FileBackend,
+            None if scheme == "data" => &backend::DataBackend,
+            None => &self.http,
+        }
+    }
+
+    /// Picks the filename for an asset from its response, with the backend that
+    /// answered it
+    fn filename(&self, url: &UrlStr, response: &reqwest::Response) -> Result<String> {
+        let origin = self.resolve_origin(url);
+        let scheme = origin.split_once(':').map_or("", |(scheme, _)| scheme);
+        // If we were redirected, the url we ended up at knows the name best
+        // (`/releases/latest/download` doesn't say what it is, where it goes does)
+        let named_by = if response.url().as_str() == &*origin {
+            url
+        } else {
+            response.url().as_str()
+        };
+        self.backend(&scheme.to_ascii_lowercase())
+            .filename(named_by, response.headers())
+    }
+
+    /// Checks whether a response's filename should come from its contents, see
+    /// [`AxoClient::with_content_sniffing`][]
+    fn needs_sniffing(&self, filename: &str, response: &reqwest::Response) -> bool {
+        use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+
+        let headers = response.headers();
+        self.sniff_content
+            && !filename.contains('.')
+            && headers
+                .get(CONTENT_DISPOSITION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(content_disposition_filename)
+                .is_none()
+            && match headers.get(CONTENT_TYPE) {
+                Some(content_type) => content_type
+                    .as_bytes()
+                    .starts_with(b"application/octet-stream"),
+                None => true,
+            }
+    }
+
+    /// Turns an error status into an error, capturing the exchange first if
+    /// [`AxoClient::with_debug_capture`][] is enabled
+    async fn error_for_status(
+        &self,
+        url: &UrlStr,
+        response: reqwest::Response,
+        capture: Option<HttpCapture>,
+    ) -> Result<reqwest::Response> {
+        if let Err(details) = response.error_for_status_ref() {
+            let details = wrap_reqwest_err(url)(details);
+            return Err(self.captured(capture, Some(response), details).await);
+        }
+        Ok(response)
+    }
+
+    /// Wraps an error with the captured details of the exchange (and the response,
+    /// if there was one), if [`AxoClient::with_debug_capture`][] is enabled
+    async fn captured(
+        &self,
+        capture: Option<HttpCapture>,
+        response: Option<reqwest::Response>,
+        details: AxoassetError,
+    ) -> AxoassetError {
+        let (Some(options), Some(mut capture)) = (&self.debug_capture, capture) else {
+            return details;
+        };
+        if let Some(response) = response {
+            capture = capture.response(options, response).await;
+        }
+        AxoassetError::RemoteRequestCaptured {
+            capture: Box::new(capture),
+            details: Box::new(details),
+        }
+    }
+
+    fn record_bytes_downloaded(&self, url: &UrlStr, bytes: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_bytes_downloaded(url, bytes as u64);
+        }
+    }
+}
+
+/// An in-progress transfer whose [`TransferStats`][] are being tracked
+struct Transfer {
+    start: Instant,
+    retries: u32,
+    /// The captured request, if [`AxoClient::with_debug_capture`][] is enabled
+    capture: Option<HttpCapture>,
+}
+
+impl Transfer {
+    /// Finishes the transfer after downloading the given number of bytes
+    fn finish(self, bytes: usize) -> TransferStats {
+        TransferStats {
+            bytes: bytes as u64,
+            duration: self.start.elapsed(),
+            retries: self.retries,
+        }
+    }
+}
+
+/// Decompresses a gzipped body
+#[cfg(feature = "remote-gzip")]
+fn gunzip(url: &UrlStr, compressed: &[u8], max_bytes: Option<u64>) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    // Stop just past the limit, so a gzip bomb can't use up all our memory
+    let limit = max_bytes.map_or(u64::MAX, |max| max.saturating_add(1));
+    let mut bytes = vec![];
+    flate2::read::GzDecoder::new(compressed)
+        .take(limit)
+        .read_to_end(&mut bytes)
+        .map_err(|details| AxoassetError::RemoteAssetDecompressFailed {
+            origin_path: url.to_string(),
+            details,
+        })?;
+    Ok(bytes)
 }
 
 fn wrap_reqwest_err(url: &UrlStr) -> impl FnOnce(reqwest::Error) -> AxoassetError + '_ {
-    |details| AxoassetError::RemoteAssetRequestFailed {
-        origin_path: url.to_string(),
-        details,
+    |details| {
+        let origin_path = url.to_string();
+        match tls_certificate_error(&details) {
+            Some(TlsCertificateError::Expired) => AxoassetError::RemoteTlsCertificateExpired {
+                origin_path,
+                details,
+            },
+            Some(TlsCertificateError::HostnameMismatch) => {
+                AxoassetError::RemoteTlsHostnameMismatch {
+                    origin_path,
+                    details,
+                }
+            }
+            Some(TlsCertificateError::UnknownIssuer) => AxoassetError::RemoteTlsUnknownIssuer {
+                origin_path,
+                details,
+            },
+            None if details.is_redirect() => AxoassetError::RemoteTooManyRedirects {
+                origin_path,
+                details,
+            },
+            None if details.is_timeout() => AxoassetError::RemoteRequestTimedOut {
+                origin_path,
+                details,
+            },
+            None => AxoassetError::RemoteAssetRequestFailed {
+                origin_path,
+                details,
+            },
+        }
     }
 }
 
+/// The kinds of certificate problems we have dedicated errors for
+enum TlsCertificateError {
+    Expired,
+    HostnameMismatch,
+    UnknownIssuer,
+}
+
+/// Digs through the causes of a reqwest error for a rustls certificate error
+fn tls_certificate_error(error: &reqwest::Error) -> Option<TlsCertificateError> {
+    use rustls::CertificateError;
+
+    let mut cause: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(err) = cause {
+        if let Some(rustls::Error::InvalidCertificate(cert_err)) = err.downcast_ref() {
+            return match cert_err {
+                CertificateError::Expired
+                | CertificateError::ExpiredContext { .. }
+                | CertificateError::NotValidYet
+                | CertificateError::NotValidYetContext { .. } => Some(TlsCertificateError::Expired),
+                CertificateError::NotValidForName
+                | CertificateError::NotValidForNameContext { .. } => {
+                    Some(TlsCertificateError::HostnameMismatch)
+                }
+                CertificateError::UnknownIssuer => Some(TlsCertificateError::UnknownIssuer),
+                _ => None,
+            };
+        }
+        // io::Errors hide the error they wrap from source(), so look inside them instead
+        cause = match err.downcast_ref::<std::io::Error>() {
+            Some(io_err) => io_err
+                .get_ref()
+                .map(|inner| inner as &(dyn std::error::Error + 'static)),
+            None => err.source(),
+        };
+    }
+    None
+}
+
 /// A remote asset is an asset that is fetched over the network.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RemoteAsset {
     /// A string containing a valid filename and extension. The filename is
     /// determined by the origin path and the content-type headers from the
@@ -125,15 +1513,20 @@ pub struct RemoteAsset {
     /// not need to be `https://origin.com/myfile.ext` as filename is determined by
     /// content-type headers in the server response.
     url: UrlString,
+    /// The URL the asset was actually loaded from, after following redirects
+    resolved_url: UrlString,
     /// The contents of the asset as a vector of bytes
     contents: Vec<u8>,
+    /// Statistics about the download
+    stats: TransferStats,
 }
 
 impl RemoteAsset {
     /// Gets the filename of the RemoteAsset
     ///
     /// Filename may be computed based on things like mimetypes, and does not necessarily
-    /// reflect the raw URL's paths.
+    /// reflect the raw URL's paths. If the request was redirected, it's based on the
+    /// [`RemoteAsset::resolved_url`][] rather than the url that was asked for.
     pub fn filename(&self) -> &str {
         &self.filename
     }
@@ -148,6 +1541,16 @@ impl RemoteAsset {
         &self.url
     }
 
+    /// Gets the url the RemoteAsset was actually loaded from, after following any
+    /// redirects (and applying [`AxoClient::with_origin_map`][])
+    ///
+    /// This is the same as [`RemoteAsset::url`][] unless the asset moved. For short
+    /// links (like a GitHub "latest release" url) this is where the real name of
+    /// the file can be found.
+    pub fn resolved_url(&self) -> &str {
+        &self.resolved_url
+    }
+
     /// Gets the bytes of the RemoteAsset
     pub fn as_bytes(&self) -> &[u8] {
         &self.contents
@@ -158,6 +1561,11 @@ impl RemoteAsset {
         self.contents
     }
 
+    /// Gets statistics about the download of the RemoteAsset
+    pub fn stats(&self) -> TransferStats {
+        self.stats
+    }
+
     /// Writes an RemoteAsset's bytes to the given local directory
     ///
     /// The filename used will be `RemoteAsset::filename`, and the resulting file
@@ -184,6 +1592,184 @@ impl RemoteAsset {
     }
 }
 
+/// The validators of a response, for revalidating it with [`AxoClient::load_if_modified`][]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheInfo {
+    /// The value of the response's `ETag` header
+    pub etag: Option<String>,
+    /// The value of the response's `Last-Modified` header
+    pub last_modified: Option<String>,
+}
+
+/// What a server says about an asset, see [`AxoClient::load_metadata`][]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RemoteMetadata {
+    /// The size of the asset in bytes, if the server said
+    pub content_length: Option<u64>,
+    /// The value of the `Content-Type` header
+    pub content_type: Option<String>,
+    /// The validators of the asset, for [`AxoClient::load_if_modified`][]
+    pub cache_info: CacheInfo,
+}
+
+/// The result of [`AxoClient::load_if_modified`][]
+#[derive(Debug, Clone)]
+pub enum ConditionalLoad {
+    /// The asset hasn't changed, so the copy the validators came from is still good
+    NotModified {
+        /// The validators to use next time
+        cache_info: CacheInfo,
+    },
+    /// The asset has changed (or the server doesn't support conditional requests)
+    Modified {
+        /// The new contents of the asset
+        asset: RemoteAsset,
+        /// The validators of the new contents
+        cache_info: CacheInfo,
+    },
+}
+
+/// The most memory [`AxoClient::load_source_with_options`][] sets aside for a body up
+/// front, however long the server says it is
+const MAX_PREALLOCATION: u64 = 64 * 1024;
+
+/// Options for [`AxoClient::load_source_with_options`][]
+///
+/// The defaults load the whole document, like [`AxoClient::load_source`][].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct SourceLoadOptions {
+    /// The largest body to accept, in bytes
+    ///
+    /// For gzipped documents (see `AxoClient::with_gzip_decoding`) this applies
+    /// to both the download and the decompressed text.
+    pub max_bytes: Option<u64>,
+}
+
+/// Some bytes of a remote asset, see [`AxoClient::load_range`][]
+#[derive(Debug, Clone)]
+pub struct RemoteRange {
+    bytes: Vec<u8>,
+    range: std::ops::Range<u64>,
+    total_len: Option<u64>,
+    etag: Option<String>,
+    stats: TransferStats,
+}
+
+impl RemoteRange {
+    /// Gets the bytes of the range
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Gets the bytes of the range
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Gets the offsets within the asset these bytes came from
+    pub fn range(&self) -> std::ops::Range<u64> {
+        self.range.clone()
+    }
+
+    /// Gets the total length of the asset, if the server reported it
+    pub fn total_len(&self) -> Option<u64> {
+        self.total_len
+    }
+
+    /// Gets the ETag of the version of the asset these bytes came from, if known
+    ///
+    /// Pass this to later [`AxoClient::load_range`][] calls to make sure all the
+    /// ranges come from the same version.
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Gets statistics about the download of the range
+    pub fn stats(&self) -> TransferStats {
+        self.stats
+    }
+}
+
+/// Parses a `Content-Range` header like `bytes 0-99/1234` into the (exclusive)
+/// range and total length
+fn parse_content_range(header: &str) -> Option<(std::ops::Range<u64>, Option<u64>)> {
+    let (range, total) = header.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start = start.trim().parse::<u64>().ok()?;
+    let end = end.trim().parse::<u64>().ok()?.checked_add(1)?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    (start < end).then_some((start..end, total))
+}
+
+/// A remote asset that was streamed into a temporary file, see [`AxoClient::download_temp`][]
+///
+/// The temporary file is deleted when this is dropped.
+#[derive(Debug)]
+pub struct TempDownload {
+    url: UrlString,
+    filename: String,
+    path: Utf8PathBuf,
+    file: tempfile::NamedTempFile,
+    stats: TransferStats,
+}
+
+impl TempDownload {
+    /// Gets the url the asset was downloaded from
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Gets the filename of the asset (computed the same way as [`RemoteAsset::filename`][])
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Gets the path of the temporary file
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Gets statistics about the download
+    pub fn stats(&self) -> TransferStats {
+        self.stats
+    }
+
+    /// Moves the temporary file to `dest_path`, so it won't be deleted
+    ///
+    /// If the destination is on a different filesystem the file is copied instead.
+    /// Any other failure to move it is an [`AxoassetError::RemoteAssetWriteFailed`][].
+    pub fn persist(self, dest_path: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+        let dest_path = dest_path.as_ref();
+        let copy_err = |details| AxoassetError::LocalAssetCopyFailed {
+            origin_path: self.path.to_string(),
+            dest_path: dest_path.to_string(),
+            details,
+        };
+        match self.file.persist(dest_path) {
+            Ok(_) => {}
+            // Rename doesn't work across filesystems, so fall back to copying
+            // (the temp file is still cleaned up when `e` is dropped)
+            Err(e) if e.error.kind() == std::io::ErrorKind::CrossesDevices => {
+                fs::copy(e.file.path(), dest_path).map_err(copy_err)?;
+            }
+            Err(e) => {
+                return Err(AxoassetError::RemoteAssetWriteFailed {
+                    origin_url: self.url.clone(),
+                    dest_path: dest_path.to_owned(),
+                    details: e.error,
+                })
+            }
+        }
+        Ok(dest_path.to_owned())
+    }
+}
+
 fn mimetype(headers: &reqwest::header::HeaderMap, origin_url: &UrlStr) -> Result<mime::Mime> {
     match headers.get(reqwest::header::CONTENT_TYPE) {
         Some(content_type) => {
@@ -198,13 +1784,7 @@ fn mimetype(headers: &reqwest::header::HeaderMap, origin_url: &UrlStr) -> Result
                     origin_path: origin_url.to_string(),
                     details,
                 })?;
-            match mtype.type_() {
-                mime::IMAGE => Ok(mtype),
-                mime::TEXT => Ok(mtype),
-                _ => Err(AxoassetError::RemoteAssetNonImageMimeType {
-                    origin_path: origin_url.to_string(),
-                }),
-            }
+            Ok(mtype)
         }
         None => Err(AxoassetError::RemoteAssetMissingContentTypeHeader {
             origin_path: origin_url.to_string(),
@@ -216,10 +1796,35 @@ fn extension(mimetype: mime::Mime, origin_path: &UrlStr) -> Option<String> {
     match mimetype.type_() {
         mime::IMAGE => image_extension(mimetype, origin_path).ok(),
         mime::TEXT => text_extension(mimetype, origin_path).ok(),
+        mime::APPLICATION => application_extension(&mimetype),
         _ => None,
     }
 }
 
+/// Guesses the extension for an `application/*` mime type, if it's a common one
+fn application_extension(mimetype: &mime::Mime) -> Option<String> {
+    // e.g. application/vnd.github+json
+    if let Some(suffix) = mimetype.suffix() {
+        return Some(suffix.to_string());
+    }
+    let extension = match mimetype.subtype().as_str() {
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "x-yaml" => "yaml",
+        "xml" => "xml",
+        "javascript" => "js",
+        "wasm" => "wasm",
+        "pdf" => "pdf",
+        "zip" => "zip",
+        "gzip" | "x-gzip" => "gz",
+        "x-xz" => "xz",
+        "zstd" => "zst",
+        "x-tar" => "tar",
+        _ => return None,
+    };
+    Some(extension.to_owned())
+}
+
 fn text_extension(mimetype: mime::Mime, origin_path: &UrlStr) -> Result<String> {
     if let Some(extension) = mimetype.suffix() {
         Ok(extension.to_string())
@@ -255,10 +1860,105 @@ fn image_extension(mimetype: mime::Mime, origin_path: &UrlStr) -> Result<String>
     }
 }
 
+/// How much of an asset [`sniff_extension`][] needs to see
+const SNIFF_LEN: usize = 1024;
+
+/// Reads as much of the start of a downloaded file as [`sniff_extension`][] needs
+fn read_sniff_start(path: &std::path::Path) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut start = Vec::with_capacity(SNIFF_LEN);
+    fs::File::open(path)
+        .and_then(|file| file.take(SNIFF_LEN as u64).read_to_end(&mut start))
+        .map_err(|details| AxoassetError::LocalAssetReadFailed {
+            origin_path: path.display().to_string(),
+            details,
+        })?;
+    Ok(start)
+}
+
+/// Moves a finished download from its temporary file to `dest_path`
+fn persist_temp(url: &UrlStr, temp_path: tempfile::TempPath, dest_path: &Utf8Path) -> Result<()> {
+    temp_path
+        .persist(dest_path)
+        .map_err(|err| AxoassetError::RemoteAssetWriteFailed {
+            origin_url: url.to_string(),
+            dest_path: dest_path.to_owned(),
+            details: err.error,
+        })
+}
+
+/// Adds the extension an asset's contents look like they have to its filename
+fn sniffed_filename(filename: String, contents: &[u8]) -> String {
+    match sniff_extension(contents) {
+        Some(extension) => format!("{filename}.{extension}"),
+        None => filename,
+    }
+}
+
+/// Guesses the extension of a file from the signature ("magic bytes") at the start
+/// of its contents
+///
+/// This recognizes common image formats (png, jpeg, gif, webp, ico, and svg),
+/// archives and compressed files (zip, tar, gz, xz, zst), pdfs and wasm modules.
+/// Anything else that's text (valid UTF-8 without control characters) is `txt`.
+/// Only the first kilobyte is looked at, so the start of a download is enough.
+pub fn sniff_extension(contents: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "png"),
+        (b"\xff\xd8\xff", "jpg"),
+        (b"GIF87a", "gif"),
+        (b"GIF89a", "gif"),
+        (b"\x00\x00\x01\x00", "ico"),
+        (b"%PDF-", "pdf"),
+        (b"PK\x03\x04", "zip"),
+        (b"PK\x05\x06", "zip"),
+        (b"\x1f\x8b", "gz"),
+        (b"\xfd7zXZ\x00", "xz"),
+        (b"\x28\xb5\x2f\xfd", "zst"),
+        (b"\x00asm", "wasm"),
+    ];
+    let contents = &contents[..contents.len().min(SNIFF_LEN)];
+    if let Some((_, extension)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| contents.starts_with(signature))
+    {
+        return Some(extension);
+    }
+    if contents.len() >= 12 && contents.starts_with(b"RIFF") && &contents[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+    if contents.get(257..262) == Some(b"ustar") {
+        return Some("tar");
+    }
+
+    // The cutoff can land in the middle of a character
+    let text = match std::str::from_utf8(contents) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&contents[..err.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    if text.is_empty()
+        || text
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+    {
+        return None;
+    }
+    let markup = text.trim_start_matches('\u{feff}').trim_start();
+    let is_svg = markup.starts_with("<svg")
+        || ((markup.starts_with("<?xml") || markup.starts_with("<!DOCTYPE svg"))
+            && markup.contains("<svg"));
+    Some(if is_svg { "svg" } else { "txt" })
+}
+
 // FIXME: https://github.com/axodotdev/axoasset/issues/6
 // FIXME: https://github.com/axodotdev/axoasset/issues/9
-/// Currently, this function will take an asset's origin path, and attempt
-/// to identify if the final segment of the URL is a filename.
+/// Currently, this function will use the filename from the response's
+/// `Content-Disposition` header if it has one. Otherwise it will take an asset's
+/// origin path, and attempt to identify if the final segment of the URL is a filename.
 ///
 /// If it does not find a filename it will drop the host from the origin
 /// url, slugify the set of the path, and then add an extension based on the
@@ -268,6 +1968,13 @@ fn image_extension(mimetype: mime::Mime, origin_path: &UrlStr) -> Result<String>
 /// avoid name conflicts, but this is a half measure at best and leaves a
 /// lot of room for improvement.
 pub fn filename(origin_url: &UrlStr, headers: &reqwest::header::HeaderMap) -> Result<String> {
+    if let Some(filename) = headers
+        .get(reqwest::header::CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(content_disposition_filename)
+    {
+        return Ok(filename);
+    }
     let mut filestem = url::Url::parse(origin_url)
         .map_err(|details| AxoassetError::UrlParse {
             origin_path: origin_url.to_owned(),
@@ -289,3 +1996,181 @@ pub fn filename(origin_url: &UrlStr, headers: &reqwest::header::HeaderMap) -> Re
         Ok(filestem)
     }
 }
+
+/// Gets the filename from a `Content-Disposition` header, like
+/// `attachment; filename="app.tar.gz"`
+///
+/// The RFC 6266 `filename*=UTF-8''...` form is preferred if both are present. Only
+/// the last path component of the name is kept, so a server can't pick where the
+/// file ends up.
+fn content_disposition_filename(header: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+    for param in split_header_params(header).into_iter().skip(1) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename" => {
+                plain = Some(
+                    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                        None => value.to_owned(),
+                    },
+                )
+            }
+            "filename*" => {
+                // charset'language'percent-encoded-name, and only UTF-8 is worth supporting
+                let mut parts = value.splitn(3, '\'');
+                let (Some(charset), Some(_language), Some(encoded)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                if charset.eq_ignore_ascii_case("utf-8") {
+                    extended = percent_encoding::percent_decode_str(encoded)
+                        .decode_utf8()
+                        .ok()
+                        .map(|name| name.into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    let filename = extended.or(plain)?;
+    let filename = filename.rsplit(['/', '\\']).next()?.trim();
+    (!filename.is_empty() && filename != "." && filename != "..").then(|| filename.to_owned())
+}
+
+/// Splits a header value on the `;`s that aren't inside quotes
+fn split_header_params(header: &str) -> Vec<&str> {
+    let mut params = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in header.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(&header[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&header[start..]);
+    params
+}
+
+/// Parses the body of a remote directory listing into the URLs of its entries
+///
+/// Two kinds of listings are understood:
+///
+/// * S3-style XML bucket listings (`<ListBucketResult>`), where every `<Key>` is
+///   resolved against the listing url (minus its query)
+/// * HTML auto-indexes like the ones produced by nginx and apache, where every
+///   `href` is resolved against the listing url
+///
+/// Only links that point beneath the listing url are returned, so parent directory
+/// links, column sorting links (`?C=N;O=D`), and links to other sites are skipped.
+/// Results are deduplicated and keep the order they appeared in.
+pub fn parse_index(origin_url: &UrlStr, body: &str) -> Result<Vec<UrlString>> {
+    let mut base = url::Url::parse(origin_url).map_err(|details| AxoassetError::UrlParse {
+        origin_path: origin_url.to_owned(),
+        details,
+    })?;
+    base.set_query(None);
+    base.set_fragment(None);
+
+    let links = if body.contains("<ListBucketResult") {
+        // S3 keys are plain paths, so make sure nothing in them looks like a scheme
+        xml_tag_contents(body, "Key")
+            .into_iter()
+            .map(|key| format!("./{key}"))
+            .collect()
+    } else {
+        html_hrefs(body)
+    };
+
+    let mut children: Vec<UrlString> = vec![];
+    for link in links {
+        let Ok(mut child) = base.join(&unescape_html(&link)) else {
+            continue;
+        };
+        child.set_fragment(None);
+        if child.query().is_some()
+            || child.as_str() == base.as_str()
+            || !child.as_str().starts_with(base.as_str())
+        {
+            continue;
+        }
+        let child = child.to_string();
+        if !children.contains(&child) {
+            children.push(child);
+        }
+    }
+    Ok(children)
+}
+
+/// Gets the text inside of every `<tag>...</tag>` in an xml document
+fn xml_tag_contents(body: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut results = vec![];
+    let mut rest = body;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        results.push(rest[..end].to_owned());
+        rest = &rest[end + close.len()..];
+    }
+    results
+}
+
+/// Gets the value of every `href` attribute in an html document
+fn html_hrefs(body: &str) -> Vec<String> {
+    let lowercase = body.to_ascii_lowercase();
+    let mut results = vec![];
+    let mut offset = 0;
+    while let Some(found) = lowercase[offset..].find("href") {
+        let mut idx = offset + found + "href".len();
+        offset = idx;
+        // Skip whitespace around the '='
+        idx += body[idx..].len() - body[idx..].trim_start().len();
+        if !body[idx..].starts_with('=') {
+            continue;
+        }
+        idx += 1;
+        idx += body[idx..].len() - body[idx..].trim_start().len();
+        let value = &body[idx..];
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                value.find(quote).map(|end| &value[..end])
+            }
+            Some(_) => value
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .map(|end| &value[..end]),
+            None => None,
+        };
+        if let Some(value) = value {
+            results.push(value.to_owned());
+        }
+    }
+    results
+}
+
+/// Undoes the handful of entity escapes that show up in listing links
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
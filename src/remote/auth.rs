@@ -0,0 +1,289 @@
+//! Credentials for fetching assets from private endpoints
+
+use std::sync::Arc;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::UrlStr;
+use crate::error::*;
+
+/// Credentials to send in a request's `Authorization` header
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Credentials {
+    /// A bearer token, like a GitHub personal access token
+    Bearer(String),
+    /// A username and (optional) password for HTTP basic auth
+    Basic {
+        /// The username
+        username: String,
+        /// The password, if any
+        password: Option<String>,
+    },
+}
+
+impl Credentials {
+    /// Creates bearer token credentials
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(token.into())
+    }
+
+    /// Creates HTTP basic auth credentials
+    pub fn basic(username: impl Into<String>, password: Option<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password,
+        }
+    }
+
+    /// Adds the credentials to a request
+    pub(crate) fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self {
+            Credentials::Bearer(token) => request.bearer_auth(token),
+            Credentials::Basic { username, password } => {
+                request.basic_auth(username, password.as_ref())
+            }
+        }
+    }
+}
+
+// Hand-written so secrets never end up in logs
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Credentials::Bearer(_) => f.write_str("Bearer(REDACTED)"),
+            Credentials::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"REDACTED")
+                .finish(),
+        }
+    }
+}
+
+type ProviderFn = dyn Fn(&UrlStr) -> Option<Credentials> + Send + Sync;
+
+/// A callback choosing the [`Credentials`][] to send to a url, see
+/// [`AxoClient::with_credentials`][super::AxoClient::with_credentials]
+#[derive(Clone)]
+pub struct CredentialProvider(Arc<ProviderFn>);
+
+impl CredentialProvider {
+    /// Create a CredentialProvider from a function that's given the url being
+    /// requested, and returns the credentials for it (if any)
+    pub fn new(provider: impl Fn(&UrlStr) -> Option<Credentials> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+
+    /// Create a CredentialProvider that sends the given credentials to one host
+    /// (like `api.github.com`), and nothing to any other host
+    pub fn for_host(host: impl Into<String>, credentials: Credentials) -> Self {
+        let host = host.into();
+        Self::new(move |url| {
+            let url = url::Url::parse(url).ok()?;
+            (url.host_str()? == host).then(|| credentials.clone())
+        })
+    }
+
+    /// Create a CredentialProvider that looks up each host in the user's netrc file,
+    /// the same way curl does
+    ///
+    /// The file is `$NETRC` if that's set, and otherwise `.netrc` in the user's home
+    /// dir (or `_netrc`, which is the traditional name on Windows). It's fine for the
+    /// file not to exist, there just won't be any credentials.
+    pub fn netrc() -> Result<Self> {
+        let path = match std::env::var("NETRC") {
+            Ok(path) => Some(Utf8PathBuf::from(path)),
+            Err(_) => std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .ok()
+                .and_then(|home| {
+                    let home = Utf8PathBuf::from(home);
+                    [".netrc", "_netrc"]
+                        .into_iter()
+                        .map(|name| home.join(name))
+                        .find(|path| path.exists())
+                }),
+        };
+        match path {
+            Some(path) if path.exists() => Self::from_netrc(path),
+            _ => Ok(Self::from_netrc_str("")),
+        }
+    }
+
+    /// Create a CredentialProvider from the netrc file at `path`, see
+    /// [`CredentialProvider::netrc`][]
+    pub fn from_netrc(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let contents = crate::LocalAsset::load_string(path)?;
+        Ok(Self::from_netrc_str(&contents))
+    }
+
+    /// Create a CredentialProvider from the contents of a netrc file
+    ///
+    /// The first `machine` entry matching a url's host is used, falling back to the
+    /// `default` entry if there is one. Entries are sent as HTTP basic auth, and
+    /// entries without a `login` are ignored.
+    pub fn from_netrc_str(contents: &str) -> Self {
+        let entries = parse_netrc(contents);
+        Self::new(move |url| {
+            let url = url::Url::parse(url).ok()?;
+            let host = url.host_str()?;
+            entries
+                .iter()
+                .find(|entry| entry.machine.as_deref() == Some(host))
+                .or_else(|| entries.iter().find(|entry| entry.machine.is_none()))
+                .and_then(|entry| {
+                    let login = entry.login.clone()?;
+                    Some(Credentials::basic(login, entry.password.clone()))
+                })
+        })
+    }
+
+    /// Gets the credentials for a url
+    pub(crate) fn credentials(&self, url: &UrlStr) -> Option<Credentials> {
+        (self.0)(url)
+    }
+}
+
+impl std::fmt::Debug for CredentialProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CredentialProvider")
+    }
+}
+
+/// An entry of a netrc file (`machine` is `None` for the `default` entry)
+#[derive(Default)]
+struct NetrcEntry {
+    machine: Option<String>,
+    login: Option<String>,
+    password: Option<String>,
+}
+
+/// Parses the entries out of a netrc file
+///
+/// Like curl, this is forgiving: unknown tokens are skipped, and `macdef` macros
+/// (which run until the next blank line) are ignored. Tokens are separated by any
+/// whitespace, newlines included, so an entry can be split across lines however
+/// it likes.
+fn parse_netrc(contents: &str) -> Vec<NetrcEntry> {
+    let mut entries = vec![];
+    let mut current: Option<NetrcEntry> = None;
+    let mut tokens = NetrcTokens {
+        rest: contents,
+        line_start: true,
+    };
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "machine" => {
+                entries.extend(current.take());
+                // A `machine` that's missing its name is dropped, rather than being
+                // mistaken for `default`
+                current = tokens.next().map(|machine| NetrcEntry {
+                    machine: Some(machine),
+                    ..NetrcEntry::default()
+                });
+            }
+            "default" => {
+                entries.extend(current.take());
+                current = Some(NetrcEntry::default());
+            }
+            "login" | "password" => {
+                let value = tokens.next();
+                if let Some(entry) = &mut current {
+                    if token == "login" {
+                        entry.login = value;
+                    } else {
+                        entry.password = value;
+                    }
+                }
+            }
+            "account" => {
+                tokens.next();
+            }
+            "macdef" => tokens.skip_macdef(),
+            _ => {}
+        }
+    }
+    entries.extend(current);
+    entries
+}
+
+/// The tokens of a netrc file
+///
+/// Values can be wrapped in double quotes to include whitespace, with `\"`, `\\`,
+/// `\n`, `\r`, and `\t` escapes. Lines starting with `#` are comments.
+struct NetrcTokens<'a> {
+    rest: &'a str,
+    /// Whether `rest` starts at the beginning of a line
+    line_start: bool,
+}
+
+impl Iterator for NetrcTokens<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let trimmed = self.rest.trim_start();
+            let skipped = &self.rest[..self.rest.len() - trimmed.len()];
+            let line_start = self.line_start || skipped.contains('\n');
+            self.rest = trimmed;
+            self.line_start = false;
+            if !(line_start && trimmed.starts_with('#')) {
+                break;
+            }
+            self.rest = trimmed.split_once('\n').map_or("", |(_, rest)| rest);
+            self.line_start = true;
+        }
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let Some(quoted) = self.rest.strip_prefix('"') else {
+            let end = self
+                .rest
+                .find(char::is_whitespace)
+                .unwrap_or(self.rest.len());
+            let (token, rest) = self.rest.split_at(end);
+            self.rest = rest;
+            return Some(token.to_owned());
+        };
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &quoted[index + 1..];
+                    return Some(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        // A quote that's never closed runs to the end of the file
+        self.rest = "";
+        Some(value)
+    }
+}
+
+impl NetrcTokens<'_> {
+    /// Skips the rest of a `macdef` line, and the macro's body on the lines after
+    /// it, up to and including the next blank line
+    fn skip_macdef(&mut self) {
+        let mut lines = self.rest.split_inclusive('\n');
+        let mut skipped = lines.next().map_or(0, str::len);
+        for line in lines {
+            skipped += line.len();
+            if line.trim().is_empty() {
+                break;
+            }
+        }
+        self.rest = &self.rest[skipped..];
+        self.line_start = true;
+    }
+}
@@ -0,0 +1,297 @@
+//! The backends that actually answer an [`AxoClient`][super::AxoClient]'s requests
+
+use camino::Utf8PathBuf;
+use percent_encoding::percent_decode_str;
+use reqwest::ResponseBuilderExt;
+
+use super::{wrap_reqwest_err, UrlStr};
+use crate::error::*;
+
+/// Something that can answer requests for urls with some scheme, see
+/// [`AxoClient::with_backend`][super::AxoClient::with_backend]
+///
+/// Requests are ordinary [`reqwest::Request`][]s (GETs, HEADs, and GETs with a
+/// `Range` header), and should be answered with a [`reqwest::Response`][] as if an
+/// HTTP server had sent it (use [`http::Response::builder`][] with
+/// [`reqwest::ResponseBuilderExt::url`][] and [`reqwest::Response::from`][] to make
+/// one). That way everything the AxoClient
+/// does on top of fetching (caching, checksums, ranges, metrics, writing to disk)
+/// works the same for every scheme. Urls are the ones actually being fetched, after
+/// any [`OriginMap`][super::OriginMap] rules, but errors should refer to `origin_path`.
+/// Failures that aren't covered by an existing error can be reported with
+/// [`AxoassetError::RemoteBackendFailed`][].
+///
+/// ```
+/// # use axoasset::remote::{AxoClient, RemoteBackend};
+/// # use axoasset::AxoassetError;
+/// use reqwest::ResponseBuilderExt;
+///
+/// /// Answers `mem://` urls with the name of the file they ask for
+/// #[derive(Debug)]
+/// struct EchoBackend;
+///
+/// #[async_trait::async_trait]
+/// impl RemoteBackend for EchoBackend {
+///     async fn fetch(
+///         &self,
+///         origin_path: &str,
+///         request: reqwest::Request,
+///     ) -> Result<reqwest::Response, AxoassetError> {
+///         let body = request.url().path().trim_start_matches('/').to_owned();
+///         let response = http::Response::builder()
+///             .url(request.url().clone())
+///             .body(body)
+///             .unwrap();
+///         Ok(reqwest::Response::from(response))
+///     }
+/// }
+///
+/// let client = AxoClient::with_reqwest(reqwest::Client::new())
+///     .with_backend("mem", std::sync::Arc::new(EchoBackend));
+/// ```
+#[async_trait::async_trait]
+pub trait RemoteBackend: std::fmt::Debug + Send + Sync {
+    /// Answers a request
+    async fn fetch(
+        &self,
+        origin_path: &UrlStr,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response>;
+
+    /// Answers a HEAD request, which should get the same headers as a GET would
+    /// (but no body)
+    ///
+    /// By default this is just passed to [`RemoteBackend::fetch`][].
+    async fn head(
+        &self,
+        origin_path: &UrlStr,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        self.fetch(origin_path, request).await
+    }
+
+    /// Picks the filename for an asset fetched with this backend, from the headers
+    /// of its response
+    ///
+    /// By default this is [`filename`][super::filename], which is based on the
+    /// `Content-Disposition` and `Content-Type` headers and the url.
+    fn filename(
+        &self,
+        origin_path: &UrlStr,
+        headers: &reqwest::header::HeaderMap,
+    ) -> Result<String> {
+        super::filename(origin_path, headers)
+    }
+}
+
+/// The default backend, which sends requests over the network with reqwest
+///
+/// This is what an [`AxoClient`][super::AxoClient] uses for any url whose scheme
+/// doesn't have a backend of its own. It's public so custom backends can wrap it,
+/// e.g. to fetch from an artifact store's HTTP API after signing the request.
+#[derive(Debug, Clone)]
+pub struct HttpBackend {
+    client: reqwest::Client,
+    #[cfg(feature = "remote-middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+}
+
+impl HttpBackend {
+    /// Creates an HttpBackend that sends requests with the given reqwest::Client
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            #[cfg(feature = "remote-middleware")]
+            middleware: None,
+        }
+    }
+
+    /// Sends requests through a middleware stack, see
+    /// [`AxoClient::with_middleware`][super::AxoClient::with_middleware]
+    #[cfg(feature = "remote-middleware")]
+    pub fn with_middleware(mut self, middleware: reqwest_middleware::ClientWithMiddleware) -> Self {
+        self.middleware = Some(middleware);
+        self
+    }
+
+    /// Gets the reqwest::Client this backend sends requests with
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+#[async_trait::async_trait]
+impl RemoteBackend for HttpBackend {
+    async fn fetch(
+        &self,
+        origin_path: &UrlStr,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        #[cfg(feature = "remote-middleware")]
+        if let Some(middleware) = &self.middleware {
+            return middleware
+                .execute(request)
+                .await
+                .map_err(|details| match details {
+                    reqwest_middleware::Error::Reqwest(details) => {
+                        wrap_reqwest_err(origin_path)(details)
+                    }
+                    reqwest_middleware::Error::Middleware(details) => {
+                        AxoassetError::RemoteMiddlewareFailed {
+                            origin_path: origin_path.to_string(),
+                            details: details.into(),
+                        }
+                    }
+                });
+        }
+        self.client
+            .execute(request)
+            .await
+            .map_err(wrap_reqwest_err(origin_path))
+    }
+}
+
+/// Answers requests for `file://` urls (which reqwest can't fetch) with the
+/// contents of the file, as if an HTTP server had sent it
+///
+/// The response says the file's name in a `Content-Disposition` header, so the
+/// asset gets the same filename a [`crate::LocalAsset`][] would.
+#[derive(Debug)]
+pub(crate) struct FileBackend;
+
+#[async_trait::async_trait]
+impl RemoteBackend for FileBackend {
+    async fn fetch(
+        &self,
+        origin_path: &UrlStr,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        let path = request
+            .url()
+            .to_file_path()
+            .ok()
+            .and_then(|path| Utf8PathBuf::from_path_buf(path).ok())
+            .ok_or_else(|| AxoassetError::RemoteFileUrlInvalid {
+                origin_path: origin_path.to_owned(),
+            })?;
+        let contents = crate::LocalAsset::load_bytes(&path)?;
+        let len = contents.len();
+        let body = if request.method() == reqwest::Method::HEAD {
+            vec![]
+        } else {
+            contents
+        };
+        let mut response = http::Response::builder()
+            .url(request.url().clone())
+            .header(http::header::CONTENT_LENGTH, len);
+        if let Some(name) = path.file_name() {
+            let encoded = name
+                .bytes()
+                .map(|byte| match byte {
+                    b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        (byte as char).to_string()
+                    }
+                    _ => format!("%{byte:02X}"),
+                })
+                .collect::<String>();
+            response = response.header(
+                http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename*=UTF-8''{encoded}"),
+            );
+        }
+        let response = response
+            .body(body)
+            .expect("a content-length and content-disposition are always a valid response");
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+/// Answers requests for `data:` urls with the data in them, as if an HTTP server
+/// had sent it
+///
+/// Both base64 (`data:image/png;base64,iVBO...`) and percent-encoded
+/// (`data:text/plain,hello%20world`) data are supported.
+#[derive(Debug)]
+pub(crate) struct DataBackend;
+
+#[async_trait::async_trait]
+impl RemoteBackend for DataBackend {
+    async fn fetch(
+        &self,
+        origin_path: &UrlStr,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        use base64::Engine;
+
+        let invalid = |reason: &str| AxoassetError::RemoteDataUrlInvalid {
+            // The whole url could be huge, the start is enough to identify it
+            origin_path: match origin_path.char_indices().nth(48) {
+                Some((idx, _)) => format!("{}...", &origin_path[..idx]),
+                None => origin_path.to_owned(),
+            },
+            reason: reason.to_owned(),
+        };
+        // The data can contain `?` and `#`, so it's everything after the scheme,
+        // not just the url's path (the scheme itself is always lowercased by now)
+        let (header, data) = request
+            .url()
+            .as_str()
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(','))
+            .ok_or_else(|| invalid("it doesn't have a `,` before the data"))?;
+        // `;base64` is case-insensitive like the rest of the media type
+        let base64_at = header.len().saturating_sub(";base64".len());
+        let (media_type, base64) = match header.get(base64_at..) {
+            Some(suffix) if suffix.eq_ignore_ascii_case(";base64") => (&header[..base64_at], true),
+            _ => (header, false),
+        };
+        let mut contents: Vec<u8> = percent_decode_str(data).collect();
+        if base64 {
+            contents.retain(|byte| !byte.is_ascii_whitespace());
+            contents = base64::engine::general_purpose::STANDARD
+                .decode(&contents)
+                .map_err(|_| invalid("its data isn't valid base64"))?;
+        }
+        let media_type = percent_decode_str(media_type)
+            .decode_utf8()
+            .map_err(|_| invalid("its media type isn't valid utf8"))?
+            .into_owned();
+        let media_type = if media_type.is_empty() || media_type.starts_with(';') {
+            format!("text/plain{media_type}")
+        } else {
+            media_type
+        };
+
+        let len = contents.len();
+        let body = if request.method() == reqwest::Method::HEAD {
+            vec![]
+        } else {
+            contents
+        };
+        let response = http::Response::builder()
+            .url(request.url().clone())
+            .header(http::header::CONTENT_LENGTH, len)
+            .header(http::header::CONTENT_TYPE, media_type)
+            .body(body)
+            .map_err(|_| invalid("its media type isn't a valid header"))?;
+        Ok(reqwest::Response::from(response))
+    }
+
+    /// There's no name to go on, so the asset is called `data` with an extension
+    /// picked the same way as for any other response
+    fn filename(
+        &self,
+        origin_path: &UrlStr,
+        headers: &reqwest::header::HeaderMap,
+    ) -> Result<String> {
+        Ok(
+            match super::mimetype(headers, origin_path)
+                .ok()
+                .and_then(|mime| super::extension(mime, origin_path))
+            {
+                Some(extension) => format!("data.{extension}"),
+                None => "data".to_owned(),
+            },
+        )
+    }
+}
@@ -0,0 +1,113 @@
+//! Loading remote assets without an async runtime
+
+use std::io::Write;
+use std::time::Instant;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{filename, wrap_reqwest_err, RemoteAsset, TransferStats, UrlStr};
+use crate::error::*;
+
+impl RemoteAsset {
+    /// Loads an asset from an http(s) url, blocking the current thread until it's done
+    ///
+    /// This is for sync programs that only need to download a thing or two, and don't
+    /// want to set up an async runtime (and an [`AxoClient`][super::AxoClient]) to do
+    /// it. None of an AxoClient's extras (caching, retries, `file://` urls, ...) are
+    /// available here.
+    ///
+    /// Like any of [`reqwest::blocking`][]'s functions, this panics if it's called
+    /// from inside an async runtime.
+    pub fn load_blocking(url: &UrlStr) -> Result<RemoteAsset> {
+        let start = Instant::now();
+        let response = send_blocking(url)?;
+        let filename = filename(response.url().as_str(), response.headers())?;
+        let resolved_url = response.url().to_string();
+        let contents = response.bytes().map_err(wrap_reqwest_err(url))?.to_vec();
+        Ok(RemoteAsset {
+            filename,
+            url: url.to_owned(),
+            resolved_url,
+            stats: TransferStats {
+                bytes: contents.len() as u64,
+                duration: start.elapsed(),
+                retries: 0,
+            },
+            contents,
+        })
+    }
+
+    /// Downloads an asset from an http(s) url to the given local dir, blocking the
+    /// current thread until it's done
+    ///
+    /// The filename is picked like [`RemoteAsset::filename`][], and the resulting path
+    /// is returned. The body is streamed to a temporary file in `dest_dir` as it
+    /// arrives, which only replaces anything already at the path once it's complete,
+    /// so a failed download leaves the directory as it was. See
+    /// [`RemoteAsset::load_blocking`][] for the caveats.
+    pub fn copy_blocking(url: &UrlStr, dest_dir: impl AsRef<Utf8Path>) -> Result<Utf8PathBuf> {
+        let mut response = send_blocking(url)?;
+        let dest_dir = dest_dir.as_ref();
+        let dest_path = dest_dir.join(filename(response.url().as_str(), response.headers())?);
+        let write_err = |details| AxoassetError::RemoteAssetWriteFailed {
+            origin_url: url.to_owned(),
+            dest_path: dest_path.clone(),
+            details,
+        };
+        let file = tempfile::NamedTempFile::new_in(dest_dir).map_err(write_err)?;
+        let mut writer = TrackedWriter {
+            inner: file,
+            error: None,
+        };
+        if let Err(details) = response.copy_to(&mut writer) {
+            // reqwest reports everything as its own error, so check if it was ours
+            return Err(match writer.error {
+                Some(details) => write_err(details),
+                None => wrap_reqwest_err(url)(details),
+            });
+        }
+        writer
+            .inner
+            .persist(&dest_path)
+            .map_err(|err| write_err(err.error))?;
+        Ok(dest_path)
+    }
+}
+
+/// A writer that keeps the first error it runs into
+struct TrackedWriter<W> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> TrackedWriter<W> {
+    fn track<T>(&mut self, result: std::io::Result<T>) -> std::io::Result<T> {
+        result.map_err(|details| {
+            let kind = details.kind();
+            self.error.get_or_insert(details);
+            std::io::Error::from(kind)
+        })
+    }
+}
+
+impl<W: Write> Write for TrackedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.track(result)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let result = self.inner.flush();
+        self.track(result)
+    }
+}
+
+/// GETs a url with a fresh blocking client
+fn send_blocking(url: &UrlStr) -> Result<reqwest::blocking::Response> {
+    reqwest::blocking::Client::builder()
+        .build()
+        .map_err(|details| AxoassetError::RemoteClientBuildFailed { details })?
+        .get(url)
+        .send()
+        .map_err(wrap_reqwest_err(url))
+}
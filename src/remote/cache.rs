@@ -0,0 +1,214 @@
+//! An on-disk cache of remote assets
+
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use super::{CacheInfo, UrlStr};
+use crate::{error::*, Digest, DigestAlgorithm};
+
+/// The first line of every cache entry, bumped whenever the format changes
+const ENTRY_HEADER: &str = "axoasset-cache-v1";
+
+/// An on-disk cache of remote assets, see [`AxoClient::with_cache`][super::AxoClient::with_cache]
+///
+/// Assets are stored along with the validators (`ETag` and `Last-Modified`) the
+/// server sent with them. A cached asset is always revalidated with a conditional
+/// request before it's used, so it's never stale, but an unchanged asset only costs
+/// a round trip instead of a download. Assets served without either validator
+/// aren't cached.
+///
+/// Entries are written atomically, so several processes can share a cache dir.
+///
+/// By default the cache grows without bound. Give it a size with
+/// [`RemoteCache::with_max_size`][] and the least recently used entries are evicted
+/// whenever an asset is stored and the cache is bigger than that.
+#[derive(Debug, Clone)]
+pub struct RemoteCache {
+    dir: Utf8PathBuf,
+    max_size: Option<u64>,
+}
+
+/// An asset read back out of a [`RemoteCache`][]
+pub(crate) struct CachedAsset {
+    pub filename: String,
+    /// Missing from entries written before it was recorded
+    pub resolved_url: Option<String>,
+    pub cache_info: CacheInfo,
+    pub contents: Vec<u8>,
+}
+
+impl RemoteCache {
+    /// Creates a RemoteCache that stores assets in the given dir
+    ///
+    /// The dir is created when the first asset is stored.
+    pub fn new(dir: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size: None,
+        }
+    }
+
+    /// Limits the total size of the assets in the cache to `max_bytes`
+    ///
+    /// The limit is enforced with [`RemoteCache::prune`][] every time an asset is
+    /// stored. An asset bigger than the limit is stored and then immediately evicted.
+    pub fn with_max_size(mut self, max_bytes: u64) -> Self {
+        self.max_size = Some(max_bytes);
+        self
+    }
+
+    /// Gets the dir the cache stores assets in
+    pub fn dir(&self) -> &Utf8Path {
+        &self.dir
+    }
+
+    /// Removes every asset from the cache
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            crate::LocalAsset::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    /// Evicts the least recently used assets until the cache fits in its
+    /// [max size][RemoteCache::with_max_size], returning how many bytes were freed
+    ///
+    /// An asset is used when it's stored or read back out of the cache. A cache
+    /// without a max size is left alone.
+    pub fn prune(&self) -> Result<u64> {
+        let Some(max_size) = self.max_size else {
+            return Ok(0);
+        };
+        let read_err = |details| AxoassetError::LocalAssetReadFailed {
+            origin_path: self.dir.to_string(),
+            details,
+        };
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(details) if details.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(details) => return Err(read_err(details)),
+        };
+        let mut entries = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                // Skip anything that isn't an entry (like an entry still being written)
+                let name = entry.file_name().into_string().ok()?;
+                if name.len() != 64 || !name.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                    return None;
+                }
+                let metadata = entry.metadata().ok()?;
+                Some((
+                    metadata.modified().ok()?,
+                    metadata.len(),
+                    self.dir.join(name),
+                ))
+            })
+            .collect::<Vec<_>>();
+        let mut size = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+        // Oldest first
+        entries.sort();
+        let mut freed = 0;
+        for (_, len, path) in entries {
+            if size <= max_size {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => freed += len,
+                // Another process may have evicted it first
+                Err(details) if details.kind() == std::io::ErrorKind::NotFound => {}
+                Err(details) => {
+                    return Err(AxoassetError::LocalAssetRemoveFailed {
+                        dest_path: path.to_string(),
+                        details,
+                    })
+                }
+            }
+            size -= len;
+        }
+        Ok(freed)
+    }
+
+    /// Gets the path of the entry for a url
+    fn entry_path(&self, url: &UrlStr) -> Utf8PathBuf {
+        let key = Digest::compute(DigestAlgorithm::Sha256, url.as_bytes());
+        self.dir.join(key.to_hex())
+    }
+
+    /// Reads the entry for a url, if there's a valid one
+    pub(crate) fn get(&self, url: &UrlStr) -> Option<CachedAsset> {
+        let entry_path = self.entry_path(url);
+        let entry = fs::read(&entry_path).ok()?;
+        let split = entry.windows(2).position(|window| window == b"\n\n")?;
+        let header = std::str::from_utf8(&entry[..split]).ok()?;
+        let mut lines = header.lines();
+        if lines.next()? != ENTRY_HEADER {
+            return None;
+        }
+        let mut filename = None;
+        let mut resolved_url = None;
+        let mut cache_info = CacheInfo::default();
+        for line in lines {
+            let (key, value) = line.split_once(": ")?;
+            match key {
+                "filename" => filename = Some(value.to_owned()),
+                "resolved-url" => resolved_url = Some(value.to_owned()),
+                "etag" => cache_info.etag = Some(value.to_owned()),
+                "last-modified" => cache_info.last_modified = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+        if self.max_size.is_some() {
+            // Mark the entry as recently used, for prune
+            let _ = fs::File::options()
+                .write(true)
+                .open(&entry_path)
+                .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+        }
+        Some(CachedAsset {
+            filename: filename?,
+            resolved_url,
+            cache_info,
+            contents: entry[split + 2..].to_vec(),
+        })
+    }
+
+    /// Stores the entry for a url, if it has any validators
+    ///
+    /// Caching is best-effort, so failing to write the entry isn't an error.
+    pub(crate) fn put(
+        &self,
+        url: &UrlStr,
+        filename: &str,
+        resolved_url: &UrlStr,
+        cache_info: &CacheInfo,
+        contents: &[u8],
+    ) {
+        if cache_info.etag.is_none() && cache_info.last_modified.is_none() {
+            return;
+        }
+        let mut entry =
+            format!("{ENTRY_HEADER}\nfilename: {filename}\nresolved-url: {resolved_url}\n");
+        if let Some(etag) = &cache_info.etag {
+            entry.push_str(&format!("etag: {etag}\n"));
+        }
+        if let Some(last_modified) = &cache_info.last_modified {
+            entry.push_str(&format!("last-modified: {last_modified}\n"));
+        }
+        entry.push('\n');
+        let mut entry = entry.into_bytes();
+        entry.extend_from_slice(contents);
+
+        let write = || -> std::io::Result<()> {
+            use std::io::Write;
+
+            fs::create_dir_all(&self.dir)?;
+            let mut file = tempfile::NamedTempFile::new_in(&self.dir)?;
+            file.write_all(&entry)?;
+            file.persist(self.entry_path(url))?;
+            Ok(())
+        };
+        let _ = write();
+        let _ = self.prune();
+    }
+}
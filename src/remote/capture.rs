@@ -0,0 +1,165 @@
+//! Capturing the details of failed HTTP exchanges for error reports
+
+use std::fmt;
+
+/// Headers whose values are never captured, even if they're asked for
+const CREDENTIAL_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Options for [`AxoClient::with_debug_capture`][super::AxoClient::with_debug_capture]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DebugCaptureOptions {
+    /// The request and response headers to capture (ignoring case)
+    ///
+    /// Headers that carry credentials (like `Authorization` and `Cookie`) are
+    /// always redacted, even if they're listed here.
+    pub headers: Vec<String>,
+    /// How many bytes of the response body to capture
+    pub max_body_bytes: usize,
+}
+
+impl Default for DebugCaptureOptions {
+    fn default() -> Self {
+        Self {
+            headers: [
+                "accept",
+                "content-type",
+                "content-length",
+                "content-encoding",
+                "content-range",
+                "etag",
+                "location",
+                "range",
+                "retry-after",
+                "server",
+                "user-agent",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            max_body_bytes: 512,
+        }
+    }
+}
+
+/// Sanitized details of a failed HTTP exchange
+///
+/// These are attached to errors by
+/// [`AxoClient::with_debug_capture`][super::AxoClient::with_debug_capture], and are
+/// meant to be safe to paste into a bug report: the url has its username, password,
+/// and query values removed, and only the configured headers are included.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HttpCapture {
+    /// The method of the request
+    pub method: String,
+    /// The sanitized url of the request
+    pub url: String,
+    /// The captured headers of the request
+    pub request_headers: Vec<(String, String)>,
+    /// The status of the response, if one was received
+    pub status: Option<u16>,
+    /// The captured headers of the response
+    pub response_headers: Vec<(String, String)>,
+    /// The start of the response body, if one was received
+    pub body: Option<String>,
+}
+
+impl HttpCapture {
+    /// Captures the details of a request that's about to be sent
+    pub(crate) fn request(options: &DebugCaptureOptions, request: &reqwest::Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            url: sanitize_url(request.url().clone()),
+            request_headers: capture_headers(options, request.headers()),
+            status: None,
+            response_headers: vec![],
+            body: None,
+        }
+    }
+
+    /// Adds the details of the response to a captured request, reading the start
+    /// of its body
+    pub(crate) async fn response(
+        mut self,
+        options: &DebugCaptureOptions,
+        mut response: reqwest::Response,
+    ) -> Self {
+        self.status = Some(response.status().as_u16());
+        self.response_headers = capture_headers(options, response.headers());
+        let mut body = vec![];
+        while body.len() < options.max_body_bytes {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        body.truncate(options.max_body_bytes);
+        self.body = Some(String::from_utf8_lossy(&body).into_owned());
+        self
+    }
+}
+
+impl fmt::Display for HttpCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request: {} {}", self.method, self.url)?;
+        for (name, value) in &self.request_headers {
+            write!(f, "\n  {name}: {value}")?;
+        }
+        match self.status {
+            Some(status) => write!(f, "\nresponse: {status}")?,
+            None => write!(f, "\nresponse: none received")?,
+        }
+        for (name, value) in &self.response_headers {
+            write!(f, "\n  {name}: {value}")?;
+        }
+        if let Some(body) = self.body.as_deref().filter(|body| !body.is_empty()) {
+            write!(f, "\nbody:\n{body}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes anything that looks like a credential from a url
+fn sanitize_url(mut url: url::Url) -> String {
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    if url.query().is_some() {
+        let keys = url
+            .query_pairs()
+            .map(|(key, _)| key.into_owned())
+            .collect::<Vec<_>>();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(keys.iter().map(|key| (key, "REDACTED")));
+    }
+    url.to_string()
+}
+
+/// Gets the headers that the options ask for, redacting credentials
+fn capture_headers(
+    options: &DebugCaptureOptions,
+    headers: &reqwest::header::HeaderMap,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            options
+                .headers
+                .iter()
+                .any(|wanted| wanted.eq_ignore_ascii_case(name.as_str()))
+        })
+        .map(|(name, value)| {
+            let value = if CREDENTIAL_HEADERS.contains(&name.as_str()) {
+                "REDACTED".to_owned()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
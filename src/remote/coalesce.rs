@@ -0,0 +1,69 @@
+//! Sharing one download between concurrent loads of the same url
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use super::{UrlStr, UrlString};
+use crate::error::Result;
+
+/// A kind of load and the url it's loading
+type LoadKey = (&'static str, UrlString);
+
+/// The loads an [`AxoClient`][super::AxoClient] currently has in flight
+///
+/// Each load is keyed by the url and the kind of load (since loading the same url
+/// as bytes and as a string produce different things), and holds a cell that's
+/// filled in with the result once the load finishes. The cells hold different types
+/// for different kinds of loads, so they're stored as `dyn Any`.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    loads: Mutex<HashMap<LoadKey, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl InFlight {
+    /// Runs `load`, unless the same kind of load of the url is already in flight, in
+    /// which case this waits for that one and returns a copy of its result
+    ///
+    /// Only successful results are shared. If the load fails, the error is returned
+    /// to the caller that ran it and one of the callers that were waiting on it tries
+    /// again itself.
+    pub(crate) async fn run<T, Fut>(
+        &self,
+        kind: &'static str,
+        url: &UrlStr,
+        load: impl FnOnce() -> Fut,
+    ) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>>,
+    {
+        let key = (kind, url.to_owned());
+        let cell = {
+            let mut loads = self.loads.lock().unwrap_or_else(|e| e.into_inner());
+            let cell = loads
+                .entry(key.clone())
+                .or_insert_with(|| Arc::new(tokio::sync::OnceCell::<T>::new()))
+                .clone();
+            // Each kind of load always uses the same type, so this can't fail
+            cell.downcast::<tokio::sync::OnceCell<T>>()
+                .expect("in-flight load had the wrong type")
+        };
+
+        let result = cell.get_or_try_init(load).await.cloned();
+
+        // Once the load is done, later loads should fetch the url again
+        let mut loads = self.loads.lock().unwrap_or_else(|e| e.into_inner());
+        let finished = loads.get(&key).is_some_and(|current| {
+            std::ptr::eq(
+                Arc::as_ptr(current) as *const (),
+                Arc::as_ptr(&cell) as *const (),
+            )
+        });
+        if finished {
+            loads.remove(&key);
+        }
+        result
+    }
+}
@@ -0,0 +1,122 @@
+//! Handling for the GitHub API's rate limits
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::UrlStr;
+use crate::time::UtcTime;
+
+/// How an [`AxoClient`][super::AxoClient] should handle hitting GitHub's API rate
+/// limits, see [`AxoClient::with_github_rate_limit`][super::AxoClient::with_github_rate_limit]
+///
+/// When a request to one of `hosts` is rejected for exceeding a rate limit (a 403 or
+/// 429 with `Retry-After` or exhausted `X-RateLimit-*` headers), the client waits until
+/// the limit resets and tries again. If that would take longer than `max_wait`, or
+/// there have already been `max_retries` retries, the request instead fails with
+/// [`AxoassetError::RemoteGithubRateLimited`][crate::AxoassetError::RemoteGithubRateLimited].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct GithubRateLimitOptions {
+    /// The hosts to apply GitHub's rate limit handling to
+    ///
+    /// Defaults to just `api.github.com`, add your GitHub Enterprise host if you have one.
+    pub hosts: Vec<String>,
+    /// How many times to retry a single request (0 disables retrying)
+    pub max_retries: u32,
+    /// The longest we're willing to wait for a rate limit to reset
+    pub max_wait: Duration,
+}
+
+impl Default for GithubRateLimitOptions {
+    fn default() -> Self {
+        Self {
+            hosts: vec!["api.github.com".to_owned()],
+            max_retries: 3,
+            max_wait: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The longest a rate limit is believed to last, so a bogus header can't claim
+/// one lasts practically forever
+const MAX_RATE_LIMIT_WAIT_SECS: u64 = 24 * 60 * 60;
+
+/// A response that was rejected by a rate limit
+pub(crate) struct RateLimited {
+    /// How long until the limit resets
+    pub wait: Duration,
+}
+
+impl RateLimited {
+    /// Gets when the limit resets, formatted for humans
+    pub fn reset_at(&self) -> String {
+        let Some(reset) = SystemTime::now().checked_add(self.wait) else {
+            return "an unknown time".to_owned();
+        };
+        let secs = reset
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        format_utc(secs)
+    }
+}
+
+/// Checks if a response was rejected by GitHub's rate limits
+pub(crate) fn rate_limited(
+    options: &GithubRateLimitOptions,
+    url: &UrlStr,
+    response: &reqwest::Response,
+) -> Option<RateLimited> {
+    let status = response.status().as_u16();
+    if status != 403 && status != 429 {
+        return None;
+    }
+    let host = url::Url::parse(url).ok()?.host_str()?.to_owned();
+    if !options.hosts.contains(&host) {
+        return None;
+    }
+
+    let headers = response.headers();
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.trim().parse::<u64>().ok())
+    };
+    if let Some(secs) = header("retry-after") {
+        return Some(RateLimited {
+            wait: Duration::from_secs(secs.min(MAX_RATE_LIMIT_WAIT_SECS)),
+        });
+    }
+    if header("x-ratelimit-remaining") == Some(0) {
+        if let Some(reset) = header("x-ratelimit-reset") {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0);
+            return Some(RateLimited {
+                wait: Duration::from_secs(reset.saturating_sub(now).min(MAX_RATE_LIMIT_WAIT_SECS)),
+            });
+        }
+    }
+    if status == 429 {
+        // GitHub says to wait at least a minute when a secondary limit doesn't say how long
+        return Some(RateLimited {
+            wait: Duration::from_secs(60),
+        });
+    }
+    // A plain 403 is a real permissions problem
+    None
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+fn format_utc(secs: u64) -> String {
+    let UtcTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+    } = UtcTime::from_unix(secs);
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02} UTC")
+}
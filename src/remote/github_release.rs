@@ -0,0 +1,303 @@
+//! Uploading assets to GitHub Releases
+
+use camino::Utf8Path;
+
+use super::{wrap_reqwest_err, AxoClient, UrlStr};
+use crate::error::*;
+use crate::Digest;
+
+/// Options for [`AxoClient::upload_github_release_asset_with_options`][]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct GithubReleaseOptions {
+    /// The base url of the GitHub API
+    ///
+    /// Defaults to `https://api.github.com`, change it for GitHub Enterprise (usually
+    /// `https://HOST/api/v3`).
+    pub api_url: String,
+    /// The label to show for the asset instead of its filename
+    pub label: Option<String>,
+    /// Whether to replace an asset with the same name if the release already has one
+    ///
+    /// Without this, an existing asset whose digest matches the file is assumed to be
+    /// from an earlier attempt at the same upload and is kept, and any other is an
+    /// error. One that GitHub says never finished uploading is always replaced.
+    pub overwrite: bool,
+}
+
+impl Default for GithubReleaseOptions {
+    fn default() -> Self {
+        Self {
+            api_url: "https://api.github.com".to_owned(),
+            label: None,
+            overwrite: false,
+        }
+    }
+}
+
+/// An asset of a GitHub Release, see [`AxoClient::upload_github_release_asset`][]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[non_exhaustive]
+pub struct GithubReleaseAsset {
+    /// The id of the asset
+    pub id: u64,
+    /// The filename of the asset
+    pub name: String,
+    /// The size of the asset in bytes
+    pub size: u64,
+    /// Where the asset can be downloaded from
+    pub browser_download_url: String,
+    /// Whether the asset finished uploading (`uploaded`) or not (`starter`)
+    #[serde(default)]
+    state: String,
+    /// The digest GitHub computed for the asset, like `sha256:<hex>`
+    /// (assets uploaded before GitHub started doing this don't have one)
+    #[serde(default)]
+    digest: Option<String>,
+    /// The API url of the asset, for deleting it
+    url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Release {
+    upload_url: String,
+    assets: Vec<GithubReleaseAsset>,
+}
+
+#[derive(serde::Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+impl AxoClient {
+    /// Uploads the file at `path` to the GitHub Release of `owner/repo` with the given tag
+    ///
+    /// See [`AxoClient::upload_github_release_asset_with_options`][] for details.
+    pub async fn upload_github_release_asset(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        path: impl AsRef<Utf8Path>,
+        token: &str,
+    ) -> Result<GithubReleaseAsset> {
+        self.upload_github_release_asset_with_options(
+            owner,
+            repo,
+            tag,
+            path,
+            token,
+            &GithubReleaseOptions::default(),
+        )
+        .await
+    }
+
+    /// Uploads the file at `path` to the GitHub Release of `owner/repo` with the given
+    /// tag, with the given [`GithubReleaseOptions`][]
+    ///
+    /// The asset is named after the file, and its `Content-Type` is picked from the
+    /// file's extension. `token` needs permission to write to the repo's contents.
+    ///
+    /// GitHub doesn't support resuming an upload partway through, so retrying is done
+    /// per asset instead: an asset that's already on the release with the same name
+    /// and the same digest is assumed to be from an earlier attempt and returned as-is
+    /// (unless [`GithubReleaseOptions::overwrite`][] is set), and one that GitHub says
+    /// never finished uploading is deleted and uploaded again. This makes it safe to
+    /// rerun a release pipeline that failed partway through.
+    ///
+    /// The file is streamed from disk as it's uploaded, so it's never all in memory.
+    pub async fn upload_github_release_asset_with_options(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        path: impl AsRef<Utf8Path>,
+        token: &str,
+        options: &GithubReleaseOptions,
+    ) -> Result<GithubReleaseAsset> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or_else(|| AxoassetError::LocalAssetMissingFilename {
+                origin_path: path.to_string(),
+            })?;
+        let mut release_url = parse_url(&options.api_url)?;
+        release_url
+            .path_segments_mut()
+            .map_err(|_| AxoassetError::RemoteGithubApiFailed {
+                url: options.api_url.clone(),
+                status: None,
+                message: "the API url can't have paths added to it".to_owned(),
+            })?
+            .pop_if_empty()
+            .extend(["repos", owner, repo, "releases", "tags", tag]);
+        let release: Release = self.github_get(release_url.as_str(), token).await?;
+
+        if let Some(existing) = release.assets.into_iter().find(|asset| asset.name == name) {
+            let finished = existing.state != "starter";
+            if finished && !options.overwrite {
+                if is_same_content(&existing, path)? {
+                    return Ok(existing);
+                }
+                return Err(AxoassetError::RemoteGithubAssetExists {
+                    name: name.to_owned(),
+                    tag: tag.to_owned(),
+                });
+            }
+            let request = self.github_request(reqwest::Method::DELETE, &existing.url, token);
+            let response = self.send(&existing.url, request).await?;
+            check_github_status(&existing.url, response).await?;
+        }
+
+        // The upload url is a template like `.../assets{?name,label}`
+        let upload_url = release
+            .upload_url
+            .split_once('{')
+            .map_or(release.upload_url.as_str(), |(url, _)| url);
+        let mut upload_url = parse_url(upload_url)?;
+        upload_url.query_pairs_mut().append_pair("name", name);
+        if let Some(label) = &options.label {
+            upload_url.query_pairs_mut().append_pair("label", label);
+        }
+        let url = upload_url.as_str();
+        let read_err = |details| AxoassetError::LocalAssetReadFailed {
+            origin_path: path.to_string(),
+            details,
+        };
+        let file = tokio::fs::File::open(path).await.map_err(read_err)?;
+        let len = file.metadata().await.map_err(read_err)?.len();
+        let request = self
+            .github_request(reqwest::Method::POST, url, token)
+            .header(reqwest::header::CONTENT_TYPE, content_type(name))
+            .header(reqwest::header::CONTENT_LENGTH, len)
+            .body(file);
+        let response = self.send(url, request).await?;
+        read_github_json(url, response).await
+    }
+
+    /// Sends a GET to the GitHub API and parses the JSON it answers with
+    async fn github_get<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &UrlStr,
+        token: &str,
+    ) -> Result<T> {
+        let request = self.github_request(reqwest::Method::GET, url, token);
+        let response = self.send(url, request).await?;
+        read_github_json(url, response).await
+    }
+
+    /// Starts a request to the GitHub API, authenticated with `token`
+    ///
+    /// This skips [`AxoClient::with_credentials`][], since the token is the credential.
+    fn github_request(
+        &self,
+        method: reqwest::Method,
+        url: &UrlStr,
+        token: &str,
+    ) -> reqwest::RequestBuilder {
+        let request = self
+            .request_to(method, &self.resolve_origin(url))
+            .bearer_auth(token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        // GitHub refuses requests without one
+        if self.user_agent.is_some() {
+            return request;
+        }
+        request.header(
+            reqwest::header::USER_AGENT,
+            concat!("axoasset/", env!("CARGO_PKG_VERSION")),
+        )
+    }
+}
+
+/// Checks that a GitHub API request succeeded, turning its error into
+/// [`AxoassetError::RemoteGithubApiFailed`][] if it didn't
+async fn check_github_status(
+    url: &UrlStr,
+    response: reqwest::Response,
+) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let message = match response.json::<ApiError>().await {
+        Ok(error) => error.message,
+        Err(_) => status
+            .canonical_reason()
+            .unwrap_or("unknown error")
+            .to_owned(),
+    };
+    Err(AxoassetError::RemoteGithubApiFailed {
+        url: url.to_owned(),
+        status: Some(status.as_u16()),
+        message,
+    })
+}
+
+/// Reads the JSON a GitHub API request answered with
+async fn read_github_json<T: serde::de::DeserializeOwned>(
+    url: &UrlStr,
+    response: reqwest::Response,
+) -> Result<T> {
+    check_github_status(url, response)
+        .await?
+        .json()
+        .await
+        .map_err(wrap_reqwest_err(url))
+}
+
+fn parse_url(url: &UrlStr) -> Result<url::Url> {
+    url::Url::parse(url).map_err(|details| AxoassetError::UrlParse {
+        origin_path: url.to_owned(),
+        details,
+    })
+}
+
+/// Checks if an asset already on a release is provably the file at `path`, by its digest
+///
+/// An asset without a digest we understand can't be proven to match, so it doesn't.
+fn is_same_content(asset: &GithubReleaseAsset, path: &Utf8Path) -> Result<bool> {
+    let Some(expected) = asset
+        .digest
+        .as_deref()
+        .and_then(|digest| digest.parse::<Digest>().ok())
+    else {
+        return Ok(false);
+    };
+    let actual = crate::LocalAsset::hash_file(path, expected.algorithm())?;
+    Ok(actual == expected)
+}
+
+/// Picks the `Content-Type` to upload a file with from its name
+fn content_type(name: &str) -> &'static str {
+    let name = name.to_ascii_lowercase();
+    const TYPES: &[(&str, &str)] = &[
+        (".tar.gz", "application/gzip"),
+        (".tgz", "application/gzip"),
+        (".gz", "application/gzip"),
+        (".tar.xz", "application/x-xz"),
+        (".xz", "application/x-xz"),
+        (".tar.zst", "application/zstd"),
+        (".tar.zstd", "application/zstd"),
+        (".zst", "application/zstd"),
+        (".tar", "application/x-tar"),
+        (".zip", "application/zip"),
+        (".json", "application/json"),
+        (".txt", "text/plain"),
+        (".sha256", "text/plain"),
+        (".sha512", "text/plain"),
+        (".sh", "application/x-sh"),
+        (".ps1", "text/plain"),
+        (".msi", "application/x-msi"),
+        (".exe", "application/vnd.microsoft.portable-executable"),
+        (".dmg", "application/x-apple-diskimage"),
+        (".deb", "application/vnd.debian.binary-package"),
+        (".rpm", "application/x-rpm"),
+        (".wasm", "application/wasm"),
+    ];
+    TYPES
+        .iter()
+        .find(|(extension, _)| name.ends_with(extension))
+        .map_or("application/octet-stream", |(_, content_type)| content_type)
+}
@@ -0,0 +1,226 @@
+//! Pinning remote assets to the digests they had when they were first fetched
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use camino::Utf8Path;
+
+use super::{AxoClient, RemoteAsset, UrlStr, UrlString};
+use crate::error::*;
+use crate::time::UtcTime;
+use crate::{Digest, DigestAlgorithm, LocalAsset, SourceFile};
+
+/// What an entry of [`LockedAssets`][] remembers about a remote asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LockedAsset {
+    /// The digest the asset had
+    pub digest: Digest,
+    /// The size of the asset in bytes
+    pub size: u64,
+    /// When the asset was fetched and recorded (to the second)
+    pub fetched_at: SystemTime,
+}
+
+impl LockedAsset {
+    /// Records the contents of an asset that was just fetched, with a sha256 digest
+    pub fn new(contents: &[u8]) -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            digest: Digest::compute(DigestAlgorithm::Sha256, contents),
+            size: contents.len() as u64,
+            fetched_at: SystemTime::UNIX_EPOCH + Duration::from_secs(now.as_secs()),
+        }
+    }
+}
+
+/// How [`AxoClient::load_locked`][] treats [`LockedAssets`][]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LockMode {
+    /// Check locked assets against their entries, and lock any new ones
+    #[default]
+    Update,
+    /// Check locked assets against their entries, and refuse to load any others
+    /// with [`AxoassetError::RemoteAssetNotLocked`][] (for CI)
+    Frozen,
+    /// Lock every asset as it is now, replacing any entry it already had
+    Refresh,
+}
+
+/// A lockfile for remote assets, recording the digest each url had when it was
+/// fetched, so later fetches can be checked against it
+///
+/// This brings lockfile-style reproducibility to configs that reference remote
+/// assets: load them with [`AxoClient::load_locked`][], and commit the file written
+/// by [`LockedAssets::write`][] next to the config. The file has one line per url,
+/// with its digest, size in bytes, and when it was fetched:
+///
+/// ```text
+/// # Remote assets pinned by axoasset
+/// https://example.com/logo.png sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae 3 2024-05-01T12:00:00Z
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockedAssets {
+    entries: BTreeMap<UrlString, LockedAsset>,
+}
+
+impl LockedAssets {
+    /// Creates an empty lockfile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a lockfile from disk, or an empty one if it doesn't exist yet
+    pub fn load(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        Self::parse(&SourceFile::load_local(path)?)
+    }
+
+    /// Parses the contents of a lockfile
+    pub fn parse(source: &SourceFile) -> Result<Self> {
+        let invalid = |substr: &str, reason: &str| AxoassetError::LockedAssetsParse {
+            source_file: source.clone(),
+            span: source.span_for_substr(substr),
+            reason: reason.to_owned(),
+        };
+        let mut entries = BTreeMap::new();
+        for line in source.contents().lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let fields = trimmed.split_whitespace().collect::<Vec<_>>();
+            let [url, digest, size, fetched_at] = fields[..] else {
+                return Err(invalid(
+                    trimmed,
+                    "expected a url, digest, size, and fetch time",
+                ));
+            };
+            let entry = LockedAsset {
+                digest: digest
+                    .parse()
+                    .map_err(|_| invalid(digest, "invalid digest"))?,
+                size: size.parse().map_err(|_| invalid(size, "invalid size"))?,
+                fetched_at: UtcTime::parse_rfc3339(fetched_at)
+                    .and_then(|secs| SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+                    .ok_or_else(|| invalid(fetched_at, "invalid timestamp"))?,
+            };
+            if entries.insert(url.to_owned(), entry).is_some() {
+                return Err(invalid(url, "this url is locked more than once"));
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Writes the lockfile to disk (creating its parent directories if needed)
+    pub fn write(&self, path: impl AsRef<Utf8Path>) -> Result<()> {
+        LocalAsset::write_new_all(&self.to_string(), path)?;
+        Ok(())
+    }
+
+    /// Gets the entry for a url
+    pub fn get(&self, url: &UrlStr) -> Option<&LockedAsset> {
+        self.entries.get(url)
+    }
+
+    /// Sets the entry for a url, returning the entry it replaced
+    pub fn insert(&mut self, url: impl Into<UrlString>, entry: LockedAsset) -> Option<LockedAsset> {
+        self.entries.insert(url.into(), entry)
+    }
+
+    /// Removes the entry for a url, returning it
+    pub fn remove(&mut self, url: &UrlStr) -> Option<LockedAsset> {
+        self.entries.remove(url)
+    }
+
+    /// Iterates over the urls and their entries, sorted by url
+    pub fn iter(&self) -> impl Iterator<Item = (&UrlStr, &LockedAsset)> {
+        self.entries
+            .iter()
+            .map(|(url, entry)| (url.as_str(), entry))
+    }
+
+    /// Gets how many urls are locked
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if no urls are locked
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl std::fmt::Display for LockedAssets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Remote assets pinned by axoasset")?;
+        for (url, entry) in &self.entries {
+            let secs = entry
+                .fetched_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let fetched_at = UtcTime::from_unix(secs).to_rfc3339();
+            writeln!(f, "{url} {} {} {fetched_at}", entry.digest, entry.size)?;
+        }
+        Ok(())
+    }
+}
+
+impl AxoClient {
+    /// Loads an asset like [`AxoClient::load_asset`][], checking it against (and
+    /// recording it in) a [`LockedAssets`][] according to `mode`
+    ///
+    /// An asset that doesn't match its entry fails with
+    /// [`AxoassetError::RemoteAssetSizeMismatch`][] or
+    /// [`AxoassetError::RemoteAssetChecksumMismatch`][] (unless `mode` is
+    /// [`LockMode::Refresh`][]). Remember to [`LockedAssets::write`][] the lockfile
+    /// afterwards if it changed.
+    pub async fn load_locked(
+        &self,
+        url: &UrlStr,
+        locked: &mut LockedAssets,
+        mode: LockMode,
+    ) -> Result<RemoteAsset> {
+        let entry = match mode {
+            LockMode::Refresh => None,
+            LockMode::Update | LockMode::Frozen => locked.get(url),
+        };
+        match entry {
+            Some(entry) => {
+                // A different size is a cheaper (and clearer) giveaway than the digest
+                let asset = self.load_asset(url).await?;
+                let actual = asset.as_bytes().len() as u64;
+                if actual != entry.size {
+                    return Err(AxoassetError::RemoteAssetSizeMismatch {
+                        origin_path: url.to_owned(),
+                        expected: entry.size,
+                        actual,
+                    });
+                }
+                let actual = Digest::compute(entry.digest.algorithm(), asset.as_bytes());
+                if actual != entry.digest {
+                    return Err(AxoassetError::RemoteAssetChecksumMismatch {
+                        origin_path: url.to_owned(),
+                        expected: entry.digest.clone(),
+                        actual,
+                    });
+                }
+                Ok(asset)
+            }
+            None if mode == LockMode::Frozen => Err(AxoassetError::RemoteAssetNotLocked {
+                origin_path: url.to_owned(),
+            }),
+            None => {
+                let asset = self.load_asset(url).await?;
+                locked.insert(url, LockedAsset::new(asset.as_bytes()));
+                Ok(asset)
+            }
+        }
+    }
+}
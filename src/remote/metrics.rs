@@ -0,0 +1,69 @@
+//! Hooks for collecting metrics about remote operations
+
+use std::time::Duration;
+
+use super::UrlStr;
+
+/// A sink for metrics about the remote operations an [`AxoClient`][super::AxoClient] performs
+///
+/// Implement this to forward axoasset's network activity to your own telemetry, and
+/// attach it with [`AxoClient::with_metrics`][super::AxoClient::with_metrics]. Every
+/// method has a default no-op implementation, so you only need to implement the ones
+/// you care about. All clones of an AxoClient share the same sink, so the numbers are
+/// aggregated across every operation they perform.
+pub trait RemoteMetrics: Send + Sync {
+    /// A request completed (successfully or not) after the given amount of time
+    ///
+    /// `status` is the HTTP status code of the response, or None if no response
+    /// was received at all.
+    fn record_request(&self, url: &UrlStr, status: Option<u16>, duration: Duration) {
+        let _ = (url, status, duration);
+    }
+
+    /// The body of a response was downloaded
+    fn record_bytes_downloaded(&self, url: &UrlStr, bytes: u64) {
+        let _ = (url, bytes);
+    }
+
+    /// A request was retried
+    fn record_retry(&self, url: &UrlStr) {
+        let _ = url;
+    }
+
+    /// A request was served from a cache instead of the network
+    fn record_cache_hit(&self, url: &UrlStr) {
+        let _ = url;
+    }
+}
+
+impl std::fmt::Debug for dyn RemoteMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RemoteMetrics")
+    }
+}
+
+/// Statistics about a single download, see [`RemoteAsset::stats`][super::RemoteAsset::stats]
+///
+/// Unlike [`RemoteMetrics`][] these are specific to one operation, so they can be logged
+/// next to the result (e.g. to point out slow hosts).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TransferStats {
+    /// How many bytes of body were downloaded
+    pub bytes: u64,
+    /// How long the whole operation took, from sending the first request to
+    /// receiving the last byte (including any time spent waiting to retry)
+    pub duration: Duration,
+    /// How many times the request was retried
+    pub retries: u32,
+}
+
+impl TransferStats {
+    /// Gets the average throughput of the transfer in bytes per second
+    ///
+    /// Returns None if the transfer took no measurable time.
+    pub fn throughput(&self) -> Option<f64> {
+        let secs = self.duration.as_secs_f64();
+        (secs > 0.0).then(|| self.bytes as f64 / secs)
+    }
+}
@@ -0,0 +1,87 @@
+//! Rewriting the origins of remote assets, for mirrors and air-gapped machines
+
+use super::{UrlStr, UrlString};
+use crate::error::*;
+
+/// Rewrites the urls an [`AxoClient`][super::AxoClient] loads from, see
+/// [`AxoClient::with_origin_map`][super::AxoClient::with_origin_map]
+///
+/// Each rule maps one url pattern to another. A pattern ending in `*` matches any url
+/// starting with the rest of it, and whatever the `*` matched replaces the `*` at the
+/// end of the replacement. A pattern without a `*` only matches that exact url. The
+/// first rule that matches a url wins.
+///
+/// ```
+/// # use axoasset::remote::OriginMap;
+/// let map = OriginMap::new().rewrite("https://cdn.example.com/*", "file:///mirror/*");
+/// assert_eq!(
+///     map.apply("https://cdn.example.com/app/1.0.0/app.tar.gz").as_deref(),
+///     Some("file:///mirror/app/1.0.0/app.tar.gz"),
+/// );
+/// assert_eq!(map.apply("https://example.com/app.tar.gz"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OriginMap {
+    rules: Vec<(String, String)>,
+}
+
+impl OriginMap {
+    /// Creates an OriginMap with no rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule rewriting urls matching `from` to `to`
+    pub fn rewrite(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rules.push((from.into(), to.into()));
+        self
+    }
+
+    /// Parses an OriginMap from text with one `from -> to` rule per line
+    ///
+    /// Blank lines and lines starting with `#` are ignored. This is intended for
+    /// reading rules out of a file or environment variable, so a machine can be
+    /// pointed at a mirror without changing anything else.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut map = Self::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule = line
+                .split_once("->")
+                .map(|(from, to)| (from.trim(), to.trim()))
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty());
+            let Some((from, to)) = rule else {
+                return Err(AxoassetError::OriginMapInvalid {
+                    line: idx + 1,
+                    rule: line.to_owned(),
+                });
+            };
+            map = map.rewrite(from, to);
+        }
+        Ok(map)
+    }
+
+    /// Gets whether the map has no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Gets the url a url is rewritten to, or `None` if no rule matches it
+    pub fn apply(&self, url: &UrlStr) -> Option<UrlString> {
+        self.rules
+            .iter()
+            .find_map(|(from, to)| match from.strip_suffix('*') {
+                Some(prefix) => {
+                    let rest = url.strip_prefix(prefix)?;
+                    Some(match to.strip_suffix('*') {
+                        Some(to) => format!("{to}{rest}"),
+                        None => to.clone(),
+                    })
+                }
+                None => (url == from).then(|| to.clone()),
+            })
+    }
+}
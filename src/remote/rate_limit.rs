@@ -0,0 +1,109 @@
+//! Spacing out requests so an [`AxoClient`][super::AxoClient] doesn't trip a server's throttling
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::UrlStr;
+use crate::error::*;
+
+/// How fast an [`AxoClient`][super::AxoClient] is allowed to send requests, see
+/// [`AxoClient::with_rate_limit`][super::AxoClient::with_rate_limit]
+///
+/// Limits are in requests per second, and requests that would exceed one wait until
+/// they're allowed instead of failing. A request has to be allowed by the overall
+/// limit and by the limit for its host (if any). Every limit has to allow at least
+/// one request a year.
+///
+/// ```
+/// # use axoasset::remote::RateLimitOptions;
+/// let mut options = RateLimitOptions::default();
+/// options.requests_per_second = Some(20.0);
+/// options.per_host.insert("api.github.com".to_owned(), 5.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct RateLimitOptions {
+    /// The most requests to send per second, across every host
+    pub requests_per_second: Option<f64>,
+    /// The most requests to send per second to each of these hosts (like `api.github.com`)
+    pub per_host: BTreeMap<String, f64>,
+}
+
+/// The longest wait between requests a limit can ask for, which leaves the times
+/// that requests are allowed at plenty of room before they'd overflow
+const MAX_INTERVAL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// The shared state of a rate limit, which all clones of an AxoClient wait on
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Option<Duration>,
+    host_intervals: HashMap<String, Duration>,
+    /// When the next request is allowed, overall (`None`) and for each limited host
+    next_allowed: Mutex<HashMap<Option<String>, Instant>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(options: &RateLimitOptions) -> Result<Self> {
+        let interval = |limit: &str, rate: f64| {
+            let interval = (rate > 0.0)
+                .then(|| Duration::try_from_secs_f64(1.0 / rate).ok())
+                .flatten()
+                .filter(|interval| *interval <= MAX_INTERVAL);
+            interval.ok_or_else(|| AxoassetError::RateLimitInvalid {
+                limit: limit.to_owned(),
+                rate,
+            })
+        };
+        Ok(Self {
+            interval: options
+                .requests_per_second
+                .map(|rate| interval("requests_per_second", rate))
+                .transpose()?,
+            host_intervals: options
+                .per_host
+                .iter()
+                .map(|(host, rate)| Ok((host.to_ascii_lowercase(), interval(host, *rate)?)))
+                .collect::<Result<_>>()?,
+            next_allowed: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Waits until a request to `url` is allowed
+    ///
+    /// Only urls with a host are limited, so `file://` and `data:` urls never wait.
+    pub(crate) async fn wait(&self, url: &UrlStr) {
+        let Some(host) = url::Url::parse(url)
+            .ok()
+            .filter(|url| url.scheme() != "file")
+            .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        else {
+            return;
+        };
+        let host_interval = self.host_intervals.get(&host).copied();
+        let limits = [(None, self.interval), (Some(host), host_interval)];
+
+        // Claim the next slot of every limit now, so concurrent requests queue up
+        // behind each other instead of all waking at once
+        let now = Instant::now();
+        let mut start = now;
+        {
+            let mut next_allowed = self.next_allowed.lock().unwrap();
+            for (key, interval) in &limits {
+                if interval.is_some() {
+                    if let Some(next) = next_allowed.get(key) {
+                        start = start.max(*next);
+                    }
+                }
+            }
+            for (key, interval) in limits {
+                if let Some(interval) = interval {
+                    next_allowed.insert(key, start + interval);
+                }
+            }
+        }
+        if start > now {
+            tokio::time::sleep(start - now).await;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! A builder for requests that need more than a plain url
+
+use super::{AxoClient, Credentials, RemoteAsset, UrlStr, UrlString};
+use crate::{error::*, SourceFile};
+
+/// A GET request being built up, see [`AxoClient::request`][]
+///
+/// Query parameters are escaped and appended to the url, so values can contain
+/// anything. Invalid header names or values are reported when the request is sent.
+#[derive(Debug, Clone)]
+#[must_use = "requests don't do anything until one of the load methods is called"]
+pub struct RemoteRequest<'a> {
+    client: &'a AxoClient,
+    url: UrlString,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    credentials: Option<Credentials>,
+    timeout: Option<std::time::Duration>,
+    user_agent: Option<String>,
+}
+
+impl<'a> RemoteRequest<'a> {
+    pub(crate) fn new(client: &'a AxoClient, url: &UrlStr) -> Self {
+        Self {
+            client,
+            url: url.to_owned(),
+            query: vec![],
+            headers: vec![],
+            credentials: None,
+            timeout: None,
+            user_agent: None,
+        }
+    }
+
+    /// Appends a query parameter to the url
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds a header to the request
+    ///
+    /// Adding a header more than once sends every value.
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Authenticates the request with the given credentials, instead of the ones
+    /// from [`AxoClient::with_credentials`][]
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Asks for the response in the given languages (like `"fr-CH, fr;q=0.9, en;q=0.8"`)
+    pub fn accept_language(self, languages: impl Into<String>) -> Self {
+        self.header(reqwest::header::ACCEPT_LANGUAGE.as_str(), languages)
+    }
+
+    /// Gives up on the request if it takes longer than `timeout` in total, instead
+    /// of the limit from [`AxoClient::with_timeout`][] (if any)
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends the request with the given `User-Agent` header, instead of the one from
+    /// [`AxoClient::with_user_agent`][] (if any)
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Gets the url the request will be sent to, with the query parameters applied
+    pub fn url(&self) -> Result<UrlString> {
+        if self.query.is_empty() {
+            return Ok(self.url.clone());
+        }
+        let mut url = url::Url::parse(&self.url).map_err(|details| AxoassetError::UrlParse {
+            origin_path: self.url.clone(),
+            details,
+        })?;
+        url.query_pairs_mut().extend_pairs(&self.query);
+        Ok(url.into())
+    }
+
+    /// Sends the request and returns a [`RemoteAsset`][] containing the body
+    pub async fn load_asset(self) -> Result<RemoteAsset> {
+        let (url, request) = self.build()?;
+        self.client.load_asset_with(&url, request, None).await
+    }
+
+    /// Sends the request and returns a [`crate::SourceFile`][] containing the body
+    pub async fn load_source(self) -> Result<SourceFile> {
+        let (url, request) = self.build()?;
+        let text = self.client.load_string_with(&url, request).await?;
+        Ok(SourceFile::new(&url, text))
+    }
+
+    /// Sends the request and returns the body as a `String`
+    pub async fn load_string(self) -> Result<String> {
+        let (url, request) = self.build()?;
+        self.client.load_string_with(&url, request).await
+    }
+
+    /// Sends the request and returns the body as a `Vec<u8>`
+    pub async fn load_bytes(self) -> Result<Vec<u8>> {
+        let (url, request) = self.build()?;
+        self.client.load_bytes_with(&url, request).await
+    }
+
+    /// Sends the request and returns the raw [`reqwest::Response`][]
+    pub async fn send(self) -> Result<reqwest::Response> {
+        let (url, request) = self.build()?;
+        self.client.send(&url, request).await
+    }
+
+    /// Gets the final url, and the reqwest request for it
+    fn build(&self) -> Result<(UrlString, reqwest::RequestBuilder)> {
+        let url = self.url()?;
+        let has_authorization = self
+            .headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(reqwest::header::AUTHORIZATION.as_str()));
+        let origin = self.client.resolve_origin(&url);
+        let get = || self.client.request_to(reqwest::Method::GET, &origin);
+        let mut request = match &self.credentials {
+            Some(credentials) => credentials.apply(get()),
+            None if has_authorization => get(),
+            None => self.client.new_request(reqwest::Method::GET, &url),
+        };
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+            // Unlike `header`, `headers` replaces the client's value instead of adding
+            // another one (an invalid value is left for reqwest to report when it's sent)
+            request = match HeaderValue::from_str(user_agent) {
+                Ok(value) => request.headers(HeaderMap::from_iter([(USER_AGENT, value)])),
+                Err(_) => request.header(USER_AGENT, user_agent),
+            };
+        }
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        Ok((url, request))
+    }
+}
@@ -0,0 +1,92 @@
+//! Options for how an AxoClient verifies the servers it connects to
+
+use camino::Utf8PathBuf;
+
+use crate::error::*;
+
+/// Which TLS implementation an [`AxoClient`][super::AxoClient] uses, see
+/// [`TlsOptions::backend`][]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TlsBackend {
+    /// rustls (the default)
+    #[default]
+    Rustls,
+    /// The platform's TLS library (SChannel on Windows, Security.framework on macOS,
+    /// and OpenSSL elsewhere), which needs the `tls-native` feature
+    ///
+    /// This trusts whatever certificates the platform does, which is sometimes the
+    /// easiest way to pick up a company's certificate authority.
+    #[cfg(feature = "tls-native")]
+    Native,
+}
+
+/// Options for how an [`AxoClient`][super::AxoClient] verifies the servers it
+/// connects to, see [`NetworkOptions::tls`][super::NetworkOptions::tls]
+///
+/// ```
+/// # use axoasset::remote::NetworkOptions;
+/// let mut options = NetworkOptions::default();
+/// options.tls.root_certificates.push("/etc/corp/ca.pem".into());
+/// ```
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct TlsOptions {
+    /// Which TLS implementation to use
+    pub backend: TlsBackend,
+    /// Extra certificate authorities to trust, as paths to PEM files (which can each
+    /// contain several certificates) or DER files
+    ///
+    /// This is how to get through a proxy that intercepts https traffic, or to
+    /// reach servers with certificates from a private PKI.
+    pub root_certificates: Vec<Utf8PathBuf>,
+    /// Whether to only trust `root_certificates`, and not the usual certificate
+    /// authorities
+    pub only_root_certificates: bool,
+}
+
+impl TlsOptions {
+    /// Applies these options to a reqwest::ClientBuilder
+    pub(crate) fn configure(
+        &self,
+        builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder> {
+        let mut builder = match self.backend {
+            TlsBackend::Rustls => builder.use_rustls_tls(),
+            #[cfg(feature = "tls-native")]
+            TlsBackend::Native => builder.use_native_tls(),
+        };
+        for path in &self.root_certificates {
+            for cert in load_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if self.only_root_certificates {
+            builder = builder.tls_built_in_root_certs(false);
+        }
+        Ok(builder)
+    }
+}
+
+/// Loads the certificates in a PEM or DER file
+fn load_certificates(path: &Utf8PathBuf) -> Result<Vec<reqwest::Certificate>> {
+    let invalid = |reason: String| AxoassetError::RemoteTlsCertificateInvalid {
+        origin_path: path.to_string(),
+        reason,
+    };
+    let contents = crate::LocalAsset::load_bytes(path)?;
+    let is_pem = contents
+        .windows(b"-----BEGIN".len())
+        .any(|window| window == b"-----BEGIN");
+    if !is_pem {
+        let cert = reqwest::Certificate::from_der(&contents)
+            .map_err(|details| invalid(details.to_string()))?;
+        return Ok(vec![cert]);
+    }
+    let certs = reqwest::Certificate::from_pem_bundle(&contents)
+        .map_err(|details| invalid(details.to_string()))?;
+    if certs.is_empty() {
+        return Err(invalid("it doesn't contain any certificates".to_owned()));
+    }
+    Ok(certs)
+}
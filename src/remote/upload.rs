@@ -0,0 +1,195 @@
+//! Streaming archives straight into uploads, without writing them to disk
+
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tokio::sync::{mpsc, oneshot};
+
+use super::{AxoClient, UrlStr};
+use crate::error::*;
+use crate::local::{ArchiveLayout, TarOptions};
+
+/// How many bytes of the archive to send at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+/// How many chunks can be waiting to be sent before archiving pauses to let the
+/// upload catch up
+const BUFFERED_CHUNKS: usize = 8;
+
+/// Options for [`AxoClient::upload_tar_gz_dir_with_options`][]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TarUploadOptions {
+    /// The HTTP method to upload with (usually `PUT` or `POST`)
+    ///
+    /// Defaults to `PUT`.
+    pub method: reqwest::Method,
+    /// How to build the tarball
+    pub tar: TarOptions,
+}
+
+impl Default for TarUploadOptions {
+    fn default() -> Self {
+        Self {
+            method: reqwest::Method::PUT,
+            tar: TarOptions::default(),
+        }
+    }
+}
+
+impl AxoClient {
+    /// Archives a directory as a .tar.gz and `PUT`s it to `url` as it's being made
+    ///
+    /// See [`AxoClient::upload_tar_gz_dir_with_options`][] for details.
+    pub async fn upload_tar_gz_dir(
+        &self,
+        url: &UrlStr,
+        origin_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+    ) -> Result<reqwest::Response> {
+        self.upload_tar_gz_dir_with_options(
+            url,
+            origin_dir,
+            with_root,
+            &TarUploadOptions::default(),
+        )
+        .await
+    }
+
+    /// Archives a directory as a .tar.gz and uploads it to `url` as it's being made,
+    /// with the given [`TarUploadOptions`][]
+    ///
+    /// The tarball is laid out like [`LocalAsset::tar_gz_dir`][crate::LocalAsset::tar_gz_dir]
+    /// would, but it's built on a separate thread and sent with chunked transfer
+    /// encoding as it's compressed, so it's never written to disk and only a few
+    /// chunks of it are in memory at once. Archiving waits for the upload to catch
+    /// up if the network is the slower of the two.
+    ///
+    /// The body can't be sent twice, so the upload is never retried, and an error
+    /// status from the server is an error. If archiving fails partway through, the
+    /// upload is aborted (so the server doesn't mistake what it got for the whole
+    /// tarball) and the archiving error is returned.
+    pub async fn upload_tar_gz_dir_with_options(
+        &self,
+        url: &UrlStr,
+        origin_dir: impl AsRef<Utf8Path>,
+        with_root: impl Into<ArchiveLayout>,
+        options: &TarUploadOptions,
+    ) -> Result<reqwest::Response> {
+        let origin_dir = origin_dir.as_ref().to_owned();
+        let layout = with_root.into();
+        let tar_options = options.tar.clone();
+        // Archiving errors (and the gzip header) refer to the archive by where it's
+        // going, leaving out any query or fragment
+        let archive_name = Utf8PathBuf::from(url.split(['?', '#']).next().unwrap_or(url));
+        let (chunks_tx, chunks_rx) = mpsc::channel(BUFFERED_CHUNKS);
+        let (done_tx, done_rx) = oneshot::channel();
+
+        std::thread::spawn(move || {
+            let mut writer = ChunkWriter {
+                chunks: chunks_tx,
+                buf: Vec::with_capacity(CHUNK_SIZE),
+                closed: false,
+            };
+            let archived = crate::compression::write_tar(
+                &origin_dir,
+                &archive_name,
+                &mut writer,
+                &layout,
+                &crate::compression::CompressionImpl::Gzip,
+                &tar_options,
+            )
+            .and_then(|()| {
+                writer
+                    .flush()
+                    .map_err(|details| AxoassetError::Compression {
+                        reason: format!("failed to write tar: {archive_name}"),
+                        details,
+                    })
+            });
+            let failure = match archived {
+                Ok(()) => None,
+                // The upload stopped first, and its error is the one worth reporting
+                Err(_) if writer.closed => None,
+                Err(err) => {
+                    let _ = writer
+                        .chunks
+                        .blocking_send(Err(std::io::Error::other("archiving failed")));
+                    Some(err)
+                }
+            };
+            let _ = done_tx.send(failure);
+        });
+
+        let request = self
+            .new_request(options.method.clone(), url)
+            .header(reqwest::header::CONTENT_TYPE, "application/gzip")
+            .body(reqwest::Body::wrap(ArchiveBody { chunks: chunks_rx }));
+        let response = self.send_tracked(url, request).await;
+        // Once the request is over, archiving stops soon one way or another
+        if let Ok(Some(err)) = done_rx.await {
+            return Err(err);
+        }
+        let (response, transfer) = response?;
+        self.error_for_status(url, response, transfer.capture).await
+    }
+}
+
+/// Hands the bytes of an archive over to an [`ArchiveBody`][] a chunk at a time
+struct ChunkWriter {
+    chunks: mpsc::Sender<std::io::Result<bytes::Bytes>>,
+    buf: Vec<u8>,
+    /// Whether the body stopped reading, because the upload ended
+    closed: bool,
+}
+
+impl ChunkWriter {
+    fn send_buf(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        if self.chunks.blocking_send(Ok(chunk.into())).is_err() {
+            self.closed = true;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "the upload stopped reading the archive",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.send_buf()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send_buf()
+    }
+}
+
+/// The body of an upload, made of the chunks of an archive as they're written
+struct ArchiveBody {
+    chunks: mpsc::Receiver<std::io::Result<bytes::Bytes>>,
+}
+
+impl http_body::Body for ArchiveBody {
+    type Data = bytes::Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::io::Result<http_body::Frame<bytes::Bytes>>>> {
+        self.chunks
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(http_body::Frame::data)))
+    }
+}
@@ -0,0 +1,180 @@
+//! Reading the central directory of a remote zip, without downloading the rest of it
+//!
+//! A zip ends with an "end of central directory" record (EOCD), which says where the
+//! central directory (the list of every entry) starts and how big it is. Zips over
+//! 4GB (or with over 65535 entries) also have a zip64 EOCD record and a locator
+//! pointing at it, just before the normal EOCD.
+
+use std::ops::Range;
+
+const EOCD_SIGNATURE: u32 = 0x06054b50;
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_EOCD_LEN: usize = 56;
+const ENTRY_SIGNATURE: u32 = 0x02014b50;
+const ENTRY_LEN: usize = 46;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+/// How many bytes from the end of a zip could contain the EOCD and zip64 records
+/// (the EOCD can be followed by a comment of up to 64KiB)
+pub(crate) const MAX_TAIL_LEN: u64 =
+    (EOCD_LEN + u16::MAX as usize + ZIP64_LOCATOR_LEN + ZIP64_EOCD_LEN) as u64;
+
+/// An entry in a remote zip, see [`AxoClient::list_zip`][super::AxoClient::list_zip]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RemoteZipEntry {
+    /// The path of the entry within the zip (directories end with `/`)
+    pub name: String,
+    /// The uncompressed size of the entry
+    pub size: u64,
+    /// The compressed size of the entry
+    pub compressed_size: u64,
+    /// The CRC-32 of the uncompressed entry
+    pub crc32: u32,
+}
+
+impl RemoteZipEntry {
+    /// Whether the entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+/// Where a zip's central directory is, and how many entries it has
+pub(crate) struct CentralDirectory {
+    pub range: Range<u64>,
+    pub entries: u64,
+}
+
+/// Finds the central directory using the last bytes of a zip, which start at
+/// `tail_start` within it
+pub(crate) fn find_central_directory(
+    tail: &[u8],
+    tail_start: u64,
+) -> Result<CentralDirectory, String> {
+    // The comment can contain anything (including the signature), so search from
+    // the back for an EOCD whose comment runs exactly to the end
+    let eocd = (0..=tail.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&idx| {
+            read_u32(tail, idx) == Some(EOCD_SIGNATURE)
+                && read_u16(tail, idx + 20).map(|len| idx + EOCD_LEN + len as usize)
+                    == Some(tail.len())
+        })
+        .ok_or("couldn't find the end of the central directory, is it a zip?")?;
+    let entries = read_u16(tail, eocd + 10).ok_or("truncated")?;
+    let size = read_u32(tail, eocd + 12).ok_or("truncated")?;
+    let offset = read_u32(tail, eocd + 16).ok_or("truncated")?;
+    if entries != u16::MAX && size != u32::MAX && offset != u32::MAX {
+        let start = offset as u64;
+        return Ok(CentralDirectory {
+            range: start..start + size as u64,
+            entries: entries as u64,
+        });
+    }
+
+    // Some of the fields overflowed, so the real values are in the zip64 record
+    let locator = eocd
+        .checked_sub(ZIP64_LOCATOR_LEN)
+        .filter(|&idx| read_u32(tail, idx) == Some(ZIP64_LOCATOR_SIGNATURE))
+        .ok_or("the zip64 end of central directory locator is missing")?;
+    let record = read_u64(tail, locator + 8)
+        .and_then(|record| record.checked_sub(tail_start))
+        .and_then(|record| usize::try_from(record).ok())
+        .filter(|&record| read_u32(tail, record) == Some(ZIP64_EOCD_SIGNATURE))
+        .filter(|&record| record + ZIP64_EOCD_LEN <= tail.len())
+        .ok_or("couldn't find the zip64 end of central directory record")?;
+    let entries = read_u64(tail, record + 32).ok_or("truncated")?;
+    let size = read_u64(tail, record + 40).ok_or("truncated")?;
+    let start = read_u64(tail, record + 48).ok_or("truncated")?;
+    Ok(CentralDirectory {
+        range: start..start.checked_add(size).ok_or("invalid size")?,
+        entries,
+    })
+}
+
+/// Parses the entries of a central directory
+pub(crate) fn parse_central_directory(
+    dir: &[u8],
+    entries: u64,
+) -> Result<Vec<RemoteZipEntry>, String> {
+    let mut parsed = vec![];
+    let mut idx = 0;
+    for _ in 0..entries {
+        if read_u32(dir, idx) != Some(ENTRY_SIGNATURE) {
+            return Err(format!(
+                "central directory entry {} is corrupt",
+                parsed.len()
+            ));
+        }
+        let truncated = || format!("central directory entry {} is truncated", parsed.len());
+        let header = dir.get(idx..idx + ENTRY_LEN).ok_or_else(truncated)?;
+        let crc32 = read_u32(header, 16).unwrap_or_default();
+        let mut compressed_size = read_u32(header, 20).unwrap_or_default() as u64;
+        let mut size = read_u32(header, 24).unwrap_or_default() as u64;
+        let name_len = read_u16(header, 28).unwrap_or_default() as usize;
+        let extra_len = read_u16(header, 30).unwrap_or_default() as usize;
+        let comment_len = read_u16(header, 32).unwrap_or_default() as usize;
+
+        let name_start = idx + ENTRY_LEN;
+        let name = dir
+            .get(name_start..name_start + name_len)
+            .ok_or_else(truncated)?;
+        let extra_start = name_start + name_len;
+        let extra = dir
+            .get(extra_start..extra_start + extra_len)
+            .ok_or_else(truncated)?;
+
+        // Sizes that overflowed are in the zip64 extra field instead, in this order
+        if let Some(mut zip64) = find_extra_field(extra, ZIP64_EXTRA_ID) {
+            for field in [&mut size, &mut compressed_size] {
+                if *field == u32::MAX as u64 {
+                    *field = read_u64(zip64, 0).ok_or_else(truncated)?;
+                    zip64 = &zip64[8..];
+                }
+            }
+        }
+
+        parsed.push(RemoteZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            size,
+            compressed_size,
+            crc32,
+        });
+        idx = extra_start + extra_len + comment_len;
+    }
+    Ok(parsed)
+}
+
+/// Finds the data of the extra field with the given id
+fn find_extra_field(mut extra: &[u8], id: u16) -> Option<&[u8]> {
+    while let (Some(field_id), Some(len)) = (read_u16(extra, 0), read_u16(extra, 2)) {
+        let data = extra.get(4..4 + len as usize)?;
+        if field_id == id {
+            return Some(data);
+        }
+        extra = &extra[4 + len as usize..];
+    }
+    None
+}
+
+fn read_u16(bytes: &[u8], idx: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(idx..idx + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], idx: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(idx..idx + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], idx: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(idx..idx + 8)?.try_into().ok()?,
+    ))
+}
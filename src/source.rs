@@ -17,6 +17,124 @@ use crate::serde_json;
 #[cfg(feature = "yaml-serde")]
 use crate::serde_yml;
 
+/// A structured text format that a [`SourceFile`][] can be deserialized from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SourceFormat {
+    /// JSON
+    Json,
+    /// TOML
+    Toml,
+    /// YAML
+    Yaml,
+}
+
+impl SourceFormat {
+    /// Gets the format conventionally associated with a file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(SourceFormat::Json),
+            "toml" => Some(SourceFormat::Toml),
+            "yaml" | "yml" => Some(SourceFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of some text by looking at its first meaningful line
+    pub fn sniff(contents: &str) -> Option<Self> {
+        let contents = contents.strip_prefix('\u{FEFF}').unwrap_or(contents);
+        let first_line = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+
+        if first_line.starts_with('{') {
+            Some(SourceFormat::Json)
+        } else if first_line.starts_with('[') {
+            // Both a TOML table header and a JSON array can start with `[`
+            if looks_like_toml_header(first_line) {
+                Some(SourceFormat::Toml)
+            } else {
+                Some(SourceFormat::Json)
+            }
+        } else if first_line.starts_with("---") {
+            Some(SourceFormat::Yaml)
+        } else {
+            match (first_line.find('='), first_line.find(':')) {
+                (Some(eq), Some(colon)) if eq < colon => Some(SourceFormat::Toml),
+                (Some(_), None) => Some(SourceFormat::Toml),
+                (_, Some(_)) => Some(SourceFormat::Yaml),
+                (None, None) => None,
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SourceFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SourceFormat::Json => "JSON",
+            SourceFormat::Toml => "TOML",
+            SourceFormat::Yaml => "YAML",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Splits a YAML stream into the byte ranges of its documents
+///
+/// Each range starts at the beginning of a line, with the document's `---` marker
+/// (if it has one). Documents with nothing but comments and whitespace are skipped.
+#[cfg(feature = "yaml-serde")]
+fn yaml_documents(contents: &str) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let marker = line.trim_end();
+        let starts_doc = marker == "---" || marker.starts_with("--- ");
+        if starts_doc || marker == "..." {
+            ranges.push(start..offset);
+            start = if starts_doc {
+                offset
+            } else {
+                offset + line.len()
+            };
+        }
+        offset += line.len();
+    }
+    ranges.push(start..offset);
+
+    ranges.retain(|range| {
+        let doc = &contents[range.clone()];
+        let doc = doc.strip_prefix("---").unwrap_or(doc);
+        doc.lines()
+            .map(str::trim)
+            .any(|line| !line.is_empty() && !line.starts_with('#'))
+    });
+    ranges
+}
+
+/// Checks if a line is a `[table]` or `[[array.of.tables]]` header
+fn looks_like_toml_header(line: &str) -> bool {
+    let line = match line.find('#') {
+        Some(comment) => line[..comment].trim_end(),
+        None => line,
+    };
+    let Some(inner) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) else {
+        return false;
+    };
+    let inner = inner
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .unwrap_or(inner)
+        .trim();
+    !inner.is_empty()
+        && inner
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '"' | '\'' | ' '))
+}
+
 /// The inner contents of a [`SourceFile`][].
 #[derive(Eq, PartialEq)]
 struct SourceFileInner {
@@ -92,21 +210,24 @@ impl SourceFile {
             contents = stripped;
         }
 
-        let json = serde_json::from_str(contents).map_err(|details| {
-            let span = self.span_for_line_col(details.line(), details.column());
-            AxoassetError::Json {
-                source: self.clone(),
-                span,
-                details,
-            }
-        })?;
+        let json = crate::spanned::without_spans(|| serde_json::from_str(contents)).map_err(
+            |details| {
+                let span = self.span_for_line_col(details.line(), details.column());
+                AxoassetError::Json {
+                    source: self.clone(),
+                    span,
+                    details,
+                }
+            },
+        )?;
         Ok(json)
     }
 
     /// Try to deserialize the contents of the SourceFile as toml
     #[cfg(feature = "toml-serde")]
     pub fn deserialize_toml<'a, T: for<'de> serde::Deserialize<'de>>(&'a self) -> Result<T> {
-        let toml = toml::from_str(self.contents()).map_err(|details| {
+        let deserializer = toml::Deserializer::new(self.contents());
+        let toml = crate::spanned::deserialize_with_spans(deserializer).map_err(|details| {
             let span = details.span().map(SourceSpan::from);
             AxoassetError::Toml {
                 source: self.clone(),
@@ -131,22 +252,298 @@ impl SourceFile {
         Ok(toml)
     }
 
+    /// Deep-merge the tables of `overlay` into the contents of the SourceFile as toml,
+    /// returning the merged text
+    ///
+    /// Comments and formatting of the existing document are preserved. Tables present
+    /// in both documents are merged recursively, any other conflicting value is replaced
+    /// by the one from `overlay`.
+    #[cfg(feature = "toml-edit")]
+    pub fn merge_toml(&self, overlay: &DocumentMut) -> Result<String> {
+        let mut toml = self.deserialize_toml_edit()?;
+        merge_toml_tables(toml.as_table_mut(), overlay.as_table());
+        Ok(toml.to_string())
+    }
+
     /// Try to deserialize the contents of the SourceFile as yaml
     #[cfg(feature = "yaml-serde")]
     pub fn deserialize_yaml<'a, T: for<'de> serde::Deserialize<'de>>(&self) -> Result<T> {
-        let yaml = serde_yml::from_str(self.contents()).map_err(|details| {
-            let span = details
-                .location()
-                .and_then(|location| self.span_for_line_col(location.line(), location.column()));
-            AxoassetError::Yaml {
-                source: self.clone(),
-                span,
-                details,
-            }
-        })?;
+        let yaml = crate::spanned::without_spans(|| serde_yml::from_str(self.contents())).map_err(
+            |details| {
+                let span = details.location().and_then(|location| {
+                    self.span_for_line_col(location.line(), location.column())
+                });
+                AxoassetError::Yaml {
+                    source: self.clone(),
+                    span,
+                    details,
+                }
+            },
+        )?;
         Ok(yaml)
     }
 
+    /// Guesses the [`SourceFormat`][] of the SourceFile
+    ///
+    /// The file extension is used if it's a recognized one, otherwise the
+    /// contents are sniffed with [`SourceFormat::sniff`][].
+    pub fn detect_format(&self) -> Option<SourceFormat> {
+        Utf8Path::new(self.filename())
+            .extension()
+            .and_then(SourceFormat::from_extension)
+            .or_else(|| SourceFormat::sniff(self.contents()))
+    }
+
+    /// Try to deserialize the contents of the SourceFile as whichever of json, toml,
+    /// or yaml it appears to be, returning the format that was used
+    ///
+    /// See [`SourceFile::detect_format`][] for how the format is picked. Formats whose
+    /// features aren't enabled will produce an error.
+    #[cfg(any(feature = "json-serde", feature = "toml-serde", feature = "yaml-serde"))]
+    pub fn deserialize_auto<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+    ) -> Result<(T, SourceFormat)> {
+        let format = self
+            .detect_format()
+            .ok_or_else(|| AxoassetError::SourceFormatUnknown {
+                origin_path: self.origin_path().to_owned(),
+            })?;
+        let val = match format {
+            #[cfg(feature = "json-serde")]
+            SourceFormat::Json => self.deserialize_json()?,
+            #[cfg(feature = "toml-serde")]
+            SourceFormat::Toml => self.deserialize_toml()?,
+            #[cfg(feature = "yaml-serde")]
+            SourceFormat::Yaml => self.deserialize_yaml()?,
+            #[allow(unreachable_patterns)]
+            _ => {
+                return Err(AxoassetError::SourceFormatDisabled {
+                    origin_path: self.origin_path().to_owned(),
+                    format,
+                })
+            }
+        };
+        Ok((val, format))
+    }
+
+    /// Try to deserialize each record of the SourceFile as CSV
+    ///
+    /// The first row is the header, whose names are matched up with the fields of
+    /// `T`. Files ending in `.tsv` or `.tab` are tab-separated instead. Every record
+    /// gets its own result, so one bad row doesn't stop the rest from being read.
+    /// Records are spanned with where they appear in the file, and errors point at
+    /// the offending field when it's known.
+    #[cfg(feature = "csv-serde")]
+    pub fn deserialize_csv<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+    ) -> Vec<Result<crate::Spanned<T>>> {
+        let delimiter = match self.origin_path().rsplit_once('.') {
+            Some((_, "tsv" | "tab")) => b'\t',
+            _ => b',',
+        };
+        let contents = self.contents();
+        let bom = if contents.starts_with('\u{FEFF}') {
+            3
+        } else {
+            0
+        };
+        let error = |span, details| AxoassetError::Csv {
+            source: self.clone(),
+            span,
+            details,
+        };
+        // The whole line starting at a position, for errors that don't say more
+        let line_span = |position: Option<&csv::Position>| {
+            let start = bom + position?.byte() as usize;
+            let line = contents.get(start..)?.lines().next()?;
+            Some(SourceSpan::from(start..start + line.len()))
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .from_reader(&contents.as_bytes()[bom..]);
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(details) => return vec![Err(error(line_span(details.position()), details))],
+        };
+        let mut results = vec![];
+        let mut record = csv::StringRecord::new();
+        loop {
+            let before = reader.position().byte();
+            match reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => {}
+                Err(details) => {
+                    results.push(Err(error(line_span(details.position()), details)));
+                    // Errors aren't fatal, but don't spin on one that makes no progress
+                    if reader.position().byte() == before {
+                        break;
+                    }
+                    continue;
+                }
+            }
+            let start = bom + record.position().map_or(before, |position| position.byte()) as usize;
+            let end = bom + reader.position().byte() as usize;
+            let raw = contents[start..end].trim_end_matches(['\r', '\n']);
+            let span = SourceSpan::from(start..start + raw.len());
+            match crate::spanned::without_spans(|| record.deserialize::<T>(Some(&headers))) {
+                Ok(value) => results.push(Ok(crate::Spanned::with_source_span(value, span))),
+                Err(details) => {
+                    let field = match details.kind() {
+                        csv::ErrorKind::Deserialize { err, .. } => err.field(),
+                        _ => None,
+                    };
+                    let field_span = field
+                        .and_then(|field| csv_field_ranges(raw, delimiter).nth(field as usize))
+                        .map(|range| SourceSpan::from(start + range.start..start + range.end));
+                    results.push(Err(error(field_span.or(Some(span)), details)));
+                }
+            }
+        }
+        results
+    }
+
+    /// Try to deserialize every document in the SourceFile, for files that bundle
+    /// several documents together
+    ///
+    /// The format is picked like [`SourceFile::deserialize_auto`][]. YAML streams are
+    /// split on `---` (and `...`) markers, JSON files can contain any number of
+    /// concatenated values, and TOML files are always a single document. Each
+    /// document is spanned with where it appears in the file, so errors in it can
+    /// be reported precisely.
+    #[cfg(any(feature = "json-serde", feature = "toml-serde", feature = "yaml-serde"))]
+    pub fn deserialize_multi<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+    ) -> Result<Vec<crate::Spanned<T>>> {
+        let format = self
+            .detect_format()
+            .ok_or_else(|| AxoassetError::SourceFormatUnknown {
+                origin_path: self.origin_path().to_owned(),
+            })?;
+        let contents = self.contents();
+        match format {
+            #[cfg(feature = "json-serde")]
+            SourceFormat::Json => {
+                let bom = if contents.starts_with('\u{FEFF}') {
+                    3
+                } else {
+                    0
+                };
+                let json = &contents[bom..];
+                let mut stream = serde_json::Deserializer::from_str(json).into_iter::<T>();
+                let mut docs = vec![];
+                loop {
+                    let start = stream.byte_offset();
+                    let Some(doc) = crate::spanned::without_spans(|| stream.next()) else {
+                        break;
+                    };
+                    let doc = doc.map_err(|details| {
+                        let span = self.span_for_line_col(details.line(), details.column());
+                        AxoassetError::Json {
+                            source: self.clone(),
+                            span,
+                            details,
+                        }
+                    })?;
+                    let end = stream.byte_offset();
+                    let start = end - json[start..end].trim_start().len();
+                    docs.push(crate::Spanned::with_source_span(
+                        doc,
+                        (bom + start..bom + end).into(),
+                    ));
+                }
+                Ok(docs)
+            }
+            #[cfg(feature = "toml-serde")]
+            SourceFormat::Toml => {
+                let doc = self.deserialize_toml()?;
+                Ok(vec![crate::Spanned::with_source_span(
+                    doc,
+                    (0..contents.len()).into(),
+                )])
+            }
+            #[cfg(feature = "yaml-serde")]
+            SourceFormat::Yaml => {
+                let mut docs = vec![];
+                for range in yaml_documents(contents) {
+                    // Blank out the document's `---` so line and column numbers
+                    // within it match the whole file
+                    let mut text = contents[range.clone()].to_owned();
+                    if text.starts_with("---") {
+                        text.replace_range(..3, "   ");
+                    }
+                    let doc = crate::spanned::without_spans(|| serde_yml::from_str(&text))
+                        .map_err(|details| {
+                            let span = details.location().and_then(|location| {
+                                let (first_line, _) = self.line_col(range.start)?;
+                                self.span_for_line_col(
+                                    first_line + location.line() - 1,
+                                    location.column(),
+                                )
+                            });
+                            AxoassetError::Yaml {
+                                source: self.clone(),
+                                span,
+                                details,
+                            }
+                        })?;
+                    let start = range.start + (text.len() - text.trim_start().len());
+                    let end = range.start + text.trim_end().len();
+                    docs.push(crate::Spanned::with_source_span(doc, (start..end).into()));
+                }
+                Ok(docs)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(AxoassetError::SourceFormatDisabled {
+                origin_path: self.origin_path().to_owned(),
+                format,
+            }),
+        }
+    }
+
+    /// Checks if two SourceFiles contain the same data, ignoring formatting, comments,
+    /// key order, and even which format they're written in
+    ///
+    /// Both files are parsed as described in [`SourceFile::deserialize_auto`][].
+    #[cfg(feature = "json-serde")]
+    pub fn semantic_eq(&self, other: &SourceFile) -> Result<bool> {
+        let (old, _) = self.deserialize_auto::<serde_json::Value>()?;
+        let (new, _) = other.deserialize_auto::<serde_json::Value>()?;
+        Ok(old == new)
+    }
+
+    /// Lists every value that differs between this SourceFile and a newer version of it
+    ///
+    /// Like [`SourceFile::semantic_eq`][], only the data matters, not how it's written.
+    /// Changes are reported for the most specific key path that changed (so changing
+    /// one field of a table reports that field, not the whole table).
+    #[cfg(feature = "json-serde")]
+    pub fn semantic_diff(&self, new: &SourceFile) -> Result<Vec<SemanticChange>> {
+        let (old_val, _) = self.deserialize_auto::<serde_json::Value>()?;
+        let (new_val, _) = new.deserialize_auto::<serde_json::Value>()?;
+        let mut changes = vec![];
+        diff_values(String::new(), old_val, new_val, &mut changes);
+        Ok(changes)
+    }
+
+    /// Rewrites the contents of the SourceFile as json in a canonical form, so that
+    /// the same data always gets the same bytes
+    ///
+    /// This is meant for hashing or signing json manifests, where writers disagreeing
+    /// about incidental formatting shouldn't break the signature. It follows the
+    /// [JSON Canonicalization Scheme (RFC 8785)](https://www.rfc-editor.org/rfc/rfc8785):
+    /// no whitespace, object keys sorted by their UTF-16 code units, strings escaped
+    /// minimally, and floats written the way JavaScript writes them (`1.0` becomes `1`,
+    /// `1e21` becomes `1e+21`). Unlike RFC 8785, integers that fit in 64 bits keep every
+    /// digit instead of being rounded to the nearest float.
+    #[cfg(feature = "json-serde")]
+    pub fn canonicalize_json(&self) -> Result<String> {
+        let value = self.deserialize_json::<serde_json::Value>()?;
+        let mut out = String::new();
+        write_canonical_json(&value, &mut out);
+        Ok(out)
+    }
+
     /// Get the filename of a SourceFile
     pub fn filename(&self) -> &str {
         &self.inner.filename
@@ -167,6 +564,54 @@ impl SourceFile {
         &self.inner.contents
     }
 
+    /// Applies [`Suggestion`][]s to the contents of the SourceFile, returning the result
+    ///
+    /// The suggestions can be given in any order, and exact duplicates are only applied
+    /// once. It's an error for suggestions to overlap (insertions at the same position
+    /// are fine, and get applied in the order they were given), or for them to not
+    /// line up with the contents.
+    pub fn apply_suggestions(&self, suggestions: &[Suggestion]) -> Result<String> {
+        let contents = self.contents();
+        let mut sorted: Vec<&Suggestion> = vec![];
+        for suggestion in suggestions {
+            if !sorted.contains(&suggestion) {
+                sorted.push(suggestion);
+            }
+        }
+        sorted.sort_by_key(|suggestion| {
+            (
+                suggestion.span.offset(),
+                suggestion.span.offset() + suggestion.span.len(),
+            )
+        });
+
+        let mut patched = String::with_capacity(contents.len());
+        let mut copied_up_to = 0;
+        for suggestion in sorted {
+            let start = suggestion.span.offset();
+            let end = start + suggestion.span.len();
+            let reason = if start < copied_up_to {
+                Some("it overlaps another suggestion")
+            } else if contents.get(start..end).is_none() {
+                Some("it isn't a valid span of the file")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(AxoassetError::InvalidSuggestion {
+                    source_file: self.clone(),
+                    span: suggestion.span.offset().min(contents.len()).into(),
+                    reason: reason.to_owned(),
+                });
+            }
+            patched.push_str(&contents[copied_up_to..start]);
+            patched.push_str(&suggestion.replacement);
+            copied_up_to = end;
+        }
+        patched.push_str(&contents[copied_up_to..]);
+        Ok(patched)
+    }
+
     /// Gets a proper [`SourceSpan`] from a line-and-column representation
     ///
     /// Both values are 1's based, so `(1, 1)` is the start of the file.
@@ -192,6 +637,33 @@ impl SourceFile {
         Some(SourceSpan::from(start..end))
     }
 
+    /// Gets the line and column of a byte offset into `contents`
+    ///
+    /// This is the inverse of [`SourceFile::span_for_line_col`][]: lines and columns
+    /// are 1-based, and columns count bytes from the start of the line. An offset at
+    /// the very end of the file is allowed (for the end of a span), anything past
+    /// that returns `None`, as does an offset in the middle of a character.
+    pub fn line_col(&self, offset: usize) -> Option<(usize, usize)> {
+        let before = self.contents().get(..offset)?;
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        let line = before.matches('\n').count() + 1;
+        Some((line, offset - line_start + 1))
+    }
+
+    /// Iterates over the lines of `contents`, each with its span
+    ///
+    /// Lines are split the same way as [`str::lines`][] (so neither `\n` nor `\r\n`
+    /// is part of the line or its span). This is handy for simple line-based checks
+    /// (like linting a shell script or a `.env` file) that want to point
+    /// diagnostics at the offending line.
+    pub fn lines_spanned(&self) -> impl Iterator<Item = (&str, SourceSpan)> + '_ {
+        let base_addr = self.contents().as_ptr() as usize;
+        self.contents().lines().map(move |line| {
+            let start = line.as_ptr() as usize - base_addr;
+            (line, SourceSpan::from(start..start + line.len()))
+        })
+    }
+
     /// Creates a span for an item using a substring of `contents`
     ///
     /// Note that substr must be a literal substring, as in it must be
@@ -221,6 +693,434 @@ impl SourceFile {
     }
 }
 
+/// Maps spans in preprocessed text back to the [`SourceFile`][] it was produced from
+///
+/// When a tool rewrites a file before parsing it (stripping front-matter, expanding
+/// includes, ...), any errors will have spans into the rewritten text. Recording how
+/// the rewritten text was assembled in a SpanMap lets you translate those spans back
+/// so diagnostics can be reported against the original SourceFile.
+///
+/// The processed text is described as a sequence of pieces, in order:
+///
+/// * [`SpanMap::push_original`][] for text copied verbatim from the original
+/// * [`SpanMap::push_replaced`][] for text that replaced some range of the original
+///   (like an expanded include directive)
+#[derive(Debug, Clone, Default)]
+pub struct SpanMap {
+    /// The pieces of the processed text, sorted by `processed_start`
+    pieces: Vec<SpanMapPiece>,
+    /// The length of the processed text described so far
+    processed_len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct SpanMapPiece {
+    /// Where this piece starts in the processed text
+    processed_start: usize,
+    /// The range of the original text this piece came from
+    original: std::ops::Range<usize>,
+    /// Whether the piece is a verbatim copy of `original`
+    verbatim: bool,
+}
+
+impl SpanMap {
+    /// Create an empty SpanMap
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the next piece of the processed text is copied verbatim from
+    /// the given range of the original text
+    pub fn push_original(&mut self, original: std::ops::Range<usize>) {
+        let len = original.len();
+        self.push(len, original, true);
+    }
+
+    /// Record that the next `processed_len` bytes of the processed text replaced
+    /// the given range of the original text
+    ///
+    /// Any span that lands in the replacement maps to the whole original range.
+    pub fn push_replaced(&mut self, processed_len: usize, original: std::ops::Range<usize>) {
+        self.push(processed_len, original, false);
+    }
+
+    fn push(&mut self, processed_len: usize, original: std::ops::Range<usize>, verbatim: bool) {
+        if processed_len == 0 {
+            return;
+        }
+        self.pieces.push(SpanMapPiece {
+            processed_start: self.processed_len,
+            original,
+            verbatim,
+        });
+        self.processed_len += processed_len;
+    }
+
+    /// Gets the length of the processed text described so far
+    pub fn processed_len(&self) -> usize {
+        self.processed_len
+    }
+
+    /// Maps a span in the processed text to the original text
+    ///
+    /// Returns `None` if the span extends past the end of the processed text.
+    pub fn map_span(&self, span: SourceSpan) -> Option<SourceSpan> {
+        let start = span.offset();
+        let end = start.checked_add(span.len())?;
+        if end > self.processed_len {
+            return None;
+        }
+        if start == end {
+            // Empty spans just need their position mapped (the end of the text maps to the
+            // end of the last piece)
+            let offset = if start == self.processed_len {
+                self.pieces
+                    .last()
+                    .map(|piece| piece.original.end)
+                    .unwrap_or(0)
+            } else {
+                self.map_start(start)
+            };
+            return Some(SourceSpan::from(offset..offset));
+        }
+        let mapped_start = self.map_start(start);
+        // Map the last byte rather than the exclusive end, so spans that end at a piece
+        // boundary stay in that piece
+        let last = self.piece_at(end - 1);
+        let mapped_end = if last.verbatim {
+            last.original.start + (end - last.processed_start)
+        } else {
+            last.original.end
+        };
+        Some(SourceSpan::from(mapped_start..mapped_end.max(mapped_start)))
+    }
+
+    /// Maps a [`Spanned`][crate::Spanned] value's span in the processed text to the original text
+    ///
+    /// The span is left alone if it can't be mapped.
+    pub fn map_spanned<T>(&self, mut spanned: crate::Spanned<T>) -> crate::Spanned<T> {
+        let span = crate::Spanned::span(&spanned);
+        if let Some(mapped) = self.map_span(span) {
+            crate::Spanned::update_span(
+                &mut spanned,
+                mapped.offset(),
+                mapped.offset() + mapped.len(),
+            );
+        }
+        spanned
+    }
+
+    fn map_start(&self, offset: usize) -> usize {
+        let piece = self.piece_at(offset);
+        if piece.verbatim {
+            piece.original.start + (offset - piece.processed_start)
+        } else {
+            piece.original.start
+        }
+    }
+
+    /// Gets the piece containing the given (in bounds) offset of the processed text
+    fn piece_at(&self, offset: usize) -> &SpanMapPiece {
+        let idx = self
+            .pieces
+            .partition_point(|piece| piece.processed_start <= offset);
+        &self.pieces[idx - 1]
+    }
+}
+
+/// A machine-applicable fix for a [`SourceFile`][]: replace the text at a span with something else
+///
+/// Attach these to your diagnostics (see [`Suggestion::label`][]) so users can see what
+/// would change, and apply them all at once with [`SourceFile::apply_suggestions`][]
+/// to implement things like `--fix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    span: SourceSpan,
+    replacement: String,
+    message: Option<String>,
+}
+
+impl Suggestion {
+    /// Create a Suggestion to replace the text at `span` with `replacement`
+    ///
+    /// Use an empty span to insert text, or an empty replacement to delete it.
+    pub fn new(span: SourceSpan, replacement: impl Into<String>) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+            message: None,
+        }
+    }
+
+    /// Explain the Suggestion (used as the text of its [`Suggestion::label`][])
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Gets the span of text to replace
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+
+    /// Gets the text to replace the span with
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Gets the message explaining the Suggestion, if any
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Gets a label for displaying the Suggestion in a diagnostic
+    ///
+    /// This can be returned from [`miette::Diagnostic::labels`][] alongside any other labels.
+    pub fn label(&self) -> miette::LabeledSpan {
+        let text = match &self.message {
+            Some(message) => format!("{message}: `{}`", self.replacement),
+            None if self.replacement.is_empty() => "remove this".to_owned(),
+            None => format!("replace with `{}`", self.replacement),
+        };
+        miette::LabeledSpan::new_with_span(Some(text), self.span)
+    }
+}
+
+/// One difference found by [`SourceFile::semantic_diff`][]
+///
+/// Paths look like `package.authors[0]`, with keys that aren't plain identifiers quoted
+/// (`dependencies."serde.json"`). The empty path is the whole document.
+#[cfg(feature = "json-serde")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticChange {
+    /// A value was added
+    Added {
+        /// The key path of the value
+        path: String,
+        /// The new value
+        value: serde_json::Value,
+    },
+    /// A value was removed
+    Removed {
+        /// The key path of the value
+        path: String,
+        /// The old value
+        value: serde_json::Value,
+    },
+    /// A value was changed
+    Changed {
+        /// The key path of the value
+        path: String,
+        /// The old value
+        old: serde_json::Value,
+        /// The new value
+        new: serde_json::Value,
+    },
+}
+
+#[cfg(feature = "json-serde")]
+impl SemanticChange {
+    /// Gets the key path of the value that changed
+    pub fn path(&self) -> &str {
+        match self {
+            SemanticChange::Added { path, .. }
+            | SemanticChange::Removed { path, .. }
+            | SemanticChange::Changed { path, .. } => path,
+        }
+    }
+}
+
+#[cfg(feature = "json-serde")]
+impl std::fmt::Display for SemanticChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SemanticChange::Added { path, value } => write!(f, "+ {path} = {value}"),
+            SemanticChange::Removed { path, value } => write!(f, "- {path} = {value}"),
+            SemanticChange::Changed { path, old, new } => write!(f, "~ {path}: {old} => {new}"),
+        }
+    }
+}
+
+/// Recursively diffs two values, see [`SourceFile::semantic_diff`][]
+#[cfg(feature = "json-serde")]
+fn diff_values(
+    path: String,
+    old: serde_json::Value,
+    new: serde_json::Value,
+    changes: &mut Vec<SemanticChange>,
+) {
+    use serde_json::Value;
+
+    match (old, new) {
+        (Value::Object(mut old), Value::Object(new)) => {
+            for (key, new_val) in new {
+                let key_path = join_key_path(&path, &key);
+                match old.remove(&key) {
+                    Some(old_val) => diff_values(key_path, old_val, new_val, changes),
+                    None => changes.push(SemanticChange::Added {
+                        path: key_path,
+                        value: new_val,
+                    }),
+                }
+            }
+            for (key, old_val) in old {
+                changes.push(SemanticChange::Removed {
+                    path: join_key_path(&path, &key),
+                    value: old_val,
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            let mut old = old.into_iter();
+            let mut new = new.into_iter();
+            for idx in 0.. {
+                let idx_path = format!("{path}[{idx}]");
+                match (old.next(), new.next()) {
+                    (Some(old_val), Some(new_val)) => {
+                        diff_values(idx_path, old_val, new_val, changes)
+                    }
+                    (Some(old_val), None) => changes.push(SemanticChange::Removed {
+                        path: idx_path,
+                        value: old_val,
+                    }),
+                    (None, Some(new_val)) => changes.push(SemanticChange::Added {
+                        path: idx_path,
+                        value: new_val,
+                    }),
+                    (None, None) => break,
+                }
+            }
+        }
+        (old, new) => {
+            if old != new {
+                changes.push(SemanticChange::Changed { path, old, new });
+            }
+        }
+    }
+}
+
+/// Appends a key to a key path, quoting it if it's not a plain identifier
+#[cfg(feature = "json-serde")]
+fn join_key_path(path: &str, key: &str) -> String {
+    let is_plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let key = if is_plain {
+        key.to_owned()
+    } else {
+        format!("{key:?}")
+    };
+    if path.is_empty() {
+        key
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Writes a value in canonical form, see [`SourceFile::canonicalize_json`][]
+#[cfg(feature = "json-serde")]
+fn write_canonical_json(value: &serde_json::Value, out: &mut String) {
+    use serde_json::Value;
+
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => match number.as_f64().filter(|_| number.is_f64()) {
+            Some(float) => out.push_str(&canonical_float(float)),
+            None => out.push_str(&number.to_string()),
+        },
+        Value::Array(items) => {
+            out.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (idx, (key, item)) in entries.into_iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical_json(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Writes a float the way JavaScript's `Number.prototype.toString` does
+#[cfg(feature = "json-serde")]
+fn canonical_float(float: f64) -> String {
+    if float == 0.0 {
+        return "0".to_owned();
+    }
+    let sign = if float < 0.0 { "-" } else { "" };
+    // `{:e}` gives the shortest digits that round-trip, like `1.2345e-7`
+    let scientific = format!("{:e}", float.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("floats are always formatted with an exponent");
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent.parse().expect("exponents are always integers");
+    // Where the decimal point goes, relative to the start of the digits
+    let point = exponent + 1;
+    let len = digits.len() as i32;
+    let body = if len <= point && point <= 21 {
+        format!("{digits}{}", "0".repeat((point - len) as usize))
+    } else if 0 < point && point <= 21 {
+        let (int, frac) = digits.split_at(point as usize);
+        format!("{int}.{frac}")
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    } else {
+        let exponent_sign = if exponent < 0 { "-" } else { "+" };
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        format!("{first}{rest}e{exponent_sign}{}", exponent.abs())
+    };
+    format!("{sign}{body}")
+}
+
+/// Recursively merge `overlay` into `base`, see [`SourceFile::merge_toml`][]
+#[cfg(feature = "toml-edit")]
+fn merge_toml_tables(base: &mut dyn toml_edit::TableLike, overlay: &dyn toml_edit::TableLike) {
+    for (key, overlay_item) in overlay.iter() {
+        let base_table = base
+            .get_mut(key)
+            .and_then(|base_item| base_item.as_table_like_mut());
+        match (base_table, overlay_item.as_table_like()) {
+            (Some(base_table), Some(overlay_table)) => {
+                merge_toml_tables(base_table, overlay_table);
+            }
+            _ => {
+                // Keep any comments attached to a value we're replacing with another value
+                if let (Some(base_value), Some(overlay_value)) = (
+                    base.get_mut(key).and_then(|item| item.as_value_mut()),
+                    overlay_item.as_value(),
+                ) {
+                    let decor = base_value.decor().clone();
+                    *base_value = overlay_value.clone();
+                    *base_value.decor_mut() = decor;
+                    continue;
+                }
+                base.insert(key, overlay_item.clone());
+            }
+        }
+    }
+}
+
 impl SourceCode for SourceFile {
     fn read_span<'a>(
         &'a self,
@@ -250,3 +1150,24 @@ impl Debug for SourceFile {
             .finish()
     }
 }
+
+/// Finds the byte ranges of the fields of a raw CSV record, including their quotes
+#[cfg(feature = "csv-serde")]
+fn csv_field_ranges(record: &str, delimiter: u8) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (idx, byte) in record.bytes().enumerate() {
+        match byte {
+            // An escaped quote ("") just toggles twice
+            b'"' => quoted = !quoted,
+            _ if byte == delimiter && !quoted => {
+                ranges.push(start..idx);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    ranges.push(start..record.len());
+    ranges.into_iter()
+}
@@ -9,10 +9,35 @@ use std::{
 };
 
 use miette::SourceSpan;
-#[cfg(feature = "toml-serde")]
+#[cfg(feature = "serde")]
 use serde::{de, ser};
 
+#[cfg(feature = "toml-serde")]
+mod tracking;
+#[cfg(feature = "toml-serde")]
+pub(crate) use tracking::deserialize_with_spans;
+#[cfg(all(
+    feature = "toml-serde",
+    any(feature = "json-serde", feature = "yaml-serde", feature = "csv-serde")
+))]
+pub(crate) use tracking::without_spans;
+
+/// Runs `deserialize`, which reads a format that doesn't record spans
+///
+/// Only toml's spans need any special handling, so this does nothing extra.
+#[cfg(all(
+    not(feature = "toml-serde"),
+    any(feature = "json-serde", feature = "yaml-serde", feature = "csv-serde")
+))]
+pub(crate) fn without_spans<R>(deserialize: impl FnOnce() -> R) -> R {
+    deserialize()
+}
+
 /// A spanned value, indicating the range at which it is defined in the source.
+///
+/// Values that weren't given a span (made with `From`, or deserialized without one,
+/// see the Deserialize impl) have an empty span at offset 0, which points at the
+/// start of the source rather than at the value.
 #[derive(Clone, Default)]
 pub struct Spanned<T> {
     start: usize,
@@ -57,6 +82,24 @@ impl<T> Spanned<T> {
         (Self::start(this)..Self::end(this)).into()
     }
 
+    /// Get the line and column the span starts at, in the SourceFile the value was
+    /// parsed from
+    ///
+    /// See [`SourceFile::line_col`][crate::SourceFile::line_col] for how lines and
+    /// columns are counted.
+    pub fn start_line_col(this: &Self, source: &crate::SourceFile) -> Option<(usize, usize)> {
+        source.line_col(this.start)
+    }
+
+    /// Get the line and column the span ends at (just past its last character), in
+    /// the SourceFile the value was parsed from
+    ///
+    /// See [`SourceFile::line_col`][crate::SourceFile::line_col] for how lines and
+    /// columns are counted.
+    pub fn end_line_col(this: &Self, source: &crate::SourceFile) -> Option<(usize, usize)> {
+        source.line_col(this.end)
+    }
+
     /// Consumes the spanned value and returns the contained value.
     pub fn into_inner(this: Self) -> T {
         this.value
@@ -208,17 +251,34 @@ impl<T> From<toml::Spanned<T>> for Spanned<T> {
     }
 }
 
-#[cfg(feature = "toml-serde")]
+/// Deserializes the value, along with its span if the format supports it
+///
+/// Currently only toml records spans. When it's read with the SourceFile methods
+/// (like [`SourceFile::deserialize_toml`][crate::SourceFile::deserialize_toml]),
+/// values that can't have spans (from other formats, or toml values that serde
+/// buffers before deserializing them, as it does for `#[serde(flatten)]` fields and
+/// `#[serde(untagged)]` enums) are deserialized as normal with an empty span at
+/// offset 0.
+///
+/// Used with a Deserializer directly (like `toml::from_str`), the Deserializer is
+/// assumed to be toml's, so spans are recorded just like
+/// [`toml::Spanned`][] does, and other Deserializers (and buffered values) produce
+/// an error.
+#[cfg(feature = "serde")]
 impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Spanned<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: de::Deserializer<'de>,
     {
-        Ok(toml::Spanned::<T>::deserialize(deserializer)?.into())
+        #[cfg(feature = "toml-serde")]
+        if tracking::take_spans_available() != Some(false) {
+            return Ok(toml::Spanned::<T>::deserialize(deserializer)?.into());
+        }
+        Ok(T::deserialize(deserializer)?.into())
     }
 }
 
-#[cfg(feature = "toml-serde")]
+#[cfg(feature = "serde")]
 impl<T: ser::Serialize> ser::Serialize for Spanned<T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
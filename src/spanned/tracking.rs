@@ -0,0 +1,297 @@
+//! Telling [`Spanned`][super::Spanned] when it's being handed one of toml's own
+//! Deserializers, which are the only ones that know how to report spans
+//!
+//! toml reports spans by recognizing a magic struct name, and any other Deserializer
+//! asked for that struct errors out (even for a plain string), so Spanned has to know
+//! which kind it has before asking. The Deserializer is generic, so instead every
+//! Deserializer, Visitor, and access that toml hands out is wrapped here, and each
+//! time a value is about to be deserialized straight from toml a thread-local flag is
+//! raised. The Deserialize impl for the value either takes the flag (Spanned does) or
+//! calls a method of the Deserializer, which lowers it. Values that serde buffers
+//! (for `#[serde(flatten)]` and `#[serde(untagged)]`) are read with `deserialize_any`
+//! and replayed later by serde's own Deserializers, which never raise it.
+//!
+//! All of this only happens inside deserializations that a SourceFile starts. Outside
+//! of them (say, a direct call to `toml::from_str`) nothing is known about the
+//! Deserializer, and Spanned assumes it's toml's, as it always has.
+
+use std::cell::Cell;
+
+use serde::de::{self, DeserializeSeed, Deserializer, Visitor};
+
+thread_local! {
+    static TRACKING: Cell<bool> = const { Cell::new(false) };
+    static SPANS_AVAILABLE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Deserializes a value from one of toml's Deserializers, recording the spans of
+/// any [`Spanned`][super::Spanned] values in it
+pub(crate) fn deserialize_with_spans<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: de::Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let _tracking = Tracking::start();
+    Seed(std::marker::PhantomData::<T>).deserialize(deserializer)
+}
+
+/// Runs `deserialize`, which reads a format that doesn't record spans, so that any
+/// [`Spanned`][super::Spanned] values in it get empty spans instead of errors
+#[cfg(any(feature = "json-serde", feature = "yaml-serde", feature = "csv-serde"))]
+pub(crate) fn without_spans<R>(deserialize: impl FnOnce() -> R) -> R {
+    let _tracking = Tracking::start();
+    deserialize()
+}
+
+/// Whether the Deserializer about to be used is one of toml's, consuming the answer
+///
+/// Returns `None` outside of [`deserialize_with_spans`][] and [`without_spans`][],
+/// where there's no telling.
+pub(super) fn take_spans_available() -> Option<bool> {
+    TRACKING
+        .with(|tracking| tracking.get())
+        .then(|| SPANS_AVAILABLE.with(|spans| spans.replace(false)))
+}
+
+/// Marks a deserialization as tracked until it's dropped
+struct Tracking {
+    was_tracking: bool,
+}
+
+impl Tracking {
+    fn start() -> Self {
+        lower();
+        Tracking {
+            was_tracking: TRACKING.with(|tracking| tracking.replace(true)),
+        }
+    }
+}
+
+impl Drop for Tracking {
+    fn drop(&mut self) {
+        TRACKING.with(|tracking| tracking.set(self.was_tracking));
+        lower();
+    }
+}
+
+fn raise() {
+    SPANS_AVAILABLE.with(|spans| spans.set(true));
+}
+
+fn lower() {
+    SPANS_AVAILABLE.with(|spans| spans.set(false));
+}
+
+/// A DeserializeSeed that raises the flag before handing its value a wrapped
+/// Deserializer
+struct Seed<S>(S);
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Seed<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        raise();
+        self.0.deserialize(Wrapped(deserializer))
+    }
+}
+
+/// A Deserializer, Visitor, or access that wraps everything it hands out
+struct Wrapped<T>(T);
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error> {
+                lower();
+                self.0.$method($($arg,)* Wrapped(visitor))
+            }
+        )*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Wrapped<D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any(),
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_option(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map(),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<V::Value, E> {
+                self.0.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for Wrapped<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_i128(i128),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_u128(u128),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.0.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        raise();
+        self.0.visit_some(Wrapped(deserializer))
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        raise();
+        self.0.visit_newtype_struct(Wrapped(deserializer))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.0.visit_seq(Wrapped(seq))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.0.visit_map(Wrapped(map))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.0.visit_enum(Wrapped(data))
+    }
+}
+
+impl<'de, A: de::SeqAccess<'de>> de::SeqAccess<'de> for Wrapped<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        self.0.next_element_seed(Seed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for Wrapped<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        self.0.next_key_seed(Seed(seed))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.0.next_value_seed(Seed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+}
+
+impl<'de, A: de::EnumAccess<'de>> de::EnumAccess<'de> for Wrapped<A> {
+    type Error = A::Error;
+    type Variant = Wrapped<A::Variant>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Wrapped<A::Variant>), A::Error> {
+        let (value, variant) = self.0.variant_seed(Seed(seed))?;
+        Ok((value, Wrapped(variant)))
+    }
+}
+
+impl<'de, A: de::VariantAccess<'de>> de::VariantAccess<'de> for Wrapped<A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.0.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, A::Error> {
+        self.0.newtype_variant_seed(Seed(seed))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        self.0.tuple_variant(len, Wrapped(visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.0.struct_variant(fields, Wrapped(visitor))
+    }
+}
@@ -0,0 +1,83 @@
+//! Formatting for timestamps, without pulling in a whole date library
+
+/// A unix timestamp broken down into its UTC calendar date and time of day
+pub(crate) struct UtcTime {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+}
+
+impl UtcTime {
+    /// Breaks down a unix timestamp (seconds since the epoch)
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86400) as i64;
+        let time = secs % 86400;
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time % 3600 / 60,
+            second: time % 60,
+        }
+    }
+
+    /// Formats the time as an RFC 3339 timestamp (`YYYY-MM-DDTHH:MM:SSZ`)
+    #[cfg(any(feature = "remote", feature = "__tar", feature = "compression-zip"))]
+    pub fn to_rfc3339(&self) -> String {
+        let Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        } = self;
+        format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+    }
+
+    /// Parses an RFC 3339 timestamp in UTC (`YYYY-MM-DDTHH:MM:SSZ`), as written by
+    /// [`UtcTime::to_rfc3339`][], into a unix timestamp
+    ///
+    /// Returns `None` if it's malformed or too far in the future to represent.
+    #[cfg(feature = "remote")]
+    pub fn parse_rfc3339(timestamp: &str) -> Option<u64> {
+        let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+        let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+        let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+        let mut time = time.splitn(3, ':').map(str::parse::<u64>);
+        let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = year - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = (month + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era.checked_mul(146097)?.checked_add(doe - 719468)?;
+        let days = u64::try_from(days).ok()?;
+        days.checked_mul(86400)?
+            .checked_add(hour * 3600 + minute * 60 + second)
+    }
+}
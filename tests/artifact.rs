@@ -0,0 +1,53 @@
+use axoasset::ArtifactName;
+
+#[test]
+fn it_names_artifacts() {
+    let name = ArtifactName::new("my-app", "v1.2.3", "x86_64-pc-windows-msvc", ".zip");
+    assert_eq!(name.to_string(), "my-app-v1.2.3-x86_64-pc-windows-msvc.zip");
+    assert_eq!(name.version(), "1.2.3");
+    assert_eq!(name.format(), "zip");
+}
+
+#[test]
+fn it_parses_artifact_names() {
+    let cases = [
+        (
+            "myapp-v1.2.3-x86_64-unknown-linux-gnu.tar.gz",
+            ("myapp", "1.2.3", "x86_64-unknown-linux-gnu", "tar.gz"),
+        ),
+        (
+            "my-app-v0.1.0-beta.2-aarch64-apple-darwin.tar.xz",
+            ("my-app", "0.1.0-beta.2", "aarch64-apple-darwin", "tar.xz"),
+        ),
+        (
+            "tool-v2.0.0-armv7-unknown-linux-musleabihf.tar.zstd",
+            (
+                "tool",
+                "2.0.0",
+                "armv7-unknown-linux-musleabihf",
+                "tar.zstd",
+            ),
+        ),
+        (
+            "app-v1.0.0-x86_64-pc-windows-msvc.msi",
+            ("app", "1.0.0", "x86_64-pc-windows-msvc", "msi"),
+        ),
+    ];
+    for (name, (base, version, triple, format)) in cases {
+        let parsed = ArtifactName::parse(name).unwrap();
+        assert_eq!(parsed.base(), base, "{name}");
+        assert_eq!(parsed.version(), version, "{name}");
+        assert_eq!(parsed.triple(), triple, "{name}");
+        assert_eq!(parsed.format(), format, "{name}");
+        assert_eq!(parsed.to_string(), name);
+    }
+
+    for name in [
+        "myapp-x86_64-unknown-linux-gnu.tar.gz",
+        "myapp-v1.2.3.tar.gz",
+        "myapp-v1.2.3-x86_64-unknown-linux-gnu.7z",
+        "v1.2.3-x86_64-unknown-linux-gnu.tar.gz",
+    ] {
+        assert!(ArtifactName::parse(name).is_err(), "{name}");
+    }
+}
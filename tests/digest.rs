@@ -0,0 +1,163 @@
+use axoasset::{AxoassetError, Digest, DigestAlgorithm};
+
+const EMPTY_SHA256_HEX: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+const EMPTY_SHA256_SRI: &str = "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+#[test]
+fn digest_formats() {
+    let digest = Digest::compute(DigestAlgorithm::Sha256, b"");
+    assert_eq!(digest.to_hex(), EMPTY_SHA256_HEX);
+    assert_eq!(digest.to_string(), format!("sha256:{EMPTY_SHA256_HEX}"));
+    assert_eq!(digest.to_sri(), EMPTY_SHA256_SRI);
+    assert!(digest.matches(b""));
+    assert!(!digest.matches(b"not empty"));
+}
+
+#[test]
+fn digest_parses() {
+    let expected = Digest::compute(DigestAlgorithm::Sha256, b"");
+    let formats = [
+        format!("sha256:{EMPTY_SHA256_HEX}"),
+        format!("SHA-256:{}", EMPTY_SHA256_HEX.to_uppercase()),
+        EMPTY_SHA256_HEX.to_owned(),
+        EMPTY_SHA256_SRI.to_owned(),
+    ];
+    for format in formats {
+        let digest = format.parse::<Digest>().unwrap();
+        assert_eq!(digest, expected, "{format}");
+    }
+
+    let sha512 = Digest::compute(DigestAlgorithm::Sha512, b"hello");
+    assert_eq!(sha512.to_hex().parse::<Digest>().unwrap(), sha512);
+    assert_eq!(sha512.to_sri().parse::<Digest>().unwrap(), sha512);
+}
+
+#[test]
+fn digest_parse_errors() {
+    let bad = [
+        "md5:d41d8cd98f00b204e9800998ecf8427e",
+        "sha256:abcd",
+        "sha256:zz",
+        "sha256-not base64",
+        "abcdef",
+        "",
+    ];
+    for digest in bad {
+        let res = digest.parse::<Digest>();
+        assert!(
+            matches!(res, Err(AxoassetError::DigestParse { .. })),
+            "{digest} should have failed to parse"
+        );
+    }
+}
+
+#[test]
+fn digest_hasher_streams() {
+    let mut hasher = Digest::hasher(DigestAlgorithm::Sha384);
+    hasher.update(b"hello ");
+    std::io::copy(&mut &b"world"[..], &mut hasher).unwrap();
+    assert_eq!(
+        hasher.finish(),
+        Digest::compute(DigestAlgorithm::Sha384, b"hello world")
+    );
+}
+
+#[test]
+fn hash_dir() {
+    use axoasset::LocalAsset;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let make_tree = |dir: &camino::Utf8Path| {
+        for i in 0..20 {
+            LocalAsset::write_new_all(
+                &format!("file {i}"),
+                dir.join(format!("sub{}/{i}.txt", i % 3)),
+            )
+            .unwrap();
+        }
+        LocalAsset::create_dir_all(dir.join("empty")).unwrap();
+    };
+    let a = root.join("a");
+    let b = root.join("b");
+    make_tree(&a);
+    make_tree(&b);
+
+    let digest = LocalAsset::hash_dir(&a, DigestAlgorithm::Sha256).unwrap();
+    assert_eq!(digest.algorithm(), DigestAlgorithm::Sha256);
+    assert_eq!(
+        digest,
+        LocalAsset::hash_dir(&b, DigestAlgorithm::Sha256).unwrap()
+    );
+
+    // Single files hash the same as they do in memory
+    assert_eq!(
+        LocalAsset::hash_file(a.join("sub0/0.txt"), DigestAlgorithm::Sha512).unwrap(),
+        Digest::compute(DigestAlgorithm::Sha512, b"file 0")
+    );
+
+    // Changing contents, renaming, and adding empty dirs all change the digest
+    LocalAsset::write_new("changed", b.join("sub1/1.txt")).unwrap();
+    let changed = LocalAsset::hash_dir(&b, DigestAlgorithm::Sha256).unwrap();
+    assert_ne!(digest, changed);
+    LocalAsset::write_new("file 1", b.join("sub1/1.txt")).unwrap();
+    assert_eq!(
+        digest,
+        LocalAsset::hash_dir(&b, DigestAlgorithm::Sha256).unwrap()
+    );
+    std::fs::rename(b.join("sub1/1.txt"), b.join("sub1/one.txt")).unwrap();
+    assert_ne!(
+        digest,
+        LocalAsset::hash_dir(&b, DigestAlgorithm::Sha256).unwrap()
+    );
+    std::fs::rename(b.join("sub1/one.txt"), b.join("sub1/1.txt")).unwrap();
+    LocalAsset::create_dir_all(b.join("empty2")).unwrap();
+    assert_ne!(
+        digest,
+        LocalAsset::hash_dir(&b, DigestAlgorithm::Sha256).unwrap()
+    );
+}
+
+#[test]
+fn digests_from_checksum_files() {
+    use axoasset::SourceFile;
+
+    let empty = Digest::compute(DigestAlgorithm::Sha256, b"");
+    let other = Digest::compute(DigestAlgorithm::Sha512, b"other");
+    let pick = |contents: &str, filename: &str| {
+        Digest::from_checksum_file(
+            &SourceFile::new("SHA256SUMS", contents.to_owned()),
+            filename,
+        )
+        .map_err(Box::new)
+    };
+
+    // A bare sidecar applies to anything
+    assert_eq!(
+        pick(&format!("{EMPTY_SHA256_HEX}\n"), "app.zip").unwrap(),
+        empty
+    );
+
+    let sums = format!(
+        "# generated by a release pipeline\n\
+         {EMPTY_SHA256_HEX}  ./dist/app.tar.gz\n\
+         {} *app.zip\n\
+         SHA512 (app.msi) = {}\n",
+        other.to_hex(),
+        other.to_hex()
+    );
+    assert_eq!(pick(&sums, "app.tar.gz").unwrap(), empty);
+    assert_eq!(pick(&sums, "app.zip").unwrap(), other);
+    assert_eq!(pick(&sums, "app.msi").unwrap(), other);
+
+    let err = pick(&sums, "app.dmg").unwrap_err();
+    assert!(matches!(
+        *err,
+        AxoassetError::ChecksumFileParse { span: None, .. }
+    ));
+    let err = pick("nothex  app.dmg\n", "app.dmg").unwrap_err();
+    assert!(matches!(
+        *err,
+        AxoassetError::ChecksumFileParse { span: Some(_), .. }
+    ));
+}
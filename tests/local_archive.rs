@@ -0,0 +1,772 @@
+#![cfg(all(feature = "compression-tar", feature = "compression-zip"))]
+
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+
+#[test]
+fn it_unpacks_nested_archives() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+
+    // app/bin/app => app.tar.gz
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    let wrapper_dir = tmp_dir.join("wrapper");
+    LocalAsset::create_dir_all(&wrapper_dir).unwrap();
+    LocalAsset::tar_gz_dir(&app_dir, wrapper_dir.join("app.tar.gz"), Some("app")).unwrap();
+    LocalAsset::write_new("notes", wrapper_dir.join("NOTES.txt")).unwrap();
+
+    // wrapper/{app.tar.gz,NOTES.txt} => app.zip
+    let zip = tmp_dir.join("app.zip");
+    LocalAsset::zip_dir(&wrapper_dir, &zip, None::<&str>).unwrap();
+
+    // Only the outer archive
+    let shallow = tmp_dir.join("shallow");
+    let unpacked = LocalAsset::unpack_archive_nested(&zip, &shallow, 0).unwrap();
+    assert!(unpacked.is_empty());
+    assert!(shallow.join("app.tar.gz").exists());
+    assert!(!shallow.join("app/bin/app").exists());
+
+    // Everything
+    let deep = tmp_dir.join("deep");
+    let unpacked = LocalAsset::unpack_archive_nested(&zip, &deep, 3).unwrap();
+    assert_eq!(unpacked, vec![deep.join("app.tar.gz")]);
+    assert!(!deep.join("app.tar.gz").exists());
+    assert!(deep.join("NOTES.txt").exists());
+    assert_eq!(
+        LocalAsset::load_string(deep.join("app/bin/app")).unwrap(),
+        "#!/bin/sh"
+    );
+}
+
+#[test]
+fn it_only_unpacks_nested_archives_it_extracted() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+
+    // The archive being unpacked, and another one, are already in the destination
+    let dest = tmp_dir.join("dest");
+    let tarball = dest.join("app.tar.gz");
+    let other = dest.join("other.tar.gz");
+    LocalAsset::create_dir_all(&dest).unwrap();
+    LocalAsset::tar_gz_dir(&app_dir, &tarball, Some("app")).unwrap();
+    LocalAsset::tar_gz_dir(&app_dir, &other, Some("other")).unwrap();
+
+    let unpacked = LocalAsset::unpack_archive_nested(&tarball, &dest, 3).unwrap();
+    assert!(unpacked.is_empty());
+    assert!(tarball.exists());
+    assert!(other.exists());
+    assert!(dest.join("app/bin/app").exists());
+    assert!(!dest.join("other").exists());
+}
+
+#[test]
+fn it_rejects_unknown_archives() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let archive = tmp_dir.join("archive.rar");
+    LocalAsset::write_new("", &archive).unwrap();
+
+    let res = LocalAsset::unpack_archive_nested(&archive, tmp_dir.join("out"), 1);
+    assert!(matches!(
+        res,
+        Err(axoasset::AxoassetError::UnknownArchiveFormat { .. })
+    ));
+}
+
+#[test]
+fn it_builds_tarballs_from_readers() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+
+    for name in ["app.tar.gz", "app.tar.xz", "app.tar.zstd"] {
+        let tarball = tmp_dir.join(name);
+        let mut builder = match name {
+            "app.tar.gz" => axoasset::TarBuilder::tar_gz(&tarball),
+            "app.tar.xz" => axoasset::TarBuilder::tar_xz(&tarball),
+            _ => axoasset::TarBuilder::tar_zstd(&tarball),
+        }
+        .unwrap();
+        builder.append_bytes("app/README.md", "# app\n").unwrap();
+        // Only the first len bytes of the reader are used
+        builder
+            .append_reader("app/data.bin", 4, std::io::Cursor::new(b"datadata"))
+            .unwrap();
+        builder.finish().unwrap();
+
+        let dest = tmp_dir.join(format!("{name}-unpacked"));
+        match name {
+            "app.tar.gz" => LocalAsset::untar_gz_all(&tarball, &dest),
+            "app.tar.xz" => LocalAsset::untar_xz_all(&tarball, &dest),
+            _ => LocalAsset::untar_zstd_all(&tarball, &dest),
+        }
+        .unwrap();
+        assert_eq!(
+            LocalAsset::load_string(dest.join("app/README.md")).unwrap(),
+            "# app\n"
+        );
+        assert_eq!(
+            LocalAsset::load_string(dest.join("app/data.bin")).unwrap(),
+            "data"
+        );
+    }
+
+    // Readers that come up short are errors
+    let mut builder = axoasset::TarBuilder::tar_gz(tmp_dir.join("short.tar.gz")).unwrap();
+    assert!(builder
+        .append_reader("short.txt", 100, std::io::Cursor::new(b"short"))
+        .is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn it_includes_xattrs_when_asked() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    let file = app_dir.join("bin/app");
+    LocalAsset::write_new_all("#!/bin/sh", &file).unwrap();
+    if xattr::set(&file, "user.axoasset.test", b"hello").is_err() {
+        // Not every filesystem supports user xattrs (tmpfs on older kernels doesn't)
+        return;
+    }
+
+    // Collects the xattrs recorded for each file in a tarball
+    let tarball_xattrs = |tarball: &Utf8Path| {
+        let file = std::fs::File::open(tarball).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut found = vec![];
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().display().to_string();
+            if let Some(extensions) = entry.pax_extensions().unwrap() {
+                for extension in extensions {
+                    let extension = extension.unwrap();
+                    found.push((
+                        path.clone(),
+                        extension.key().unwrap().to_owned(),
+                        extension.value_bytes().to_owned(),
+                    ));
+                }
+            }
+        }
+        found
+    };
+
+    // Off by default
+    let plain = tmp_dir.join("plain.tar.gz");
+    LocalAsset::tar_gz_dir(&app_dir, &plain, Some("app")).unwrap();
+    assert!(tarball_xattrs(&plain).is_empty());
+
+    let mut options = axoasset::local::TarOptions::default();
+    options.include_xattrs = true;
+    let with_xattrs = tmp_dir.join("xattrs.tar.gz");
+    LocalAsset::tar_gz_dir_with_options(&app_dir, &with_xattrs, Some("app"), &options).unwrap();
+    assert_eq!(
+        tarball_xattrs(&with_xattrs),
+        vec![(
+            "app/bin/app".to_owned(),
+            "SCHILY.xattr.user.axoasset.test".to_owned(),
+            b"hello".to_vec()
+        )]
+    );
+    // The rest of the archive is unaffected
+    let dest = tmp_dir.join("unpacked");
+    LocalAsset::untar_gz_all(&with_xattrs, &dest).unwrap();
+    assert_eq!(
+        LocalAsset::load_string(dest.join("app/bin/app")).unwrap(),
+        "#!/bin/sh"
+    );
+}
+
+#[test]
+fn it_lays_out_archives_consistently() {
+    use axoasset::local::ArchiveLayout;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    LocalAsset::write_new("# app", app_dir.join("README.md")).unwrap();
+
+    // Lists the entries of an archive, with a trailing slash for dirs
+    let tar_entries = |tarball: &Utf8Path| {
+        let file = std::fs::File::open(tarball).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut entries = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let path = entry.path().unwrap().display().to_string();
+                let path = path.trim_end_matches('/').to_owned();
+                if entry.header().entry_type().is_dir() {
+                    format!("{path}/")
+                } else {
+                    path
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    };
+    let zip_entries = |zipfile: &Utf8Path| {
+        let file = std::fs::File::open(zipfile).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut entries = (0..archive.len())
+            .map(|idx| archive.by_index(idx).unwrap().name().to_owned())
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+    };
+
+    let cases: Vec<(ArchiveLayout, Vec<&str>)> = vec![
+        (ArchiveLayout::Flat, vec!["README.md", "bin/", "bin/app"]),
+        (
+            ArchiveLayout::RootDir("app-1.0.0".to_owned()),
+            vec![
+                "app-1.0.0/",
+                "app-1.0.0/README.md",
+                "app-1.0.0/bin/",
+                "app-1.0.0/bin/app",
+            ],
+        ),
+        (
+            ArchiveLayout::Prefix("./share/doc/app/".into()),
+            vec![
+                "share/",
+                "share/doc/",
+                "share/doc/app/",
+                "share/doc/app/README.md",
+                "share/doc/app/bin/",
+                "share/doc/app/bin/app",
+            ],
+        ),
+    ];
+    for (idx, (layout, expected)) in cases.into_iter().enumerate() {
+        let tarball = tmp_dir.join(format!("{idx}.tar.gz"));
+        LocalAsset::tar_gz_dir(&app_dir, &tarball, layout.clone()).unwrap();
+        assert_eq!(tar_entries(&tarball), expected, "{layout:?}");
+        let zipfile = tmp_dir.join(format!("{idx}.zip"));
+        LocalAsset::zip_dir(&app_dir, &zipfile, layout.clone()).unwrap();
+        assert_eq!(zip_entries(&zipfile), expected, "{layout:?}");
+    }
+
+    // The old Option-based API maps onto layouts
+    let tarball = tmp_dir.join("option.tar.gz");
+    LocalAsset::tar_gz_dir(&app_dir, &tarball, Some("a/b")).unwrap();
+    assert_eq!(
+        tar_entries(&tarball),
+        vec!["a/", "a/b/", "a/b/README.md", "a/b/bin/", "a/b/bin/app"]
+    );
+
+    // Prefixes can't escape the archive
+    for layout in [
+        ArchiveLayout::Prefix("../oops".into()),
+        ArchiveLayout::Prefix("/oops".into()),
+        ArchiveLayout::RootDir("two/dirs".to_owned()),
+    ] {
+        assert!(LocalAsset::zip_dir(&app_dir, tmp_dir.join("bad.zip"), layout.clone()).is_err());
+        assert!(LocalAsset::tar_gz_dir(&app_dir, tmp_dir.join("bad.tar.gz"), layout).is_err());
+    }
+}
+
+#[test]
+fn it_orders_archive_entries_by_name() {
+    use axoasset::local::{TarOptions, WalkOrder, ZipOptions};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    // Created out of order, so filesystems that list in creation order don't sort them for us
+    for name in ["zed", "b/2", "alpha", "b/1", "m"] {
+        LocalAsset::write_new_all(name, app_dir.join(name)).unwrap();
+    }
+    let expected = vec!["alpha", "b/", "b/1", "b/2", "m", "zed"];
+
+    let tarball = tmp_dir.join("app.tar.gz");
+    LocalAsset::tar_gz_dir(&app_dir, &tarball, None::<&str>).unwrap();
+    let file = std::fs::File::open(&tarball).unwrap();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let tar_entries = archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+        .collect::<Vec<_>>();
+    // tar doesn't put a trailing slash on dirs
+    assert_eq!(tar_entries, ["alpha", "b", "b/1", "b/2", "m", "zed"]);
+
+    let zipfile = tmp_dir.join("app.zip");
+    LocalAsset::zip_dir(&app_dir, &zipfile, None::<&str>).unwrap();
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&zipfile).unwrap()).unwrap();
+    let zip_entries = (0..archive.len())
+        .map(|idx| archive.by_index(idx).unwrap().name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(zip_entries, expected);
+
+    // Filesystem order has the same entries, in whatever order
+    let mut tar_options = TarOptions::default();
+    tar_options.walk_order = WalkOrder::Filesystem;
+    let fast_tarball = tmp_dir.join("fast.tar.gz");
+    LocalAsset::tar_gz_dir_with_options(&app_dir, &fast_tarball, None::<&str>, &tar_options)
+        .unwrap();
+    let file = std::fs::File::open(&fast_tarball).unwrap();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    assert_eq!(archive.entries().unwrap().count(), expected.len());
+
+    let mut zip_options = ZipOptions::default();
+    zip_options.walk_order = WalkOrder::Filesystem;
+    let fast_zipfile = tmp_dir.join("fast.zip");
+    LocalAsset::zip_dir_with_options(&app_dir, &fast_zipfile, None::<&str>, &zip_options).unwrap();
+    let archive = zip::ZipArchive::new(std::fs::File::open(&fast_zipfile).unwrap()).unwrap();
+    assert_eq!(archive.len(), expected.len());
+}
+
+#[test]
+fn it_includes_empty_dirs_in_archives() {
+    use axoasset::local::{ArchiveLayout, TarOptions, ZipOptions};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    let empty_dirs = vec![
+        "logs".into(),
+        "config/local".into(),
+        "bin".into(),
+        "bin/app".into(),
+    ];
+    let layout = ArchiveLayout::RootDir("app-1.0.0".to_owned());
+
+    let mut tar_options = TarOptions::default();
+    tar_options.empty_dirs = empty_dirs.clone();
+    let tarball = tmp_dir.join("app.tar.gz");
+    LocalAsset::tar_gz_dir_with_options(&app_dir, &tarball, layout.clone(), &tar_options).unwrap();
+    let mut zip_options = ZipOptions::default();
+    zip_options.empty_dirs = empty_dirs;
+    let zipfile = tmp_dir.join("app.zip");
+    LocalAsset::zip_dir_with_options(&app_dir, &zipfile, layout.clone(), &zip_options).unwrap();
+
+    // Dirs (or files) that already exist aren't added twice
+    let file = std::fs::File::open(&zipfile).unwrap();
+    let archive = zip::ZipArchive::new(file).unwrap();
+    let mut names = archive.file_names().collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "app-1.0.0/",
+            "app-1.0.0/bin/",
+            "app-1.0.0/bin/app",
+            "app-1.0.0/config/",
+            "app-1.0.0/config/local/",
+            "app-1.0.0/logs/",
+        ]
+    );
+
+    LocalAsset::untar_gz_all(&tarball, &tmp_dir.join("from-tar")).unwrap();
+    LocalAsset::unzip_all(&zipfile, tmp_dir.join("from-zip")).unwrap();
+    for unpacked in ["from-tar", "from-zip"] {
+        let root = tmp_dir.join(unpacked).join("app-1.0.0");
+        assert!(root.join("logs").is_dir(), "{unpacked}");
+        assert!(root.join("config/local").is_dir(), "{unpacked}");
+        assert!(root.join("bin/app").is_file(), "{unpacked}");
+    }
+
+    // Empty dirs can't escape the archive
+    tar_options.empty_dirs = vec!["../logs".into()];
+    assert!(LocalAsset::tar_gz_dir_with_options(
+        &app_dir,
+        tmp_dir.join("bad.tar.gz"),
+        layout,
+        &tar_options
+    )
+    .is_err());
+
+    // The builder can add them too
+    let tarball = tmp_dir.join("built.tar.gz");
+    let mut builder = axoasset::TarBuilder::tar_gz(&tarball).unwrap();
+    builder.add_empty_dir("app/logs").unwrap();
+    builder.append_bytes("app/README.md", "# app\n").unwrap();
+    builder.finish().unwrap();
+    LocalAsset::untar_gz_all(&tarball, &tmp_dir.join("built")).unwrap();
+    assert!(tmp_dir.join("built/app/logs").is_dir());
+    // With a fixed mtime, so building the same tarball twice gives the same bytes
+    let file = std::fs::File::open(&tarball).unwrap();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut entries = archive.entries().unwrap();
+    let dir = entries.next().unwrap().unwrap();
+    assert_eq!(&*dir.path().unwrap(), std::path::Path::new("app/logs"));
+    assert_eq!(dir.header().mtime().unwrap(), 0);
+}
+
+#[test]
+fn it_only_rebuilds_changed_archives() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    let options = axoasset::local::TarOptions::default();
+    let fingerprints = tmp_dir.join("cache/fingerprints");
+
+    for name in ["app.tar.gz", "app.zip"] {
+        let archive = tmp_dir.join(name);
+        let build = |root: Option<&str>| {
+            LocalAsset::archive_dir_if_changed(&app_dir, &archive, root, &options, &fingerprints)
+                .unwrap()
+        };
+        assert!(build(None), "{name}");
+        let built = std::fs::metadata(&archive).unwrap().modified().unwrap();
+        assert!(!build(None), "{name}");
+        let reused = std::fs::metadata(&archive).unwrap().modified().unwrap();
+        assert_eq!(built, reused, "{name}");
+
+        // Changing the layout, the contents, or the archive itself all force a rebuild
+        assert!(build(Some("app")), "{name}");
+        assert!(!build(Some("app")), "{name}");
+        LocalAsset::write_new("more", app_dir.join("README.md")).unwrap();
+        assert!(build(Some("app")), "{name}");
+        assert!(!build(Some("app")), "{name}");
+        LocalAsset::write_new("garbage", &archive).unwrap();
+        assert!(build(Some("app")), "{name}");
+        assert!(!build(Some("app")), "{name}");
+        LocalAsset::remove_file(&archive).unwrap();
+        assert!(build(Some("app")), "{name}");
+
+        LocalAsset::remove_file(app_dir.join("README.md")).unwrap();
+    }
+
+    // The fingerprints stay out of the way of the archives
+    let mut names = std::fs::read_dir(tmp_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["app", "app.tar.gz", "app.zip", "cache"]);
+    assert_eq!(std::fs::read_dir(&fingerprints).unwrap().count(), 2);
+
+    let err = LocalAsset::archive_dir_if_changed(
+        &app_dir,
+        tmp_dir.join("app.rar"),
+        None::<&str>,
+        &options,
+        &fingerprints,
+    );
+    assert!(err.is_err());
+}
+
+#[test]
+fn it_picks_zip_compression_per_file() {
+    use axoasset::local::{ZipCompression, ZipCompressionHook, ZipOptions};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    let text = "all work and no play makes jack a dull boy\n".repeat(100);
+    LocalAsset::write_new_all(&text, app_dir.join("README.md")).unwrap();
+    LocalAsset::write_new_all(&text, app_dir.join("img/logo.PNG")).unwrap();
+    LocalAsset::write_new_all(&text, app_dir.join("fonts/inter.woff2")).unwrap();
+
+    let methods = |zipfile: &Utf8Path| {
+        let file = std::fs::File::open(zipfile).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut methods = (0..archive.len())
+            .map(|idx| {
+                let entry = archive.by_index(idx).unwrap();
+                (entry.name().to_owned(), entry.compression())
+            })
+            .filter(|(name, _)| !name.ends_with('/'))
+            .collect::<Vec<_>>();
+        methods.sort_by(|(a, _), (b, _)| a.cmp(b));
+        methods
+    };
+
+    // By default nothing is compressed
+    let zipfile = tmp_dir.join("default.zip");
+    LocalAsset::zip_dir(&app_dir, &zipfile, None::<&str>).unwrap();
+    assert!(methods(&zipfile)
+        .iter()
+        .all(|(_, method)| *method == zip::CompressionMethod::Stored));
+
+    let mut options = ZipOptions::default();
+    options.compression = Some(ZipCompressionHook::skip_compressed());
+    let zipfile = tmp_dir.join("mixed.zip");
+    LocalAsset::zip_dir_with_options(&app_dir, &zipfile, Some("app"), &options).unwrap();
+    assert_eq!(
+        methods(&zipfile),
+        vec![
+            ("app/README.md".to_owned(), zip::CompressionMethod::Deflated),
+            (
+                "app/fonts/inter.woff2".to_owned(),
+                zip::CompressionMethod::Stored
+            ),
+            (
+                "app/img/logo.PNG".to_owned(),
+                zip::CompressionMethod::Stored
+            ),
+        ]
+    );
+    let unpacked = tmp_dir.join("unpacked");
+    LocalAsset::unzip_all(&zipfile, &unpacked).unwrap();
+    assert_eq!(
+        LocalAsset::load_string(unpacked.join("app/README.md")).unwrap(),
+        text
+    );
+
+    // Hooks see the path within the archive
+    options.compression = Some(ZipCompressionHook::new(|path| {
+        if path.starts_with("app/fonts") {
+            ZipCompression::Deflated
+        } else {
+            ZipCompression::Stored
+        }
+    }));
+    LocalAsset::zip_dir_with_options(&app_dir, &zipfile, Some("app"), &options).unwrap();
+    let deflated = methods(&zipfile)
+        .into_iter()
+        .filter(|(_, method)| *method == zip::CompressionMethod::Deflated)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(deflated, vec!["app/fonts/inter.woff2"]);
+}
+
+#[test]
+fn it_reports_extracted_entries() {
+    use axoasset::local::{ExtractHook, ExtractOptions};
+    use std::sync::{Arc, Mutex};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    LocalAsset::write_new("# app", app_dir.join("README.md")).unwrap();
+
+    let tarball = tmp_dir.join("app.tar.gz");
+    LocalAsset::tar_gz_dir(&app_dir, &tarball, Some("app")).unwrap();
+    let zipfile = tmp_dir.join("app.zip");
+    LocalAsset::zip_dir(&app_dir, &zipfile, Some("app")).unwrap();
+
+    for (archive, dest) in [(&tarball, "untarred"), (&zipfile, "unzipped")] {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut options = ExtractOptions::default();
+        let hook_seen = seen.clone();
+        options.on_entry = Some(ExtractHook::new(move |entry| {
+            hook_seen.lock().unwrap().push((
+                entry.path.as_str().trim_end_matches('/').to_owned(),
+                entry.size,
+                entry.is_dir,
+                entry.index,
+                entry.total,
+            ));
+        }));
+        let dest = tmp_dir.join(dest);
+        if archive == &tarball {
+            LocalAsset::untar_gz_all_with_options(archive, &dest, &options).unwrap();
+        } else {
+            LocalAsset::unzip_all_with_options(archive, &dest, &options).unwrap();
+        }
+
+        let mut seen = seen.lock().unwrap().clone();
+        let total = seen.len();
+        for (idx, (_, _, _, index, entry_total)) in seen.iter().enumerate() {
+            assert_eq!(*index, idx);
+            assert_eq!(*entry_total, total);
+        }
+        seen.sort();
+        let seen = seen
+            .into_iter()
+            .map(|(path, size, is_dir, _, _)| (path, size, is_dir))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            seen,
+            vec![
+                ("app".to_owned(), 0, true),
+                ("app/README.md".to_owned(), 5, false),
+                ("app/bin".to_owned(), 0, true),
+                ("app/bin/app".to_owned(), 9, false),
+            ],
+            "{archive}"
+        );
+        assert_eq!(
+            LocalAsset::load_string(dest.join("app/bin/app")).unwrap(),
+            "#!/bin/sh"
+        );
+    }
+}
+
+#[test]
+fn it_embeds_provenance_in_archives() {
+    use axoasset::local::{Provenance, TarOptions, ZipOptions};
+    use axoasset::{AxoassetError, Digest, DigestAlgorithm};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let app_dir = tmp_dir.join("app");
+    LocalAsset::write_new_all("#!/bin/sh", app_dir.join("bin/app")).unwrap();
+    LocalAsset::write_new("# app", app_dir.join("README.md")).unwrap();
+
+    let mut provenance = Provenance::new("builder \"pro\"", "1.2.3");
+    provenance.timestamp = Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000));
+    let mut tar_options = TarOptions::default();
+    tar_options.provenance = Some(provenance.clone());
+    let mut zip_options = ZipOptions::default();
+    zip_options.provenance = Some(provenance);
+
+    let tarball = tmp_dir.join("app.tar.gz");
+    LocalAsset::tar_gz_dir_with_options(&app_dir, &tarball, Some("app"), &tar_options).unwrap();
+    let zipfile = tmp_dir.join("app.zip");
+    LocalAsset::zip_dir_with_options(&app_dir, &zipfile, Some("app"), &zip_options).unwrap();
+
+    let expected = format!(
+        r#"{{
+  "tool": {{ "name": "builder \"pro\"", "version": "1.2.3" }},
+  "created": "2023-11-14T22:13:20Z",
+  "files": {{
+    "README.md": "{}",
+    "bin/app": "{}"
+  }}
+}}
+"#,
+        Digest::compute(DigestAlgorithm::Sha256, b"# app"),
+        Digest::compute(DigestAlgorithm::Sha256, b"#!/bin/sh"),
+    );
+    for (archive, dest) in [(&tarball, "untarred"), (&zipfile, "unzipped")] {
+        let dest = tmp_dir.join(dest);
+        if archive == &tarball {
+            LocalAsset::untar_gz_all(archive, &dest).unwrap();
+        } else {
+            LocalAsset::unzip_all(archive, &dest).unwrap();
+        }
+        assert_eq!(
+            LocalAsset::load_string(dest.join("app").join(Provenance::FILE_NAME)).unwrap(),
+            expected,
+            "{archive}"
+        );
+    }
+
+    // The provenance file can't shadow a real one
+    LocalAsset::write_new("{}", app_dir.join(Provenance::FILE_NAME)).unwrap();
+    let res = LocalAsset::tar_gz_dir_with_options(&app_dir, &tarball, Some("app"), &tar_options);
+    assert!(matches!(
+        res,
+        Err(AxoassetError::ArchiveProvenanceConflict { .. })
+    ));
+}
+
+#[test]
+fn it_plans_extraction_without_writing() {
+    use axoasset::local::{ExtractRejection, PlannedEntry, PlannedEntryKind};
+    use camino::Utf8PathBuf;
+    use std::io::Write;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+
+    // A tarball with a file, a dir, a symlink, and an entry that tries to escape
+    let tarball = tmp_dir.join("sketchy.tar.gz");
+    let file = std::fs::File::create(&tarball).unwrap();
+    let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+        file,
+        flate2::Compression::default(),
+    ));
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    tar.append_data(&mut header, "app/", std::io::empty())
+        .unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(9);
+    tar.append_data(&mut header, "app/run.sh", &b"#!/bin/sh"[..])
+        .unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    tar.append_link(&mut header, "app/current", "run.sh")
+        .unwrap();
+    // tar won't write a path with `..` in it, so sneak it into the header directly
+    let mut header = tar::Header::new_gnu();
+    header.set_size(4);
+    header.as_gnu_mut().unwrap().name[..11].copy_from_slice(b"../evil.txt");
+    header.set_cksum();
+    tar.append(&header, &b"evil"[..]).unwrap();
+    // Absolute paths just get extracted inside the destination
+    let mut header = tar::Header::new_gnu();
+    header.set_size(4);
+    header.as_gnu_mut().unwrap().name[..8].copy_from_slice(b"/abs.txt");
+    header.set_cksum();
+    tar.append(&header, &b"abs!"[..]).unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+
+    let entry = |path: &str, size, kind, rejected| (Utf8PathBuf::from(path), size, kind, rejected);
+    let summarize = |planned: Vec<PlannedEntry>| {
+        planned
+            .into_iter()
+            .map(|entry| (entry.path, entry.size, entry.kind, entry.rejected))
+            .collect::<Vec<_>>()
+    };
+    let planned = summarize(LocalAsset::plan_unpack_archive(&tarball).unwrap());
+    assert_eq!(
+        planned,
+        vec![
+            entry("app", 0, PlannedEntryKind::Dir, None),
+            entry("app/run.sh", 9, PlannedEntryKind::File, None),
+            entry(
+                "app/current",
+                0,
+                PlannedEntryKind::Symlink {
+                    target: "run.sh".into()
+                },
+                None
+            ),
+            entry(
+                "../evil.txt",
+                4,
+                PlannedEntryKind::File,
+                Some(ExtractRejection::OutsideDest)
+            ),
+            entry("abs.txt", 4, PlannedEntryKind::File, None),
+        ]
+    );
+
+    let zipfile = tmp_dir.join("sketchy.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zipfile).unwrap());
+    let options = zip::write::FileOptions::default();
+    zip.add_directory("app/", options).unwrap();
+    zip.start_file("app/run.sh", options).unwrap();
+    zip.write_all(b"#!/bin/sh").unwrap();
+    zip.start_file("../evil.txt", options).unwrap();
+    zip.write_all(b"evil").unwrap();
+    zip.start_file("/abs.txt", options).unwrap();
+    zip.write_all(b"abs!").unwrap();
+    zip.finish().unwrap();
+    let planned = summarize(LocalAsset::plan_unpack_archive(&zipfile).unwrap());
+    assert_eq!(
+        planned,
+        vec![
+            entry("app/", 0, PlannedEntryKind::Dir, None),
+            entry("app/run.sh", 9, PlannedEntryKind::File, None),
+            entry(
+                "../evil.txt",
+                4,
+                PlannedEntryKind::File,
+                Some(ExtractRejection::OutsideDest)
+            ),
+            entry(
+                "/abs.txt",
+                4,
+                PlannedEntryKind::File,
+                Some(ExtractRejection::AbsolutePath)
+            ),
+        ]
+    );
+
+    // Nothing was written
+    let mut remaining = std::fs::read_dir(tmp_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    remaining.sort();
+    assert_eq!(remaining, vec!["sketchy.tar.gz", "sketchy.zip"]);
+}
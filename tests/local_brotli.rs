@@ -0,0 +1,29 @@
+#![cfg(feature = "compression-brotli")]
+
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+
+#[test]
+fn it_round_trips_brotli_files() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let contents = "body { color: papayawhip; }\n".repeat(100);
+    let css = dest_dir.join("styles.css");
+    LocalAsset::write_new(&contents, &css).unwrap();
+
+    let compressed = dest_dir.join("styles.css.br");
+    LocalAsset::brotli_file(&css, &compressed).unwrap();
+    assert!(LocalAsset::load_bytes(&compressed).unwrap().len() < contents.len());
+
+    let decompressed = dest_dir.join("styles.roundtrip.css");
+    LocalAsset::unbrotli_file(&compressed, &decompressed).unwrap();
+    assert_eq!(LocalAsset::load_string(&decompressed).unwrap(), contents);
+
+    // Not brotli!
+    let res = LocalAsset::unbrotli_file(&css, dest_dir.join("garbage"));
+    assert!(matches!(
+        res,
+        Err(axoasset::AxoassetError::Decompression { .. })
+    ));
+}
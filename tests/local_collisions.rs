@@ -0,0 +1,44 @@
+use axoasset::LocalAsset;
+use camino::{Utf8Path, Utf8PathBuf};
+
+#[test]
+fn it_detects_case_collisions() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    LocalAsset::write_new_all("a", dest_dir.join("README.md")).unwrap();
+    LocalAsset::write_new_all("b", dest_dir.join("src/lib.rs")).unwrap();
+    LocalAsset::check_case_collisions(dest_dir).unwrap();
+
+    // This test only makes sense on case-sensitive filesystems
+    if LocalAsset::write_new_all("c", dest_dir.join("readme.md")).is_err()
+        || !dest_dir.join("README.md").exists()
+        || LocalAsset::load_string(dest_dir.join("README.md")).unwrap() != "a"
+    {
+        return;
+    }
+    LocalAsset::write_new_all("d", dest_dir.join("Src/Lib.rs")).unwrap();
+
+    let collisions = LocalAsset::find_case_collisions(dest_dir).unwrap();
+    assert_eq!(
+        collisions,
+        vec![
+            vec![
+                Utf8PathBuf::from("README.md"),
+                Utf8PathBuf::from("readme.md")
+            ],
+            vec![Utf8PathBuf::from("Src"), Utf8PathBuf::from("src")],
+            vec![
+                Utf8PathBuf::from("Src/Lib.rs"),
+                Utf8PathBuf::from("src/lib.rs")
+            ],
+        ]
+    );
+
+    let err = LocalAsset::check_case_collisions(dest_dir).unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::PathCaseCollisions { .. }
+    ));
+    assert!(err.to_string().contains("README.md, readme.md"));
+}
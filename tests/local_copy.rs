@@ -141,3 +141,336 @@ async fn it_copies_named_dirs() {
         }
     }
 }
+
+#[test]
+fn it_copies_over_readonly_files() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let origin_file = dest_dir.join("new.txt");
+    let dest_file = dest_dir.join("old.txt");
+    std::fs::write(&origin_file, "new contents").unwrap();
+    std::fs::write(&dest_file, "old contents").unwrap();
+    let mut permissions = std::fs::metadata(&dest_file).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&dest_file, permissions).unwrap();
+
+    let mut options = axoasset::local::CopyOptions::default();
+    options.clear_readonly = true;
+    axoasset::LocalAsset::copy_file_to_file_with_options(&origin_file, &dest_file, &options)
+        .unwrap();
+
+    let loaded = axoasset::LocalAsset::load_string(&dest_file).unwrap();
+    assert_eq!(loaded, "new contents");
+}
+
+#[test]
+fn it_replaces_unwritable_files() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let origin_file = dest_dir.join("new.txt");
+    let dest_file = dest_dir.join("old.txt");
+    std::fs::write(&origin_file, "new contents").unwrap();
+    std::fs::write(&dest_file, "old contents").unwrap();
+    let mut permissions = std::fs::metadata(&dest_file).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&dest_file, permissions).unwrap();
+
+    // (Privileged users can write to anything, in which case there's nothing to fall back from)
+    let writable = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&dest_file)
+        .is_ok();
+    if !writable {
+        axoasset::LocalAsset::copy_file_to_file(&origin_file, &dest_file).unwrap_err();
+    }
+
+    let mut options = axoasset::local::CopyOptions::default();
+    options.remove_on_permission_denied = true;
+    axoasset::LocalAsset::copy_file_to_file_with_options(&origin_file, &dest_file, &options)
+        .unwrap();
+
+    let loaded = axoasset::LocalAsset::load_string(&dest_file).unwrap();
+    assert_eq!(loaded, "new contents");
+}
+
+#[test]
+fn it_preserves_dir_metadata() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let origin = root.join("origin");
+    axoasset::LocalAsset::write_new_all("contents", origin.join("sub/file.txt")).unwrap();
+    axoasset::LocalAsset::create_dir_all(origin.join("empty")).unwrap();
+    let old = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+    for dir in ["sub", "empty", ""] {
+        filetime::set_file_mtime(origin.join(dir), old).unwrap();
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(origin.join("empty"), std::fs::Permissions::from_mode(0o700))
+            .unwrap();
+    }
+
+    let mtime = |path: &camino::Utf8Path| {
+        filetime::FileTime::from_last_modification_time(&std::fs::metadata(path).unwrap())
+    };
+
+    // By default the copies are brand new
+    let plain = root.join("plain");
+    axoasset::LocalAsset::copy_dir_to_dir(&origin, &plain).unwrap();
+    assert_ne!(mtime(&plain.join("sub")), old);
+
+    let mut options = axoasset::local::CopyOptions::default();
+    options.preserve_dir_mtimes = true;
+    options.preserve_dir_permissions = true;
+    let preserved = root.join("preserved");
+    axoasset::LocalAsset::copy_dir_to_dir_with_options(&origin, &preserved, &options).unwrap();
+    for dir in ["sub", "empty", ""] {
+        assert_eq!(mtime(&preserved.join(dir)), old, "{dir}");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(preserved.join("empty"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
+
+#[test]
+fn it_renames_files() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let origin = root.join("origin.txt");
+    let dest = root.join("dest.txt");
+    axoasset::LocalAsset::write_new("new", &origin).unwrap();
+    axoasset::LocalAsset::write_new("old", &dest).unwrap();
+
+    axoasset::LocalAsset::rename(&origin, &dest).unwrap();
+    assert!(!origin.exists());
+    assert_eq!(axoasset::LocalAsset::load_string(&dest).unwrap(), "new");
+
+    // Dirs work too
+    axoasset::LocalAsset::write_new_all("nested", root.join("dir/file.txt")).unwrap();
+    axoasset::LocalAsset::rename(root.join("dir"), root.join("moved")).unwrap();
+    assert_eq!(
+        axoasset::LocalAsset::load_string(root.join("moved/file.txt")).unwrap(),
+        "nested"
+    );
+
+    let err = axoasset::LocalAsset::rename(&origin, &dest).unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::LocalAssetRenameFailed { .. }
+    ));
+}
+
+#[test]
+fn it_compares_file_contents() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+
+    // Big enough to span several chunks, with the difference in the last one
+    let contents = "0123456789".repeat(20_000);
+    let mut changed = contents.clone();
+    changed.replace_range(contents.len() - 1.., "x");
+
+    let a = tmp_dir.join("a.txt");
+    let b = tmp_dir.join("b.txt");
+    let c = tmp_dir.join("c.txt");
+    let d = tmp_dir.join("d.txt");
+    LocalAsset::write_new(&contents, &a).unwrap();
+    LocalAsset::write_new(&contents, &b).unwrap();
+    LocalAsset::write_new(&changed, &c).unwrap();
+    LocalAsset::write_new(&contents[1..], &d).unwrap();
+
+    assert!(LocalAsset::files_identical(&a, &b).unwrap());
+    assert!(LocalAsset::files_identical(&a, &a).unwrap());
+    assert!(!LocalAsset::files_identical(&a, &c).unwrap());
+    assert!(!LocalAsset::files_identical(&a, &d).unwrap());
+
+    let err = LocalAsset::files_identical(&a, tmp_dir.join("missing.txt")).unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::LocalAssetReadFailed { .. }
+    ));
+}
+
+#[test]
+fn it_resolves_collisions_copying_into_a_dir() {
+    use axoasset::local::{CollisionStrategy, CopyOutcome};
+
+    let origin = assert_fs::TempDir::new().unwrap();
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+    let small = origin.child("a/app.tar.gz");
+    small.write_str("small").unwrap();
+    let large = origin.child("b/app.tar.gz");
+    large.write_str("much larger").unwrap();
+    let origins = [small.to_str().unwrap(), large.to_str().unwrap()];
+
+    let outcomes =
+        LocalAsset::copy_files_to_dir(origins, dest_dir, CollisionStrategy::Rename).unwrap();
+    assert_eq!(
+        outcomes[0].1,
+        CopyOutcome::Copied(dest_dir.join("app.tar.gz"))
+    );
+    assert_eq!(
+        outcomes[1].1,
+        CopyOutcome::Renamed(dest_dir.join("app-1.tar.gz"))
+    );
+    assert_eq!(
+        LocalAsset::load_string(dest_dir.join("app-1.tar.gz")).unwrap(),
+        "much larger"
+    );
+
+    let err = LocalAsset::copy_files_to_dir(origins, dest_dir, CollisionStrategy::Error);
+    assert!(matches!(
+        err,
+        Err(axoasset::AxoassetError::LocalAssetOverwriteRefused { .. })
+    ));
+
+    let outcomes =
+        LocalAsset::copy_files_to_dir(origins, dest_dir, CollisionStrategy::KeepLargest).unwrap();
+    assert_eq!(outcomes[0].1, CopyOutcome::Skipped);
+    assert_eq!(
+        outcomes[1].1,
+        CopyOutcome::Replaced(dest_dir.join("app.tar.gz"))
+    );
+    assert_eq!(
+        LocalAsset::load_string(dest_dir.join("app.tar.gz")).unwrap(),
+        "much larger"
+    );
+}
+
+#[test]
+fn it_skips_errors_copying_dirs() {
+    use axoasset::local::CopyOptions;
+    use axoasset::AxoassetError;
+
+    let origin = assert_fs::TempDir::new().unwrap();
+    let origin_dir = Utf8Path::from_path(origin.path()).unwrap();
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+    origin.child("a.txt").write_str("a").unwrap();
+    origin.child("b.txt").write_str("b").unwrap();
+    origin.child("sub/c.txt").write_str("c").unwrap();
+    // Files can't be copied over directories
+    dest.child("b.txt/blocker").write_str("").unwrap();
+
+    let res = LocalAsset::copy_dir_to_dir(origin_dir, dest_dir);
+    assert!(res.is_err());
+
+    let mut options = CopyOptions::default();
+    options.skip_errors = true;
+    let res = LocalAsset::copy_dir_to_dir_with_options(origin_dir, dest_dir, &options);
+    let Err(AxoassetError::LocalAssetCopyDirIncomplete { failures, .. }) = res else {
+        panic!("expected the copy to be incomplete: {res:?}");
+    };
+    // (the dest dir already existing is reported too)
+    assert_eq!(failures.len(), 2, "{failures:?}");
+    assert!(matches!(
+        &failures[1],
+        AxoassetError::LocalAssetCopyFailed { dest_path, .. } if dest_path.ends_with("b.txt")
+    ));
+    assert_eq!(
+        LocalAsset::load_string(dest_dir.join("a.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        LocalAsset::load_string(dest_dir.join("sub/c.txt")).unwrap(),
+        "c"
+    );
+}
+
+#[test]
+fn it_plans_dir_syncs() {
+    use axoasset::local::{
+        ChangeAction, ChangeReason, CopyOptions, OverwriteDecision, OverwriteHook,
+    };
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = Utf8Path::from_path(tmp.path()).unwrap();
+    let origin = root.join("site");
+    let dest = root.join("deployed");
+    LocalAsset::write_new_all("home", origin.join("index.html")).unwrap();
+    LocalAsset::write_new_all("new post", origin.join("blog/post.html")).unwrap();
+    LocalAsset::write_new_all("body {}", origin.join("style.css")).unwrap();
+    LocalAsset::write_new_all("pinned", origin.join("robots.txt")).unwrap();
+    LocalAsset::write_new_all("home", dest.join("index.html")).unwrap();
+    LocalAsset::write_new_all("old styles", dest.join("style.css")).unwrap();
+    LocalAsset::write_new_all("keep me", dest.join("robots.txt")).unwrap();
+    LocalAsset::write_new_all("gone", dest.join("old/page.html")).unwrap();
+
+    let mut options = CopyOptions::default();
+    options.on_overwrite = Some(OverwriteHook::new(|path| {
+        if path.ends_with("robots.txt") {
+            OverwriteDecision::Skip
+        } else {
+            OverwriteDecision::Overwrite
+        }
+    }));
+    let plan = LocalAsset::plan_sync_dir(&origin, &dest, &options).unwrap();
+    let changes = plan
+        .changes()
+        .iter()
+        .map(|change| (change.path.as_str(), change.action, change.reason))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            ("blog", ChangeAction::Create, ChangeReason::Missing),
+            (
+                "blog/post.html",
+                ChangeAction::Create,
+                ChangeReason::Missing
+            ),
+            ("index.html", ChangeAction::Skip, ChangeReason::Unchanged),
+            (
+                "robots.txt",
+                ChangeAction::Skip,
+                ChangeReason::OverwriteSkipped
+            ),
+            ("style.css", ChangeAction::Overwrite, ChangeReason::Changed),
+            ("old", ChangeAction::Delete, ChangeReason::NotInOrigin),
+        ]
+    );
+    assert!(!plan.is_noop());
+
+    // Planning doesn't touch anything
+    assert!(!dest.join("blog").exists());
+    assert!(dest.join("old/page.html").exists());
+
+    plan.execute().unwrap();
+    assert_eq!(
+        LocalAsset::load_string(dest.join("blog/post.html")).unwrap(),
+        "new post"
+    );
+    assert_eq!(
+        LocalAsset::load_string(dest.join("style.css")).unwrap(),
+        "body {}"
+    );
+    assert_eq!(
+        LocalAsset::load_string(dest.join("robots.txt")).unwrap(),
+        "keep me"
+    );
+    assert!(!dest.join("old").exists());
+
+    // Copies don't delete, and nothing is left to do afterwards
+    let plan = LocalAsset::plan_copy_dir(&origin, &dest, &options).unwrap();
+    assert!(plan.is_noop());
+    std::fs::remove_file(origin.join("index.html")).unwrap();
+    std::fs::create_dir(origin.join("index.html")).unwrap();
+    let plan = LocalAsset::plan_copy_dir(&origin, &dest, &CopyOptions::default()).unwrap();
+    let change = &plan.changes()[2];
+    assert_eq!(change.path, "index.html");
+    assert!(change.is_dir);
+    assert_eq!(change.action, ChangeAction::Overwrite);
+    assert_eq!(change.reason, ChangeReason::KindChanged);
+    plan.execute().unwrap();
+    assert!(dest.join("index.html").is_dir());
+}
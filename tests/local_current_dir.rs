@@ -0,0 +1,61 @@
+use assert_fs::prelude::*;
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+
+#[test]
+fn it_overrides_current_dir_per_thread() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    tmp.child("project/Cargo.toml")
+        .write_str("[package]")
+        .unwrap();
+    tmp.child("project/src/lib.rs").write_str("").unwrap();
+    let real_dir = LocalAsset::current_dir().unwrap();
+
+    let found = LocalAsset::with_current_dir(tmp_dir.join("project/src"), || {
+        assert_eq!(
+            LocalAsset::current_dir().unwrap(),
+            tmp_dir.join("project/src")
+        );
+        // Other threads don't see the override
+        let other = std::thread::spawn(|| LocalAsset::current_dir().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(other, real_dir);
+
+        // Relative overrides nest
+        LocalAsset::with_current_dir("../", || {
+            assert_eq!(LocalAsset::current_dir().unwrap(), tmp_dir.join("project"));
+        })
+        .unwrap();
+        assert_eq!(
+            LocalAsset::current_dir().unwrap(),
+            tmp_dir.join("project/src")
+        );
+
+        LocalAsset::search_ancestors(".", "Cargo.toml").unwrap()
+    })
+    .unwrap();
+    assert_eq!(found, tmp_dir.join("project/Cargo.toml"));
+    assert_eq!(LocalAsset::current_dir().unwrap(), real_dir);
+
+    // The override is restored even if we panic
+    let panicked = std::panic::catch_unwind(|| {
+        LocalAsset::with_current_dir(tmp_dir, || panic!("oops")).unwrap();
+    });
+    assert!(panicked.is_err());
+    assert_eq!(LocalAsset::current_dir().unwrap(), real_dir);
+
+    // The process-wide override applies to every thread, but per-thread ones win
+    LocalAsset::set_current_dir_override(Some(tmp_dir)).unwrap();
+    let other = std::thread::spawn(|| LocalAsset::current_dir().unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(other, tmp_dir);
+    LocalAsset::with_current_dir("project", || {
+        assert_eq!(LocalAsset::current_dir().unwrap(), tmp_dir.join("project"));
+    })
+    .unwrap();
+    LocalAsset::set_current_dir_override(None::<&str>).unwrap();
+    assert_eq!(LocalAsset::current_dir().unwrap(), real_dir);
+}
@@ -0,0 +1,34 @@
+use axoasset::{AxoassetError, LocalAsset};
+use camino::Utf8Path;
+
+// The process-wide display root affects every test running at the same time, so it
+// gets a test binary of its own
+#[test]
+fn it_displays_error_paths_relative_to_a_process_wide_root() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = Utf8Path::from_path(tmp.path()).unwrap();
+    let missing = root.join("assets/logo.png");
+    let err = LocalAsset::load_bytes(&missing).unwrap_err();
+    let full = err.to_string();
+    assert!(full.starts_with(&format!("failed to read asset from {missing} (")));
+
+    // Paths outside the root are left alone
+    AxoassetError::set_display_root(root.join("elsewhere"));
+    assert_eq!(err.to_string(), full);
+
+    // The root applies to every thread, but per-thread ones win
+    AxoassetError::set_display_root(root.join("assets"));
+    let relative = full.replace(missing.as_str(), "logo.png");
+    assert_eq!(err.to_string(), relative);
+    let other = std::thread::scope(|scope| scope.spawn(|| err.to_string()).join().unwrap());
+    assert_eq!(other, relative);
+    AxoassetError::with_display_root(root, || {
+        assert_eq!(
+            err.to_string(),
+            full.replace(missing.as_str(), "assets/logo.png")
+        );
+    });
+
+    AxoassetError::clear_display_root();
+    assert_eq!(err.to_string(), full);
+}
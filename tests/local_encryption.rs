@@ -0,0 +1,51 @@
+#![cfg(feature = "encryption")]
+
+use axoasset::{age, LocalAsset};
+use camino::Utf8Path;
+
+#[test]
+fn it_round_trips_encrypted_files() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let token = dest_dir.join("token");
+    LocalAsset::write_new("hunter2", &token).unwrap();
+
+    let identity = age::x25519::Identity::generate();
+    let recipient = identity.to_public();
+    let encrypted = dest_dir.join("token.age");
+    LocalAsset::encrypt_file(&token, &encrypted, &[&recipient]).unwrap();
+    let ciphertext = LocalAsset::load_bytes(&encrypted).unwrap();
+    assert!(!ciphertext.windows(7).any(|window| window == b"hunter2"));
+
+    let decrypted = dest_dir.join("token.roundtrip");
+    LocalAsset::decrypt_file(&encrypted, &decrypted, &[&identity]).unwrap();
+    assert_eq!(LocalAsset::load_string(&decrypted).unwrap(), "hunter2");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&decrypted).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // The wrong key can't decrypt it, and nothing gets written
+    let wrong = age::x25519::Identity::generate();
+    let garbage = dest_dir.join("garbage");
+    let res = LocalAsset::decrypt_file(&encrypted, &garbage, &[&wrong]);
+    assert!(matches!(
+        res,
+        Err(axoasset::AxoassetError::LocalAssetDecryptFailed { .. })
+    ));
+    assert!(!garbage.exists());
+
+    // Neither can the right key if the file has been tampered with
+    let mut tampered = ciphertext;
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    std::fs::write(&encrypted, tampered).unwrap();
+    let res = LocalAsset::decrypt_file(&encrypted, &garbage, &[&identity]);
+    assert!(matches!(
+        res,
+        Err(axoasset::AxoassetError::LocalAssetDecryptFailed { .. })
+    ));
+}
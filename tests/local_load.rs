@@ -67,3 +67,46 @@ async fn it_loads_local_assets_as_strings() {
         assert!(loaded_string.contains(contents))
     }
 }
+
+#[test]
+fn it_displays_error_paths_relative_to_a_root() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let missing = root.join("assets/logo.png");
+    let err = axoasset::LocalAsset::load_bytes(&missing).unwrap_err();
+    // The io error's kind and OS error code are included too
+    #[cfg(unix)]
+    assert_eq!(
+        err.to_string(),
+        format!("failed to read asset from {missing} (entity not found, os error 2)")
+    );
+    let io_err = std::fs::read(&missing).unwrap_err();
+    let cause = format!(
+        " ({}, os error {})",
+        io_err.kind(),
+        io_err.raw_os_error().unwrap()
+    );
+    assert_eq!(
+        err.to_string(),
+        format!("failed to read asset from {missing}{cause}")
+    );
+
+    axoasset::AxoassetError::with_display_root(root, || {
+        assert_eq!(
+            err.to_string(),
+            format!("failed to read asset from assets/logo.png{cause}")
+        );
+        // Other threads don't see it
+        let other = std::thread::scope(|scope| scope.spawn(|| err.to_string()).join().unwrap());
+        assert_eq!(other, format!("failed to read asset from {missing}{cause}"));
+    });
+    assert_eq!(
+        err.to_string(),
+        format!("failed to read asset from {missing}{cause}")
+    );
+    // The stored path is still the full one
+    let axoasset::AxoassetError::LocalAssetReadFailed { origin_path, .. } = &err else {
+        panic!("unexpected error {err:?}");
+    };
+    assert_eq!(origin_path, missing.as_str());
+}
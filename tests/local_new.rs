@@ -56,3 +56,23 @@ fn it_creates_a_new_directory() {
 
     assert!(Path::new(&dest.as_os_str()).join("subdir").exists());
 }
+
+#[test]
+fn it_ensures_a_directory_exists() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(dest.path())
+        .unwrap()
+        .join("subdir");
+
+    axoasset::LocalAsset::ensure_dir(&dest_dir).unwrap();
+    assert!(dest_dir.is_dir());
+    // Again is fine
+    axoasset::LocalAsset::ensure_dir(&dest_dir).unwrap();
+
+    // A file in the way isn't
+    let file = dest_dir.join("file.txt");
+    axoasset::LocalAsset::write_new("file content", &file).unwrap();
+    assert!(axoasset::LocalAsset::ensure_dir(&file).is_err());
+    // Neither is a missing parent
+    assert!(axoasset::LocalAsset::ensure_dir(dest_dir.join("missing/subdir")).is_err());
+}
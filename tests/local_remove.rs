@@ -16,3 +16,95 @@ fn it_removes_both_file_and_directory() {
     axoasset::LocalAsset::remove_dir(dir_path.display().to_string()).unwrap();
     assert!(!dir_path.exists());
 }
+
+#[test]
+fn it_removes_globs() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(dest.path()).unwrap();
+    for file in [
+        "app.tar.gz",
+        "app.zip",
+        "lib.tar.gz",
+        "docs/index.html",
+        "keep.txt",
+    ] {
+        axoasset::LocalAsset::write_new_all("", root.join(file)).unwrap();
+    }
+
+    // Dry runs don't touch anything
+    let mut options = axoasset::local::RemoveGlobOptions::default();
+    options.dry_run = true;
+    let pattern = format!("{root}/*.tar.gz");
+    let removed = axoasset::LocalAsset::remove_glob_with_options(&pattern, &options).unwrap();
+    assert_eq!(
+        removed,
+        vec![root.join("app.tar.gz"), root.join("lib.tar.gz")]
+    );
+    assert!(root.join("app.tar.gz").exists());
+
+    let removed = axoasset::LocalAsset::remove_glob(&pattern).unwrap();
+    assert_eq!(
+        removed,
+        vec![root.join("app.tar.gz"), root.join("lib.tar.gz")]
+    );
+    assert!(!root.join("app.tar.gz").exists());
+    assert!(!root.join("lib.tar.gz").exists());
+
+    // Directories go with their contents, which aren't listed separately
+    let removed = axoasset::LocalAsset::remove_glob(&format!("{root}/**/*")).unwrap();
+    assert_eq!(
+        removed,
+        vec![
+            root.join("app.zip"),
+            root.join("docs"),
+            root.join("keep.txt")
+        ]
+    );
+    assert!(!root.join("docs").exists());
+    assert!(!root.join("keep.txt").exists());
+
+    assert!(axoasset::LocalAsset::remove_glob("[").is_err());
+}
+
+#[test]
+fn it_only_removes_dirs_within_a_root() {
+    use axoasset::{AxoassetError, LocalAsset};
+
+    let dest = assert_fs::TempDir::new().unwrap();
+    let tmp = camino::Utf8Path::from_path(dest.path()).unwrap();
+    let root = tmp.join("target");
+    let outside = tmp.join("precious");
+    LocalAsset::write_new_all("build", root.join("dist/app")).unwrap();
+    LocalAsset::write_new_all("keep", outside.join("file")).unwrap();
+
+    // Anything that resolves to the root or outside of it is refused
+    for target in [
+        root.clone(),
+        root.join("."),
+        root.join("dist/.."),
+        root.join("../precious"),
+        outside.clone(),
+    ] {
+        let err = LocalAsset::remove_dir_all_within(&root, &target).unwrap_err();
+        assert!(
+            matches!(err, AxoassetError::LocalAssetRemoveOutsideRoot { .. }),
+            "{target}"
+        );
+    }
+    assert!(outside.join("file").exists());
+    assert!(root.join("dist/app").exists());
+
+    // Symlinks are removed as themselves
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        LocalAsset::remove_dir_all_within(&root, root.join("link")).unwrap();
+        assert!(!root.join("link").exists());
+        assert!(outside.join("file").exists());
+    }
+
+    LocalAsset::remove_dir_all_within(&root, root.join("dist")).unwrap();
+    assert!(!root.join("dist").exists());
+    // Missing targets are fine
+    LocalAsset::remove_dir_all_within(&root, root.join("dist")).unwrap();
+}
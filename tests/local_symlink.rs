@@ -0,0 +1,45 @@
+#![cfg(unix)]
+
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+
+#[test]
+fn it_creates_relative_symlinks() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let target = dest_dir.join("share/app/config.toml");
+    LocalAsset::write_new_all("hello = true", &target).unwrap();
+    LocalAsset::create_dir_all(dest_dir.join("etc/app")).unwrap();
+
+    let file_link = dest_dir.join("etc/app/config.toml");
+    LocalAsset::symlink(&target, &file_link).unwrap();
+    assert_eq!(
+        std::fs::read_link(&file_link).unwrap(),
+        Utf8Path::new("../../share/app/config.toml")
+    );
+    assert_eq!(LocalAsset::load_string(&file_link).unwrap(), "hello = true");
+
+    // Paths with `..` in them get cleaned up
+    let dir_link = dest_dir.join("current");
+    LocalAsset::symlink(dest_dir.join("etc/../share/app"), &dir_link).unwrap();
+    assert_eq!(
+        std::fs::read_link(&dir_link).unwrap(),
+        Utf8Path::new("share/app")
+    );
+    assert!(dir_link.join("config.toml").exists());
+}
+
+#[test]
+fn it_reports_symlink_failures() {
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = Utf8Path::from_path(dest.path()).unwrap();
+
+    let link = dest_dir.join("link");
+    LocalAsset::write_new("occupied", &link).unwrap();
+    let res = LocalAsset::symlink(dest_dir.join("target"), &link);
+    assert!(matches!(
+        res,
+        Err(axoasset::AxoassetError::LocalAssetSymlinkFailed { .. })
+    ));
+}
@@ -52,3 +52,205 @@ async fn it_writes_local_assets() {
         }
     }
 }
+
+#[test]
+fn it_writes_durably() {
+    use axoasset::local::{Durability, WriteOptions};
+
+    let dest = assert_fs::TempDir::new().unwrap();
+    for durability in [
+        Durability::None,
+        Durability::SyncFile,
+        Durability::SyncFileAndDir,
+    ] {
+        let dest_file = Path::new(dest.to_str().unwrap())
+            .join(format!("{durability:?}"))
+            .join("manifest.json");
+
+        let mut options = WriteOptions::default();
+        options.durability = durability;
+        axoasset::LocalAsset::write_new_all_with_options(
+            b"{}",
+            dest_file.to_str().unwrap(),
+            &options,
+        )
+        .unwrap();
+
+        let loaded_contents =
+            axoasset::LocalAsset::load_string(dest_file.display().to_string()).unwrap();
+        assert_eq!(loaded_contents, "{}");
+    }
+}
+
+#[test]
+fn it_consults_the_overwrite_hook() {
+    use axoasset::local::{CopyOptions, OverwriteDecision, OverwriteHook, WriteOptions};
+    use axoasset::{AxoassetError, LocalAsset};
+    use std::sync::{Arc, Mutex};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let existing = root.join("existing.txt");
+    LocalAsset::write_new("original", &existing).unwrap();
+
+    let asked = Arc::new(Mutex::new(vec![]));
+    let hook = |decision: OverwriteDecision| {
+        let asked = asked.clone();
+        OverwriteHook::new(move |path| {
+            asked.lock().unwrap().push(path.to_owned());
+            decision
+        })
+    };
+
+    // New files don't need to ask
+    let mut options = WriteOptions::default();
+    options.on_overwrite = Some(hook(OverwriteDecision::Abort));
+    LocalAsset::write_new_with_options("new", root.join("new.txt"), &options).unwrap();
+    assert!(asked.lock().unwrap().is_empty());
+
+    let err = LocalAsset::write_new_with_options("changed", &existing, &options).unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::LocalAssetOverwriteRefused { .. }
+    ));
+    options.on_overwrite = Some(hook(OverwriteDecision::Skip));
+    LocalAsset::write_new_with_options("changed", &existing, &options).unwrap();
+    assert_eq!(LocalAsset::load_string(&existing).unwrap(), "original");
+    options.on_overwrite = Some(hook(OverwriteDecision::Overwrite));
+    LocalAsset::write_new_with_options("changed", &existing, &options).unwrap();
+    assert_eq!(LocalAsset::load_string(&existing).unwrap(), "changed");
+    assert_eq!(asked.lock().unwrap().len(), 3);
+
+    // Copies too
+    let mut options = CopyOptions::default();
+    options.on_overwrite = Some(hook(OverwriteDecision::Skip));
+    LocalAsset::copy_file_to_file_with_options(root.join("new.txt"), &existing, &options).unwrap();
+    assert_eq!(LocalAsset::load_string(&existing).unwrap(), "changed");
+    assert_eq!(asked.lock().unwrap().last(), Some(&existing));
+}
+
+#[test]
+#[cfg(unix)]
+fn it_writes_with_modes() {
+    use axoasset::local::WriteOptions;
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let mode =
+        |path: &camino::Utf8Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    let mut options = WriteOptions::default();
+    options.mode = Some(0o755);
+    let script = root.join("bin/install.sh");
+    axoasset::LocalAsset::write_new_all_with_options("#!/bin/sh\n", &script, &options).unwrap();
+    assert_eq!(mode(&script), 0o755);
+
+    // Existing files are updated too
+    options.mode = Some(0o600);
+    axoasset::LocalAsset::write_new_with_options("#!/bin/sh\nexit 0\n", &script, &options).unwrap();
+    assert_eq!(mode(&script), 0o600);
+    assert_eq!(
+        axoasset::LocalAsset::load_string(&script).unwrap(),
+        "#!/bin/sh\nexit 0\n"
+    );
+}
+
+#[test]
+fn it_makes_files_executable() {
+    use axoasset::LocalAsset;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let script = if cfg!(windows) {
+        root.join("install.cmd")
+    } else {
+        root.join("install.sh")
+    };
+    LocalAsset::write_new("exit 0\n", &script).unwrap();
+    assert_eq!(LocalAsset::is_executable(&script), cfg!(windows));
+
+    LocalAsset::make_executable(&script).unwrap();
+    assert!(LocalAsset::is_executable(&script));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode & 0o111, (mode & 0o444) >> 2);
+    }
+
+    // Directories and missing files never are
+    assert!(!LocalAsset::is_executable(root));
+    assert!(!LocalAsset::is_executable(root.join("missing.exe")));
+    let err = LocalAsset::make_executable(root.join("missing.exe")).unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::LocalAssetPermissionsFailed { .. }
+    ));
+}
+
+#[test]
+fn it_writes_batches() {
+    use axoasset::local::{Durability, OverwriteDecision, OverwriteHook, WriteBatch, WriteOptions};
+    use axoasset::LocalAsset;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    LocalAsset::write_new("original", root.join("kept.txt")).unwrap();
+
+    let mut options = WriteOptions::default();
+    options.durability = Durability::SyncFileAndDir;
+    options.on_overwrite = Some(OverwriteHook::new(|_| OverwriteDecision::Skip));
+    let mut batch = WriteBatch::new(options);
+    batch.write(root.join("site/index.html"), "<h1>hi</h1>");
+    batch.write(root.join("site/blog/post.html"), "draft");
+    batch.write(root.join("kept.txt"), "changed");
+    batch.write(root.join("site/blog/post.html"), "final");
+    assert_eq!(batch.len(), 4);
+
+    // Nothing happens until the batch is committed
+    assert!(!root.join("site").exists());
+    // Skipped paths aren't reported as written
+    let written = batch.commit().unwrap();
+    assert_eq!(
+        written,
+        vec![
+            root.join("site/index.html"),
+            root.join("site/blog/post.html"),
+        ]
+    );
+    assert_eq!(
+        LocalAsset::load_string(root.join("site/index.html")).unwrap(),
+        "<h1>hi</h1>"
+    );
+    assert_eq!(
+        LocalAsset::load_string(root.join("site/blog/post.html")).unwrap(),
+        "final"
+    );
+    assert_eq!(
+        LocalAsset::load_string(root.join("kept.txt")).unwrap(),
+        "original"
+    );
+}
+
+#[test]
+fn it_writes_batches_bigger_than_the_fd_limit() {
+    use axoasset::local::{Durability, WriteBatch, WriteOptions};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = camino::Utf8Path::from_path(tmp.path()).unwrap();
+
+    // More files than the usual limit of 1024 open files
+    let mut options = WriteOptions::default();
+    options.durability = Durability::SyncFileAndDir;
+    let mut batch = WriteBatch::new(options);
+    for idx in 0..1100 {
+        batch.write(root.join(format!("files/f{idx}.txt")), idx.to_string());
+    }
+    let written = batch.commit().unwrap();
+    assert_eq!(written.len(), 1100);
+    assert_eq!(
+        std::fs::read_to_string(root.join("files/f1099.txt")).unwrap(),
+        "1099"
+    );
+}
@@ -0,0 +1,107 @@
+#![cfg(all(feature = "toml-edit", feature = "json-serde"))]
+
+use axoasset::{toml_edit, AxoassetError, Migrator, SourceFile};
+
+#[test]
+fn it_migrates_toml_preserving_formatting() {
+    let migrator = Migrator::<toml_edit::DocumentMut>::new("config-version")
+        // Added out of order on purpose, they still run in version order
+        .migration(2, |doc| {
+            let name = doc.remove("name").ok_or("name is required")?;
+            let mut package = toml_edit::Table::new();
+            package["name"] = name;
+            doc["package"] = toml_edit::Item::Table(package);
+            Ok(())
+        })
+        .migration(1, |doc| {
+            if let Some(ci) = doc.get("ci").and_then(|ci| ci.as_str()) {
+                let mut list = toml_edit::Array::new();
+                list.push(ci);
+                doc["ci"] = toml_edit::value(list);
+            }
+            Ok(())
+        });
+    assert_eq!(migrator.latest_version(), 2);
+
+    let source = SourceFile::new(
+        "config.toml",
+        "# my config\nci = \"github\"\nname = \"app\"\n".to_owned(),
+    );
+    let migrated = migrator.migrate(&source).unwrap();
+    assert_eq!(migrated.from_version(), 0);
+    assert_eq!(migrated.to_version(), 2);
+    assert_eq!(migrated.applied(), &[1, 2]);
+    assert!(migrated.changed());
+    assert_eq!(migrated.source_file().origin_path(), "config.toml");
+    assert_eq!(
+        migrated.source_file().contents(),
+        "# my config\nci = [\"github\"]\nconfig-version = 2\n\n[package]\nname = \"app\"\n"
+    );
+
+    // Migrating again does nothing
+    let again = migrator.migrate(migrated.source_file()).unwrap();
+    assert!(!again.changed());
+    assert_eq!(again.from_version(), 2);
+    assert_eq!(again.to_version(), 2);
+    assert_eq!(
+        again.source_file().contents(),
+        migrated.source_file().contents()
+    );
+
+    // Partially migrated documents only get the newer migrations
+    let source = SourceFile::new(
+        "config.toml",
+        "config-version = 1 # keep me\nname = \"app\"\n".to_owned(),
+    );
+    let migrated = migrator.migrate(&source).unwrap();
+    assert_eq!(migrated.applied(), &[2]);
+    assert!(migrated
+        .source_file()
+        .contents()
+        .starts_with("config-version = 2 # keep me\n"));
+}
+
+#[test]
+fn it_migrates_json() {
+    let migrator = Migrator::<axoasset::serde_json::Value>::new("version").migration(1, |doc| {
+        doc["renamed"] = doc["old"].take();
+        Ok(())
+    });
+    let source = SourceFile::new("config.json", r#"{"old": true}"#.to_owned());
+    let migrated = migrator.migrate(&source).unwrap();
+    assert_eq!(migrated.applied(), &[1]);
+    let value: axoasset::serde_json::Value = migrated.source_file().deserialize_json().unwrap();
+    assert_eq!(
+        value,
+        axoasset::serde_json::json!({"old": null, "renamed": true, "version": 1})
+    );
+}
+
+#[test]
+fn it_rejects_unmigratable_documents() {
+    let migrator = Migrator::<toml_edit::DocumentMut>::new("version")
+        .migration(1, |_| Ok(()))
+        .migration(2, |_| Err("oops".into()));
+
+    let source = SourceFile::new("config.toml", "version = 3\n".to_owned());
+    let err = migrator.migrate(&source).unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::MigrationVersionTooNew {
+            version: 3,
+            latest_version: 2,
+            ..
+        }
+    ));
+
+    let source = SourceFile::new("config.toml", "version = \"one\"\n".to_owned());
+    let err = migrator.migrate(&source).unwrap_err();
+    assert!(matches!(err, AxoassetError::MigrationVersionInvalid { .. }));
+
+    let source = SourceFile::new("config.toml", "version = 1\n".to_owned());
+    let err = migrator.migrate(&source).unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::MigrationFailed { version: 2, .. }
+    ));
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "remote")]
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axoasset::remote::RemoteBackend;
+use axoasset::AxoassetError;
+use reqwest::ResponseBuilderExt;
+
+mod common;
+
+/// Serves `mem://bucket/key` urls out of a map, like a tiny object store
+#[derive(Debug)]
+struct MemoryBackend(BTreeMap<String, String>);
+
+#[async_trait::async_trait]
+impl RemoteBackend for MemoryBackend {
+    async fn fetch(
+        &self,
+        origin_path: &str,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, AxoassetError> {
+        let key = format!(
+            "{}{}",
+            request.url().host_str().unwrap_or_default(),
+            request.url().path()
+        );
+        let Some(contents) = self.0.get(&key) else {
+            return Err(AxoassetError::RemoteBackendFailed {
+                origin_path: origin_path.to_owned(),
+                details: format!("no such key: {key}").into(),
+            });
+        };
+        let response = http::Response::builder()
+            .url(request.url().clone())
+            .body(contents.clone())
+            .unwrap();
+        Ok(reqwest::Response::from(response))
+    }
+
+    fn filename(
+        &self,
+        _origin_path: &str,
+        _headers: &reqwest::header::HeaderMap,
+    ) -> Result<String, AxoassetError> {
+        Ok("object.txt".to_owned())
+    }
+}
+
+#[tokio::test]
+async fn it_loads_assets_with_custom_backends() {
+    let objects = BTreeMap::from([("bucket/app/1.0.0/notes".to_owned(), "hello".to_owned())]);
+    let client = common::client().with_backend("mem", Arc::new(MemoryBackend(objects)));
+
+    let asset = client
+        .load_asset("mem://bucket/app/1.0.0/notes")
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"hello");
+    assert_eq!(asset.filename(), "object.txt");
+    assert_eq!(asset.resolved_url(), "mem://bucket/app/1.0.0/notes");
+    assert_eq!(
+        client
+            .load_string("mem://bucket/app/1.0.0/notes")
+            .await
+            .unwrap(),
+        "hello"
+    );
+
+    let err = client.load_bytes("mem://bucket/missing").await.unwrap_err();
+    assert!(matches!(err, AxoassetError::RemoteBackendFailed { .. }));
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "remote-blocking")]
+
+use axoasset::remote::RemoteAsset;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn it_loads_assets_without_a_runtime() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/plain")
+                .set_body_string("hello"),
+        )
+        .mount(&mock_server)
+        .await;
+    let url = format!("{}/notes", mock_server.uri());
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8PathBuf::from_path_buf(dest.path().to_owned()).unwrap();
+
+    // The blocking API can't be used from inside the test's runtime
+    let (asset, dest_path) = tokio::task::spawn_blocking(move || {
+        let asset = RemoteAsset::load_blocking(&url).unwrap();
+        let dest_path = RemoteAsset::copy_blocking(&url, &dest_dir).unwrap();
+        (asset, dest_path)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(asset.as_bytes(), b"hello");
+    assert_eq!(asset.filename(), "notes.txt");
+    assert_eq!(asset.stats().bytes, 5);
+    assert_eq!(dest_path.file_name(), Some("notes.txt"));
+    assert_eq!(
+        axoasset::LocalAsset::load_string(&dest_path).unwrap(),
+        "hello"
+    );
+}
+
+#[test]
+fn it_keeps_existing_files_when_blocking_downloads_fail() {
+    use std::io::{Read, Write};
+
+    // A response that claims to be longer than it is, so the download fails partway
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/notes.txt", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let response =
+                "HTTP/1.1 200 OK\r\ncontent-length: 1000\r\nconnection: close\r\n\r\npartial";
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(dest.path()).unwrap();
+    let dest_path = dest_dir.join("notes.txt");
+    std::fs::write(&dest_path, "the old notes").unwrap();
+
+    let err = RemoteAsset::copy_blocking(&url, dest_dir).unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetRequestFailed { .. }
+    ));
+    assert_eq!(
+        std::fs::read_to_string(&dest_path).unwrap(),
+        "the old notes"
+    );
+    assert_eq!(std::fs::read_dir(dest_dir).unwrap().count(), 1);
+}
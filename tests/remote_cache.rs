@@ -0,0 +1,129 @@
+#![cfg(feature = "remote")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axoasset::remote::{RemoteCache, RemoteMetrics, UrlStr};
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[derive(Default)]
+struct CacheHits(AtomicUsize);
+
+impl RemoteMetrics for CacheHits {
+    fn record_cache_hit(&self, _url: &UrlStr) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn it_caches_remote_assets_on_disk() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/logo.svg"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/logo.svg"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<svg/>")
+                .insert_header("ETag", "\"v1\""),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/uncacheable.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("fresh"))
+        .mount(&mock_server)
+        .await;
+    let base = format!("http://{}", mock_server.address());
+
+    let cache_dir = assert_fs::TempDir::new().unwrap();
+    let cache_dir = Utf8Path::from_path(cache_dir.path()).unwrap();
+    let hits = Arc::new(CacheHits::default());
+    let client = common::client()
+        .with_cache(RemoteCache::new(cache_dir))
+        .with_metrics(hits.clone());
+
+    let url = format!("{base}/logo.svg");
+    assert_eq!(client.load_string(&url).await.unwrap(), "<svg/>");
+    assert_eq!(hits.0.load(Ordering::SeqCst), 0);
+
+    // A new client sharing the cache dir only revalidates
+    let client = common::client()
+        .with_cache(RemoteCache::new(cache_dir))
+        .with_metrics(hits.clone());
+    let asset = client.load_asset(&url).await.unwrap();
+    assert_eq!(asset.as_bytes(), b"<svg/>");
+    assert_eq!(asset.filename(), "logo.svg");
+    assert_eq!(asset.stats().bytes, 0);
+    let written = client.load_and_write_to_dir(&url, cache_dir).await.unwrap();
+    assert_eq!(LocalAsset::load_string(written).unwrap(), "<svg/>");
+    assert_eq!(hits.0.load(Ordering::SeqCst), 2);
+
+    // Assets without validators can't be revalidated, so they aren't cached
+    let url = format!("{base}/uncacheable.txt");
+    client.load_string(&url).await.unwrap();
+    client.load_string(&url).await.unwrap();
+    assert_eq!(hits.0.load(Ordering::SeqCst), 2);
+
+    RemoteCache::new(cache_dir).clear().unwrap();
+    assert!(!cache_dir.exists());
+}
+
+#[tokio::test]
+async fn it_evicts_least_recently_used_assets() {
+    let mock_server = MockServer::start().await;
+    for name in ["a", "b", "c"] {
+        Mock::given(method("GET"))
+            .and(path(format!("/{name}.txt")))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/{name}.txt")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(name.repeat(1000))
+                    .insert_header("ETag", "\"v1\""),
+            )
+            .mount(&mock_server)
+            .await;
+    }
+    let url = |name: &str| format!("http://{}/{name}.txt", mock_server.address());
+
+    let cache_dir = assert_fs::TempDir::new().unwrap();
+    let cache_dir = Utf8Path::from_path(cache_dir.path()).unwrap();
+    let hits = Arc::new(CacheHits::default());
+    let cache = RemoteCache::new(cache_dir).with_max_size(2500);
+    let client = common::client()
+        .with_cache(cache.clone())
+        .with_metrics(hits.clone());
+
+    // Using `a` again makes `b` the least recently used when `c` doesn't fit
+    client.load_string(&url("a")).await.unwrap();
+    client.load_string(&url("b")).await.unwrap();
+    client.load_string(&url("a")).await.unwrap();
+    assert_eq!(hits.0.load(Ordering::SeqCst), 1);
+    client.load_string(&url("c")).await.unwrap();
+    assert_eq!(std::fs::read_dir(cache_dir).unwrap().count(), 2);
+    client.load_string(&url("a")).await.unwrap();
+    client.load_string(&url("c")).await.unwrap();
+    assert_eq!(hits.0.load(Ordering::SeqCst), 3);
+    client.load_string(&url("b")).await.unwrap();
+    assert_eq!(hits.0.load(Ordering::SeqCst), 3);
+
+    // Pruning by hand is a no-op once the cache fits
+    assert_eq!(cache.prune().unwrap(), 0);
+    assert_eq!(RemoteCache::new(cache_dir).prune().unwrap(), 0);
+}
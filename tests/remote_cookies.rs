@@ -0,0 +1,72 @@
+#![cfg(feature = "remote-cookies")]
+
+use std::sync::Arc;
+
+use axoasset::AxoClient;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn it_keeps_cookies_from_redirects() {
+    let mock_server = MockServer::start().await;
+    let base = format!("http://{}", mock_server.address());
+    Mock::given(method("GET"))
+        .and(path("/artifact.txt"))
+        .and(header("cookie", "session=letmein"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("secret"))
+        .with_priority(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/artifact.txt"))
+        .respond_with(
+            ResponseTemplate::new(302).insert_header("location", format!("{base}/sso").as_str()),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/sso"))
+        .respond_with(
+            ResponseTemplate::new(302)
+                .insert_header("set-cookie", "session=letmein; Path=/")
+                .insert_header("location", format!("{base}/artifact.txt").as_str()),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let jar = Arc::new(reqwest::cookie::Jar::default());
+    let client = AxoClient::with_cookie_jar(reqwest::ClientBuilder::new(), jar).unwrap();
+    let url = format!("{base}/artifact.txt");
+    assert_eq!(client.load_string(&url).await.unwrap(), "secret");
+
+    // Clones share the session, so they go straight to the artifact
+    let cloned = client.clone();
+    assert_eq!(cloned.load_string(&url).await.unwrap(), "secret");
+    let sso_requests = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|req| req.url.path() == "/sso")
+        .count();
+    assert_eq!(sso_requests, 1);
+    assert!(client.cookie_jar().is_some());
+}
+
+#[tokio::test]
+async fn it_sends_preloaded_cookies() {
+    let mock_server = MockServer::start().await;
+    let base = format!("http://{}", mock_server.address());
+    Mock::given(method("GET"))
+        .and(path("/artifact.txt"))
+        .and(header("cookie", "token=abc"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("secret"))
+        .mount(&mock_server)
+        .await;
+
+    let jar = Arc::new(reqwest::cookie::Jar::default());
+    jar.add_cookie_str("token=abc", &base.parse().unwrap());
+    let client = AxoClient::with_cookie_jar(reqwest::ClientBuilder::new(), jar).unwrap();
+    let response = client.get(&format!("{base}/artifact.txt")).await.unwrap();
+    assert_eq!(response.status(), 200);
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "remote")]
+
+use std::time::Duration;
+
+use axoasset::remote::GithubRateLimitOptions;
+use axoasset::AxoassetError;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+/// Rate limit options that treat the mock server like api.github.com
+fn options() -> GithubRateLimitOptions {
+    let mut options = GithubRateLimitOptions::default();
+    options.hosts = vec!["127.0.0.1".to_owned()];
+    options
+}
+
+#[tokio::test]
+async fn it_retries_after_rate_limits() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/repos/axodotdev/axoasset/releases"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/axodotdev/axoasset/releases"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+        .mount(&mock_server)
+        .await;
+
+    let client = common::client().with_github_rate_limit(options());
+    let url = format!(
+        "http://{}/repos/axodotdev/axoasset/releases",
+        mock_server.address()
+    );
+    let start = std::time::Instant::now();
+    let asset = client.load_asset(&url).await.unwrap();
+    assert_eq!(asset.as_bytes(), b"[]");
+    assert!(start.elapsed() >= Duration::from_secs(1));
+
+    // The wait shows up in the stats of the download
+    let stats = asset.stats();
+    assert_eq!(stats.retries, 1);
+    assert_eq!(stats.bytes, 2);
+    assert!(stats.duration >= Duration::from_secs(1));
+    assert!(stats.throughput().unwrap() < 2.0);
+}
+
+#[tokio::test]
+async fn it_gives_up_on_long_rate_limits() {
+    let mock_server = MockServer::start().await;
+    let reset = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    Mock::given(method("GET"))
+        .and(path("/rate_limited"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .insert_header("x-ratelimit-remaining", "0")
+                .insert_header("x-ratelimit-reset", reset.to_string().as_str()),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/forbidden"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&mock_server)
+        .await;
+
+    let client = common::client().with_github_rate_limit(options());
+    let base = format!("http://{}", mock_server.address());
+    let err = client
+        .get(&format!("{base}/rate_limited"))
+        .await
+        .unwrap_err();
+    let AxoassetError::RemoteGithubRateLimited {
+        wait_secs,
+        reset_at,
+        ..
+    } = err
+    else {
+        panic!("unexpected error {err:?}");
+    };
+    assert!((3590..=3600).contains(&wait_secs), "{wait_secs}");
+    assert!(reset_at.ends_with(" UTC"), "{reset_at}");
+
+    // 403s that aren't about rate limits are just normal responses
+    let response = client.get(&format!("{base}/forbidden")).await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    // Hosts that aren't GitHub's API are left alone
+    let response = common::client()
+        .with_github_rate_limit(GithubRateLimitOptions::default())
+        .get(&format!("{base}/rate_limited"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // And so is everything if the client didn't opt in
+    let response = common::client()
+        .get(&format!("{base}/rate_limited"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 4);
+
+    // Absurdly long waits are capped rather than overflowing
+    Mock::given(method("GET"))
+        .and(path("/forever"))
+        .respond_with(
+            ResponseTemplate::new(429).insert_header("retry-after", u64::MAX.to_string().as_str()),
+        )
+        .mount(&mock_server)
+        .await;
+    let err = client.get(&format!("{base}/forever")).await.unwrap_err();
+    let AxoassetError::RemoteGithubRateLimited { wait_secs, .. } = err else {
+        panic!("unexpected error {err:?}");
+    };
+    assert_eq!(wait_secs, 24 * 60 * 60);
+}
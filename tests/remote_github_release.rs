@@ -0,0 +1,138 @@
+#![cfg(feature = "github")]
+
+use axoasset::remote::GithubReleaseOptions;
+use axoasset::{AxoassetError, Digest, DigestAlgorithm, LocalAsset};
+use camino::Utf8Path;
+use wiremock::matchers::{body_bytes, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+fn asset_json(
+    server: &MockServer,
+    id: u64,
+    name: &str,
+    contents: &str,
+    state: &str,
+) -> serde_json::Value {
+    let digest = Digest::compute(DigestAlgorithm::Sha256, contents.as_bytes());
+    serde_json::json!({
+        "id": id,
+        "name": name,
+        "size": contents.len(),
+        "state": state,
+        "digest": format!("sha256:{}", digest.to_hex()),
+        "url": format!("{}/repos/axodotdev/axolotlsay/releases/assets/{id}", server.uri()),
+        "browser_download_url": format!("https://github.com/axodotdev/axolotlsay/releases/download/v1.0.0/{name}"),
+    })
+}
+
+#[tokio::test]
+async fn it_uploads_github_release_assets() {
+    let mock_server = MockServer::start().await;
+    let release = serde_json::json!({
+        "upload_url": format!("{}/uploads/repos/axodotdev/axolotlsay/releases/1/assets{{?name,label}}", mock_server.uri()),
+        "assets": [
+            asset_json(&mock_server, 10, "done.txt", "done", "uploaded"),
+            asset_json(&mock_server, 11, "broken.tar.gz", "ta", "starter"),
+            asset_json(&mock_server, 12, "other.txt", "a lot longer", "uploaded"),
+            // Same size, different contents
+            asset_json(&mock_server, 14, "app.sha256", "abcd", "uploaded"),
+        ],
+    });
+    Mock::given(method("GET"))
+        .and(path("/repos/axodotdev/axolotlsay/releases/tags/v1.0.0"))
+        .and(header("authorization", "Bearer hunter2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(release))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/repos/axodotdev/axolotlsay/releases/assets/11"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/repos/axodotdev/axolotlsay/releases/assets/14"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(
+            "/uploads/repos/axodotdev/axolotlsay/releases/1/assets",
+        ))
+        .and(query_param("name", "app.sha256"))
+        .and(body_bytes(b"dcba".to_vec()))
+        .respond_with(ResponseTemplate::new(201).set_body_json(asset_json(
+            &mock_server,
+            15,
+            "app.sha256",
+            "dcba",
+            "uploaded",
+        )))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(
+            "/uploads/repos/axodotdev/axolotlsay/releases/1/assets",
+        ))
+        .and(query_param("name", "broken.tar.gz"))
+        .and(header("content-type", "application/gzip"))
+        .and(body_bytes(b"tarball".to_vec()))
+        .respond_with(ResponseTemplate::new(201).set_body_json(asset_json(
+            &mock_server,
+            13,
+            "broken.tar.gz",
+            "tarball",
+            "uploaded",
+        )))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    let client = common::client();
+    let mut options = GithubReleaseOptions::default();
+    options.api_url = mock_server.uri();
+    let upload = |name: &str, contents: &str, overwrite: bool| {
+        let file = tmp_dir.join(name);
+        LocalAsset::write_new(contents, &file).unwrap();
+        let mut options = options.clone();
+        options.overwrite = overwrite;
+        let client = client.clone();
+        async move {
+            client
+                .upload_github_release_asset_with_options(
+                    "axodotdev",
+                    "axolotlsay",
+                    "v1.0.0",
+                    &file,
+                    "hunter2",
+                    &options,
+                )
+                .await
+        }
+    };
+
+    // Incomplete uploads are replaced
+    let asset = upload("broken.tar.gz", "tarball", false).await.unwrap();
+    assert_eq!(asset.id, 13);
+    assert_eq!(asset.size, 7);
+
+    // Earlier uploads are kept
+    let asset = upload("done.txt", "done", false).await.unwrap();
+    assert_eq!(asset.id, 10);
+
+    // Different assets aren't clobbered, even if they're the same size
+    let err = upload("other.txt", "other", false).await.unwrap_err();
+    assert!(matches!(err, AxoassetError::RemoteGithubAssetExists { .. }));
+    let err = upload("app.sha256", "dcba", false).await.unwrap_err();
+    assert!(matches!(err, AxoassetError::RemoteGithubAssetExists { .. }));
+
+    // Unless they're meant to be
+    let asset = upload("app.sha256", "dcba", true).await.unwrap();
+    assert_eq!(asset.id, 15);
+}
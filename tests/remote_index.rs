@@ -0,0 +1,73 @@
+#![cfg(feature = "remote")]
+
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[tokio::test]
+async fn it_loads_html_indexes() {
+    let mock_server = MockServer::start().await;
+
+    let listing = r#"<html>
+<head><title>Index of /releases/</title></head>
+<body>
+<h1>Index of /releases/</h1><hr><pre><a href="../">../</a>
+<a href="?C=N;O=D">Name</a>
+<a href="app-x86_64.tar.gz">app-x86_64.tar.gz</a>     09-Dec-2024 10:00    1024
+<a HREF='app-aarch64.tar.gz'>app-aarch64.tar.gz</a>   09-Dec-2024 10:00    1024
+<a href=checksums/>checksums/</a>
+<a href="https://example.com/elsewhere">elsewhere</a>
+<a href="app-x86_64.tar.gz">app-x86_64.tar.gz</a>
+</pre><hr></body>
+</html>"#;
+
+    Mock::given(method("GET"))
+        .and(path("/releases/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(listing))
+        .mount(&mock_server)
+        .await;
+
+    let base = format!("http://{}/releases/", mock_server.address());
+    let children = common::client().load_index(&base).await.unwrap();
+    assert_eq!(
+        children,
+        vec![
+            format!("{base}app-x86_64.tar.gz"),
+            format!("{base}app-aarch64.tar.gz"),
+            format!("{base}checksums/"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn it_loads_s3_indexes() {
+    let mock_server = MockServer::start().await;
+
+    let listing = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>my-bucket</Name>
+  <Prefix>releases/</Prefix>
+  <Contents><Key>releases/app.tar.gz</Key><Size>1024</Size></Contents>
+  <Contents><Key>releases/app &amp; friends.zip</Key><Size>2048</Size></Contents>
+</ListBucketResult>"#;
+
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(listing))
+        .mount(&mock_server)
+        .await;
+
+    let base = format!("http://{}/", mock_server.address());
+    let children = common::client()
+        .load_index(&format!("{base}?prefix=releases/"))
+        .await
+        .unwrap();
+    assert_eq!(
+        children,
+        vec![
+            format!("{base}releases/app.tar.gz"),
+            format!("{base}releases/app%20&%20friends.zip"),
+        ]
+    );
+}
@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 use std::fs;
 
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 mod common;
@@ -122,3 +122,768 @@ async fn it_loads_remote_assets_as_string() {
         assert!(loaded_string.contains(contents));
     }
 }
+
+#[tokio::test]
+async fn it_loads_ranges_of_the_same_version() {
+    let mock_server = MockServer::start().await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    // The current version only serves ranges to clients that know its ETag
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .and(header("Range", "bytes=0-3"))
+        .and(header("If-Range", "\"v2\""))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .set_body_bytes(b"0123".to_vec())
+                .insert_header("Content-Range", "bytes 0-3/10")
+                .insert_header("ETag", "\"v2\""),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .and(header("Range", "bytes=8-11"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .set_body_bytes(b"89".to_vec())
+                .insert_header("Content-Range", "bytes 8-9/10")
+                .insert_header("ETag", "\"v2\""),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_bytes(b"0123456789".to_vec())
+                .insert_header("ETag", "\"v2\""),
+        )
+        .mount(&mock_server)
+        .await;
+    let client = common::client();
+
+    let range = client.load_range(&url, 0..4, Some("\"v2\"")).await.unwrap();
+    assert_eq!(range.as_bytes(), b"0123");
+    assert_eq!(range.range(), 0..4);
+    assert_eq!(range.total_len(), Some(10));
+    assert_eq!(range.etag(), Some("\"v2\""));
+
+    // Ranges past the end get truncated
+    let range = client.load_range(&url, 8..12, None).await.unwrap();
+    assert_eq!(range.into_bytes(), b"89");
+
+    // A stale ETag gets the whole new version, which we refuse
+    let err = client
+        .load_range(&url, 0..4, Some("\"v1\""))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteRangeResourceChanged { found_etag: Some(ref etag), .. }
+            if etag == "\"v2\""
+    ));
+
+    // As does a server that ignores ranges
+    let err = client.load_range(&url, 4..8, None).await.unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteRangeNotSatisfied { status: 200, .. }
+    ));
+}
+
+#[tokio::test]
+async fn it_builds_requests() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/search"))
+        .and(query_param("q", "a&b=c d"))
+        .and(query_param("page", "2"))
+        .and(header("Accept-Language", "fr"))
+        .and(header("X-Api-Key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("trouvé"))
+        .mount(&mock_server)
+        .await;
+    let client = common::client();
+    let base = format!("http://{}/search?page=2", mock_server.address());
+
+    let request = client
+        .request(&base)
+        .query("q", "a&b=c d")
+        .accept_language("fr")
+        .header("X-Api-Key", "secret");
+    assert_eq!(request.url().unwrap(), format!("{base}&q=a%26b%3Dc+d"));
+    assert_eq!(request.clone().load_string().await.unwrap(), "trouvé");
+    let source = request.load_source().await.unwrap();
+    assert_eq!(source.origin_path(), format!("{base}&q=a%26b%3Dc+d"));
+    assert_eq!(source.contents(), "trouvé");
+
+    // Without the extra bits the mock doesn't match
+    let response = client.request(&base).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Bad headers are reported when the request is sent
+    let err = client
+        .request(&base)
+        .header("bad header", "value")
+        .load_bytes()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetRequestFailed { .. }
+    ));
+}
+
+#[tokio::test]
+async fn it_sends_user_agents() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/asset.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("hi"))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/asset.txt", mock_server.address());
+    let client = common::client().with_user_agent("cargo-dist/0.28.0");
+
+    client.load_bytes(&url).await.unwrap();
+    client
+        .request(&url)
+        .user_agent("release-script/1.0")
+        .load_bytes()
+        .await
+        .unwrap();
+
+    // Each request has exactly one, and the per-request one wins
+    let user_agents = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            request
+                .headers
+                .get_all("user-agent")
+                .iter()
+                .map(|value| value.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        user_agents,
+        [vec!["cargo-dist/0.28.0"], vec!["release-script/1.0"]]
+    );
+}
+
+#[cfg(feature = "remote-gzip")]
+#[tokio::test]
+async fn it_decodes_gzipped_assets() {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(b"# axoasset\n").unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let mock_server = MockServer::start().await;
+    for (route, body) in [
+        ("/README.md.gz", gzipped.clone()),
+        ("/app.tar.gz", gzipped.clone()),
+        ("/corrupt.txt.gz", gzipped[..gzipped.len() / 2].to_vec()),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+            .mount(&mock_server)
+            .await;
+    }
+    let base = format!("http://{}", mock_server.address());
+
+    // Off by default
+    let client = common::client();
+    let url = format!("{base}/README.md.gz");
+    assert_eq!(client.load_bytes(&url).await.unwrap(), gzipped);
+
+    let client = client.with_gzip_decoding(true);
+    assert_eq!(client.load_string(&url).await.unwrap(), "# axoasset\n");
+    assert_eq!(client.load_bytes(&url).await.unwrap(), b"# axoasset\n");
+    assert_eq!(client.load_asset(&url).await.unwrap().as_bytes(), gzipped);
+
+    // Tarballs are left alone
+    let url = format!("{base}/app.tar.gz");
+    assert_eq!(client.load_bytes(&url).await.unwrap(), gzipped);
+
+    let url = format!("{base}/corrupt.txt.gz");
+    let err = client.load_string(&url).await.unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetDecompressFailed { .. }
+    ));
+}
+
+#[cfg(feature = "remote-gzip")]
+#[tokio::test]
+async fn it_caps_gzipped_sources_while_decoding() {
+    use axoasset::remote::SourceLoadOptions;
+    use std::io::Write;
+
+    // A few KB that decompress to 10MB
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+    encoder.write_all(&vec![b'a'; 10_000_000]).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/bomb.txt.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(gzipped))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/bomb.txt.gz", mock_server.address());
+    let client = common::client().with_gzip_decoding(true);
+
+    let mut options = SourceLoadOptions::default();
+    options.max_bytes = Some(100_000);
+    let err = client
+        .load_source_with_options(&url, &options)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetTooLarge {
+            max_bytes: 100_000,
+            received: 100_001,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn it_coalesces_concurrent_loads() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/logo.svg"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<svg/>")
+                .set_delay(std::time::Duration::from_millis(200)),
+        )
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/logo.svg", mock_server.address());
+    let requests = || async { mock_server.received_requests().await.unwrap().len() };
+
+    let client = common::client().with_request_coalescing(true);
+    let clone = client.clone();
+    let (a, b, c) = tokio::join!(
+        client.load_string(&url),
+        client.load_string(&url),
+        clone.load_string(&url)
+    );
+    assert_eq!(a.unwrap(), "<svg/>");
+    assert_eq!(b.unwrap(), "<svg/>");
+    assert_eq!(c.unwrap(), "<svg/>");
+    assert_eq!(requests().await, 1);
+
+    // Different kinds of loads don't share, and nothing is cached afterwards
+    let (bytes, asset) = tokio::join!(client.load_bytes(&url), client.load_asset(&url));
+    assert_eq!(bytes.unwrap(), b"<svg/>");
+    assert_eq!(asset.unwrap().as_bytes(), b"<svg/>");
+    assert_eq!(requests().await, 3);
+    client.load_string(&url).await.unwrap();
+    assert_eq!(requests().await, 4);
+
+    // Without coalescing every load is its own request
+    let client = common::client();
+    let (a, b) = tokio::join!(client.load_string(&url), client.load_string(&url));
+    a.unwrap();
+    b.unwrap();
+    assert_eq!(requests().await, 6);
+}
+
+#[tokio::test]
+async fn it_verifies_remote_asset_digests() {
+    use axoasset::{AxoassetError, Digest, DigestAlgorithm};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("app contents"))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+
+    let expected = Digest::compute(DigestAlgorithm::Sha256, b"app contents");
+    let asset = client.load_verified(&url, &expected).await.unwrap();
+    assert_eq!(asset.as_bytes(), b"app contents");
+
+    let wrong = Digest::compute(DigestAlgorithm::Sha512, b"other contents");
+    let err = client.load_verified(&url, &wrong).await.unwrap_err();
+    let AxoassetError::RemoteAssetChecksumMismatch {
+        expected, actual, ..
+    } = err
+    else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(expected, wrong);
+    assert_eq!(
+        actual,
+        Digest::compute(DigestAlgorithm::Sha512, b"app contents")
+    );
+}
+
+#[tokio::test]
+async fn it_verifies_downloads_against_checksum_files() {
+    use axoasset::{AxoassetError, Digest, DigestAlgorithm};
+
+    let mock_server = MockServer::start().await;
+    let digest = Digest::compute(DigestAlgorithm::Sha256, b"app contents");
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("app contents"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz.sha256"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(format!("{} *app.tar.gz\n", digest.to_hex())),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/SHA256SUMS"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+            "{}  app.tar.gz\n",
+            Digest::compute(DigestAlgorithm::Sha256, b"old contents").to_hex()
+        )))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(tmp.path()).unwrap();
+
+    let dest_path = client
+        .copy_with_checksum_file(&url, &format!("{url}.sha256"), dest_dir)
+        .await
+        .unwrap();
+    assert_eq!(dest_path, dest_dir.join("app.tar.gz"));
+    assert_eq!(std::fs::read(&dest_path).unwrap(), b"app contents");
+
+    // Nothing is written if it doesn't match
+    std::fs::remove_file(&dest_path).unwrap();
+    let sums = format!("http://{}/SHA256SUMS", mock_server.address());
+    let err = client
+        .copy_with_checksum_file(&url, &sums, dest_dir)
+        .await
+        .unwrap_err();
+    let AxoassetError::RemoteAssetChecksumMismatch { actual, .. } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(actual, digest);
+    assert!(!dest_path.exists());
+}
+
+#[tokio::test]
+async fn it_captures_failed_exchanges() {
+    use axoasset::remote::DebugCaptureOptions;
+    use axoasset::AxoassetError;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html>no ranges here</html>")
+                .insert_header("Server", "misconfigured")
+                .insert_header("X-Secret", "hunter2"),
+        )
+        .mount(&mock_server)
+        .await;
+    let url = format!(
+        "http://user:pass@{}/app.tar.gz?token=hunter2",
+        mock_server.address()
+    );
+
+    // Without capturing, errors are left alone
+    let err = common::client().load_range(&url, 0..10, None).await;
+    assert!(matches!(
+        err,
+        Err(AxoassetError::RemoteRangeNotSatisfied { .. })
+    ));
+
+    let mut options = DebugCaptureOptions::default();
+    options.max_body_bytes = 6;
+    let client = common::client().with_debug_capture(options);
+    let err = client.load_range(&url, 0..10, None).await.unwrap_err();
+    let AxoassetError::RemoteRequestCaptured { capture, details } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert!(matches!(
+        *details,
+        AxoassetError::RemoteRangeNotSatisfied { status: 200, .. }
+    ));
+    assert_eq!(capture.method, "GET");
+    assert_eq!(
+        capture.url,
+        format!("http://{}/app.tar.gz?token=REDACTED", mock_server.address())
+    );
+    assert!(capture
+        .request_headers
+        .contains(&("range".to_owned(), "bytes=0-9".to_owned())));
+    assert_eq!(capture.status, Some(200));
+    assert!(capture
+        .response_headers
+        .contains(&("server".to_owned(), "misconfigured".to_owned())));
+    assert!(!capture.to_string().contains("hunter2"));
+    assert_eq!(capture.body.as_deref(), Some("<html>"));
+}
+
+#[tokio::test]
+async fn it_captures_error_statuses() {
+    use axoasset::remote::DebugCaptureOptions;
+    use axoasset::AxoassetError;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(403).insert_header("Server", "picky"))
+        .mount(&mock_server)
+        .await;
+    let url = format!("{}/app.tar.gz", mock_server.uri());
+
+    let err = common::client().load_metadata(&url).await.unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::RemoteAssetRequestFailed { .. }
+    ));
+
+    let client = common::client().with_debug_capture(DebugCaptureOptions::default());
+    let err = client.load_metadata(&url).await.unwrap_err();
+    let AxoassetError::RemoteRequestCaptured { capture, details } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert!(matches!(
+        *details,
+        AxoassetError::RemoteAssetRequestFailed { .. }
+    ));
+    assert_eq!(capture.method, "HEAD");
+    assert_eq!(capture.status, Some(403));
+    assert!(capture
+        .response_headers
+        .contains(&("server".to_owned(), "picky".to_owned())));
+}
+
+#[tokio::test]
+async fn it_revalidates_remote_assets() {
+    use axoasset::remote::{CacheInfo, ConditionalLoad};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/index.json"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/index.json"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("{}")
+                .insert_header("ETag", "\"v1\"")
+                .insert_header("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        )
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/index.json", mock_server.address());
+    let client = common::client();
+
+    let ConditionalLoad::Modified { asset, cache_info } = client
+        .load_if_modified(&url, &CacheInfo::default())
+        .await
+        .unwrap()
+    else {
+        panic!("expected the asset to be loaded");
+    };
+    assert_eq!(asset.as_bytes(), b"{}");
+    assert_eq!(cache_info.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(
+        cache_info.last_modified.as_deref(),
+        Some("Wed, 21 Oct 2015 07:28:00 GMT")
+    );
+
+    let ConditionalLoad::NotModified {
+        cache_info: revalidated,
+    } = client.load_if_modified(&url, &cache_info).await.unwrap()
+    else {
+        panic!("expected the asset to be unchanged");
+    };
+    assert_eq!(revalidated, cache_info);
+}
+
+#[tokio::test]
+async fn it_names_assets_from_content_disposition() {
+    let mock_server = MockServer::start().await;
+    let cases = [
+        (
+            "attachment; filename=\"app-1.0.0.tar.gz\"",
+            "app-1.0.0.tar.gz",
+        ),
+        ("attachment; filename=app.zip", "app.zip"),
+        (
+            "attachment; filename=\"fallback.txt\"; filename*=UTF-8''caf%C3%A9.txt",
+            "café.txt",
+        ),
+        ("attachment; filename=\"a;b.txt\"", "a;b.txt"),
+        // Servers don't get to pick the directory
+        ("attachment; filename=\"../../.bashrc\"", ".bashrc"),
+        // Without a filename we fall back to the url (and content type)
+        ("inline", "download"),
+    ];
+    for (idx, (disposition, expected)) in cases.into_iter().enumerate() {
+        Mock::given(method("GET"))
+            .and(path(format!("/{idx}/download")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("contents")
+                    .insert_header("Content-Disposition", disposition),
+            )
+            .mount(&mock_server)
+            .await;
+        let url = format!("http://{}/{idx}/download", mock_server.address());
+        let asset = common::client().load_asset(&url).await.unwrap();
+        let expected = if expected == "download" {
+            format!("{idx}_download.txt")
+        } else {
+            expected.to_owned()
+        };
+        assert_eq!(asset.filename(), expected, "{disposition}");
+    }
+}
+
+#[tokio::test]
+async fn it_sniffs_assets_without_content_types() {
+    use axoasset::remote::sniff_extension;
+
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    let svg = b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>".to_vec();
+    assert_eq!(sniff_extension(&png), Some("png"));
+    assert_eq!(sniff_extension(&svg), Some("svg"));
+    assert_eq!(sniff_extension(b"\xff\xd8\xff\xe0"), Some("jpg"));
+    assert_eq!(
+        sniff_extension("# Notes\ncaf\u{e9}".as_bytes()),
+        Some("txt")
+    );
+    assert_eq!(sniff_extension(b"\x7fELF\x02\x01"), None);
+    assert_eq!(sniff_extension(b""), None);
+
+    let mock_server = MockServer::start().await;
+    for (name, body) in [("logo", png), ("icon", svg), ("notes", b"hi\n".to_vec())] {
+        Mock::given(method("GET"))
+            .and(path(format!("/{name}")))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/octet-stream"))
+            .mount(&mock_server)
+            .await;
+    }
+    let url = |name| format!("http://{}/{name}", mock_server.address());
+    let client = common::client().with_content_sniffing(true);
+
+    assert_eq!(
+        client.load_asset(&url("logo")).await.unwrap().filename(),
+        "logo.png"
+    );
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let written = client
+        .load_and_write_to_dir(&url("icon"), dest_dir)
+        .await
+        .unwrap();
+    assert_eq!(written, dest_dir.join("icon.svg"));
+    assert_eq!(
+        client
+            .download_temp(&url("notes"))
+            .await
+            .unwrap()
+            .filename(),
+        "notes.txt"
+    );
+
+    // It's off by default
+    assert_eq!(
+        common::client()
+            .load_asset(&url("logo"))
+            .await
+            .unwrap()
+            .filename(),
+        "logo"
+    );
+}
+
+#[tokio::test]
+async fn it_loads_file_urls() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let path = tmp_dir.join("app config.toml");
+    axoasset::LocalAsset::write_new("name = \"app\"", &path).unwrap();
+    let url = url::Url::from_file_path(&path).unwrap().to_string();
+
+    let client = common::client();
+    let asset = client.load_asset(&url).await.unwrap();
+    assert_eq!(asset.as_bytes(), b"name = \"app\"");
+    assert_eq!(asset.filename(), "app config.toml");
+    let source = client.load_source(&url).await.unwrap();
+    assert_eq!(source.contents(), "name = \"app\"");
+
+    let missing = url::Url::from_file_path(tmp_dir.join("missing.toml")).unwrap();
+    assert!(client.load_asset(missing.as_str()).await.is_err());
+    #[cfg(unix)]
+    assert!(matches!(
+        client.load_asset("file://example.com/app.toml").await,
+        Err(axoasset::AxoassetError::RemoteFileUrlInvalid { .. })
+    ));
+}
+
+#[tokio::test]
+async fn it_loads_data_urls() {
+    let client = common::client();
+
+    let asset = client
+        .load_asset("data:text/plain;base64,SGVsbG8sIFdvcmxkIQ==")
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"Hello, World!");
+    assert_eq!(asset.filename(), "data.txt");
+
+    let text = client.load_string("data:,hello%20world").await.unwrap();
+    assert_eq!(text, "hello world");
+
+    // Everything after the comma is data, even if it looks like a query or fragment
+    let text = client.load_string("data:,what?really#yes").await.unwrap();
+    assert_eq!(text, "what?really#yes");
+    let asset = client
+        .load_asset("data:text/plain;BASE64,SGk/Pz8=")
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"Hi???");
+
+    let asset = client
+        .load_asset("data:image/png;base64,iVBORw0KGgo=")
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"\x89PNG\r\n\x1a\n");
+    assert_eq!(asset.filename(), "data.png");
+
+    assert!(matches!(
+        client.load_asset("data:text/plain;base64,!!!").await,
+        Err(axoasset::AxoassetError::RemoteDataUrlInvalid { .. })
+    ));
+}
+
+#[tokio::test]
+async fn it_streams_sources_with_a_size_cap() {
+    use axoasset::remote::SourceLoadOptions;
+
+    let mock_server = MockServer::start().await;
+    let config = "[package]\nname = \"axoasset\"\n".repeat(100);
+    Mock::given(method("GET"))
+        .and(path("/Cargo.toml"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(config.clone()))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/Cargo.toml", mock_server.address());
+    let client = common::client();
+
+    let mut seen = vec![];
+    let mut options = SourceLoadOptions::default();
+    options.max_bytes = Some(1_000_000);
+    let source = client
+        .load_source_with_progress(&url, &options, |received, total| {
+            seen.push((received, total))
+        })
+        .await
+        .unwrap();
+    assert_eq!(source.contents(), config);
+    assert_eq!(source.origin_path(), url);
+    let len = config.len() as u64;
+    assert_eq!(seen.first(), Some(&(0, Some(len))));
+    assert_eq!(seen.last(), Some(&(len, Some(len))));
+
+    let mut options = SourceLoadOptions::default();
+    options.max_bytes = Some(100);
+    let err = client
+        .load_source_with_options(&url, &options)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetTooLarge { max_bytes: 100, .. }
+    ));
+}
+
+#[tokio::test]
+async fn it_loads_metadata_without_the_body() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+        .and(path("/app.tar.gz"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-length", "12345")
+                .insert_header("content-type", "application/gzip")
+                .insert_header("etag", "\"v1\"")
+                .insert_header("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    let client = common::client();
+
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let metadata = client.load_metadata(&url).await.unwrap();
+    assert_eq!(metadata.content_length, Some(12345));
+    assert_eq!(metadata.content_type.as_deref(), Some("application/gzip"));
+    assert_eq!(metadata.cache_info.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(
+        metadata.cache_info.last_modified.as_deref(),
+        Some("Wed, 21 Oct 2015 07:28:00 GMT")
+    );
+
+    let url = format!("http://{}/missing.tar.gz", mock_server.address());
+    assert!(client.load_metadata(&url).await.is_err());
+}
+
+#[test]
+fn it_names_assets_of_any_content_type() {
+    let name = |content_type: &str| {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("content-type", content_type.parse().unwrap());
+        axoasset::remote::filename("https://example.com/releases/latest", &headers).unwrap()
+    };
+    assert_eq!(name("application/json"), "releases_latest.json");
+    assert_eq!(name("application/vnd.github+json"), "releases_latest.json");
+    assert_eq!(name("application/gzip"), "releases_latest.gz");
+    assert_eq!(name("application/octet-stream"), "releases_latest");
+    assert_eq!(name("video/mp4"), "releases_latest");
+    assert_eq!(name("text/plain; charset=utf-8"), "releases_latest.txt");
+}
+
+#[test]
+fn it_makes_futures_that_can_be_spawned() {
+    use axoasset::{Digest, DigestAlgorithm};
+
+    fn assert_send<T: Send>(_: T) {}
+
+    // Futures do nothing until they're polled, so this only checks their types
+    let client = common::client();
+    let url = "https://example.com/app.tar.gz";
+    let digest = Digest::compute(DigestAlgorithm::Sha256, b"");
+    let dest = camino::Utf8Path::new("app.tar.gz");
+    assert_send(client.load_asset(url));
+    assert_send(client.load_asset_with_progress(url, |_, _| {}));
+    assert_send(client.load_bytes(url));
+    assert_send(client.load_string(url));
+    assert_send(client.load_source(url));
+    assert_send(client.load_verified(url, &digest));
+    assert_send(client.load_and_write_to_file(url, dest));
+    assert_send(client.load_and_write_to_file_with_progress(url, dest, |_, _| {}));
+    assert_send(client.load_and_write_to_dir(url, "."));
+    assert_send(client.download_temp(url));
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "remote")]
+
+use axoasset::remote::{LockMode, LockedAsset, LockedAssets};
+use axoasset::{AxoassetError, SourceFile};
+use camino::Utf8Path;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[tokio::test]
+async fn it_pins_assets_in_a_lockfile() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/logo.svg"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<svg/>"))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/logo.svg"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<svg>changed</svg>"))
+        .mount(&mock_server)
+        .await;
+    let url = format!("{}/logo.svg", mock_server.uri());
+    let other_url = format!("{}/other.svg", mock_server.uri());
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let lock_path = Utf8Path::from_path(tmp.path()).unwrap().join("assets.lock");
+    let client = common::client();
+
+    // New assets are recorded, and the lockfile round-trips
+    let mut locked = LockedAssets::load(&lock_path).unwrap();
+    assert!(locked.is_empty());
+    client
+        .load_locked(&url, &mut locked, LockMode::default())
+        .await
+        .unwrap();
+    let entry = locked.get(&url).unwrap().clone();
+    assert_eq!(entry.size, 6);
+    assert_eq!(
+        entry.digest.to_string(),
+        format!("sha256:{}", entry.digest.to_hex())
+    );
+    locked.write(&lock_path).unwrap();
+    let mut locked = LockedAssets::load(&lock_path).unwrap();
+    assert_eq!(locked.get(&url), Some(&entry));
+
+    // Matching assets load fine, even when frozen
+    client
+        .load_locked(&url, &mut locked, LockMode::Frozen)
+        .await
+        .unwrap();
+    let err = client
+        .load_locked(&other_url, &mut locked, LockMode::Frozen)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AxoassetError::RemoteAssetNotLocked { .. }));
+
+    // Changed assets are refused unless the lock is refreshed
+    let err = client
+        .load_locked(&url, &mut locked, LockMode::Update)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::RemoteAssetSizeMismatch {
+            expected: 6,
+            actual: 18,
+            ..
+        }
+    ));
+    locked.insert(&url, LockedAsset::new(b"<svg>CHANGED</svg>"));
+    let err = client
+        .load_locked(&url, &mut locked, LockMode::Update)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::RemoteAssetChecksumMismatch { .. }
+    ));
+    let asset = client
+        .load_locked(&url, &mut locked, LockMode::Refresh)
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"<svg>changed</svg>");
+    assert_eq!(locked.get(&url).unwrap().size, 18);
+}
+
+#[test]
+fn it_reports_invalid_lockfiles() {
+    let source = SourceFile::new(
+        "assets.lock",
+        "# comment\nhttps://example.com/a sha256:nothex 3 2024-05-01T12:00:00Z\n".to_owned(),
+    );
+    let err = LockedAssets::parse(&source).unwrap_err();
+    let AxoassetError::LockedAssetsParse { span, reason, .. } = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(reason, "invalid digest");
+    assert_eq!(span.unwrap().offset(), 32);
+
+    let source = SourceFile::new(
+        "assets.lock",
+        "https://example.com/a sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae 3 2024-02-30T25:00:00Z\n"
+            .to_owned(),
+    );
+    assert!(LockedAssets::parse(&source).is_err());
+
+    // Timestamps too far in the future to represent are invalid, not a crash
+    let source = SourceFile::new(
+        "assets.lock",
+        "https://example.com/a sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae 3 400000000000-01-01T00:00:00Z\n"
+            .to_owned(),
+    );
+    let err = LockedAssets::parse(&source).unwrap_err();
+    let AxoassetError::LockedAssetsParse { reason, .. } = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(reason, "invalid timestamp");
+}
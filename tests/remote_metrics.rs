@@ -0,0 +1,61 @@
+#![cfg(feature = "remote")]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axoasset::remote::{RemoteMetrics, UrlStr};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    failed_requests: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl RemoteMetrics for Counters {
+    fn record_request(&self, _url: &UrlStr, status: Option<u16>, _duration: Duration) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        if status.map(|s| s >= 400).unwrap_or(true) {
+            self.failed_requests.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn record_bytes_downloaded(&self, _url: &UrlStr, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn it_records_metrics() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/README.md"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("# axoasset"))
+        .mount(&mock_server)
+        .await;
+
+    let counters = Arc::new(Counters::default());
+    let client = common::client().with_metrics(counters.clone());
+    // Clones share the same sink
+    let cloned = client.clone();
+
+    let base = format!("http://{}", mock_server.address());
+    client
+        .load_string(&format!("{base}/README.md"))
+        .await
+        .unwrap();
+    cloned
+        .load_bytes(&format!("{base}/README.md"))
+        .await
+        .unwrap();
+    let _ = cloned.get(&format!("{base}/missing")).await.unwrap();
+
+    assert_eq!(counters.requests.load(Ordering::SeqCst), 3);
+    assert_eq!(counters.failed_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(counters.bytes.load(Ordering::SeqCst), 20);
+}
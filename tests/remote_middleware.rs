@@ -0,0 +1,76 @@
+#![cfg(feature = "remote-middleware")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axoasset::{AxoClient, AxoassetError};
+use reqwest_middleware::{Middleware, Next};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Adds an auth header to every request, and counts them
+struct Auth(Arc<AtomicUsize>);
+
+#[async_trait::async_trait]
+impl Middleware for Auth {
+    async fn handle(
+        &self,
+        mut req: reqwest::Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        req.headers_mut()
+            .insert("authorization", "Bearer hunter2".parse().unwrap());
+        next.run(req, extensions).await
+    }
+}
+
+/// Refuses to send anything
+struct Offline;
+
+#[async_trait::async_trait]
+impl Middleware for Offline {
+    async fn handle(
+        &self,
+        _req: reqwest::Request,
+        _extensions: &mut http::Extensions,
+        _next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        Err(reqwest_middleware::Error::middleware(
+            std::io::Error::other("offline mode"),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn it_sends_requests_through_middleware() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/artifact.txt"))
+        .and(header("authorization", "Bearer hunter2"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("secret"))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/artifact.txt", mock_server.address());
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let reqwest_client = reqwest::Client::new();
+    let middleware = reqwest_middleware::ClientBuilder::new(reqwest_client.clone())
+        .with(Auth(count.clone()))
+        .build();
+    let client = AxoClient::with_reqwest(reqwest_client).with_middleware(middleware);
+    assert_eq!(client.load_string(&url).await.unwrap(), "secret");
+    assert_eq!(client.load_bytes(&url).await.unwrap(), b"secret");
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    let middleware = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(Offline)
+        .build();
+    let client = AxoClient::with_reqwest(reqwest::Client::new()).with_middleware(middleware);
+    let err = client.load_string(&url).await.unwrap_err();
+    assert!(
+        matches!(&err, AxoassetError::RemoteMiddlewareFailed { details, .. } if details.to_string() == "offline mode"),
+        "{err:?}"
+    );
+}
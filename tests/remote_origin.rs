@@ -0,0 +1,53 @@
+#![cfg(feature = "remote")]
+
+use axoasset::remote::OriginMap;
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[tokio::test]
+async fn it_rewrites_origins() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/mirror/app.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("from the mirror"))
+        .mount(&mock_server)
+        .await;
+    let mirror = format!("http://{}/mirror/*", mock_server.address());
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = Utf8Path::from_path(tmp.path()).unwrap();
+    LocalAsset::write_new_all("from disk", tmp_dir.join("local/app.txt")).unwrap();
+    let local_mirror = format!(
+        "{}*",
+        url::Url::from_directory_path(tmp_dir.join("local")).unwrap()
+    );
+
+    // Remote mirrors
+    let client = common::client().with_origin_map(
+        OriginMap::new().rewrite("https://cdn.example.com/releases/*", mirror.as_str()),
+    );
+    let asset = client
+        .load_asset("https://cdn.example.com/releases/app.txt")
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), b"from the mirror");
+    assert_eq!(
+        asset.origin_path(),
+        "https://cdn.example.com/releases/app.txt"
+    );
+
+    // Local mirrors
+    let rules = format!("# the cdn is down\nhttps://cdn.example.com/* -> {local_mirror}\n");
+    let client = common::client().with_origin_map(OriginMap::parse(&rules).unwrap());
+    let text = client
+        .load_string("https://cdn.example.com/app.txt")
+        .await
+        .unwrap();
+    assert_eq!(text, "from disk");
+
+    assert!(OriginMap::parse("https://cdn.example.com/*").is_err());
+}
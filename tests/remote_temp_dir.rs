@@ -0,0 +1,39 @@
+#![cfg(feature = "remote")]
+
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+// This is the only test in this binary, since the temp dir is process-wide
+#[tokio::test]
+async fn it_stages_downloads_in_the_temp_dir() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 1000]))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+
+    let system_temp = std::env::temp_dir();
+    assert_eq!(LocalAsset::temp_dir().unwrap(), system_temp);
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let staging = Utf8Path::from_path(tmp.path()).unwrap().join("staging");
+    LocalAsset::set_temp_dir(Some(&staging)).unwrap();
+    assert!(staging.is_dir());
+    assert_eq!(LocalAsset::temp_dir().unwrap(), staging);
+
+    let download = client.download_temp(&url).await.unwrap();
+    assert!(download.path().starts_with(&staging));
+    drop(download);
+
+    LocalAsset::set_temp_dir(None::<&Utf8Path>).unwrap();
+    assert_eq!(LocalAsset::temp_dir().unwrap(), system_temp);
+    let download = client.download_temp(&url).await.unwrap();
+    assert!(!download.path().starts_with(&staging));
+}
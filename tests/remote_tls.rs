@@ -0,0 +1,141 @@
+#![cfg(feature = "remote")]
+
+use std::sync::Arc;
+
+use axoasset::{AxoClient, AxoassetError};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+const TLS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/tls");
+
+/// Starts an https server presenting the given certificate, returning its port
+async fn serve_tls(cert: &str) -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let cert = CertificateDer::from_pem_file(format!("{TLS_DIR}/{cert}")).unwrap();
+    let key = PrivateKeyDer::from_pem_file(format!("{TLS_DIR}/server.key")).unwrap();
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let config = tokio_rustls::rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            // Answer every request the same way (if the client doesn't reject us)
+            let Ok(mut stream) = acceptor.accept(stream).await else {
+                continue;
+            };
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let response =
+                "HTTP/1.1 200 OK\r\ncontent-length: 10\r\nconnection: close\r\n\r\n# axoasset";
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+    port
+}
+
+/// A client that trusts the test certificate authority
+fn trusting_client() -> AxoClient {
+    let ca = std::fs::read(format!("{TLS_DIR}/ca.pem")).unwrap();
+    let client = reqwest::ClientBuilder::new()
+        .use_rustls_tls()
+        .add_root_certificate(reqwest::Certificate::from_pem(&ca).unwrap())
+        .resolve("localhost", ([127, 0, 0, 1], 0).into())
+        .build()
+        .unwrap();
+    AxoClient::with_reqwest(client)
+}
+
+#[tokio::test]
+async fn it_reports_unknown_issuers() {
+    let port = serve_tls("localhost.pem").await;
+    let url = format!("https://localhost:{port}/README.md");
+    let client = AxoClient::with_reqwest(
+        reqwest::ClientBuilder::new()
+            .use_rustls_tls()
+            .resolve("localhost", ([127, 0, 0, 1], 0).into())
+            .build()
+            .unwrap(),
+    );
+    let err = client.load_string(&url).await.unwrap_err();
+    assert!(
+        matches!(err, AxoassetError::RemoteTlsUnknownIssuer { .. }),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn it_reports_expired_certificates() {
+    let port = serve_tls("expired.pem").await;
+    let url = format!("https://localhost:{port}/README.md");
+    let err = trusting_client().load_string(&url).await.unwrap_err();
+    assert!(
+        matches!(err, AxoassetError::RemoteTlsCertificateExpired { .. }),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn it_reports_hostname_mismatches() {
+    let port = serve_tls("example.com.pem").await;
+    let url = format!("https://localhost:{port}/README.md");
+    let err = trusting_client().load_string(&url).await.unwrap_err();
+    assert!(
+        matches!(err, AxoassetError::RemoteTlsHostnameMismatch { .. }),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn it_trusts_valid_certificates() {
+    let port = serve_tls("localhost.pem").await;
+    let url = format!("https://localhost:{port}/README.md");
+    let text = trusting_client().load_string(&url).await.unwrap();
+    assert_eq!(text, "# axoasset");
+}
+
+#[tokio::test]
+async fn it_trusts_extra_root_certificates() {
+    use axoasset::remote::NetworkOptions;
+
+    let port = serve_tls("localhost.pem").await;
+    let url = format!("https://localhost:{port}/README.md");
+    let client = |options: &NetworkOptions| {
+        let builder =
+            reqwest::ClientBuilder::new().resolve("localhost", ([127, 0, 0, 1], 0).into());
+        AxoClient::with_network_options(builder, options).unwrap()
+    };
+
+    let mut options = NetworkOptions::default();
+    options
+        .tls
+        .root_certificates
+        .push(format!("{TLS_DIR}/ca.pem").into());
+    let text = client(&options).load_string(&url).await.unwrap();
+    assert_eq!(text, "# axoasset");
+
+    options.tls.only_root_certificates = true;
+    let text = client(&options).load_string(&url).await.unwrap();
+    assert_eq!(text, "# axoasset");
+
+    #[cfg(feature = "tls-native")]
+    {
+        options.tls.backend = axoasset::remote::TlsBackend::Native;
+        let text = client(&options).load_string(&url).await.unwrap();
+        assert_eq!(text, "# axoasset");
+    }
+
+    options.tls.root_certificates = vec![format!("{TLS_DIR}/server.key").into()];
+    let err = AxoClient::with_network_options(reqwest::ClientBuilder::new(), &options).unwrap_err();
+    assert!(
+        matches!(err, AxoassetError::RemoteTlsCertificateInvalid { .. }),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "remote-upload")]
+
+use std::io::Read;
+
+use axoasset::local::ArchiveLayout;
+use axoasset::remote::TarUploadOptions;
+use axoasset::{AxoassetError, LocalAsset};
+use camino::Utf8Path;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+mod common;
+
+#[tokio::test]
+async fn it_streams_tarballs_into_uploads() {
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let root = Utf8Path::from_path(tmp.path()).unwrap();
+    let src = root.join("dist");
+    LocalAsset::write_new_all("#!/bin/sh\n", src.join("bin/app")).unwrap();
+    // Big and random enough to take more than one chunk once compressed
+    let mut state = 1u64;
+    let noise = (0..512 * 1024)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 56) as u8
+        })
+        .collect::<Vec<_>>();
+    std::fs::write(src.join("data.bin"), &noise).unwrap();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/uploads/app.tar.gz"))
+        .and(header("content-type", "application/gzip"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&mock_server)
+        .await;
+    let url = format!("{}/uploads/app.tar.gz", mock_server.uri());
+    let client = common::client();
+
+    let mut options = TarUploadOptions::default();
+    options.method = reqwest::Method::POST;
+    let response = client
+        .upload_tar_gz_dir_with_options(&url, &src, Some("app-1.0.0"), &options)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let body = flate2::read::GzDecoder::new(&requests[0].body[..]);
+    let mut archive = tar::Archive::new(body);
+    let mut files = vec![];
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let name = entry.path().unwrap().to_string_lossy().into_owned();
+        let mut contents = vec![];
+        entry.read_to_end(&mut contents).unwrap();
+        if name == "app-1.0.0/data.bin" {
+            assert!(contents == noise);
+        }
+        files.push(name);
+    }
+    assert!(files.contains(&"app-1.0.0/bin/app".to_owned()));
+    assert!(files.contains(&"app-1.0.0/data.bin".to_owned()));
+
+    // The gzip header names the archive without the url's query or fragment
+    client
+        .upload_tar_gz_dir_with_options(
+            &format!("{url}?signature=secret#frag"),
+            &src,
+            ArchiveLayout::Flat,
+            &options,
+        )
+        .await
+        .unwrap();
+    let requests = mock_server.received_requests().await.unwrap();
+    let mut body = flate2::read::GzDecoder::new(&requests[1].body[..]);
+    std::io::copy(&mut body, &mut std::io::sink()).unwrap();
+    assert_eq!(
+        body.header().unwrap().filename(),
+        Some(&b"app.tar.gz.tar"[..])
+    );
+
+    // Error statuses are errors
+    let err = client
+        .upload_tar_gz_dir(
+            &format!("{}/elsewhere", mock_server.uri()),
+            &src,
+            ArchiveLayout::Flat,
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        AxoassetError::RemoteAssetRequestFailed { .. }
+    ));
+
+    // And so are archiving failures, even if the server didn't notice
+    let err = client
+        .upload_tar_gz_dir_with_options(&url, root.join("missing"), ArchiveLayout::Flat, &options)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AxoassetError::Compression { .. }));
+}
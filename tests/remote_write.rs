@@ -48,3 +48,167 @@ async fn it_writes_remote_assets() {
         fs::read_to_string(dest).unwrap().contains(contents);
     }
 }
+
+#[tokio::test]
+async fn it_downloads_to_temp_files() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 100_000]))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+
+    // Dropping the download cleans it up
+    let download = client.download_temp(&url).await.unwrap();
+    assert_eq!(download.filename(), "app.tar.gz");
+    assert_eq!(download.url(), url);
+    assert_eq!(download.stats().bytes, 100_000);
+    assert_eq!(download.stats().retries, 0);
+    let temp_path = download.path().to_owned();
+    assert_eq!(fs::read(&temp_path).unwrap(), vec![7u8; 100_000]);
+    drop(download);
+    assert!(!temp_path.exists());
+
+    // Persisting it keeps it
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_path = camino::Utf8Path::from_path(dest.path())
+        .unwrap()
+        .join("app.tar.gz");
+    let download = client.download_temp(&url).await.unwrap();
+    let temp_path = download.path().to_owned();
+    assert_eq!(download.persist(&dest_path).unwrap(), dest_path);
+    assert!(!temp_path.exists());
+    assert_eq!(fs::read(&dest_path).unwrap(), vec![7u8; 100_000]);
+
+    // Failing to move it is an error, not a copy that fails too
+    let download = client.download_temp(&url).await.unwrap();
+    let missing_dir_path = dest_path.with_file_name("missing").join("app.tar.gz");
+    let err = download.persist(&missing_dir_path).unwrap_err();
+    assert!(
+        matches!(err, axoasset::AxoassetError::RemoteAssetWriteFailed { .. }),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn it_streams_downloads_to_disk() {
+    let mock_server = MockServer::start().await;
+    let body = (0..4_000_000u32).map(|i| i as u8).collect::<Vec<_>>();
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(dest.path()).unwrap();
+    let dest_file = dest_dir.join("renamed.tar.gz");
+    client
+        .load_and_write_to_file(&url, &dest_file)
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&dest_file).unwrap(), body);
+
+    let dest_path = client.load_and_write_to_dir(&url, dest_dir).await.unwrap();
+    assert_eq!(dest_path, dest_dir.join("app.tar.gz"));
+    assert_eq!(fs::read(&dest_path).unwrap(), body);
+
+    // Errors creating the file are reported against its path
+    let missing = dest_dir.join("missing/app.tar.gz");
+    let err = client
+        .load_and_write_to_file(&url, &missing)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        axoasset::AxoassetError::RemoteAssetWriteFailed { dest_path, .. } if dest_path == missing
+    ));
+}
+
+#[tokio::test]
+async fn it_reports_download_progress() {
+    let mock_server = MockServer::start().await;
+    let body = vec![7u8; 1_000_000];
+    Mock::given(method("GET"))
+        .and(path("/app.tar.gz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body.clone()))
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.tar.gz", mock_server.address());
+    let client = common::client();
+
+    // Progress only ever goes up, and ends at the full size
+    let check = |reports: &[(u64, Option<u64>)]| {
+        assert_eq!(reports.first(), Some(&(0, Some(1_000_000))));
+        assert_eq!(reports.last(), Some(&(1_000_000, Some(1_000_000))));
+        assert!(reports.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    };
+
+    let mut reports = vec![];
+    let asset = client
+        .load_asset_with_progress(&url, |done, total| reports.push((done, total)))
+        .await
+        .unwrap();
+    assert_eq!(asset.as_bytes(), body);
+    check(&reports);
+
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_file = camino::Utf8Path::from_path(dest.path())
+        .unwrap()
+        .join("app.tar.gz");
+    let mut reports = vec![];
+    client
+        .load_and_write_to_file_with_progress(&url, &dest_file, |done, total| {
+            reports.push((done, total))
+        })
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&dest_file).unwrap(), body);
+    check(&reports);
+}
+
+/// Serves a response that claims to be longer than it is, so downloads fail partway
+async fn truncating_server() -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let response =
+                "HTTP/1.1 200 OK\r\ncontent-length: 1000\r\nconnection: close\r\n\r\npartial";
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+    format!("http://{addr}/app.tar.gz")
+}
+
+#[tokio::test]
+async fn it_keeps_existing_files_when_downloads_fail() {
+    let url = truncating_server().await;
+    let client = common::client();
+
+    let dest = assert_fs::TempDir::new().unwrap();
+    let dest_dir = camino::Utf8Path::from_path(dest.path()).unwrap();
+    let dest_file = dest_dir.join("app.tar.gz");
+    fs::write(&dest_file, "the old version").unwrap();
+
+    client
+        .load_and_write_to_file(&url, &dest_file)
+        .await
+        .unwrap_err();
+    client
+        .load_and_write_to_dir(&url, dest_dir)
+        .await
+        .unwrap_err();
+    assert_eq!(fs::read_to_string(&dest_file).unwrap(), "the old version");
+    // And the partial download was cleaned up
+    assert_eq!(fs::read_dir(dest_dir).unwrap().count(), 1);
+}
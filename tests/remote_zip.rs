@@ -0,0 +1,80 @@
+#![cfg(all(feature = "remote", feature = "compression-zip"))]
+
+use std::sync::{Arc, Mutex};
+
+use assert_fs::prelude::*;
+use axoasset::LocalAsset;
+use camino::Utf8Path;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+mod common;
+
+/// Serves ranges of a file, remembering how many bytes it served
+struct RangeServer {
+    bytes: Vec<u8>,
+    served: Arc<Mutex<usize>>,
+}
+
+impl Respond for RangeServer {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let range = request.headers.get("range").unwrap().to_str().unwrap();
+        let (start, end) = range
+            .strip_prefix("bytes=")
+            .unwrap()
+            .split_once('-')
+            .unwrap();
+        let start = start.parse::<usize>().unwrap();
+        let end = end.parse::<usize>().unwrap().min(self.bytes.len() - 1);
+        *self.served.lock().unwrap() += end + 1 - start;
+        ResponseTemplate::new(206)
+            .set_body_bytes(self.bytes[start..=end].to_vec())
+            .insert_header(
+                "Content-Range",
+                format!("bytes {start}-{end}/{}", self.bytes.len()).as_str(),
+            )
+            .insert_header("ETag", "\"v1\"")
+    }
+}
+
+#[tokio::test]
+async fn it_lists_remote_zips() {
+    let origin = assert_fs::TempDir::new().unwrap();
+    let origin_dir = Utf8Path::from_path(origin.path()).unwrap();
+    origin.child("app/bin/app").write_str("binary").unwrap();
+    // Make the zip much bigger than its central directory
+    let padding = (0..200_000u32)
+        .map(|n| n.wrapping_mul(2_654_435_761).to_string())
+        .collect::<String>();
+    origin.child("app/padding.txt").write_str(&padding).unwrap();
+    let zip_path = origin_dir.join("app.zip");
+    LocalAsset::zip_dir(origin_dir.join("app"), &zip_path, None::<&str>).unwrap();
+    let zip_bytes = LocalAsset::load_bytes(&zip_path).unwrap();
+
+    let mock_server = MockServer::start().await;
+    let served = Arc::new(Mutex::new(0));
+    Mock::given(method("GET"))
+        .and(path("/app.zip"))
+        .respond_with(RangeServer {
+            bytes: zip_bytes.clone(),
+            served: served.clone(),
+        })
+        .mount(&mock_server)
+        .await;
+    let url = format!("http://{}/app.zip", mock_server.address());
+
+    let entries = common::client().list_zip(&url).await.unwrap();
+    let mut names = entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["bin/", "bin/app", "padding.txt"]);
+    let app = entries
+        .iter()
+        .find(|entry| entry.name == "bin/app")
+        .unwrap();
+    assert_eq!(app.size, 6);
+    assert!(!app.is_dir());
+    assert!(*served.lock().unwrap() < zip_bytes.len() / 2);
+}
@@ -19,6 +19,21 @@ fn substr_span() {
     assert_eq!(std::str::from_utf8(span_bytes).unwrap(), "there");
 }
 
+#[test]
+fn lines_spanned() {
+    let source = axoasset::SourceFile::new("app.env", "A=1\r\n\nexport B=2\n".to_owned());
+    let lines = source.lines_spanned().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    for (line, span) in &lines {
+        let spanned = &source.contents()[span.offset()..span.offset() + span.len()];
+        assert_eq!(spanned, *line);
+    }
+    assert_eq!(lines[0].0, "A=1");
+    assert_eq!(lines[1], ("", miette::SourceSpan::from(5..5)));
+    assert_eq!(lines[2].0, "export B=2");
+    assert_eq!(source.line_col(lines[2].1.offset()), Some((3, 1)));
+}
+
 #[test]
 fn substr_span_invalid() {
     // Make the file
@@ -174,7 +189,7 @@ goodbye = true
     // Get the span for a non-substring (string literal isn't pointing into the String)
     let val = source.deserialize_toml_edit().unwrap();
     assert_eq!(val["hello"].as_str().unwrap(), "there");
-    assert_eq!(val["goodbye"].as_bool().unwrap(), true);
+    assert!(val["goodbye"].as_bool().unwrap());
 }
 
 #[cfg(feature = "toml-edit")]
@@ -199,6 +214,49 @@ goodbye =
     };
 }
 
+#[cfg(feature = "toml-edit")]
+#[test]
+fn toml_edit_merge() {
+    // Make the file
+    let contents = String::from(
+        r##"
+# the greeting
+hello = "there"
+
+[package]
+name = "axoasset" # keep me
+"##,
+    );
+    let source = axoasset::SourceFile::new("file.toml", contents);
+
+    let overlay = r##"
+goodbye = true
+
+[package]
+version = "1.0.0"
+
+[workspace.metadata.dist]
+cargo-dist-version = "0.1.0"
+"##
+    .parse::<axoasset::toml_edit::DocumentMut>()
+    .unwrap();
+
+    let merged = source.merge_toml(&overlay).unwrap();
+    assert!(merged.contains("# the greeting"));
+    assert!(merged.contains(r#"name = "axoasset" # keep me"#));
+    let val = merged.parse::<axoasset::toml_edit::DocumentMut>().unwrap();
+    assert_eq!(val["hello"].as_str().unwrap(), "there");
+    assert!(val["goodbye"].as_bool().unwrap());
+    assert_eq!(val["package"]["name"].as_str().unwrap(), "axoasset");
+    assert_eq!(val["package"]["version"].as_str().unwrap(), "1.0.0");
+    assert_eq!(
+        val["workspace"]["metadata"]["dist"]["cargo-dist-version"]
+            .as_str()
+            .unwrap(),
+        "0.1.0"
+    );
+}
+
 #[test]
 #[cfg(feature = "yaml-serde")]
 fn yaml_valid() {
@@ -219,7 +277,7 @@ goodbye: true
 
     let res = source.deserialize_yaml::<MyType>().unwrap();
     assert_eq!(res.hello, "there");
-    assert_eq!(res.goodbye, true);
+    assert!(res.goodbye);
 }
 
 #[test]
@@ -248,3 +306,383 @@ goodbye: "this shouldn't be a string"
         panic!("span was invalid");
     };
 }
+
+#[test]
+#[cfg(feature = "csv-serde")]
+fn csv_records() {
+    use axoasset::AxoassetError;
+
+    #[derive(serde::Deserialize, PartialEq, Eq, Debug)]
+    struct Download {
+        name: String,
+        count: u64,
+    }
+    fn text(source: &axoasset::SourceFile, span: miette::SourceSpan) -> &str {
+        &source.contents()[span.offset()..span.offset() + span.len()]
+    }
+
+    let contents = "\u{FEFF}name,count\napp.tar.gz,12\n\"app,.zip\",oops\napp.msi\napp.exe,3\n";
+    let source = axoasset::SourceFile::new("downloads.csv", contents.to_owned());
+    let records = source.deserialize_csv::<Download>();
+    assert_eq!(records.len(), 4);
+
+    let first = records[0].as_ref().unwrap();
+    assert_eq!(first.name, "app.tar.gz");
+    assert_eq!(first.count, 12);
+    assert_eq!(
+        text(&source, axoasset::Spanned::span(first)),
+        "app.tar.gz,12"
+    );
+
+    // A bad field points at the field
+    let Err(AxoassetError::Csv {
+        span: Some(span), ..
+    }) = &records[1]
+    else {
+        panic!("span was missing");
+    };
+    assert_eq!(text(&source, *span), "oops");
+
+    // A short record points at the record, and reading carries on after it
+    let Err(AxoassetError::Csv {
+        span: Some(span), ..
+    }) = &records[2]
+    else {
+        panic!("span was missing");
+    };
+    assert_eq!(text(&source, *span), "app.msi");
+    assert_eq!(records[3].as_ref().unwrap().count, 3);
+
+    // TSV
+    let source = axoasset::SourceFile::new("downloads.tsv", "name\tcount\napp.zip\t7\n".to_owned());
+    let records = source.deserialize_csv::<Download>();
+    assert_eq!(records[0].as_ref().unwrap().name, "app.zip");
+}
+
+#[test]
+fn detect_format() {
+    use axoasset::source::SourceFormat;
+
+    let cases = [
+        ("config.json", "", Some(SourceFormat::Json)),
+        ("config.TOML", "", Some(SourceFormat::Toml)),
+        ("config.yml", "", Some(SourceFormat::Yaml)),
+        (
+            "config",
+            r#"{ "hello": "there" }"#,
+            Some(SourceFormat::Json),
+        ),
+        ("config", r#"["hello", "there"]"#, Some(SourceFormat::Json)),
+        (
+            "config",
+            "# comment\n\n[package]\nname = \"x\"",
+            Some(SourceFormat::Toml),
+        ),
+        ("config", "[[bin]]\nname = \"x\"", Some(SourceFormat::Toml)),
+        (
+            "config",
+            "hello = \"there: friend\"",
+            Some(SourceFormat::Toml),
+        ),
+        ("config", "---\nhello: there", Some(SourceFormat::Yaml)),
+        ("config", "hello: there = friend", Some(SourceFormat::Yaml)),
+        ("config", "hello there", None),
+    ];
+    for (name, contents, expected) in cases {
+        let source = axoasset::SourceFile::new(name, contents.to_owned());
+        assert_eq!(source.detect_format(), expected, "{name}: {contents}");
+    }
+}
+
+#[cfg(all(feature = "json-serde", feature = "toml-serde"))]
+#[test]
+fn deserialize_auto() {
+    use axoasset::source::SourceFormat;
+
+    #[derive(serde::Deserialize, PartialEq, Eq, Debug)]
+    struct MyType {
+        hello: String,
+        goodbye: bool,
+    }
+    let expected = MyType {
+        hello: "there".to_string(),
+        goodbye: true,
+    };
+
+    let json = axoasset::SourceFile::new(
+        "config",
+        String::from(r##"{ "hello": "there", "goodbye": true }"##),
+    );
+    let (val, format) = json.deserialize_auto::<MyType>().unwrap();
+    assert_eq!(val, expected);
+    assert_eq!(format, SourceFormat::Json);
+
+    let toml = axoasset::SourceFile::new(
+        "config.toml",
+        String::from("hello = \"there\"\ngoodbye = true\n"),
+    );
+    let (val, format) = toml.deserialize_auto::<MyType>().unwrap();
+    assert_eq!(val, expected);
+    assert_eq!(format, SourceFormat::Toml);
+
+    let unknown = axoasset::SourceFile::new("config", String::from("hello there"));
+    assert!(matches!(
+        unknown.deserialize_auto::<MyType>(),
+        Err(axoasset::AxoassetError::SourceFormatUnknown { .. })
+    ));
+}
+
+#[test]
+fn span_map() {
+    let original = "---\ntitle: hi\n---\nname = 1\n#include other\nend = 2\n";
+    let source = axoasset::SourceFile::new("file.toml", original.to_owned());
+    let body_start = original.find("name").unwrap();
+    let include_start = original.find("#include").unwrap();
+    let include_end = original.find("end").unwrap();
+
+    // Strip the front-matter and expand the include
+    let include = "included = 3\n";
+    let mut processed = String::new();
+    let mut map = axoasset::source::SpanMap::new();
+    processed.push_str(&original[body_start..include_start]);
+    map.push_original(body_start..include_start);
+    processed.push_str(include);
+    map.push_replaced(include.len(), include_start..include_end);
+    processed.push_str(&original[include_end..]);
+    map.push_original(include_end..original.len());
+    assert_eq!(map.processed_len(), processed.len());
+
+    // Spans in verbatim text map to the same text in the original
+    let name = processed.find("name").unwrap();
+    let mapped = map.map_span((name..name + 4).into()).unwrap();
+    assert_eq!(source.read_span(&mapped, 0, 0).unwrap().data(), b"name");
+    let end = processed.find("end").unwrap();
+    let mapped = map.map_span((end..end + 7).into()).unwrap();
+    assert_eq!(source.read_span(&mapped, 0, 0).unwrap().data(), b"end = 2");
+
+    // Spans in replaced text map to whatever they replaced
+    let included = processed.find("included").unwrap();
+    let mapped = map.map_span((included..included + 8).into()).unwrap();
+    assert_eq!(mapped, (include_start..include_end).into());
+
+    // Spans that straddle pieces cover everything in between
+    let mapped = map.map_span((name..included + 2).into()).unwrap();
+    assert_eq!(mapped, (body_start..include_end).into());
+
+    // Spanned values get remapped too
+    let spanned = axoasset::Spanned::with_source_span((), (end..end + 3).into());
+    let spanned = map.map_spanned(spanned);
+    assert_eq!(
+        axoasset::Spanned::span(&spanned),
+        (include_end..include_end + 3).into()
+    );
+
+    // Empty spans and out of bounds spans
+    let mapped = map.map_span((processed.len()..processed.len()).into());
+    assert_eq!(mapped, Some((original.len()..original.len()).into()));
+    assert_eq!(map.map_span((end..processed.len() + 1).into()), None);
+}
+
+#[test]
+fn apply_suggestions() {
+    use axoasset::source::Suggestion;
+
+    let contents = "name = \"axoasset\"\nlicence = \"MIT\"\nedition = 2018\n";
+    let source = axoasset::SourceFile::new("Cargo.toml", contents.to_owned());
+    let licence = source.span_for_substr(&source.contents()[18..25]).unwrap();
+    let year_start = contents.find("2018").unwrap();
+    let fixes = vec![
+        // Out of order and duplicated on purpose
+        Suggestion::new((year_start..year_start + 4).into(), "2021"),
+        Suggestion::new(licence, "license").with_message("did you mean"),
+        Suggestion::new((year_start..year_start + 4).into(), "2021"),
+        Suggestion::new((0..0).into(), "# generated\n"),
+    ];
+    assert_eq!(
+        source.apply_suggestions(&fixes).unwrap(),
+        "# generated\nname = \"axoasset\"\nlicense = \"MIT\"\nedition = 2021\n"
+    );
+    assert_eq!(fixes[1].label().label(), Some("did you mean: `license`"));
+    assert_eq!(fixes[0].label().label(), Some("replace with `2021`"));
+    assert_eq!(
+        Suggestion::new(licence, "").label().label(),
+        Some("remove this")
+    );
+
+    // Overlapping and out of bounds suggestions are errors
+    let overlapping = [
+        Suggestion::new((0..10).into(), "a"),
+        Suggestion::new((5..12).into(), "b"),
+    ];
+    assert!(source.apply_suggestions(&overlapping).is_err());
+    let out_of_bounds = [Suggestion::new((40..400).into(), "")];
+    assert!(source.apply_suggestions(&out_of_bounds).is_err());
+}
+
+#[test]
+#[cfg(all(feature = "json-serde", feature = "toml-serde"))]
+fn semantic_diff() {
+    use axoasset::source::SemanticChange;
+
+    let old = axoasset::SourceFile::new(
+        "old.toml",
+        r#"
+# a comment that doesn't matter
+[package]
+name = "axoasset"
+version = "1.0.0"
+authors = ["axo"]
+
+[dependencies]
+"serde.json" = "1"
+"#
+        .to_owned(),
+    );
+    let reformatted = axoasset::SourceFile::new(
+        "reformatted.json",
+        r#"{
+            "dependencies": { "serde.json": "1" },
+            "package": { "authors": ["axo"], "version": "1.0.0", "name": "axoasset" }
+        }"#
+        .to_owned(),
+    );
+    assert!(old.semantic_eq(&reformatted).unwrap());
+    assert!(old.semantic_diff(&reformatted).unwrap().is_empty());
+
+    let new = axoasset::SourceFile::new(
+        "new.toml",
+        r#"
+[package]
+name = "axoasset"
+version = "1.1.0"
+authors = ["axo", "someone else"]
+edition = "2021"
+"#
+        .to_owned(),
+    );
+    assert!(!old.semantic_eq(&new).unwrap());
+    let changes = old.semantic_diff(&new).unwrap();
+    let rendered = changes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        rendered,
+        vec![
+            r#"+ package.authors[1] = "someone else""#,
+            r#"+ package.edition = "2021""#,
+            r#"~ package.version: "1.0.0" => "1.1.0""#,
+            r#"- dependencies = {"serde.json":"1"}"#,
+        ]
+    );
+    assert_eq!(changes[3].path(), "dependencies");
+    assert!(matches!(changes[1], SemanticChange::Added { .. }));
+}
+
+#[test]
+#[cfg(feature = "json-serde")]
+fn canonicalize_json() {
+    let a = axoasset::SourceFile::new(
+        "a.json",
+        "\u{FEFF}{ \"b\": [1.0, 2.50, -0.0, 1e21, 1E-7, 18446744073709551615],\n  \"a\": \"\\u00e9\\n\",\n  \"\u{1F600}\": null, \"\u{FB33}\": true }"
+            .to_owned(),
+    );
+    let b = axoasset::SourceFile::new(
+        "b.json",
+        "{\"a\":\"é\\n\",\"b\":[1,2.5,0,1e+21,1e-7,18446744073709551615],\"\u{FB33}\":true,\"\u{1F600}\":null}"
+            .to_owned(),
+    );
+    let canonical =
+        "{\"a\":\"é\\n\",\"b\":[1,2.5,0,1e+21,1e-7,18446744073709551615],\"\u{1F600}\":null,\"\u{FB33}\":true}";
+    assert_eq!(a.canonicalize_json().unwrap(), canonical);
+    assert_eq!(b.canonicalize_json().unwrap(), canonical);
+
+    let invalid = axoasset::SourceFile::new("invalid.json", "{".to_owned());
+    assert!(invalid.canonicalize_json().is_err());
+}
+
+#[cfg(all(feature = "json-serde", feature = "toml-serde", feature = "toml-edit"))]
+#[test]
+fn write_config_by_extension() {
+    use axoasset::{AxoassetError, LocalAsset};
+
+    let tmp = assert_fs::TempDir::new().unwrap();
+    let tmp_dir = camino::Utf8Path::from_path(tmp.path()).unwrap();
+    let config = axoasset::serde_json::json!({
+        "name": "axoasset",
+        "features": { "remote": true },
+    });
+
+    let json_path = tmp_dir.join("config.json");
+    LocalAsset::write_config(&json_path, &config).unwrap();
+    assert_eq!(
+        LocalAsset::load_string(&json_path).unwrap(),
+        "{\n  \"features\": {\n    \"remote\": true\n  },\n  \"name\": \"axoasset\"\n}\n"
+    );
+
+    // Existing toml keeps its comments
+    let toml_path = tmp_dir.join("config.toml");
+    LocalAsset::write_new(
+        "# the app\nname = \"old\" # keep me\n\n[features]\n# on by default\nremote = false\n",
+        &toml_path,
+    )
+    .unwrap();
+    LocalAsset::write_config(&toml_path, &config).unwrap();
+    assert_eq!(
+        LocalAsset::load_string(&toml_path).unwrap(),
+        "# the app\nname = \"axoasset\" # keep me\n\n[features]\n# on by default\nremote = true\n"
+    );
+
+    let err = LocalAsset::write_config(tmp_dir.join("config.ini"), &config).unwrap_err();
+    assert!(matches!(err, AxoassetError::SourceFormatUnknown { .. }));
+}
+
+#[cfg(feature = "json-serde")]
+#[test]
+fn deserialize_multi_json() {
+    use axoasset::Spanned;
+
+    #[derive(serde::Deserialize, PartialEq, Eq, Debug)]
+    struct Manifest {
+        name: String,
+    }
+
+    let contents = "{\"name\": \"a\"}\n\n  {\"name\": \"b\"}{\"name\": \"c\"}\n";
+    let source = axoasset::SourceFile::new("manifests.json", contents.to_owned());
+    let docs = source.deserialize_multi::<Manifest>().unwrap();
+    let names = docs.iter().map(|doc| doc.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(
+        &contents[Spanned::start(&docs[1])..Spanned::end(&docs[1])],
+        "{\"name\": \"b\"}"
+    );
+    assert_eq!(Spanned::start_line_col(&docs[1], &source), Some((3, 3)));
+
+    let source = axoasset::SourceFile::new(
+        "manifests.json",
+        String::from("{\"name\": \"a\"}\n{\"name\": 1}\n"),
+    );
+    assert!(matches!(
+        source.deserialize_multi::<Manifest>(),
+        Err(axoasset::AxoassetError::Json { span: Some(_), .. })
+    ));
+}
+
+#[cfg(feature = "yaml-serde")]
+#[test]
+fn deserialize_multi_yaml() {
+    use axoasset::Spanned;
+
+    #[derive(serde::Deserialize, PartialEq, Eq, Debug)]
+    struct Manifest {
+        name: String,
+    }
+
+    let contents = "# bundle\n---\nname: a\n---\n# empty\n---\nname: b\n...\n";
+    let source = axoasset::SourceFile::new("manifests.yaml", contents.to_owned());
+    let docs = source.deserialize_multi::<Manifest>().unwrap();
+    let names = docs.iter().map(|doc| doc.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(
+        &contents[Spanned::start(&docs[1])..Spanned::end(&docs[1])],
+        "name: b"
+    );
+    assert_eq!(Spanned::start_line_col(&docs[1], &source), Some((7, 1)));
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "toml-serde")]
+
+use axoasset::{SourceFile, Spanned};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Installer {
+    Shell,
+    Npm { scope: Spanned<String> },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum StringOrList {
+    String(Spanned<String>),
+    List(Vec<Spanned<String>>),
+}
+
+#[derive(Debug, Deserialize)]
+struct Common {
+    version: Spanned<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    name: Spanned<String>,
+    installers: Vec<Spanned<Installer>>,
+    targets: StringOrList,
+    ci: Spanned<StringOrList>,
+    homepage: Option<Spanned<String>>,
+    #[serde(flatten)]
+    common: Common,
+}
+
+#[test]
+fn spanned_works_with_enums_and_flatten() {
+    let source = SourceFile::new(
+        "config.toml",
+        r#"name = "app"
+installers = ["shell", { npm = { scope = "@axo" } }]
+targets = ["x86_64", "aarch64"]
+ci = "github"
+homepage = "https://axo.dev"
+version = "1.0.0"
+"#
+        .to_owned(),
+    );
+    let config = source.deserialize_toml::<Config>().unwrap();
+    let text = |spanned_span: miette::SourceSpan| {
+        &source.contents()[spanned_span.offset()..spanned_span.offset() + spanned_span.len()]
+    };
+
+    // Directly deserialized values get their spans
+    assert_eq!(config.name, "app".to_owned());
+    assert_eq!(text(Spanned::span(&config.name)), "\"app\"");
+    assert_eq!(*config.installers[0], Installer::Shell);
+    assert_eq!(text(Spanned::span(&config.installers[0])), "\"shell\"");
+    let Installer::Npm { scope } = &*config.installers[1] else {
+        panic!("wrong installer {:?}", config.installers[1]);
+    };
+    assert_eq!(scope, &"@axo".to_owned());
+    assert_eq!(text(Spanned::span(scope)), "\"@axo\"");
+    assert_eq!(text(Spanned::span(&config.ci)), "\"github\"");
+    let homepage = config.homepage.as_ref().unwrap();
+    assert_eq!(text(Spanned::span(homepage)), "\"https://axo.dev\"");
+
+    // Values that serde buffers (untagged enums and flattened structs) don't have
+    // spans, but still deserialize
+    let StringOrList::List(targets) = &config.targets else {
+        panic!("wrong targets {:?}", config.targets);
+    };
+    assert_eq!(targets, &["x86_64".to_owned(), "aarch64".to_owned()]);
+    assert_eq!(*config.common.version, "1.0.0");
+    assert_eq!(Spanned::span(&config.common.version).len(), 0);
+}
+
+#[cfg(feature = "json-serde")]
+#[test]
+fn spanned_works_without_spans() {
+    let source = SourceFile::new(
+        "config.json",
+        r#"{"name": "app", "installers": ["shell"], "targets": "x86_64", "ci": ["github"], "version": "1.0.0"}"#
+            .to_owned(),
+    );
+    let config = source.deserialize_json::<Config>().unwrap();
+    assert_eq!(config.name, "app".to_owned());
+    assert_eq!(*config.installers[0], Installer::Shell);
+    assert_eq!(
+        config.targets,
+        StringOrList::String("x86_64".to_owned().into())
+    );
+    assert_eq!(*config.common.version, "1.0.0");
+}
+
+#[test]
+fn spanned_works_with_toml_directly() {
+    #[derive(Debug, Deserialize)]
+    struct Package {
+        name: Spanned<String>,
+        version: Option<Spanned<String>>,
+    }
+
+    let package =
+        axoasset::toml::from_str::<Package>("name = \"app\"\nversion = \"1.0.0\"").unwrap();
+    assert_eq!(Spanned::start(&package.name), 7);
+    assert_eq!(Spanned::end(&package.name), 12);
+    let version = package.version.unwrap();
+    assert_eq!((Spanned::start(&version), Spanned::end(&version)), (23, 30));
+}
+
+#[test]
+fn spanned_resolves_lines_and_columns() {
+    let source = SourceFile::new(
+        "config.toml",
+        r#"name = "app"
+installers = ["shell", { npm = { scope = "@axo" } }]
+targets = ["x86_64", "aarch64"]
+ci = "github"
+version = "1.0.0"
+"#
+        .to_owned(),
+    );
+    let config = source.deserialize_toml::<Config>().unwrap();
+
+    assert_eq!(Spanned::start_line_col(&config.name, &source), Some((1, 8)));
+    assert_eq!(Spanned::end_line_col(&config.name, &source), Some((1, 13)));
+    assert_eq!(
+        Spanned::start_line_col(&config.installers[1], &source),
+        Some((2, 24))
+    );
+    assert_eq!(Spanned::start_line_col(&config.ci, &source), Some((4, 6)));
+
+    // Round trips with span_for_line_col
+    let span = source.span_for_line_col(4, 6).unwrap();
+    assert_eq!(source.line_col(span.offset()), Some((4, 6)));
+    // The end of the file is a valid position, but nothing past it is
+    assert_eq!(source.line_col(source.contents().len()), Some((6, 1)));
+    assert_eq!(source.line_col(source.contents().len() + 1), None);
+}